@@ -0,0 +1,146 @@
+//! REST calls to the server's `/auth` endpoints.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+/// Tokens for one logged-in session, as returned by `/auth/login` and
+/// `/auth/refresh`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Session {
+    pub player_id: String,
+    pub access_token: String,
+    /// Unix time in milliseconds.
+    pub access_expires_at: u64,
+    pub refresh_token: String,
+}
+
+impl Session {
+    /// True once the access token is expired or about to be, so a fresh
+    /// connection should refresh it first.
+    pub fn access_expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        now + 10_000 >= self.access_expires_at
+    }
+}
+
+/// A password typed on the login screen, and whether it opens a new account.
+pub struct Credentials {
+    pub password: String,
+    pub create_account: bool,
+}
+
+impl Credentials {
+    pub fn sign_in(password: String) -> Self {
+        Self {
+            password,
+            create_account: false,
+        }
+    }
+
+    pub fn create_account(password: String) -> Self {
+        Self {
+            password,
+            create_account: true,
+        }
+    }
+}
+
+/// Why a login or refresh did not produce a session.
+#[derive(Debug)]
+pub enum AuthFailure {
+    /// The server could not be reached or failed; worth retrying.
+    Unreachable(String),
+    /// The server said no: wrong password, revoked session, ...
+    Rejected(String),
+}
+
+impl std::fmt::Display for AuthFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unreachable(reason) | Self::Rejected(reason) => f.write_str(reason),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+fn client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+}
+
+async fn session_from(resp: reqwest::Response) -> Result<Session, AuthFailure> {
+    let status = resp.status();
+    if status.is_success() {
+        return resp
+            .json()
+            .await
+            .map_err(|e| AuthFailure::Unreachable(e.to_string()));
+    }
+    let reason = match resp.json::<ErrorBody>().await {
+        Ok(body) => body.error,
+        Err(_) => format!("server returned {status}"),
+    };
+    if status.is_server_error() {
+        Err(AuthFailure::Unreachable(reason))
+    } else {
+        Err(AuthFailure::Rejected(reason))
+    }
+}
+
+fn unreachable(e: reqwest::Error) -> AuthFailure {
+    AuthFailure::Unreachable(format!("Cannot connect: {e}"))
+}
+
+/// Signs in, or creates the account first with `credentials.create_account`.
+pub async fn login(
+    server_url: &str,
+    username: &str,
+    credentials: &Credentials,
+) -> Result<Session, AuthFailure> {
+    let path = if credentials.create_account {
+        "register"
+    } else {
+        "login"
+    };
+    let password = &credentials.password;
+    let resp = client()
+        .map_err(unreachable)?
+        .post(format!("{server_url}/auth/{path}"))
+        .json(&serde_json::json!({"username": username, "password": password}))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    session_from(resp).await
+}
+
+pub async fn refresh(server_url: &str, refresh_token: &str) -> Result<Session, AuthFailure> {
+    let resp = client()
+        .map_err(unreachable)?
+        .post(format!("{server_url}/auth/refresh"))
+        .json(&serde_json::json!({"refresh_token": refresh_token}))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    session_from(resp).await
+}
+
+/// Ends the session on the server. Failures are ignored: the tokens are
+/// dropped locally either way and expire on their own.
+pub async fn logout(server_url: &str, access_token: &str) {
+    if let Ok(client) = client() {
+        let _ = client
+            .post(format!("{server_url}/auth/logout"))
+            .bearer_auth(access_token)
+            .send()
+            .await;
+    }
+}
@@ -1,3 +1,4 @@
+use super::auth::Session;
 use crate::state::lobby::TableSummary;
 use crossterm::event::KeyCode;
 
@@ -10,8 +11,36 @@ pub enum AppEvent {
     LobbyRefreshed(Vec<TableSummary>),
     LobbyPollDone,
     WsMessage(String),
-    WsConnected { player_id: String, generation: u64 },
-    WsDisconnected { generation: u64 },
-    AuthFailed { reason: String, generation: u64 },
+    /// Login or refresh produced new tokens for the connection `generation`.
+    SessionIssued {
+        session: Session,
+        generation: u64,
+    },
+    /// The stored session was turned down; the user must log in again.
+    SessionEnded {
+        reason: String,
+        generation: u64,
+    },
+    WsConnected {
+        player_id: String,
+        role: String,
+        generation: u64,
+    },
+    WsDisconnected {
+        generation: u64,
+    },
+    /// A reconnect attempt could not reach the server.
+    ConnectFailed {
+        reason: String,
+        generation: u64,
+    },
+    /// Time for the next reconnect attempt.
+    Reconnect {
+        generation: u64,
+    },
+    AuthFailed {
+        reason: String,
+        generation: u64,
+    },
     ServerError(String),
 }
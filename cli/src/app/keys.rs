@@ -1,8 +1,9 @@
 use crossterm::event::KeyCode;
 use tokio::sync::mpsc;
 
-use crate::state::{GamePhase, LoginField, LoginStatus, Screen};
+use crate::state::{GamePhase, LoginField, LoginMode, LoginStatus, Screen};
 
+use super::auth::Credentials;
 use super::event::AppEvent;
 use super::state::App;
 
@@ -31,28 +32,59 @@ fn handle_login_key(app: &mut App, key: KeyCode, tx: &mpsc::Sender<AppEvent>) {
 
     match key {
         KeyCode::Tab | KeyCode::BackTab => {
-            login.active_field = match login.active_field {
-                LoginField::Username => LoginField::Password,
-                LoginField::Password => LoginField::Username,
+            let fields = login.fields();
+            let i = fields
+                .iter()
+                .position(|f| *f == login.active_field)
+                .unwrap_or(0);
+            let next = if key == KeyCode::Tab {
+                i + 1
+            } else {
+                i + fields.len() - 1
             };
+            login.active_field = fields[next % fields.len()];
         }
-        KeyCode::Char(c) => match login.active_field {
-            LoginField::Username => login.username.push(c),
-            LoginField::Password => login.password.push(c),
-        },
-        KeyCode::Backspace => match login.active_field {
-            LoginField::Username => {
-                login.username.pop();
+        KeyCode::F(2) => {
+            login.mode = match login.mode {
+                LoginMode::SignIn => LoginMode::CreateAccount,
+                LoginMode::CreateAccount => LoginMode::SignIn,
+            };
+            login.confirm.clear();
+            login.status = LoginStatus::Idle;
+            if !login.fields().contains(&login.active_field) {
+                login.active_field = LoginField::Password;
             }
-            LoginField::Password => {
-                login.password.pop();
+        }
+        KeyCode::Char(c) => login.active_value().push(c),
+        KeyCode::Backspace => {
+            login.active_value().pop();
+        }
+        KeyCode::Enter if login.mode == LoginMode::CreateAccount => {
+            if login.username.is_empty() || login.password.is_empty() {
+                return;
             }
-        },
+            if login.password != login.confirm {
+                login.status = LoginStatus::Error("Passwords do not match".into());
+                login.confirm.clear();
+                login.active_field = LoginField::Confirm;
+                return;
+            }
+            app.username = login.username.clone();
+            let password = std::mem::take(&mut login.password);
+            login.confirm.clear();
+            login.status = LoginStatus::Connecting;
+            crate::app::spawn_ws(app, tx, Some(Credentials::create_account(password)));
+        }
         KeyCode::Enter if !login.username.is_empty() && !login.password.is_empty() => {
             app.username = login.username.clone();
-            app.password = login.password.clone();
+            let password = std::mem::take(&mut login.password);
             login.status = LoginStatus::Connecting;
-            crate::app::spawn_ws(app, tx);
+            crate::app::spawn_ws(app, tx, Some(Credentials::sign_in(password)));
+        }
+        // Same user with an empty password: reconnect on the stored session.
+        KeyCode::Enter if app.session.is_some() && login.username == app.username => {
+            login.status = LoginStatus::Connecting;
+            crate::app::spawn_ws(app, tx, None);
         }
         KeyCode::Esc => {
             app.should_quit = true;
@@ -88,6 +120,22 @@ fn handle_lobby_key(app: &mut App, key: KeyCode, _tx: &mpsc::Sender<AppEvent>) {
                 }
             }
         }
+        KeyCode::Char('o') => {
+            if let Some(session) = app.session.take() {
+                let server_url = app.server_url.clone();
+                tokio::spawn(async move {
+                    crate::app::auth::logout(&server_url, &session.access_token).await;
+                });
+            }
+            // Drop the socket and ignore anything the old task still sends.
+            app.ws_generation += 1;
+            app.reconnect_attempt = 0;
+            app.ws_tx = None;
+            if let Some(task) = app.ws_task.take() {
+                task.abort();
+            }
+            app.ui = crate::state::UiState::login();
+        }
         _ => {}
     }
 }
@@ -129,6 +177,25 @@ fn handle_table_key(app: &mut App, key: KeyCode, tx: &mpsc::Sender<AppEvent>) {
         return;
     }
 
+    // Observer with the dealer role: drive the round
+    if is_observer && app.can_deal() {
+        let kind = match key {
+            KeyCode::Char('b') => Some("DealerOpenBetting"),
+            KeyCode::Char('d') => Some("DealerDealCards"),
+            KeyCode::Char('p') => Some("DealerPlayHand"),
+            KeyCode::Char('e') => Some("DealerSettle"),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            let rid = app.next_request_id();
+            if let (Some(ref ws_tx), Some(ref tid)) = (&app.ws_tx, &app.current_table_id) {
+                let msg = serde_json::json!({"type": kind, "table_id": tid, "request_id": rid});
+                let _ = ws_tx.try_send(msg.to_string());
+            }
+            return;
+        }
+    }
+
     // Observer: request a seat
     if is_observer {
         if let KeyCode::Char('t') = key {
@@ -1,3 +1,4 @@
+pub mod auth;
 pub mod event;
 pub mod keys;
 pub mod state;
@@ -9,7 +10,9 @@ use crossterm::event::{self as ct_event, Event};
 use ratatui::DefaultTerminal;
 use tokio::sync::mpsc;
 
+use crate::state::link::Link;
 use crate::ui::render;
+use auth::{AuthFailure, Credentials};
 use event::AppEvent;
 use keys::handle_key;
 use state::App;
@@ -58,7 +61,7 @@ pub async fn run(terminal: &mut DefaultTerminal) -> Result<()> {
     let mut tick_count: u64 = 0;
 
     loop {
-        terminal.draw(|f| render(f, &app.ui))?;
+        terminal.draw(|f| render(f, &app.ui, app.maintenance.as_ref(), app.link.as_ref()))?;
 
         if let Some(event) = rx.recv().await {
             match event {
@@ -116,12 +119,33 @@ pub async fn run(terminal: &mut DefaultTerminal) -> Result<()> {
                 AppEvent::LobbyPollDone => {
                     app.lobby_poll_in_flight = false;
                 }
+                AppEvent::SessionIssued {
+                    session,
+                    generation,
+                } => {
+                    if generation == app.ws_generation {
+                        app.session = Some(session);
+                    }
+                }
+                AppEvent::SessionEnded { reason, generation } => {
+                    if generation == app.ws_generation {
+                        app.ws_tx = None;
+                        app.session = None;
+                        app.reconnect_attempt = 0;
+                        set_login_error(&mut app, &format!("Session ended: {reason}"));
+                    }
+                }
                 AppEvent::WsConnected {
                     player_id,
+                    role,
                     generation,
                 } => {
                     if generation == app.ws_generation {
                         app.player_id = player_id;
+                        app.role = role;
+                        app.reconnect_attempt = 0;
+                        app.maintenance = None;
+                        // A held seat is restored by the Snapshot that follows.
                         app.ui = crate::state::UiState::lobby();
                     }
                 }
@@ -132,7 +156,24 @@ pub async fn run(terminal: &mut DefaultTerminal) -> Result<()> {
                     if generation == app.ws_generation {
                         app.ws_tx = None;
                         app.current_table_id = None;
-                        set_login_error(&mut app, "Disconnected from server");
+                        app.link = None;
+                        if app.session.is_some() {
+                            schedule_reconnect(&mut app, &tx);
+                        } else {
+                            set_login_error(&mut app, "Disconnected from server");
+                        }
+                    }
+                }
+                AppEvent::ConnectFailed { reason, generation } => {
+                    if generation == app.ws_generation {
+                        tracing::warn!("reconnect failed: {reason}");
+                        app.ws_tx = None;
+                        schedule_reconnect(&mut app, &tx);
+                    }
+                }
+                AppEvent::Reconnect { generation } => {
+                    if generation == app.ws_generation {
+                        spawn_ws(&mut app, &tx, None);
                     }
                 }
                 AppEvent::AuthFailed { reason, generation } => {
@@ -162,9 +203,51 @@ pub async fn run(terminal: &mut DefaultTerminal) -> Result<()> {
     Ok(())
 }
 
+/// Delay before each reconnect attempt; the last one gives up.
+const RECONNECT_BACKOFF: &[Duration] = &[
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(4),
+    Duration::from_secs(8),
+    Duration::from_secs(15),
+    Duration::from_secs(15),
+    Duration::from_secs(15),
+];
+
+/// Retries the connection after the next backoff delay, keeping the current
+/// screen so a held seat can be picked up again. Gives up at the login
+/// screen once the server has had long enough to release the seat anyway.
+fn schedule_reconnect(app: &mut App, tx: &mpsc::Sender<AppEvent>) {
+    let Some(&delay) = RECONNECT_BACKOFF.get(app.reconnect_attempt as usize) else {
+        app.reconnect_attempt = 0;
+        set_login_error(app, "Disconnected from server");
+        return;
+    };
+    app.reconnect_attempt += 1;
+    app.ui.header.subtitle = format!(
+        "Reconnecting in {:.1}s (attempt {}/{})",
+        delay.as_secs_f32(),
+        app.reconnect_attempt,
+        RECONNECT_BACKOFF.len()
+    );
+    let generation = app.ws_generation;
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        let _ = tx.send(AppEvent::Reconnect { generation }).await;
+    });
+}
+
 fn set_login_error(app: &mut App, msg: &str) {
-    use crate::state::{LoginField, LoginState, LoginStatus, Screen};
+    use crate::state::{LoginField, LoginMode, LoginState, LoginStatus, Screen};
+    // A failed signup stays on the signup form.
+    let mode = match &app.ui.screen {
+        Screen::Login(login) => login.mode,
+        _ => LoginMode::SignIn,
+    };
     let login = LoginState {
+        mode,
         username: app.username.clone(),
         active_field: LoginField::Password,
         status: LoginStatus::Error(msg.to_string()),
@@ -173,17 +256,24 @@ fn set_login_error(app: &mut App, msg: &str) {
     app.ui.screen = Screen::Login(login);
 }
 
-pub fn spawn_ws(app: &mut App, tx: &mpsc::Sender<AppEvent>) {
+/// Connects the game socket. With `credentials` this logs in (or signs up)
+/// first; without them it reuses the stored session, refreshing its access
+/// token if needed.
+pub fn spawn_ws(app: &mut App, tx: &mpsc::Sender<AppEvent>, credentials: Option<Credentials>) {
     // Increment generation so stale events from the aborted task are ignored.
     app.ws_generation += 1;
+    if credentials.is_some() {
+        app.reconnect_attempt = 0;
+    }
     let generation = app.ws_generation;
 
     if let Some(h) = app.ws_task.take() {
         h.abort();
     }
     let ws_url = format!("{}/ws", app.server_url.replace("http", "ws"));
+    let server_url = app.server_url.clone();
     let username = app.username.clone();
-    let password = app.password.clone();
+    let stored = app.session.clone();
     let tx_app = tx.clone();
     let (ws_cmd_tx, mut ws_cmd_rx) = mpsc::channel::<String>(32);
     app.ws_tx = Some(ws_cmd_tx);
@@ -193,50 +283,87 @@ pub fn spawn_ws(app: &mut App, tx: &mpsc::Sender<AppEvent>) {
         use tokio_tungstenite::connect_async;
         use tokio_tungstenite::tungstenite::Message;
 
+        // A reconnect retries when the server is out of reach and starts over
+        // at the login screen when it is turned away; an interactive login
+        // reports both.
+        let reconnecting = credentials.is_none();
+        let unreachable = |reason: String| {
+            if reconnecting {
+                AppEvent::ConnectFailed { reason, generation }
+            } else {
+                AppEvent::AuthFailed { reason, generation }
+            }
+        };
+        let rejected = |reason: String| {
+            if reconnecting {
+                AppEvent::SessionEnded { reason, generation }
+            } else {
+                AppEvent::AuthFailed { reason, generation }
+            }
+        };
+
+        let session = match (credentials, stored) {
+            (Some(credentials), _) => auth::login(&server_url, &username, &credentials).await,
+            (None, Some(session)) if session.access_expired() => {
+                auth::refresh(&server_url, &session.refresh_token).await
+            }
+            (None, Some(session)) => Ok(session),
+            (None, None) => Err(AuthFailure::Rejected("Not logged in".into())),
+        };
+        let session = match session {
+            Ok(session) => session,
+            Err(AuthFailure::Unreachable(reason)) => {
+                let _ = tx_app.send(unreachable(reason)).await;
+                return;
+            }
+            Err(AuthFailure::Rejected(reason)) => {
+                let _ = tx_app.send(rejected(reason)).await;
+                return;
+            }
+        };
+        let token = session.access_token.clone();
+        let _ = tx_app
+            .send(AppEvent::SessionIssued {
+                session,
+                generation,
+            })
+            .await;
+
         let (mut ws, _) = match connect_async(&ws_url).await {
             Ok(v) => v,
             Err(e) => {
                 let _ = tx_app
-                    .send(AppEvent::AuthFailed {
-                        reason: format!("Cannot connect: {e}"),
-                        generation,
-                    })
+                    .send(unreachable(format!("Cannot connect: {e}")))
                     .await;
                 return;
             }
         };
 
-        let auth = serde_json::json!({"type": "Auth", "username": username, "password": password});
+        let auth = serde_json::json!({"type": "Auth", "token": token});
         if ws
             .send(Message::Text(auth.to_string().into()))
             .await
             .is_err()
         {
-            let _ = tx_app
-                .send(AppEvent::AuthFailed {
-                    reason: "Connection lost".into(),
-                    generation,
-                })
-                .await;
+            let _ = tx_app.send(unreachable("Connection lost".into())).await;
             return;
         }
 
         // Wait for AuthOk/AuthError
-        let confirmed_player_id = loop {
+        let (confirmed_player_id, role) = loop {
             match ws.next().await {
                 Some(Ok(Message::Text(t))) => {
                     if let Ok(v) = serde_json::from_str::<serde_json::Value>(&t) {
                         match v["type"].as_str() {
                             Some("AuthOk") => {
                                 let pid = v["player_id"].as_str().unwrap_or("").to_string();
-                                break pid;
+                                let role = v["role"].as_str().unwrap_or("player").to_string();
+                                break (pid, role);
                             }
                             Some("AuthError") => {
                                 let reason =
                                     v["reason"].as_str().unwrap_or("auth failed").to_string();
-                                let _ = tx_app
-                                    .send(AppEvent::AuthFailed { reason, generation })
-                                    .await;
+                                let _ = tx_app.send(rejected(reason)).await;
                                 return;
                             }
                             _ => {}
@@ -244,12 +371,7 @@ pub fn spawn_ws(app: &mut App, tx: &mpsc::Sender<AppEvent>) {
                     }
                 }
                 _ => {
-                    let _ = tx_app
-                        .send(AppEvent::AuthFailed {
-                            reason: "Connection lost".into(),
-                            generation,
-                        })
-                        .await;
+                    let _ = tx_app.send(unreachable("Connection lost".into())).await;
                     return;
                 }
             }
@@ -258,6 +380,7 @@ pub fn spawn_ws(app: &mut App, tx: &mpsc::Sender<AppEvent>) {
         let _ = tx_app
             .send(AppEvent::WsConnected {
                 player_id: confirmed_player_id,
+                role,
                 generation,
             })
             .await;
@@ -284,6 +407,9 @@ pub fn spawn_ws(app: &mut App, tx: &mpsc::Sender<AppEvent>) {
                         Some(Ok(Message::Text(t))) => {
                             let _ = tx_app.send(AppEvent::WsMessage(t.to_string())).await;
                         }
+                        // The pong is queued by the read; send it now rather
+                        // than with the next command.
+                        Some(Ok(Message::Ping(_))) if ws.flush().await.is_err() => break,
                         None | Some(Err(_)) | Some(Ok(Message::Close(_))) => break,
                         _ => {}
                     }
@@ -329,6 +455,29 @@ fn handle_ws_message(app: &mut App, json: String) {
                 }
             }
         }
+        "MissedEvents" => {
+            use bj_core::domain::engine::snapshot::GameEventDto;
+            let Ok(events) = serde_json::from_value::<Vec<GameEventDto>>(v["events"].clone())
+            else {
+                return;
+            };
+            // The snapshot before this already shows their effect.
+            if let crate::state::Screen::Table(ref mut table) = app.ui.screen {
+                use bj_core::domain::engine::event::payload::EventPayload;
+                let rounds = events
+                    .iter()
+                    .filter(|e| matches!(e.payload, EventPayload::GameFinished { .. }))
+                    .count();
+                table.log(format!(
+                    "reconnected · missed {} events, {} rounds finished",
+                    events.len(),
+                    rounds
+                ));
+                if let Some(last) = events.last() {
+                    table.event_seq = last.seq;
+                }
+            }
+        }
         "Balance" => {
             if let Some(amount) = v["amount"].as_u64() {
                 app.ui.header.my_balance = Some(amount as u32);
@@ -337,6 +486,35 @@ fn handle_ws_message(app: &mut App, json: String) {
         "CommandError" => {
             tracing::warn!("command error: {json}");
         }
+        "Latency" => {
+            let (Some(rtt_ms), Some(ping_interval_ms)) =
+                (v["rtt_ms"].as_u64(), v["ping_interval_ms"].as_u64())
+            else {
+                return;
+            };
+            app.link = Some(Link::new(rtt_ms, ping_interval_ms));
+        }
+        "RateLimited" => {
+            tracing::warn!("rate limited: {json}");
+            let wait = v["retry_after_ms"].as_u64().unwrap_or(0);
+            if let crate::state::Screen::Table(ref mut table) = app.ui.screen {
+                table.log(format!("too many actions · try again in {wait}ms"));
+            }
+        }
+        "ServerShuttingDown" => {
+            use crate::state::table::{Countdown, CountdownKind};
+            use bj_core::domain::engine::Timestamp;
+            let Some(deadline) = v["deadline"].as_u64() else {
+                return;
+            };
+            app.maintenance = Some(Countdown::new(
+                CountdownKind::Shutdown,
+                Timestamp::from_millis(deadline),
+            ));
+            if let crate::state::Screen::Table(ref mut table) = app.ui.screen {
+                table.log("server shutting down for maintenance; no new bets");
+            }
+        }
         _ => {}
     }
 }
@@ -348,7 +526,7 @@ fn table_state_from_snapshot(
 ) -> crate::state::table::TableState {
     use crate::state::{
         cards::{UiCard, UiHand},
-        table::{PlayerUiState, TableState},
+        table::{Countdown, CountdownKind, PlayerUiState, TableState},
     };
     use bj_core::domain::engine::phase::Phase;
 
@@ -372,6 +550,8 @@ fn table_state_from_snapshot(
             };
             let status = if p.is_bust {
                 "BUST".into()
+            } else if p.bet.is_some() && snap.phase == Phase::Finished {
+                "settled".into()
             } else if p.bet.is_some() {
                 "bet placed".into()
             } else {
@@ -457,7 +637,7 @@ fn table_state_from_snapshot(
     let mut state = TableState {
         game_id: snap.game_id.to_string(),
         phase,
-        event_seq: 0,
+        event_seq: snap.last_seq,
         dealer: UiHand {
             cards: dealer_cards,
             value: dealer_value,
@@ -469,6 +649,15 @@ fn table_state_from_snapshot(
         event_log: vec!["— snapshot —".into()],
         is_my_turn,
         round_result: None,
+        countdown: snap.deadline.and_then(|at| {
+            let kind = match snap.phase {
+                Phase::WaitingForBets => CountdownKind::Betting,
+                Phase::PlayerTurn(_) => CountdownKind::Turn,
+                Phase::Finished => CountdownKind::NextRound,
+                _ => return None,
+            };
+            Some(Countdown::new(kind, at))
+        }),
     };
 
     // Seed log with current table state so history isn't blank on join
@@ -500,7 +689,7 @@ fn apply_event_payload(
 ) {
     use crate::state::{
         cards::{UiCard, UiHand},
-        table::{GamePhase, PlayerUiState},
+        table::{Countdown, CountdownKind, GamePhase, PlayerUiState},
     };
     use bj_core::domain::engine::event::payload::EventPayload;
     use bj_core::domain::engine::phase::Phase;
@@ -508,8 +697,12 @@ fn apply_event_payload(
     // Extract phase change before borrowing screen
     let phase_change: Option<Phase> = match &payload {
         EventPayload::PhaseChanged { to, .. } => Some(to.clone()),
+        // A void refunds confirmed bets, so betting must be re-armed even when
+        // no phase change follows.
+        EventPayload::RoundVoided { .. } => Some(Phase::WaitingForBets),
         _ => None,
     };
+    let table_closed = matches!(payload, EventPayload::TableClosed);
     let my_player_id = app.player_id.clone();
     let my_username = app.username.clone();
 
@@ -626,6 +819,11 @@ fn apply_event_payload(
                 let pid = player.to_string();
                 table.log(format!("#{seq} {} left", short_id(&pid)));
                 table.players.retain(|p| p.player_id != pid);
+                if matches!(table.phase, GamePhase::WaitingForBets | GamePhase::Betting)
+                    && table.players.iter().all(|p| p.bet.is_none())
+                {
+                    table.countdown = None;
+                }
             }
             EventPayload::PlayerPlacedBet { player, amount } => {
                 let pid = player.to_string();
@@ -649,6 +847,54 @@ fn apply_event_payload(
                 table.phase = GamePhase::Dealing;
                 table.log(format!("#{seq} — game started"));
             }
+            EventPayload::BettingWindowOpened { closes_at } => {
+                table.countdown = Some(Countdown::new(CountdownKind::Betting, closes_at));
+                table.log(format!("#{seq} betting window open"));
+            }
+            EventPayload::TurnStarted { expires_at, .. } => {
+                table.countdown = Some(Countdown::new(CountdownKind::Turn, expires_at));
+            }
+            EventPayload::NextRoundScheduled { starts_at } => {
+                table.countdown = Some(Countdown::new(CountdownKind::NextRound, starts_at));
+            }
+            EventPayload::RoundVoided { reason } => {
+                use crate::state::table::{RoundOutcome, RoundResult};
+                let mut my_bet = 0;
+                for p in &mut table.players {
+                    if let Some(bet) = p.bet.take() {
+                        p.balance += bet;
+                        if p.player_id == my_player_id {
+                            my_bet = bet;
+                        }
+                    }
+                    p.hand.cards.clear();
+                    p.hand.value = None;
+                    p.hand_value = 0;
+                    p.is_bust = false;
+                    p.status = "refunded".into();
+                }
+                table.dealer.cards.clear();
+                table.dealer.value = None;
+                table.countdown = None;
+                table.round_result = Some(RoundResult {
+                    outcome: RoundOutcome::Voided {
+                        reason: reason.clone(),
+                    },
+                    bet: my_bet,
+                    payout: my_bet,
+                });
+                table.log(format!("#{seq} — round voided: {reason}"));
+            }
+            EventPayload::TableClosing => {
+                table.log(format!("#{seq} — table closes after this round"));
+            }
+            EventPayload::TableClosed => {
+                table.log(format!("#{seq} — table closed"));
+            }
+            EventPayload::RoundReset { game_id, .. } => {
+                table.game_id = game_id.to_string();
+                table.log(format!("#{seq} — new round"));
+            }
             EventPayload::PlayerCardDealt { player, card } => {
                 let pid = player.to_string();
                 let mut hand_value = 0u8;
@@ -746,6 +992,7 @@ fn apply_event_payload(
             EventPayload::PhaseChanged { to, .. } => {
                 let new_phase = server_phase_to_game_phase(&to);
                 table.phase = new_phase;
+                table.countdown = None;
                 table.log(format!("#{seq} phase → {:?}", to));
 
                 let active_pid = if let Phase::PlayerTurn(pid) = &to {
@@ -781,6 +1028,16 @@ fn apply_event_payload(
         }
     }
 
+    if table_closed {
+        app.event_queue.clear();
+        app.current_table_id = None;
+        app.ui = crate::state::UiState::lobby();
+        if let crate::state::Screen::Lobby(ref mut lobby) = app.ui.screen {
+            lobby.notice = Some("The table you were at has closed.".into());
+        }
+        return;
+    }
+
     // After borrow on screen is dropped, sync UI chrome based on phase change
     if let Some(new_phase) = phase_change {
         sync_ui_chrome(app, server_phase_to_game_phase(&new_phase));
@@ -800,6 +1057,47 @@ fn sync_ui_chrome(app: &mut App, phase: crate::state::table::GamePhase) {
         false
     };
 
+    if is_observer && app.can_deal() {
+        app.ui.betting = None;
+        let mut hints = Vec::new();
+        // Admins may play as well as deal.
+        if app.role == "admin" {
+            hints.push(FooterHint {
+                key: "t",
+                label: "take seat",
+            });
+        }
+        hints.extend([
+            FooterHint {
+                key: "b",
+                label: "open betting",
+            },
+            FooterHint {
+                key: "d",
+                label: "deal",
+            },
+            FooterHint {
+                key: "p",
+                label: "play hand",
+            },
+            FooterHint {
+                key: "e",
+                label: "settle",
+            },
+            FooterHint {
+                key: "l",
+                label: "leave",
+            },
+            FooterHint {
+                key: "q",
+                label: "quit",
+            },
+        ]);
+        app.ui.footer = FooterState { hints };
+        app.ui.header.subtitle = format!("Table – {} (dealing)", phase);
+        return;
+    }
+
     if is_observer {
         app.ui.betting = None;
         app.ui.footer = FooterState {
@@ -1,6 +1,7 @@
 use std::collections::VecDeque;
 
-use crate::state::UiState;
+use super::auth::Session;
+use crate::state::{link::Link, table::Countdown, UiState};
 use bj_core::domain::engine::event::payload::EventPayload;
 use tokio::{sync::mpsc, task::JoinHandle};
 use ulid::Ulid;
@@ -11,7 +12,11 @@ pub struct App {
     pub server_url: String,
     pub player_id: String,
     pub username: String,
-    pub password: String,
+    /// The account's role from `AuthOk`: `player`, `dealer` or `admin`.
+    pub role: String,
+    /// Tokens of the logged-in session, kept so the client can reconnect
+    /// without asking for the password again.
+    pub session: Option<Session>,
     pub ws_tx: Option<mpsc::Sender<String>>,
     /// Handle to the active WS background task; awaited on clean shutdown.
     pub ws_task: Option<JoinHandle<()>>,
@@ -26,6 +31,13 @@ pub struct App {
     /// Incremented each time a new WS task is spawned. Stale WsDisconnected/
     /// AuthFailed events carry the old generation and are ignored.
     pub ws_generation: u64,
+    /// Reconnect attempts made since the connection dropped; 0 when connected.
+    pub reconnect_attempt: u32,
+    /// Set by `ServerShuttingDown`; shown as a banner on every screen until
+    /// the client is connected again.
+    pub maintenance: Option<Countdown>,
+    /// The last heartbeat from the server; shown as the connection quality.
+    pub link: Option<Link>,
 }
 
 impl App {
@@ -37,7 +49,8 @@ impl App {
                 .unwrap_or_else(|_| "http://127.0.0.1:3000".into()),
             player_id: Ulid::new().to_string(),
             username: String::new(),
-            password: String::new(),
+            role: "player".into(),
+            session: None,
             ws_tx: None,
             ws_task: None,
             current_table_id: None,
@@ -48,11 +61,19 @@ impl App {
             lobby_poll_in_flight: false,
             next_request_id: 1,
             ws_generation: 0,
+            reconnect_attempt: 0,
+            maintenance: None,
+            link: None,
         }
     }
 }
 
 impl App {
+    /// Dealers and admins drive the rounds at manual-dealer tables.
+    pub fn can_deal(&self) -> bool {
+        matches!(self.role.as_str(), "dealer" | "admin")
+    }
+
     pub fn next_request_id(&mut self) -> u64 {
         let id = self.next_request_id;
         self.next_request_id += 1;
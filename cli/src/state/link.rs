@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+
+/// The connection as the server's last heartbeat left it.
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub rtt_ms: u64,
+    pub ping_interval_ms: u64,
+    pub heard_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    Good,
+    Fair,
+    Poor,
+    /// A heartbeat is overdue; the connection may be gone.
+    Stale,
+}
+
+impl Link {
+    pub fn new(rtt_ms: u64, ping_interval_ms: u64) -> Self {
+        Self {
+            rtt_ms,
+            ping_interval_ms,
+            heard_at: Instant::now(),
+        }
+    }
+
+    pub fn quality(&self) -> Quality {
+        // Two heartbeats missed, with a little slack for the round trip.
+        let overdue = Duration::from_millis(self.ping_interval_ms * 2 + 1_000);
+        if self.heard_at.elapsed() > overdue {
+            Quality::Stale
+        } else if self.rtt_ms < 100 {
+            Quality::Good
+        } else if self.rtt_ms < 300 {
+            Quality::Fair
+        } else {
+            Quality::Poor
+        }
+    }
+}
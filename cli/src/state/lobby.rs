@@ -7,6 +7,11 @@ pub struct TableSummary {
     pub player_count: usize,
     pub phase: String,
     pub is_joinable: bool,
+    #[serde(default)]
+    pub status: String,
+    /// The server rebuilt the table after an internal error.
+    #[serde(default)]
+    pub degraded: bool,
     pub settings: TableSummarySettings,
 }
 
@@ -22,6 +27,8 @@ pub struct LobbyState {
     pub status: LobbyStatus,
     pub tables: Vec<TableSummary>,
     pub selected: usize,
+    /// One-off message shown under the table list, e.g. why we were sent here.
+    pub notice: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -1,15 +1,25 @@
 #[derive(Debug, Clone)]
 pub struct LoginState {
+    pub mode: LoginMode,
     pub username: String,
     pub password: String,
+    /// Password typed again; only used when creating an account.
+    pub confirm: String,
     pub active_field: LoginField,
     pub status: LoginStatus,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginMode {
+    SignIn,
+    CreateAccount,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LoginField {
     Username,
     Password,
+    Confirm,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -19,11 +29,35 @@ pub enum LoginStatus {
     Error(String),
 }
 
+impl LoginState {
+    /// Fields shown in the current mode, in tab order.
+    pub fn fields(&self) -> &'static [LoginField] {
+        match self.mode {
+            LoginMode::SignIn => &[LoginField::Username, LoginField::Password],
+            LoginMode::CreateAccount => &[
+                LoginField::Username,
+                LoginField::Password,
+                LoginField::Confirm,
+            ],
+        }
+    }
+
+    pub fn active_value(&mut self) -> &mut String {
+        match self.active_field {
+            LoginField::Username => &mut self.username,
+            LoginField::Password => &mut self.password,
+            LoginField::Confirm => &mut self.confirm,
+        }
+    }
+}
+
 impl Default for LoginState {
     fn default() -> Self {
         Self {
+            mode: LoginMode::SignIn,
             username: String::new(),
             password: String::new(),
+            confirm: String::new(),
             active_field: LoginField::Username,
             status: LoginStatus::Idle,
         }
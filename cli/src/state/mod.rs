@@ -1,11 +1,12 @@
 pub mod betting;
 pub mod cards;
+pub mod link;
 pub mod lobby;
 pub mod login;
 pub mod table;
 pub mod ui_state;
 
 pub use betting::*;
-pub use login::{LoginField, LoginState, LoginStatus};
+pub use login::{LoginField, LoginMode, LoginState, LoginStatus};
 pub use table::*;
 pub use ui_state::{Screen, UiState};
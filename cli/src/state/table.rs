@@ -1,4 +1,6 @@
-use std::fmt;
+use std::{fmt, time::Duration};
+
+use bj_core::domain::engine::{Clock, SystemClock, Timestamp};
 
 use super::cards::UiHand;
 
@@ -9,6 +11,10 @@ pub enum RoundOutcome {
     Push,
     Lost,
     Bust,
+    /// The round was cancelled by the house and all bets were refunded.
+    Voided {
+        reason: String,
+    },
 }
 
 impl fmt::Display for RoundOutcome {
@@ -19,6 +25,7 @@ impl fmt::Display for RoundOutcome {
             RoundOutcome::Push => "PUSH",
             RoundOutcome::Lost => "YOU LOSE",
             RoundOutcome::Bust => "BUST",
+            RoundOutcome::Voided { .. } => "ROUND VOIDED",
         };
         write!(f, "{}", s)
     }
@@ -46,6 +53,8 @@ pub struct TableState {
     pub is_my_turn: bool,
     /// Populated after GameFinished for the local player; shown as overlay popup.
     pub round_result: Option<RoundResult>,
+    /// The server deadline for the current phase, if one is running.
+    pub countdown: Option<Countdown>,
 }
 
 impl TableState {
@@ -65,6 +74,7 @@ impl TableState {
             event_log: vec![],
             is_my_turn: false,
             round_result: None,
+            countdown: None,
         }
     }
 
@@ -114,3 +124,57 @@ pub struct PlayerUiState {
     pub bet: Option<u32>,
     pub status: String,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountdownKind {
+    Betting,
+    Turn,
+    NextRound,
+    /// The server is shutting down for maintenance.
+    Shutdown,
+}
+
+impl fmt::Display for CountdownKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            CountdownKind::Betting => "Dealing in",
+            CountdownKind::Turn => "Turn ends in",
+            CountdownKind::NextRound => "Next round in",
+            CountdownKind::Shutdown => "Server stops in",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A server deadline counted down against the local clock.
+///
+/// `total` is the time left when the deadline arrived, so the bar starts full
+/// even if the event was delayed by the card animation queue.
+#[derive(Debug, Clone, Copy)]
+pub struct Countdown {
+    pub kind: CountdownKind,
+    pub ends_at: Timestamp,
+    pub total: Duration,
+}
+
+impl Countdown {
+    pub fn new(kind: CountdownKind, ends_at: Timestamp) -> Self {
+        Self {
+            kind,
+            ends_at,
+            total: ends_at.saturating_duration_since(SystemClock.now()),
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.ends_at.saturating_duration_since(SystemClock.now())
+    }
+
+    /// Share of the countdown still left, from 1.0 down to 0.0.
+    pub fn fraction_left(&self) -> f64 {
+        if self.total.is_zero() {
+            return 0.0;
+        }
+        (self.remaining().as_secs_f64() / self.total.as_secs_f64()).clamp(0.0, 1.0)
+    }
+}
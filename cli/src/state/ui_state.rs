@@ -39,6 +39,10 @@ impl UiState {
                         key: "enter",
                         label: "login",
                     },
+                    FooterHint {
+                        key: "f2",
+                        label: "sign in / create account",
+                    },
                     FooterHint {
                         key: "esc",
                         label: "quit",
@@ -55,6 +59,7 @@ impl UiState {
                 status: LobbyStatus::Disconnected,
                 selected: 0,
                 tables: vec![],
+                notice: None,
             }),
             header: HeaderState {
                 title: "Blackjack".into(),
@@ -71,6 +76,10 @@ impl UiState {
                         key: "enter",
                         label: "join",
                     },
+                    FooterHint {
+                        key: "o",
+                        label: "log out",
+                    },
                     FooterHint {
                         key: "q",
                         label: "quit",
@@ -0,0 +1,40 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::Span,
+};
+
+use crate::state::table::Countdown;
+use crate::ui::theme::{
+    TOKIO_NIGHT_GREEN, TOKIO_NIGHT_MUTED, TOKIO_NIGHT_ORANGE, TOKIO_NIGHT_RED, TOKIO_NIGHT_SUBTLE,
+};
+
+/// Renders `label 12s ███████░░░` for a running countdown. The bar turns
+/// orange below half and red in the last fifth.
+pub fn countdown_spans(countdown: &Countdown, width: usize) -> Vec<Span<'static>> {
+    let left = countdown.fraction_left();
+    let filled = (left * width as f64).ceil() as usize;
+    let color = if left <= 0.2 {
+        TOKIO_NIGHT_RED
+    } else if left <= 0.5 {
+        TOKIO_NIGHT_ORANGE
+    } else {
+        TOKIO_NIGHT_GREEN
+    };
+    let secs = countdown.remaining().as_secs_f64().ceil() as u64;
+
+    vec![
+        Span::styled(
+            format!("{} ", countdown.kind),
+            Style::default().fg(TOKIO_NIGHT_MUTED),
+        ),
+        Span::styled(
+            format!("{secs:>2}s "),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("█".repeat(filled), Style::default().fg(color)),
+        Span::styled(
+            "░".repeat(width.saturating_sub(filled)),
+            Style::default().fg(TOKIO_NIGHT_SUBTLE),
+        ),
+    ]
+}
@@ -6,7 +6,8 @@ use ratatui::{
     Frame,
 };
 
-use crate::state::UiState;
+use crate::state::{Screen, UiState};
+use crate::ui::countdown::countdown_spans;
 use crate::ui::theme::{TOKIO_NIGHT_CYAN, TOKIO_NIGHT_MUTED, TOKIO_NIGHT_SUBTLE};
 
 pub fn render_footer(frame: &mut Frame, area: Rect, ui: &UiState) {
@@ -34,6 +35,13 @@ pub fn render_footer(frame: &mut Frame, area: Rect, ui: &UiState) {
         ));
     }
 
+    if let Screen::Table(table) = &ui.screen {
+        if let Some(countdown) = &table.countdown {
+            spans.push(Span::styled("   ", Style::default()));
+            spans.extend(countdown_spans(countdown, 20));
+        }
+    }
+
     let footer = Paragraph::new(Line::from(spans));
     frame.render_widget(footer, area);
 }
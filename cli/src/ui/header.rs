@@ -6,14 +6,26 @@ use ratatui::{
     Frame,
 };
 
-use crate::state::UiState;
+use crate::state::{
+    link::{Link, Quality},
+    table::Countdown,
+    UiState,
+};
+use crate::ui::{countdown::countdown_spans, theme::TOKIO_NIGHT_RED};
 
 const COLOR_ORANGE: Color = Color::Rgb(255, 158, 100);
 const COLOR_CYAN: Color = Color::Rgb(125, 207, 255);
 const COLOR_COMMENT: Color = Color::Rgb(86, 95, 137);
 const COLOR_GREEN: Color = Color::Rgb(158, 206, 106);
+const COLOR_YELLOW: Color = Color::Rgb(224, 175, 104);
 
-pub fn render_header(frame: &mut Frame, area: Rect, ui: &UiState) {
+pub fn render_header(
+    frame: &mut Frame,
+    area: Rect,
+    ui: &UiState,
+    maintenance: Option<&Countdown>,
+    link: Option<&Link>,
+) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(COLOR_COMMENT));
@@ -25,7 +37,21 @@ pub fn render_header(frame: &mut Frame, area: Rect, ui: &UiState) {
         .constraints([Constraint::Min(0), Constraint::Length(28)])
         .split(inner);
 
-    let title_line = Line::from(vec![
+    let title_line = match maintenance {
+        Some(countdown) => maintenance_line(countdown),
+        None => title_line(ui),
+    };
+    frame.render_widget(Paragraph::new(title_line), chunks[0]);
+
+    let right_line = build_right_line(ui, link);
+    frame.render_widget(
+        Paragraph::new(right_line).alignment(Alignment::Right),
+        chunks[1],
+    );
+}
+
+fn title_line(ui: &UiState) -> Line<'static> {
+    Line::from(vec![
         Span::styled(
             ui.header.title.clone(),
             Style::default()
@@ -34,19 +60,32 @@ pub fn render_header(frame: &mut Frame, area: Rect, ui: &UiState) {
         ),
         Span::styled(" — ", Style::default().fg(COLOR_COMMENT)),
         Span::styled(ui.header.subtitle.clone(), Style::default().fg(COLOR_CYAN)),
-    ]);
-    frame.render_widget(Paragraph::new(title_line), chunks[0]);
+    ])
+}
 
-    let right_line = build_right_line(ui);
-    frame.render_widget(
-        Paragraph::new(right_line).alignment(Alignment::Right),
-        chunks[1],
-    );
+/// `⚠ Maintenance — Server stops in 12s ██████░░░░`
+fn maintenance_line(countdown: &Countdown) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled(
+            "⚠ Maintenance",
+            Style::default()
+                .fg(TOKIO_NIGHT_RED)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" — ", Style::default().fg(COLOR_COMMENT)),
+    ];
+    spans.extend(countdown_spans(countdown, 10));
+    Line::from(spans)
 }
 
-fn build_right_line(ui: &UiState) -> Line<'static> {
+fn build_right_line(ui: &UiState, link: Option<&Link>) -> Line<'static> {
     let mut spans: Vec<Span<'static>> = Vec::new();
 
+    if let Some(link) = link {
+        spans.extend(link_spans(link));
+        spans.push(Span::raw("  "));
+    }
+
     if let Some(balance) = ui.header.my_balance {
         spans.push(Span::styled(
             format!("💰 {balance}"),
@@ -56,3 +95,17 @@ fn build_right_line(ui: &UiState) -> Line<'static> {
 
     Line::from(spans)
 }
+
+/// `● 42ms`, coloured by connection quality.
+fn link_spans(link: &Link) -> Vec<Span<'static>> {
+    let (color, label) = match link.quality() {
+        Quality::Good => (COLOR_GREEN, format!("{}ms", link.rtt_ms)),
+        Quality::Fair => (COLOR_YELLOW, format!("{}ms", link.rtt_ms)),
+        Quality::Poor => (TOKIO_NIGHT_RED, format!("{}ms", link.rtt_ms)),
+        Quality::Stale => (TOKIO_NIGHT_RED, "no signal".into()),
+    };
+    vec![
+        Span::styled("● ", Style::default().fg(color)),
+        Span::styled(label, Style::default().fg(COLOR_COMMENT)),
+    ]
+}
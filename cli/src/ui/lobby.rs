@@ -1,6 +1,7 @@
 use ratatui::{
     layout::{Constraint, Rect},
     style::{Color, Modifier, Style},
+    text::Line,
     widgets::{Block, Borders, Cell, HighlightSpacing, Row, Table},
     Frame,
 };
@@ -46,7 +47,11 @@ pub fn render_lobby(frame: &mut Frame, area: Rect, lobby: &LobbyState) {
                     "{}/{}",
                     table.player_count, table.settings.max_players
                 )),
-                Cell::from(if table.is_joinable {
+                Cell::from(if table.status == "Closing" {
+                    "Closing"
+                } else if table.degraded {
+                    "Recovering"
+                } else if table.is_joinable {
                     "Open"
                 } else if table.phase == "WaitingForBets" {
                     "Full"
@@ -73,7 +78,17 @@ pub fn render_lobby(frame: &mut Frame, area: Rect, lobby: &LobbyState) {
         Block::default()
             .borders(Borders::ALL)
             .title(" Lobby ")
-            .title_style(Style::default().fg(Color::Cyan).bold()),
+            .title_style(Style::default().fg(Color::Cyan).bold())
+            .title_bottom(
+                Line::from(
+                    lobby
+                        .notice
+                        .as_deref()
+                        .map(|n| format!(" {n} "))
+                        .unwrap_or_default(),
+                )
+                .style(Style::default().fg(Color::Yellow)),
+            ),
     )
     .row_highlight_style(selected_row_style)
     .highlight_symbol("▶ ")
@@ -9,7 +9,7 @@ use ratatui::{
 use unicode_width::UnicodeWidthStr;
 
 use crate::state::login::LoginField;
-use crate::state::{LoginState, LoginStatus};
+use crate::state::{LoginMode, LoginState, LoginStatus};
 use crate::ui::card::{CardWidget, CARD_HEIGHT, CARD_WIDTH};
 use crate::ui::theme::{TOKIO_NIGHT_CYAN, TOKIO_NIGHT_MUTED, TOKIO_NIGHT_SUBTLE};
 
@@ -25,6 +25,8 @@ const BANNER: &[&str] = &[
 const FORM_WIDTH: u16 = 81;
 // top-pad(1) + banner(5) + space(2) + label+input(2) + space(1) + label+input(2) + space(1) + status(1) + border(2) = 17
 const FORM_HEIGHT: u16 = 17;
+// The confirm-password field adds space(1) + label+input(2).
+const CONFIRM_HEIGHT: u16 = 3;
 
 const SCATTER_CARDS: &[(Rank, Suit)] = &[
     (Rank::Ace, Suit::Spades),
@@ -55,14 +57,24 @@ fn banner_color(line_index: usize, total: usize) -> Color {
 pub fn render_login(frame: &mut Frame, area: Rect, login: &LoginState) {
     render_scatter_bg(frame, area);
 
-    let form_area = center(area, FORM_WIDTH, FORM_HEIGHT);
+    let height = match login.mode {
+        LoginMode::SignIn => FORM_HEIGHT,
+        LoginMode::CreateAccount => FORM_HEIGHT + CONFIRM_HEIGHT,
+    };
+    let form_area = center(area, FORM_WIDTH, height);
     render_login_form(frame, form_area, login);
 }
 
 fn render_login_form(frame: &mut Frame, area: Rect, login: &LoginState) {
     frame.render_widget(Clear, area);
 
+    let title = match login.mode {
+        LoginMode::SignIn => " Sign in ",
+        LoginMode::CreateAccount => " Create account ",
+    };
     let form_block = Block::default()
+        .title(title)
+        .title_alignment(Alignment::Center)
         .borders(Borders::ALL)
         .border_style(Style::default().fg(TOKIO_NIGHT_CYAN))
         .padding(Padding::horizontal(2));
@@ -70,6 +82,10 @@ fn render_login_form(frame: &mut Frame, area: Rect, login: &LoginState) {
     let inner = form_block.inner(area);
     frame.render_widget(form_block, area);
 
+    let confirm_height = match login.mode {
+        LoginMode::SignIn => 0,
+        LoginMode::CreateAccount => CONFIRM_HEIGHT,
+    };
     let chunks = Layout::vertical([
         Constraint::Length(1),                   // top padding
         Constraint::Length(BANNER.len() as u16), // banner
@@ -77,6 +93,7 @@ fn render_login_form(frame: &mut Frame, area: Rect, login: &LoginState) {
         Constraint::Length(2),                   // username (label + input)
         Constraint::Length(1),                   // spacing
         Constraint::Length(2),                   // password (label + input)
+        Constraint::Length(confirm_height),      // confirm (spacing + label + input)
         Constraint::Length(1),                   // spacing
         Constraint::Length(1),                   // status
     ])
@@ -122,12 +139,32 @@ fn render_login_form(frame: &mut Frame, area: Rect, login: &LoginState) {
         login.active_field == LoginField::Password,
     );
 
+    // Confirm password (create account only)
+    if login.mode == LoginMode::CreateAccount {
+        let confirm_area = Rect {
+            y: chunks[6].y + 1,
+            height: 2,
+            ..chunks[6]
+        };
+        render_field(
+            frame,
+            center_horizontal(confirm_area, field_width),
+            "Confirm password",
+            &login.confirm,
+            true,
+            login.active_field == LoginField::Confirm,
+        );
+    }
+
     // Status
     let (status_text, status_color) = match &login.status {
-        LoginStatus::Idle => (
-            "Enter to login · first time? account created automatically",
-            Color::DarkGray,
-        ),
+        LoginStatus::Idle => match login.mode {
+            LoginMode::SignIn => ("Enter to login · F2 to create an account", Color::DarkGray),
+            LoginMode::CreateAccount => (
+                "8+ characters, letters and digits · F2 to sign in",
+                Color::DarkGray,
+            ),
+        },
         LoginStatus::Connecting => ("Connecting...", Color::Yellow),
         LoginStatus::Error(msg) => (msg.as_str(), Color::Red),
     };
@@ -137,7 +174,7 @@ fn render_login_form(frame: &mut Frame, area: Rect, login: &LoginState) {
         Style::default().fg(status_color),
     )))
     .alignment(Alignment::Center);
-    frame.render_widget(status, chunks[7]);
+    frame.render_widget(status, chunks[8]);
 }
 
 /// Renders a single-line input field:
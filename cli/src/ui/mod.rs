@@ -1,4 +1,5 @@
 pub mod card;
+pub mod countdown;
 pub mod footer;
 pub mod header;
 pub mod history;
@@ -14,13 +15,20 @@ pub mod waiting_list;
 
 use ratatui::Frame;
 
-use crate::state::{Screen, UiState};
+use crate::state::{link::Link, table::Countdown, Screen, UiState};
 use crate::ui::footer::render_footer;
 use crate::ui::header::render_header;
 
-pub fn render(frame: &mut Frame, ui: &UiState) {
+/// Draws the current screen, with a banner counting down to `maintenance`
+/// if the server is shutting down.
+pub fn render(
+    frame: &mut Frame,
+    ui: &UiState,
+    maintenance: Option<&Countdown>,
+    link: Option<&Link>,
+) {
     let layout = layout::split_screen(frame.area());
-    render_header(frame, layout.header, ui);
+    render_header(frame, layout.header, ui, maintenance, link);
     render_main(frame, layout.main, ui);
     render_footer(frame, layout.footer, ui);
 }
@@ -7,6 +7,7 @@ use ratatui::{
 };
 
 use crate::state::{table::TableState, UiState};
+use crate::ui::countdown::countdown_spans;
 
 const COLOR_YELLOW: Color = Color::Rgb(224, 175, 104);
 const COLOR_CYAN: Color = Color::Rgb(125, 207, 255);
@@ -52,6 +53,8 @@ pub fn render_player_turn_popup(frame: &mut Frame, area: Rect, ui: &UiState) {
             Constraint::Length(1), // hand info
             Constraint::Length(1), // spacer
             Constraint::Length(1), // buttons
+            Constraint::Length(1), // spacer
+            Constraint::Length(1), // turn countdown
             Constraint::Min(0),
         ])
         .split(inner);
@@ -83,6 +86,13 @@ pub fn render_player_turn_popup(frame: &mut Frame, area: Rect, ui: &UiState) {
         Paragraph::new(buttons).alignment(Alignment::Center),
         chunks[2],
     );
+
+    if let Some(countdown) = &table.countdown {
+        frame.render_widget(
+            Paragraph::new(Line::from(countdown_spans(countdown, 24))).alignment(Alignment::Center),
+            chunks[4],
+        );
+    }
 }
 
 fn build_hand_line(table: &TableState) -> Line<'static> {
@@ -96,6 +96,11 @@ fn build_payout_line(result: &RoundResult) -> Line<'static> {
             format!("bet {} → won +{}", result.bet, result.payout - result.bet),
             COLOR_GREEN,
         ),
+        RoundOutcome::Voided { ref reason } if result.bet > 0 => (
+            format!("{reason} · bet {} refunded", result.bet),
+            COLOR_YELLOW,
+        ),
+        RoundOutcome::Voided { ref reason } => (reason.clone(), COLOR_YELLOW),
         RoundOutcome::Blackjack => (
             format!(
                 "bet {} → won +{}  🃏",
@@ -115,7 +120,7 @@ fn outcome_colors(outcome: &RoundOutcome) -> (Color, Color) {
     match outcome {
         RoundOutcome::Blackjack => (COLOR_CYAN, COLOR_CYAN),
         RoundOutcome::Won => (COLOR_GREEN, COLOR_GREEN),
-        RoundOutcome::Push => (COLOR_YELLOW, COLOR_YELLOW),
+        RoundOutcome::Push | RoundOutcome::Voided { .. } => (COLOR_YELLOW, COLOR_YELLOW),
         RoundOutcome::Lost | RoundOutcome::Bust => (COLOR_RED, COLOR_RED),
     }
 }
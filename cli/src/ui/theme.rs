@@ -5,5 +5,6 @@ pub const TOKIO_NIGHT_CYAN: Color = Color::Rgb(125, 207, 255);
 pub const TOKIO_NIGHT_MAGENTA: Color = Color::Rgb(187, 154, 247);
 pub const TOKIO_NIGHT_GREEN: Color = Color::Rgb(158, 206, 106);
 pub const TOKIO_NIGHT_ORANGE: Color = Color::Rgb(255, 158, 100);
+pub const TOKIO_NIGHT_RED: Color = Color::Rgb(247, 118, 142);
 pub const TOKIO_NIGHT_MUTED: Color = Color::Rgb(86, 95, 137);
 pub const TOKIO_NIGHT_SUBTLE: Color = Color::Rgb(60, 67, 100);
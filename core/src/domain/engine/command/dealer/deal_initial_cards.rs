@@ -6,7 +6,7 @@ use crate::domain::{
     table::TableSettings,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DealInitialCards;
 
 impl CommandHandler for DealInitialCards {
@@ -85,7 +85,7 @@ mod tests {
             GameEngine,
         },
         player::PlayerId,
-        table::TableSettings,
+        table::{DealerMode, TableSettings},
         Card, DeckId, Rank, Suit,
     };
 
@@ -95,6 +95,8 @@ mod tests {
             max_bet: 500,
             max_players: 5,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
@@ -13,16 +13,17 @@ use crate::domain::engine::error::CommandError;
 use crate::domain::engine::event::payload::EventPayload;
 use crate::domain::engine::game_id::GameId;
 use crate::domain::engine::game_state::GameState;
+use crate::domain::engine::transition::Trigger;
 use crate::domain::table::TableSettings;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DealerCommand {
     pub game_id: GameId,
     pub command_id: CommandId,
     pub action: DealerAction,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum DealerAction {
     DealInitialCards(DealInitialCards),
     OpenBetting(OpenBetting),
@@ -30,6 +31,17 @@ pub enum DealerAction {
     SettleRound(SettleRound),
 }
 
+impl DealerAction {
+    pub fn trigger(&self) -> Trigger {
+        match self {
+            Self::DealInitialCards(_) => Trigger::DealInitialCards,
+            Self::OpenBetting(_) => Trigger::OpenBetting,
+            Self::PlayHand(_) => Trigger::PlayHand,
+            Self::SettleRound(_) => Trigger::SettleRound,
+        }
+    }
+}
+
 impl CommandHandler for DealerAction {
     fn handle(
         &self,
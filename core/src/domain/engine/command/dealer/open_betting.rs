@@ -4,10 +4,12 @@ use crate::domain::{
         game_state::GameState, phase::Phase,
     },
     table::TableSettings,
-    Seat,
+    Seat, Shoe,
 };
 
-#[derive(Debug, Clone)]
+/// Starts the next round: resets the finished one, seats the waiting list and
+/// opens betting. Does nothing if betting is already open.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct OpenBetting;
 
 impl CommandHandler for OpenBetting {
@@ -19,24 +21,11 @@ impl CommandHandler for OpenBetting {
         match &state.phase {
             Phase::WaitingForBets => Ok(vec![]),
             Phase::Finished => {
-                let mut events = vec![];
-                let mut occupied: std::collections::BTreeSet<Seat> =
-                    state.players.iter().map(|p| p.seat).collect();
-                let available_seats = settings.max_players.saturating_sub(state.players.len());
-                for &(pid, desired) in state.waiting.iter().take(available_seats) {
-                    let seat = if !occupied.contains(&desired) {
-                        desired
-                    } else {
-                        Seat::ALL
-                            .iter()
-                            .take(settings.max_players)
-                            .copied()
-                            .find(|s| !occupied.contains(s))
-                            .expect("seat available — available_seats guard ensures capacity")
-                    };
-                    occupied.insert(seat);
-                    events.push(EventPayload::PlayerJoined { player: pid, seat });
-                }
+                let mut events = vec![EventPayload::RoundReset {
+                    game_id: state.game_id.next(),
+                    shoe: Shoe::shuffled(),
+                }];
+                events.extend(seat_waiting_players(state, settings));
                 events.push(EventPayload::PhaseChanged {
                     from: Phase::Finished,
                     to: Phase::WaitingForBets,
@@ -50,6 +39,33 @@ impl CommandHandler for OpenBetting {
     }
 }
 
+/// Seats players from the waiting list, preferring their requested seat, for
+/// as long as there is room at the table.
+pub(crate) fn seat_waiting_players(
+    state: &GameState,
+    settings: &TableSettings,
+) -> Vec<EventPayload> {
+    let mut events = vec![];
+    let mut occupied: std::collections::BTreeSet<Seat> =
+        state.players.iter().map(|p| p.seat).collect();
+    let available_seats = settings.max_players.saturating_sub(state.players.len());
+    for &(pid, desired) in state.waiting.iter().take(available_seats) {
+        let seat = if !occupied.contains(&desired) {
+            desired
+        } else {
+            Seat::ALL
+                .iter()
+                .take(settings.max_players)
+                .copied()
+                .find(|s| !occupied.contains(s))
+                .expect("seat available — available_seats guard ensures capacity")
+        };
+        occupied.insert(seat);
+        events.push(EventPayload::PlayerJoined { player: pid, seat });
+    }
+    events
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,7 +80,7 @@ mod tests {
             GameEngine,
         },
         player::PlayerId,
-        table::TableSettings,
+        table::{DealerMode, TableSettings},
         Seat, Shoe,
     };
 
@@ -74,6 +90,8 @@ mod tests {
             max_bet: 1000,
             max_players: 5,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
@@ -97,9 +115,12 @@ mod tests {
         let mut state = GameState::new(GameId::new(), Shoe::shuffled(), vec![], DealerId::new());
         state.phase = Phase::Finished;
         let events = GameEngine::handle(&state, &settings(), &cmd()).unwrap();
-        assert_eq!(events.len(), 1);
+        assert_eq!(events.len(), 2);
+        assert!(
+            matches!(events[0], EventPayload::RoundReset { game_id, .. } if game_id == state.game_id.next())
+        );
         assert!(matches!(
-            &events[0],
+            &events[1],
             EventPayload::PhaseChanged {
                 to: Phase::WaitingForBets,
                 ..
@@ -116,16 +137,16 @@ mod tests {
         state.waiting.push((pid1, Seat::One));
         state.waiting.push((pid2, Seat::Two));
         let events = GameEngine::handle(&state, &settings(), &cmd()).unwrap();
-        // Two PlayerJoined + one PhaseChanged
-        assert_eq!(events.len(), 3);
+        // RoundReset, two PlayerJoined and PhaseChanged
+        assert_eq!(events.len(), 4);
         assert!(
-            matches!(events[0], EventPayload::PlayerJoined { player, seat: Seat::One } if player == pid1)
+            matches!(events[1], EventPayload::PlayerJoined { player, seat: Seat::One } if player == pid1)
         );
         assert!(
-            matches!(events[1], EventPayload::PlayerJoined { player, seat: Seat::Two } if player == pid2)
+            matches!(events[2], EventPayload::PlayerJoined { player, seat: Seat::Two } if player == pid2)
         );
         assert!(matches!(
-            &events[2],
+            &events[3],
             EventPayload::PhaseChanged {
                 to: Phase::WaitingForBets,
                 ..
@@ -155,11 +176,13 @@ mod tests {
             max_bet: 1000,
             max_players: 2,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         };
         let events = GameEngine::handle(&state, &s, &cmd()).unwrap();
-        // One PlayerJoined + PhaseChanged
-        assert_eq!(events.len(), 2);
-        assert!(matches!(events[0], EventPayload::PlayerJoined { player, .. } if player == pid1));
+        // RoundReset, one PlayerJoined and PhaseChanged
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[1], EventPayload::PlayerJoined { player, .. } if player == pid1));
     }
 
     #[test]
@@ -6,7 +6,7 @@ use crate::domain::{
     table::TableSettings,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PlayHand;
 
 impl CommandHandler for PlayHand {
@@ -73,7 +73,7 @@ mod tests {
             game_state::GameState,
             GameEngine,
         },
-        table::TableSettings,
+        table::{DealerMode, TableSettings},
         Card, DeckId, Rank, Suit,
     };
 
@@ -83,6 +83,8 @@ mod tests {
             max_bet: 500,
             max_players: 5,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
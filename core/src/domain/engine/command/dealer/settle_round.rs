@@ -14,7 +14,7 @@ use crate::domain::{
     table::TableSettings,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SettleRound;
 
 fn is_natural_blackjack(hand: &Hand) -> bool {
@@ -113,7 +113,7 @@ mod tests {
             GameEngine,
         },
         player::PlayerId,
-        table::TableSettings,
+        table::{DealerMode, TableSettings},
         Card, DeckId, Rank, Suit,
     };
 
@@ -123,6 +123,8 @@ mod tests {
             max_bet: 500,
             max_players: 5,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
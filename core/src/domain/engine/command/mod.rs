@@ -9,6 +9,7 @@ pub use system::SystemCommand;
 use crate::domain::engine::error::CommandError;
 use crate::domain::engine::event::payload::EventPayload;
 use crate::domain::engine::game_state::GameState;
+use crate::domain::engine::transition::Trigger;
 use crate::domain::table::TableSettings;
 use serde::{Deserialize, Serialize};
 
@@ -23,9 +24,19 @@ pub trait CommandHandler {
     ) -> Result<Vec<EventPayload>, CommandError>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GameCommand {
     Player(PlayerCommand),
     Dealer(DealerCommand),
     System(SystemCommand),
 }
+
+impl GameCommand {
+    pub fn trigger(&self) -> Trigger {
+        match self {
+            Self::Player(c) => c.action.trigger(),
+            Self::Dealer(c) => c.action.trigger(),
+            Self::System(c) => c.trigger(),
+        }
+    }
+}
@@ -7,7 +7,7 @@ use crate::domain::{
     table::TableSettings,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Hit {
     pub player_id: PlayerId,
 }
@@ -77,7 +77,7 @@ mod tests {
             GameEngine,
         },
         player::PlayerId,
-        table::TableSettings,
+        table::{DealerMode, TableSettings},
         Card, DeckId, Rank, Suit,
     };
 
@@ -87,6 +87,8 @@ mod tests {
             max_bet: 500,
             max_players: 5,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
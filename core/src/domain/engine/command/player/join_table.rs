@@ -7,7 +7,7 @@ use crate::domain::{
     table::TableSettings,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct JoinTable {
     pub player_id: PlayerId,
 }
@@ -37,6 +37,7 @@ impl CommandHandler for JoinTable {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::table::DealerMode;
     use crate::domain::{
         dealer::DealerId,
         engine::{
@@ -58,6 +59,8 @@ mod tests {
             max_bet: 1000,
             max_players,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
@@ -98,7 +101,7 @@ mod tests {
         let pid = PlayerId::new();
         let events = GameEngine::handle(&state, &settings(5), &cmd(pid)).unwrap();
         for e in &events {
-            state.apply_event(e);
+            state.apply_event(e).unwrap();
         }
         let events2 = GameEngine::handle(&state, &settings(5), &cmd(pid)).unwrap();
         assert!(events2.is_empty());
@@ -148,6 +151,8 @@ mod tests {
             max_bet: 1000,
             max_players: 5,
             max_observers: 2,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         };
         state.observers.push(PlayerId::new());
         state.observers.push(PlayerId::new());
@@ -7,7 +7,7 @@ use crate::domain::{
     table::TableSettings,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct LeaveSeat {
     pub player_id: PlayerId,
 }
@@ -75,7 +75,7 @@ mod tests {
             GameEngine,
         },
         player::PlayerId,
-        table::TableSettings,
+        table::{DealerMode, TableSettings},
         Seat, Shoe,
     };
 
@@ -85,6 +85,8 @@ mod tests {
             max_bet: 1000,
             max_players: 5,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
@@ -113,7 +115,7 @@ mod tests {
         )
         .unwrap();
         for e in events {
-            state.apply_event(&e);
+            state.apply_event(&e).unwrap();
         }
         state
     }
@@ -7,7 +7,7 @@ use crate::domain::{
     table::TableSettings,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct LeaveTable {
     pub player_id: PlayerId,
 }
@@ -68,7 +68,7 @@ mod tests {
             game_state::GameState,
             GameEngine,
         },
-        table::TableSettings,
+        table::{DealerMode, TableSettings},
         Seat, Shoe,
     };
 
@@ -78,6 +78,8 @@ mod tests {
             max_bet: 1000,
             max_players: 5,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
@@ -100,7 +102,7 @@ mod tests {
         )
         .unwrap();
         for e in events {
-            state.apply_event(&e);
+            state.apply_event(&e).unwrap();
         }
         state
     }
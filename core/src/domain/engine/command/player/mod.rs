@@ -19,16 +19,18 @@ use crate::domain::engine::error::CommandError;
 use crate::domain::engine::event::payload::EventPayload;
 use crate::domain::engine::game_id::GameId;
 use crate::domain::engine::game_state::GameState;
+use crate::domain::engine::transition::Trigger;
 use crate::domain::table::TableSettings;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PlayerCommand {
     pub game_id: GameId,
     pub command_id: CommandId,
     pub action: PlayerAction,
 }
 
-#[derive(Debug, Clone)]
+/// `<&str>::from(&action)` is the variant name, for metrics labels.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, strum_macros::IntoStaticStr)]
 pub enum PlayerAction {
     Hit(Hit),
     JoinTable(JoinTable),
@@ -39,6 +41,20 @@ pub enum PlayerAction {
     TakeSeat(TakeSeat),
 }
 
+impl PlayerAction {
+    pub fn trigger(&self) -> Trigger {
+        match self {
+            Self::Hit(_) => Trigger::Hit,
+            Self::JoinTable(_) => Trigger::JoinTable,
+            Self::LeaveSeat(_) => Trigger::LeaveSeat,
+            Self::LeaveTable(_) => Trigger::LeaveTable,
+            Self::PlaceBet(_) => Trigger::PlaceBet,
+            Self::Stand(_) => Trigger::Stand,
+            Self::TakeSeat(_) => Trigger::TakeSeat,
+        }
+    }
+}
+
 impl CommandHandler for PlayerAction {
     fn handle(
         &self,
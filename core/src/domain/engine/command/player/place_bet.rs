@@ -7,7 +7,7 @@ use crate::domain::{
     table::TableSettings,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PlaceBet {
     pub player_id: PlayerId,
     pub amount: u32,
@@ -72,7 +72,7 @@ mod tests {
             GameEngine,
         },
         player::PlayerId,
-        table::TableSettings,
+        table::{DealerMode, TableSettings},
         Shoe,
     };
 
@@ -82,6 +82,8 @@ mod tests {
             max_bet: 500,
             max_players: 5,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
@@ -168,7 +170,7 @@ mod tests {
         let mut state = state_with_player(pid, 1000);
         let events = GameEngine::handle(&state, &settings(), &bet_cmd(pid, 100)).unwrap();
         for e in &events {
-            state.apply_event(e);
+            state.apply_event(e).unwrap();
         }
         assert!(matches!(
             GameEngine::handle(&state, &settings(), &bet_cmd(pid, 100)),
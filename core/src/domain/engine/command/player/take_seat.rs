@@ -8,7 +8,7 @@ use crate::domain::{
     Seat,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TakeSeat {
     pub player_id: PlayerId,
     /// `None` = auto-assign the lowest available seat.
@@ -64,6 +64,7 @@ impl CommandHandler for TakeSeat {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::table::DealerMode;
     use crate::domain::{
         dealer::DealerId,
         engine::{
@@ -84,6 +85,8 @@ mod tests {
             max_bet: 1000,
             max_players,
             max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
         }
     }
 
@@ -171,7 +174,7 @@ mod tests {
         let mut state = state_with_observer(pid);
         let events = GameEngine::handle(&state, &settings(5), &cmd(pid, Seat::One)).unwrap();
         for e in &events {
-            state.apply_event(e);
+            state.apply_event(e).unwrap();
         }
         assert!(!state.observers.contains(&pid));
         assert!(state
@@ -187,7 +190,7 @@ mod tests {
         state.phase = Phase::DealerTurn;
         let events = GameEngine::handle(&state, &settings(5), &cmd(pid, Seat::Three)).unwrap();
         for e in &events {
-            state.apply_event(e);
+            state.apply_event(e).unwrap();
         }
         assert!(!state.observers.contains(&pid));
         assert!(state
@@ -2,17 +2,182 @@ use crate::domain::engine::command::CommandHandler;
 use crate::domain::engine::error::CommandError;
 use crate::domain::engine::event::payload::EventPayload;
 use crate::domain::engine::game_state::GameState;
-use crate::domain::table::TableSettings;
+use crate::domain::table::{TableSettings, TableStatus};
 
-#[derive(Debug, Clone)]
+/// Closes the table, letting any round in play finish first.
+///
+/// With a round in progress this only emits `TableClosing`; the dealer policy
+/// issues `CloseTable` again once the round is over. Closing removes observers
+/// and the waiting list and ends with `TableClosed`. Seated players stay in the
+/// state so their balances can be flushed to the wallet.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CloseTable;
 
 impl CommandHandler for CloseTable {
     fn handle(
         &self,
-        _state: &GameState,
+        state: &GameState,
         _settings: &TableSettings,
     ) -> Result<Vec<EventPayload>, CommandError> {
-        todo!("close table handler")
+        let between_rounds = state.is_between_rounds();
+        let mut events = match state.status {
+            TableStatus::Closed => return Err(CommandError::TableClosed),
+            TableStatus::Closing if !between_rounds => return Err(CommandError::TableClosing),
+            TableStatus::Closing => vec![],
+            TableStatus::Open => vec![EventPayload::TableClosing],
+        };
+        if between_rounds {
+            events.extend(
+                state
+                    .observers
+                    .iter()
+                    .map(|&player| EventPayload::ObserverLeft { player }),
+            );
+            events.extend(
+                state
+                    .waiting
+                    .iter()
+                    .map(|&(player, _)| EventPayload::PlayerRemovedFromWaitingList { player }),
+            );
+            events.push(EventPayload::TableClosed);
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::table::DealerMode;
+    use crate::domain::{
+        dealer::DealerId,
+        engine::{
+            command::{
+                player::{JoinTable, PlaceBet, PlayerAction, PlayerCommand},
+                system::SystemCommand,
+                CommandId, GameCommand,
+            },
+            game_id::GameId,
+            phase::Phase,
+            GameEngine,
+        },
+        player::PlayerId,
+        Seat, Shoe,
+    };
+
+    fn settings() -> TableSettings {
+        TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        }
+    }
+
+    fn cmd() -> GameCommand {
+        GameCommand::System(SystemCommand::CloseTable(CloseTable))
+    }
+
+    fn player_cmd(action: PlayerAction) -> GameCommand {
+        GameCommand::Player(PlayerCommand {
+            game_id: GameId::new(),
+            command_id: CommandId(0),
+            action,
+        })
+    }
+
+    fn apply(state: &mut GameState, events: &[EventPayload]) {
+        for e in events {
+            state.apply_event(e).unwrap();
+        }
+    }
+
+    #[test]
+    fn idle_table_closes_immediately() {
+        let seated = PlayerId::new();
+        let mut state = GameState::new_with_balance(
+            GameId::new(),
+            Shoe::shuffled(),
+            vec![(seated, 1000)],
+            DealerId::new(),
+        );
+        let observer = PlayerId::new();
+        let waiting = PlayerId::new();
+        state.observers.push(observer);
+        state.waiting.push((waiting, Seat::Two));
+
+        let events = GameEngine::handle(&state, &settings(), &cmd()).unwrap();
+        assert!(matches!(events.first(), Some(EventPayload::TableClosing)));
+        assert!(matches!(events.last(), Some(EventPayload::TableClosed)));
+        apply(&mut state, &events);
+
+        assert_eq!(state.status, TableStatus::Closed);
+        assert!(state.observers.is_empty());
+        assert!(state.waiting.is_empty());
+        // Seated players stay until their balances are flushed.
+        assert_eq!(state.players.len(), 1);
+    }
+
+    #[test]
+    fn round_in_progress_only_starts_closing() {
+        let pid = PlayerId::new();
+        let mut state = GameState::new_with_balance(
+            GameId::new(),
+            Shoe::shuffled(),
+            vec![(pid, 1000)],
+            DealerId::new(),
+        );
+        state.players[0].bet = Some(100);
+        state.phase = Phase::PlayerTurn(pid);
+
+        let events = GameEngine::handle(&state, &settings(), &cmd()).unwrap();
+        assert_eq!(events.len(), 1);
+        apply(&mut state, &events);
+        assert_eq!(state.status, TableStatus::Closing);
+
+        assert_eq!(
+            GameEngine::handle(&state, &settings(), &cmd()).unwrap_err(),
+            CommandError::TableClosing
+        );
+    }
+
+    #[test]
+    fn closing_table_rejects_joins_and_bets() {
+        let pid = PlayerId::new();
+        let mut state = GameState::new_with_balance(
+            GameId::new(),
+            Shoe::shuffled(),
+            vec![(pid, 1000)],
+            DealerId::new(),
+        );
+        state.status = TableStatus::Closing;
+
+        let join = player_cmd(PlayerAction::JoinTable(JoinTable {
+            player_id: PlayerId::new(),
+        }));
+        assert_eq!(
+            GameEngine::handle(&state, &settings(), &join).unwrap_err(),
+            CommandError::TableClosing
+        );
+        let bet = player_cmd(PlayerAction::PlaceBet(PlaceBet {
+            player_id: pid,
+            amount: 100,
+        }));
+        assert_eq!(
+            GameEngine::handle(&state, &settings(), &bet).unwrap_err(),
+            CommandError::TableClosing
+        );
+    }
+
+    #[test]
+    fn closed_table_rejects_everything() {
+        let mut state = GameState::new(GameId::new(), Shoe::shuffled(), vec![], DealerId::new());
+        state.status = TableStatus::Closed;
+        assert_eq!(
+            GameEngine::handle(&state, &settings(), &cmd()).unwrap_err(),
+            CommandError::TableClosed
+        );
     }
 }
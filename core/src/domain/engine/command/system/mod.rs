@@ -1,16 +1,37 @@
+pub mod close_table;
+pub mod new_round;
 pub mod player_timeout;
+pub mod void_round;
 
+pub use close_table::CloseTable;
+pub use new_round::NewRound;
 pub use player_timeout::PlayerTimeout;
+pub use void_round::VoidRound;
 
 use crate::domain::engine::command::CommandHandler;
 use crate::domain::engine::error::CommandError;
 use crate::domain::engine::event::payload::EventPayload;
 use crate::domain::engine::game_state::GameState;
+use crate::domain::engine::transition::Trigger;
 use crate::domain::table::TableSettings;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum SystemCommand {
     PlayerTimeout(PlayerTimeout),
+    NewRound(NewRound),
+    CloseTable(CloseTable),
+    VoidRound(VoidRound),
+}
+
+impl SystemCommand {
+    pub fn trigger(&self) -> Trigger {
+        match self {
+            Self::PlayerTimeout(_) => Trigger::PlayerTimeout,
+            Self::NewRound(_) => Trigger::NewRound,
+            Self::CloseTable(_) => Trigger::CloseTable,
+            Self::VoidRound(_) => Trigger::VoidRound,
+        }
+    }
 }
 
 impl CommandHandler for SystemCommand {
@@ -21,6 +42,9 @@ impl CommandHandler for SystemCommand {
     ) -> Result<Vec<EventPayload>, CommandError> {
         match self {
             Self::PlayerTimeout(h) => h.handle(state, settings),
+            Self::NewRound(h) => h.handle(state, settings),
+            Self::CloseTable(h) => h.handle(state, settings),
+            Self::VoidRound(h) => h.handle(state, settings),
         }
     }
 }
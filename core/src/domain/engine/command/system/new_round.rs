@@ -0,0 +1,147 @@
+use crate::domain::{
+    engine::{
+        command::{dealer::OpenBetting, CommandHandler},
+        error::CommandError,
+        event::payload::EventPayload,
+        game_state::GameState,
+        phase::Phase,
+    },
+    table::TableSettings,
+};
+
+/// Resets a finished round and opens betting for the next one, like the
+/// dealer's [`OpenBetting`] but issued by the house when the round delay is
+/// over.
+///
+/// Seated players, observers and the waiting list carry over; hands, bets and
+/// the dealer hand are cleared by `RoundReset`. The next round gets the id
+/// following the current one, so the command stays deterministic.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NewRound;
+
+impl CommandHandler for NewRound {
+    fn handle(
+        &self,
+        state: &GameState,
+        settings: &TableSettings,
+    ) -> Result<Vec<EventPayload>, CommandError> {
+        if !matches!(state.phase, Phase::Finished) {
+            return Err(CommandError::WrongPhase {
+                actual: state.phase.clone(),
+            });
+        }
+        OpenBetting.handle(state, settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::table::DealerMode;
+    use crate::domain::{
+        dealer::DealerId,
+        engine::{
+            command::{system::SystemCommand, GameCommand},
+            event::EventSeqId,
+            game_id::GameId,
+            GameEngine,
+        },
+        player::PlayerId,
+        Card, DeckId, Rank, Seat, Shoe, Suit,
+    };
+
+    fn settings() -> TableSettings {
+        TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        }
+    }
+
+    fn cmd() -> GameCommand {
+        GameCommand::System(SystemCommand::NewRound(NewRound))
+    }
+
+    fn finished_state(pid: PlayerId) -> GameState {
+        let mut state = GameState::new_with_balance(
+            GameId::new(),
+            Shoe::shuffled(),
+            vec![(pid, 900)],
+            DealerId::new(),
+        );
+        let card = Card::new(DeckId::One, Suit::Spades, Rank::King);
+        state.players[0].bet = Some(100);
+        state.players[0].hand.add_card(card);
+        state.dealer.hand.add_card(card);
+        state.phase = Phase::Finished;
+        state
+    }
+
+    #[test]
+    fn new_round_resets_hands_and_keeps_seats() {
+        let pid = PlayerId::new();
+        let mut state = finished_state(pid);
+        let observer = PlayerId::new();
+        state.observers.push(observer);
+        let old_game = state.game_id;
+
+        let events = GameEngine::handle(&state, &settings(), &cmd()).unwrap();
+        for e in &events {
+            state.apply_event(e).unwrap();
+        }
+
+        assert_eq!(state.phase, Phase::WaitingForBets);
+        assert_ne!(state.game_id, old_game);
+        assert_eq!(state.players.len(), 1);
+        assert_eq!(state.players[0].balance, 900);
+        assert_eq!(state.players[0].bet, None);
+        assert!(state.players[0].hand.cards.is_empty());
+        assert!(state.dealer.hand.cards.is_empty());
+        assert_eq!(state.observers, vec![observer]);
+    }
+
+    #[test]
+    fn new_round_promotes_waiting_players() {
+        let pid = PlayerId::new();
+        let mut state = finished_state(pid);
+        let waiting = PlayerId::new();
+        state.waiting.push((waiting, Seat::Three));
+
+        let events = GameEngine::handle(&state, &settings(), &cmd()).unwrap();
+        for e in &events {
+            state.apply_event(e).unwrap();
+        }
+
+        assert!(state
+            .players
+            .iter()
+            .any(|p| p.player_id == waiting && p.seat == Seat::Three));
+        assert!(state.waiting.is_empty());
+    }
+
+    #[test]
+    fn event_seq_carries_over_into_the_next_round() {
+        let mut state = finished_state(PlayerId::new());
+        state.event_seq = EventSeqId(41);
+
+        let events = GameEngine::handle(&state, &settings(), &cmd()).unwrap();
+        for e in &events {
+            state.apply_event(e).unwrap();
+        }
+
+        assert_eq!(state.event_seq, EventSeqId(41 + events.len() as u64));
+    }
+
+    #[test]
+    fn new_round_wrong_phase() {
+        let mut state = finished_state(PlayerId::new());
+        state.phase = Phase::DealerTurn;
+        assert!(matches!(
+            GameEngine::handle(&state, &settings(), &cmd()),
+            Err(CommandError::WrongPhase { .. })
+        ));
+    }
+}
@@ -0,0 +1,213 @@
+use crate::domain::{
+    engine::{
+        command::{dealer::open_betting::seat_waiting_players, CommandHandler},
+        error::CommandError,
+        event::payload::EventPayload,
+        game_state::GameState,
+        phase::Phase,
+    },
+    table::TableSettings,
+    Shoe,
+};
+
+/// Cancels the current round from any phase: a misdeal, a shoe error, a
+/// server incident or an admin decision.
+///
+/// Every open bet is refunded by `RoundVoided`, hands are cleared by
+/// `RoundReset`, waiting players are seated and betting reopens. There is no
+/// `GameFinished`, so no settlement is ever recorded for the voided round.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct VoidRound {
+    pub reason: String,
+}
+
+impl CommandHandler for VoidRound {
+    fn handle(
+        &self,
+        state: &GameState,
+        settings: &TableSettings,
+    ) -> Result<Vec<EventPayload>, CommandError> {
+        let mut events = vec![
+            EventPayload::RoundVoided {
+                reason: self.reason.clone(),
+            },
+            EventPayload::RoundReset {
+                game_id: state.game_id.next(),
+                shoe: Shoe::shuffled(),
+            },
+        ];
+        events.extend(seat_waiting_players(state, settings));
+        if !matches!(state.phase, Phase::WaitingForBets) {
+            events.push(EventPayload::PhaseChanged {
+                from: state.phase.clone(),
+                to: Phase::WaitingForBets,
+            });
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::table::DealerMode;
+    use crate::domain::{
+        dealer::DealerId,
+        engine::{
+            command::{system::SystemCommand, GameCommand},
+            game_id::GameId,
+            GameEngine,
+        },
+        player::PlayerId,
+        Card, DeckId, Rank, Seat, Shoe, Suit,
+    };
+
+    fn settings() -> TableSettings {
+        TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        }
+    }
+
+    fn cmd() -> GameCommand {
+        GameCommand::System(SystemCommand::VoidRound(VoidRound {
+            reason: "misdeal".into(),
+        }))
+    }
+
+    /// Two players mid-round: 100 and 50 already taken from their balances.
+    fn state_in(phase: Phase) -> (GameState, PlayerId, PlayerId) {
+        let (a, b) = (PlayerId::new(), PlayerId::new());
+        let mut state = GameState::new_with_balance(
+            GameId::new(),
+            Shoe::shuffled(),
+            vec![(a, 900), (b, 950)],
+            DealerId::new(),
+        );
+        let card = Card::new(DeckId::One, Suit::Spades, Rank::King);
+        state.players[0].bet = Some(100);
+        state.players[1].bet = Some(50);
+        state.players[0].hand.add_card(card);
+        state.dealer.hand.add_card(card);
+        state.phase = phase;
+        (state, a, b)
+    }
+
+    fn void(state: &mut GameState) -> Vec<EventPayload> {
+        let events = GameEngine::handle(state, &settings(), &cmd()).unwrap();
+        for e in &events {
+            state.apply_event(e).unwrap();
+        }
+        events
+    }
+
+    #[test]
+    fn void_refunds_bets_and_returns_to_betting() {
+        for phase in [
+            Phase::InitialDealing,
+            Phase::DealerTurn,
+            Phase::Payouts,
+            Phase::WaitingForBets,
+        ] {
+            let (mut state, a, _) = state_in(phase.clone());
+            state.phase = phase;
+            void(&mut state);
+
+            assert_eq!(state.phase, Phase::WaitingForBets);
+            assert_eq!(state.players[0].balance, 1000);
+            assert_eq!(state.players[1].balance, 1000);
+            assert!(state.players.iter().all(|p| p.bet.is_none()));
+            assert!(state.players[0].hand.cards.is_empty());
+            assert!(state.dealer.hand.cards.is_empty());
+            assert_eq!(state.players[0].player_id, a);
+        }
+    }
+
+    #[test]
+    fn void_during_player_turn() {
+        let (mut state, a, _) = state_in(Phase::WaitingForBets);
+        state.phase = Phase::PlayerTurn(a);
+        let old_game = state.game_id;
+        let events = void(&mut state);
+
+        assert!(matches!(
+            &events[0],
+            EventPayload::RoundVoided { reason } if reason == "misdeal"
+        ));
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, EventPayload::GameFinished { .. })));
+        assert_ne!(state.game_id, old_game);
+        assert_eq!(state.phase, Phase::WaitingForBets);
+    }
+
+    #[test]
+    fn void_seats_waiting_players() {
+        let (mut state, _, _) = state_in(Phase::DealerTurn);
+        let waiting = PlayerId::new();
+        state.waiting.push((waiting, Seat::Four));
+        void(&mut state);
+
+        assert!(state
+            .players
+            .iter()
+            .any(|p| p.player_id == waiting && p.seat == Seat::Four));
+    }
+
+    #[test]
+    fn the_reset_shoe_comes_with_the_event() {
+        let (state, _, _) = state_in(Phase::DealerTurn);
+        let events = GameEngine::handle(&state, &settings(), &cmd()).unwrap();
+        let (mut first, mut second) = (state.clone(), state);
+        for e in &events {
+            first.apply_event(e).unwrap();
+            second.apply_event(e).unwrap();
+        }
+        assert_eq!(first.shoe.len(), 52 * 4);
+        assert_eq!(first.shoe, second.shoe);
+        assert_eq!(first.dealt, 0);
+    }
+
+    #[test]
+    fn void_after_settlement_refunds_nothing() {
+        use crate::domain::engine::event::outcome::{
+            GameResult, Payout, PayoutMultiplier, PlayerOutcome, PlayerResult,
+        };
+        let (mut state, a, b) = state_in(Phase::Payouts);
+        let result = GameResult {
+            player_results: vec![
+                PlayerResult {
+                    player: a,
+                    outcome: PlayerOutcome::Won,
+                    payout: Payout::new(100, PayoutMultiplier::Win),
+                },
+                PlayerResult {
+                    player: b,
+                    outcome: PlayerOutcome::Lost,
+                    payout: Payout::new(50, PayoutMultiplier::Loss),
+                },
+            ],
+            dealer_busted: false,
+        };
+        state
+            .apply_event(&EventPayload::GameFinished { result })
+            .unwrap();
+        state.phase = Phase::Finished;
+        // The finished round still shows what was bet.
+        assert_eq!(state.players[0].bet, Some(100));
+        assert_eq!(state.players[1].bet, Some(50));
+        let events = void(&mut state);
+
+        assert_eq!(state.phase, Phase::WaitingForBets);
+        assert_eq!(state.players[0].balance, 1100);
+        assert_eq!(state.players[1].balance, 950);
+        assert!(state.players.iter().all(|p| p.bet.is_none()));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, EventPayload::RoundVoided { .. })));
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use crate::domain::{
+    engine::{clock::Timestamp, event::payload::EventPayload, game_state::GameState, phase::Phase},
+    table::DealerMode,
+};
+
+/// How long each timed phase lasts at a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableTimers {
+    /// Counted from the first bet of a round; dealing starts when it closes.
+    pub betting_window: Duration,
+    /// Time a player has to act before their turn is stood for them.
+    pub player_turn: Duration,
+    /// Pause between settlement and the next round.
+    pub round_delay: Duration,
+}
+
+impl Default for TableTimers {
+    fn default() -> Self {
+        Self {
+            betting_window: Duration::from_secs(30),
+            player_turn: Duration::from_secs(30),
+            round_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Interleaves deadline events into a command's output.
+///
+/// Each deadline event directly follows the event that started it:
+/// `TurnStarted` after a `PhaseChanged` into a player turn,
+/// `NextRoundScheduled` after settlement, and `BettingWindowOpened` after the
+/// first bet of a round. A manual `dealer` gets only the turn deadlines.
+pub(crate) fn with_deadlines(
+    state: &GameState,
+    events: Vec<EventPayload>,
+    timers: &TableTimers,
+    dealer: DealerMode,
+    now: Timestamp,
+) -> Vec<EventPayload> {
+    let house = dealer == DealerMode::Automatic;
+    let mut phase = state.phase.clone();
+    let mut window_open = state.deadline.is_some();
+    let mut out = Vec::with_capacity(events.len() + 1);
+
+    for event in events {
+        let deadline = match &event {
+            EventPayload::PhaseChanged {
+                to: Phase::PlayerTurn(player),
+                ..
+            } => Some(EventPayload::TurnStarted {
+                player: *player,
+                expires_at: now.plus(timers.player_turn),
+            }),
+            EventPayload::PhaseChanged {
+                to: Phase::Finished,
+                ..
+            } if house => Some(EventPayload::NextRoundScheduled {
+                starts_at: now.plus(timers.round_delay),
+            }),
+            EventPayload::PlayerPlacedBet { .. }
+                if house && matches!(phase, Phase::WaitingForBets) && !window_open =>
+            {
+                window_open = true;
+                Some(EventPayload::BettingWindowOpened {
+                    closes_at: now.plus(timers.betting_window),
+                })
+            }
+            _ => None,
+        };
+        if let EventPayload::PhaseChanged { to, .. } = &event {
+            phase = to.clone();
+            window_open = false;
+        }
+        out.push(event);
+        out.extend(deadline);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        dealer::DealerId,
+        engine::{
+            command::{
+                player::{PlaceBet, PlayerAction, PlayerCommand, Stand},
+                CommandId, GameCommand,
+            },
+            game_id::GameId,
+            GameEngine,
+        },
+        player::PlayerId,
+        table::{DealerMode, TableSettings},
+        Shoe,
+    };
+
+    const NOW: Timestamp = Timestamp(1_000_000);
+
+    fn settings() -> TableSettings {
+        TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        }
+    }
+
+    fn player_cmd(action: PlayerAction) -> GameCommand {
+        GameCommand::Player(PlayerCommand {
+            game_id: GameId::new(),
+            command_id: CommandId(0),
+            action,
+        })
+    }
+
+    fn bet(pid: PlayerId) -> GameCommand {
+        player_cmd(PlayerAction::PlaceBet(PlaceBet {
+            player_id: pid,
+            amount: 100,
+        }))
+    }
+
+    #[test]
+    fn first_bet_opens_betting_window() {
+        let (a, b) = (PlayerId::new(), PlayerId::new());
+        let mut state = GameState::new_with_balance(
+            GameId::new(),
+            Shoe::shuffled(),
+            vec![(a, 1000), (b, 1000)],
+            DealerId::new(),
+        );
+        let timers = TableTimers::default();
+
+        let events = GameEngine::handle_at(&state, &settings(), &bet(a), &timers, NOW).unwrap();
+        assert!(matches!(
+            events[1],
+            EventPayload::BettingWindowOpened { closes_at } if closes_at == NOW.plus(timers.betting_window)
+        ));
+        for e in &events {
+            state.apply_event(e).unwrap();
+        }
+        assert_eq!(state.deadline, Some(NOW.plus(timers.betting_window)));
+
+        // A second bet joins the open window rather than extending it.
+        let events = GameEngine::handle_at(&state, &settings(), &bet(b), &timers, NOW).unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn stand_starts_next_players_turn() {
+        let (a, b) = (PlayerId::new(), PlayerId::new());
+        let mut state = GameState::new_with_balance(
+            GameId::new(),
+            Shoe::shuffled(),
+            vec![(a, 1000), (b, 1000)],
+            DealerId::new(),
+        );
+        state.players[0].bet = Some(100);
+        state.players[1].bet = Some(100);
+        state.phase = Phase::PlayerTurn(a);
+        let timers = TableTimers::default();
+
+        let cmd = player_cmd(PlayerAction::Stand(Stand { player_id: a }));
+        let events = GameEngine::handle_at(&state, &settings(), &cmd, &timers, NOW).unwrap();
+        assert!(matches!(
+            events.last().unwrap(),
+            EventPayload::TurnStarted { player, expires_at }
+                if *player == b && *expires_at == NOW.plus(timers.player_turn)
+        ));
+    }
+
+    #[test]
+    fn phase_change_clears_deadline() {
+        let pid = PlayerId::new();
+        let mut state = GameState::new_with_balance(
+            GameId::new(),
+            Shoe::shuffled(),
+            vec![(pid, 1000)],
+            DealerId::new(),
+        );
+        state.players[0].bet = Some(100);
+        state.phase = Phase::PlayerTurn(pid);
+        state.deadline = Some(NOW);
+
+        let cmd = player_cmd(PlayerAction::Stand(Stand { player_id: pid }));
+        let events =
+            GameEngine::handle_at(&state, &settings(), &cmd, &TableTimers::default(), NOW).unwrap();
+        for e in &events {
+            state.apply_event(e).unwrap();
+        }
+        assert_eq!(state.phase, Phase::DealerTurn);
+        assert_eq!(state.deadline, None);
+    }
+}
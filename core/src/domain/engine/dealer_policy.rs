@@ -0,0 +1,290 @@
+//! House automation: what the dealer does next, given the table and the time.
+//!
+//! The policy is a pure function of [`GameState`] and a [`Timestamp`], so a
+//! server actor, a simulator or an offline client can all drive rounds with it
+//! by looping until it returns `None` and sleeping until `state.deadline`.
+
+use crate::domain::engine::{
+    clock::Timestamp,
+    command::{
+        dealer::{DealInitialCards, DealerAction, DealerCommand, PlayHand, SettleRound},
+        system::{CloseTable, NewRound, PlayerTimeout, SystemCommand},
+        CommandId, GameCommand,
+    },
+    game_state::GameState,
+    phase::Phase,
+};
+use crate::domain::table::{DealerMode, TableSettings, TableStatus};
+
+pub struct DealerPolicy;
+
+impl DealerPolicy {
+    /// The command the house issues now, or `None` if it is waiting on
+    /// players or on a deadline that has not passed yet.
+    ///
+    /// Dealer turn and payouts run immediately; closing the betting window,
+    /// timing out a turn and starting the next round wait for `state.deadline`.
+    /// A closing table is closed as soon as its last round is over. At a
+    /// manual-dealer table the house only does that and times out turns; the
+    /// dealer does the rest.
+    pub fn next_command(
+        state: &GameState,
+        settings: &TableSettings,
+        now: Timestamp,
+    ) -> Option<GameCommand> {
+        match state.status {
+            TableStatus::Open => {}
+            TableStatus::Closing if state.is_between_rounds() => {
+                return Some(GameCommand::System(SystemCommand::CloseTable(CloseTable)));
+            }
+            TableStatus::Closing => {}
+            TableStatus::Closed => return None,
+        }
+        let expired = state.deadline.is_some_and(|at| now >= at);
+        match state.phase {
+            Phase::PlayerTurn(player_id) if expired => Some(GameCommand::System(
+                SystemCommand::PlayerTimeout(PlayerTimeout { player_id }),
+            )),
+            _ if settings.dealer == DealerMode::Manual => None,
+            Phase::WaitingForBets if expired => Some(dealer(
+                state,
+                DealerAction::DealInitialCards(DealInitialCards),
+            )),
+            Phase::DealerTurn => Some(dealer(state, DealerAction::PlayHand(PlayHand))),
+            Phase::Payouts => Some(dealer(state, DealerAction::SettleRound(SettleRound))),
+            Phase::Finished if expired => {
+                Some(GameCommand::System(SystemCommand::NewRound(NewRound)))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn dealer(state: &GameState, action: DealerAction) -> GameCommand {
+    GameCommand::Dealer(DealerCommand {
+        game_id: state.game_id,
+        command_id: CommandId(0),
+        action,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::domain::{
+        dealer::DealerId,
+        engine::{
+            clock::{Clock, ManualClock},
+            command::player::{PlaceBet, PlayerAction, PlayerCommand, Stand},
+            deadline::TableTimers,
+            event::payload::EventPayload,
+            game_id::GameId,
+            GameEngine,
+        },
+        player::PlayerId,
+        table::{DealerMode, TableSettings},
+        Card, DeckId, Rank, Suit,
+    };
+
+    fn settings() -> TableSettings {
+        TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        }
+    }
+
+    /// A shoe of tens: every hand is a hard 20, so no blackjacks or busts.
+    fn tens() -> Vec<Card> {
+        vec![Card::new(DeckId::One, Suit::Spades, Rank::Ten); 20]
+    }
+
+    fn execute(state: &mut GameState, cmd: &GameCommand, clock: &ManualClock) -> Vec<EventPayload> {
+        let events = GameEngine::handle_at(
+            state,
+            &settings(),
+            cmd,
+            &TableTimers::default(),
+            clock.now(),
+        )
+        .unwrap();
+        for e in &events {
+            state.apply_event(e).unwrap();
+        }
+        events
+    }
+
+    /// Runs the policy until it has nothing left to do, like the table actor.
+    fn drive(state: &mut GameState, clock: &ManualClock) -> Vec<EventPayload> {
+        let mut events = vec![];
+        while let Some(cmd) = DealerPolicy::next_command(state, &settings(), clock.now()) {
+            events.extend(execute(state, &cmd, clock));
+        }
+        events
+    }
+
+    fn bet(state: &mut GameState, player_id: PlayerId, clock: &ManualClock) {
+        let cmd = GameCommand::Player(PlayerCommand {
+            game_id: state.game_id,
+            command_id: CommandId(1),
+            action: PlayerAction::PlaceBet(PlaceBet {
+                player_id,
+                amount: 100,
+            }),
+        });
+        execute(state, &cmd, clock);
+    }
+
+    fn table(players: Vec<PlayerId>) -> GameState {
+        GameState::new_with_balance(
+            GameId::new(),
+            tens(),
+            players.into_iter().map(|p| (p, 1000)).collect(),
+            DealerId::new(),
+        )
+    }
+
+    #[test]
+    fn idle_table_does_nothing() {
+        let state = table(vec![PlayerId::new()]);
+        assert!(DealerPolicy::next_command(&state, &settings(), Timestamp(u64::MAX)).is_none());
+    }
+
+    #[test]
+    fn deals_only_after_betting_window_closes() {
+        let clock = ManualClock::new(Timestamp(0));
+        let pid = PlayerId::new();
+        let mut state = table(vec![pid]);
+        bet(&mut state, pid, &clock);
+
+        clock.advance(Duration::from_secs(29));
+        assert!(drive(&mut state, &clock).is_empty());
+        assert_eq!(state.phase, Phase::WaitingForBets);
+
+        clock.advance(Duration::from_secs(1));
+        drive(&mut state, &clock);
+        assert_eq!(state.phase, Phase::PlayerTurn(pid));
+        assert_eq!(state.deadline, Some(Timestamp(60_000)));
+    }
+
+    #[test]
+    fn full_round_with_timeouts() {
+        let clock = ManualClock::new(Timestamp(0));
+        let (a, b) = (PlayerId::new(), PlayerId::new());
+        let mut state = table(vec![a, b]);
+        let first_game = state.game_id;
+        bet(&mut state, a, &clock);
+        bet(&mut state, b, &clock);
+
+        clock.advance(Duration::from_secs(30));
+        drive(&mut state, &clock);
+        assert_eq!(state.phase, Phase::PlayerTurn(a));
+
+        // `a` never acts and is stood by the timeout.
+        clock.advance(Duration::from_secs(30));
+        drive(&mut state, &clock);
+        assert_eq!(state.phase, Phase::PlayerTurn(b));
+
+        // `b` stands; dealer turn and payouts run without waiting.
+        let stand = GameCommand::Player(PlayerCommand {
+            game_id: state.game_id,
+            command_id: CommandId(2),
+            action: PlayerAction::Stand(Stand { player_id: b }),
+        });
+        execute(&mut state, &stand, &clock);
+        let events = drive(&mut state, &clock);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, EventPayload::GameFinished { .. })));
+        assert_eq!(state.phase, Phase::Finished);
+        // 20 against 20 is a push: both players get their stake back.
+        assert!(state.players.iter().all(|p| p.balance == 1000));
+
+        clock.advance(Duration::from_secs(5));
+        drive(&mut state, &clock);
+        assert_eq!(state.phase, Phase::WaitingForBets);
+        assert_ne!(state.game_id, first_game);
+        assert_eq!(state.deadline, None);
+    }
+
+    #[test]
+    fn manual_table_waits_for_the_dealer() {
+        let manual = TableSettings {
+            dealer: DealerMode::Manual,
+            dealers: vec![],
+            ..settings()
+        };
+        let clock = ManualClock::new(Timestamp(0));
+        let pid = PlayerId::new();
+        let mut state = table(vec![pid]);
+        let place_bet = GameCommand::Player(PlayerCommand {
+            game_id: state.game_id,
+            command_id: CommandId(1),
+            action: PlayerAction::PlaceBet(PlaceBet {
+                player_id: pid,
+                amount: 100,
+            }),
+        });
+        let events = GameEngine::handle_at(
+            &state,
+            &manual,
+            &place_bet,
+            &TableTimers::default(),
+            clock.now(),
+        )
+        .unwrap();
+        for e in &events {
+            state.apply_event(e).unwrap();
+        }
+        assert!(state.deadline.is_none(), "no betting window");
+
+        clock.advance(Duration::from_secs(3600));
+        assert!(DealerPolicy::next_command(&state, &manual, clock.now()).is_none());
+
+        // Once the dealer deals, turns still time out.
+        let deal = dealer(&state, DealerAction::DealInitialCards(DealInitialCards));
+        let events =
+            GameEngine::handle_at(&state, &manual, &deal, &TableTimers::default(), clock.now())
+                .unwrap();
+        for e in &events {
+            state.apply_event(e).unwrap();
+        }
+        assert_eq!(state.phase, Phase::PlayerTurn(pid));
+        assert!(DealerPolicy::next_command(&state, &manual, clock.now()).is_none());
+        clock.advance(Duration::from_secs(30));
+        assert!(matches!(
+            DealerPolicy::next_command(&state, &manual, clock.now()),
+            Some(GameCommand::System(SystemCommand::PlayerTimeout(PlayerTimeout { player_id })))
+                if player_id == pid
+        ));
+    }
+
+    #[test]
+    fn closing_table_closes_instead_of_starting_next_round() {
+        let clock = ManualClock::new(Timestamp(0));
+        let pid = PlayerId::new();
+        let mut state = table(vec![pid]);
+        bet(&mut state, pid, &clock);
+        clock.advance(Duration::from_secs(30));
+        drive(&mut state, &clock);
+
+        let close = GameCommand::System(SystemCommand::CloseTable(CloseTable));
+        execute(&mut state, &close, &clock);
+        assert_eq!(state.status, TableStatus::Closing);
+
+        // The turn still times out and the round settles as usual.
+        clock.advance(Duration::from_secs(30));
+        let events = drive(&mut state, &clock);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, EventPayload::GameFinished { .. })));
+        assert!(matches!(events.last(), Some(EventPayload::TableClosed)));
+        assert_eq!(state.status, TableStatus::Closed);
+        assert_eq!(state.phase, Phase::Finished);
+    }
+}
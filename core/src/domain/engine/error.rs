@@ -1,7 +1,9 @@
 use crate::domain::engine::phase::Phase;
 use crate::domain::player::PlayerId;
+use strum_macros::IntoStaticStr;
 
-#[derive(Debug, thiserror::Error, PartialEq, Clone)]
+/// `<&str>::from(&error)` is the variant name, for metrics labels.
+#[derive(Debug, thiserror::Error, PartialEq, Clone, IntoStaticStr)]
 pub enum CommandError {
     #[error("command not valid in phase {actual:?}")]
     WrongPhase { actual: Phase },
@@ -31,4 +33,10 @@ pub enum CommandError {
     SeatNotAvailable(crate::domain::Seat, usize),
     #[error("no seats available at this table")]
     NoSeatAvailable,
+    #[error("illegal phase transition {from:?} -> {to:?}")]
+    IllegalTransition { from: Phase, to: Phase },
+    #[error("table is closing")]
+    TableClosing,
+    #[error("table is closed")]
+    TableClosed,
 }
@@ -9,7 +9,12 @@ pub use payload::*;
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct EventId(pub u64);
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// Position of an event in its table's stream. Counts every event the table
+/// has applied, across rounds, so a client can ask for whatever came after
+/// the last one it saw.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
 pub struct EventSeqId(pub u64);
 
 impl EventSeqId {
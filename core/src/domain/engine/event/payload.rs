@@ -1,6 +1,6 @@
 use crate::domain::{
     dealer::DealerId,
-    engine::{action::PlayerDecision, phase::Phase},
+    engine::{action::PlayerDecision, clock::Timestamp, game_id::GameId, phase::Phase},
     player::PlayerId,
     Card, Seat,
 };
@@ -33,14 +33,41 @@ pub enum EventPayload {
         player: PlayerId,
         amount: u32,
     },
+    /// The first bet of a round was placed; dealing starts at `closes_at`.
+    BettingWindowOpened {
+        closes_at: Timestamp,
+    },
     GameStarted,
+    /// The round was cancelled without settlement; every open bet is returned
+    /// to its player.
+    RoundVoided {
+        reason: String,
+    },
+    /// Clears hands, bets and the dealer hand and moves the table to the next
+    /// round with `shoe`. Seats, observers and the waiting list carry over.
+    RoundReset {
+        game_id: GameId,
+        /// Shuffled by the command, so applying the event is deterministic.
+        /// Never serialized: clients must not learn the order of the cards.
+        #[serde(skip)]
+        shoe: Vec<Card>,
+    },
     PhaseChanged {
         from: Phase,
         to: Phase,
     },
+    /// `player` must act by `expires_at` or is stood automatically.
+    TurnStarted {
+        player: PlayerId,
+        expires_at: Timestamp,
+    },
     GameFinished {
         result: GameResult,
     },
+    /// The round is settled; the next one opens at `starts_at`.
+    NextRoundScheduled {
+        starts_at: Timestamp,
+    },
     PlayerCardDealt {
         player: PlayerId,
         card: Card,
@@ -68,4 +95,8 @@ pub enum EventPayload {
     DealerBust {
         dealer: DealerId,
     },
+    /// The table finishes the current round and then closes.
+    TableClosing,
+    /// The table is closed; seated players are returned to the lobby.
+    TableClosed,
 }
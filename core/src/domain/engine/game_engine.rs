@@ -1,8 +1,12 @@
+use crate::domain::engine::clock::Timestamp;
 use crate::domain::engine::command::{CommandHandler, GameCommand};
+use crate::domain::engine::deadline::{self, TableTimers};
 use crate::domain::engine::error::CommandError;
 use crate::domain::engine::event::payload::EventPayload;
 use crate::domain::engine::game_state::GameState;
-use crate::domain::table::TableSettings;
+use crate::domain::engine::phase::Phase;
+use crate::domain::engine::transition::{self, Trigger};
+use crate::domain::table::{TableSettings, TableStatus};
 
 pub struct GameEngine;
 
@@ -12,10 +16,67 @@ impl GameEngine {
         settings: &TableSettings,
         cmd: &GameCommand,
     ) -> Result<Vec<EventPayload>, CommandError> {
-        match cmd {
+        match (state.status, cmd.trigger()) {
+            (TableStatus::Closed, _) => return Err(CommandError::TableClosed),
+            (
+                TableStatus::Closing,
+                Trigger::JoinTable
+                | Trigger::TakeSeat
+                | Trigger::PlaceBet
+                | Trigger::OpenBetting
+                | Trigger::NewRound,
+            ) => return Err(CommandError::TableClosing),
+            _ => {}
+        }
+        let events = match cmd {
             GameCommand::Player(c) => c.handle(state, settings),
             GameCommand::Dealer(c) => c.handle(state, settings),
             GameCommand::System(c) => c.handle(state, settings),
+        }?;
+        check_transitions(&state.phase, &events, cmd.trigger())?;
+        Ok(events)
+    }
+
+    /// Like [`handle`](Self::handle), but also emits the deadline events the
+    /// command starts, computed from `now` and the table's `timers`. At a
+    /// manual-dealer table only the players' turns are timed; the dealer
+    /// closes bets and starts rounds.
+    pub fn handle_at(
+        state: &GameState,
+        settings: &TableSettings,
+        cmd: &GameCommand,
+        timers: &TableTimers,
+        now: Timestamp,
+    ) -> Result<Vec<EventPayload>, CommandError> {
+        let events = Self::handle(state, settings, cmd)?;
+        Ok(deadline::with_deadlines(
+            state,
+            events,
+            timers,
+            settings.dealer,
+            now,
+        ))
+    }
+}
+
+/// Walks the phase changes a command produced and rejects the whole batch if
+/// any of them is missing from the transition table for `trigger`.
+fn check_transitions(
+    start: &Phase,
+    events: &[EventPayload],
+    trigger: Trigger,
+) -> Result<(), CommandError> {
+    let mut current = start.clone();
+    for event in events {
+        let (from, to) = match event {
+            EventPayload::GameStarted => (current.clone(), Phase::InitialDealing),
+            EventPayload::PhaseChanged { from, to } => (from.clone(), to.clone()),
+            _ => continue,
+        };
+        if from != current || !transition::is_legal(&from, &to, trigger) {
+            return Err(CommandError::IllegalTransition { from, to });
         }
+        current = to;
     }
+    Ok(())
 }
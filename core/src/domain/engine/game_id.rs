@@ -9,6 +9,15 @@ impl GameId {
     pub fn new() -> Self {
         Self(Ulid::new())
     }
+
+    /// Id of the round that follows this one at the same table. Monotonic, so
+    /// rounds at a table sort in play order.
+    pub fn next(&self) -> Self {
+        match self.0.increment() {
+            Some(next) => Self(next),
+            None => Self::new(),
+        }
+    }
 }
 
 impl Default for GameId {
@@ -1,13 +1,22 @@
 use crate::domain::{
     dealer::{DealerId, DealerState},
-    engine::{game_id::GameId, phase::Phase},
+    engine::{
+        clock::Timestamp,
+        game_id::GameId,
+        phase::Phase,
+        transition::{self, IllegalTransition},
+    },
+    hand::Hand,
     player::{PlayerId, PlayerState},
+    table::TableStatus,
     Card, Seat,
 };
 
-use super::event::EventPayload;
+use super::event::{EventPayload, EventSeqId};
 
-#[derive(Debug, Clone)]
+/// Everything a table knows about its game. Serialisable, so a table can be
+/// saved and brought back after a restart.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct GameState {
     pub game_id: GameId,
     pub phase: Phase,
@@ -18,6 +27,13 @@ pub struct GameState {
     pub observers: Vec<PlayerId>,
     /// (player_id, desired_seat) — seat is remembered so OpenBetting can restore the player to their preferred position.
     pub waiting: Vec<(PlayerId, Seat)>,
+    /// When the current timed phase ends: the betting window closing, the
+    /// active player's turn expiring, or the next round starting.
+    pub deadline: Option<Timestamp>,
+    pub status: TableStatus,
+    /// Sequence number of the last event applied; [`EventSeqId::start`]
+    /// before the first. Not reset between rounds.
+    pub event_seq: EventSeqId,
 }
 
 impl GameState {
@@ -40,6 +56,9 @@ impl GameState {
             dealer: DealerState::new(dealer),
             observers: vec![],
             waiting: vec![],
+            deadline: None,
+            status: TableStatus::Open,
+            event_seq: EventSeqId::start(),
         }
     }
 
@@ -70,6 +89,9 @@ impl GameState {
             dealer: DealerState::new(dealer),
             observers: vec![],
             waiting: vec![],
+            deadline: None,
+            status: TableStatus::Open,
+            event_seq: EventSeqId::start(),
         }
     }
 
@@ -82,7 +104,10 @@ impl GameState {
         self.shoe.len().saturating_sub(self.dealt)
     }
 
-    pub fn apply_event(&mut self, payload: &EventPayload) {
+    /// Applies one event and advances [`event_seq`](Self::event_seq). Phase
+    /// changes are checked against the transition table; an illegal one
+    /// leaves the state untouched and is returned as an error.
+    pub fn apply_event(&mut self, payload: &EventPayload) -> Result<(), IllegalTransition> {
         match payload {
             EventPayload::PlayerJoined { player, seat } => {
                 self.observers.retain(|&p| p != *player);
@@ -92,6 +117,12 @@ impl GameState {
             EventPayload::PlayerLeft { player } => {
                 self.players.retain(|p| p.player_id != *player);
                 self.waiting.retain(|(p, _)| *p != *player);
+                // The betting window closes with nobody to deal to.
+                if matches!(self.phase, Phase::WaitingForBets)
+                    && self.players.iter().all(|p| p.bet.is_none())
+                {
+                    self.deadline = None;
+                }
             }
             EventPayload::ObserverJoined { player } => {
                 self.observers.push(*player);
@@ -114,13 +145,41 @@ impl GameState {
                 }
             }
             EventPayload::GameStarted => {
-                self.phase = Phase::InitialDealing;
+                self.transition(&self.phase.clone(), &Phase::InitialDealing)?;
+            }
+            EventPayload::RoundVoided { reason: _ } => {
+                // A settled bet is spent: voiding the finished round before
+                // the next one refunds nothing.
+                if self.phase != Phase::Finished {
+                    for player in &mut self.players {
+                        if let Some(bet) = player.bet.take() {
+                            player.balance += bet;
+                        }
+                    }
+                }
+                self.deadline = None;
+            }
+            EventPayload::RoundReset { game_id, shoe } => {
+                self.game_id = *game_id;
+                for player in &mut self.players {
+                    player.hand = Hand::new();
+                    player.clear_bet();
+                    player.decisions.clear();
+                }
+                self.dealer.hand = Hand::new();
+                self.shoe = shoe.clone();
+                self.dealt = 0;
+            }
+            EventPayload::PhaseChanged { from, to } => {
+                self.transition(from, to)?;
             }
-            EventPayload::PhaseChanged { from: _, to } => {
-                self.phase = to.clone();
+            EventPayload::BettingWindowOpened { closes_at: at }
+            | EventPayload::TurnStarted { expires_at: at, .. }
+            | EventPayload::NextRoundScheduled { starts_at: at } => {
+                self.deadline = Some(*at);
             }
             EventPayload::GameFinished { result } => {
-                self.phase = Phase::Finished;
+                // The bets stay on show until the next round resets them.
                 for player_result in &result.player_results {
                     if let Some(player_state) = self
                         .players
@@ -165,7 +224,28 @@ impl GameState {
             EventPayload::DealerBust { dealer: _ } => {
                 // Dealer has busted, hand value already reflects this
             }
+            EventPayload::TableClosing => {
+                self.status = TableStatus::Closing;
+            }
+            EventPayload::TableClosed => {
+                self.status = TableStatus::Closed;
+                self.deadline = None;
+            }
+        }
+        self.event_seq = self.event_seq.next();
+        Ok(())
+    }
+
+    fn transition(&mut self, from: &Phase, to: &Phase) -> Result<(), IllegalTransition> {
+        if *from != self.phase || !transition::is_legal_any(from, to) {
+            return Err(IllegalTransition {
+                from: from.clone(),
+                to: to.clone(),
+            });
         }
+        self.phase = to.clone();
+        self.deadline = None;
+        Ok(())
     }
 
     pub fn next_player_after(&self, current_id: PlayerId) -> Phase {
@@ -195,6 +275,16 @@ impl GameState {
         p.hand.value().is_bust() || p.decisions.last() == Some(&PlayerDecision::Stand)
     }
 
+    /// True when no round is in play: the last one has finished, or betting
+    /// is open and nobody has bet yet.
+    pub fn is_between_rounds(&self) -> bool {
+        match self.phase {
+            Phase::Finished => true,
+            Phase::WaitingForBets => self.players.iter().all(|p| p.bet.is_none()),
+            _ => false,
+        }
+    }
+
     pub fn first_betting_player(&self) -> Option<PlayerId> {
         self.players
             .iter()
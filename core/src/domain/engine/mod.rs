@@ -1,5 +1,8 @@
 pub mod action;
+pub mod clock;
 pub mod command;
+pub mod deadline;
+pub mod dealer_policy;
 pub mod error;
 pub mod event;
 pub mod game_engine;
@@ -7,16 +10,21 @@ pub mod game_id;
 pub mod game_state;
 pub mod phase;
 pub mod snapshot;
+pub mod transition;
 
 pub use action::PlayerDecision;
+pub use clock::{Clock, ManualClock, SystemClock, Timestamp};
 pub use command::{
     CommandHandler, CommandId, DealerAction, DealerCommand, GameCommand, PlayerAction,
     PlayerCommand, SystemCommand,
 };
+pub use deadline::TableTimers;
+pub use dealer_policy::DealerPolicy;
 pub use error::CommandError;
 pub use event::*;
 pub use game_engine::GameEngine;
 pub use game_id::GameId;
 pub use game_state::GameState;
-pub use phase::Phase;
+pub use phase::{Phase, PhaseKind};
 pub use snapshot::{GameEventDto, GameStateSnapshot};
+pub use transition::{IllegalTransition, Trigger};
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
+use strum_macros::EnumDiscriminants;
 
 use crate::domain::player::PlayerId;
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// `PhaseKind` is the payload-free discriminant of `Phase`, used by the
+/// transition table where the active player does not matter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, EnumDiscriminants)]
+#[strum_discriminants(name(PhaseKind), derive(Hash))]
 pub enum Phase {
     WaitingForBets,
     InitialDealing,
@@ -11,3 +15,9 @@ pub enum Phase {
     Payouts,
     Finished,
 }
+
+impl Phase {
+    pub fn kind(&self) -> PhaseKind {
+        PhaseKind::from(self)
+    }
+}
@@ -2,8 +2,12 @@ use serde::{Deserialize, Serialize};
 
 use crate::domain::{
     dealer::DealerId,
-    engine::{event::payload::EventPayload, game_id::GameId, game_state::GameState, phase::Phase},
+    engine::{
+        clock::Timestamp, event::payload::EventPayload, game_id::GameId, game_state::GameState,
+        phase::Phase,
+    },
     player::PlayerId,
+    table::TableStatus,
     Card, Seat,
 };
 
@@ -35,6 +39,12 @@ pub struct GameStateSnapshot {
     pub observers: Vec<PlayerId>,
     /// Players waiting to join the next round, with their reserved seat.
     pub waiting: Vec<(PlayerId, Seat)>,
+    /// When the current timed phase ends, if one is running.
+    pub deadline: Option<Timestamp>,
+    pub status: TableStatus,
+    /// Sequence number of the last event reflected in this snapshot. Live
+    /// events with a `seq` at or below it are already accounted for.
+    pub last_seq: u64,
 }
 
 impl GameStateSnapshot {
@@ -75,6 +85,9 @@ impl GameStateSnapshot {
             requesting_player,
             observers: state.observers.clone(),
             waiting: state.waiting.clone(),
+            deadline: state.deadline,
+            status: state.status,
+            last_seq: state.event_seq.0,
         }
     }
 }
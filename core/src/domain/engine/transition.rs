@@ -0,0 +1,158 @@
+//! The phase state machine.
+//!
+//! Every legal phase change is listed once in [`TRANSITIONS`] as a
+//! `(from, to, trigger)` triple. `GameEngine::handle` checks the transitions a
+//! command produces against this table, and `GameState::apply_event` refuses
+//! to apply a `PhaseChanged` that is not listed or does not start from the
+//! current phase.
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::engine::phase::{Phase, PhaseKind};
+
+/// The command kind that caused a phase transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Trigger {
+    // Player commands
+    Hit,
+    JoinTable,
+    LeaveSeat,
+    LeaveTable,
+    PlaceBet,
+    Stand,
+    TakeSeat,
+    // Dealer commands
+    DealInitialCards,
+    OpenBetting,
+    PlayHand,
+    SettleRound,
+    // System commands
+    PlayerTimeout,
+    NewRound,
+    CloseTable,
+    VoidRound,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub from: PhaseKind,
+    pub to: PhaseKind,
+    pub trigger: Trigger,
+}
+
+const fn t(from: PhaseKind, to: PhaseKind, trigger: Trigger) -> Transition {
+    Transition { from, to, trigger }
+}
+
+/// Every legal `(from, to, trigger)` triple.
+#[rustfmt::skip]
+pub const TRANSITIONS: &[Transition] = &[
+    t(PhaseKind::WaitingForBets, PhaseKind::InitialDealing, Trigger::DealInitialCards),
+    t(PhaseKind::InitialDealing, PhaseKind::PlayerTurn, Trigger::DealInitialCards),
+    // Turn passes to the next bettor, or to the dealer after the last one.
+    t(PhaseKind::PlayerTurn, PhaseKind::PlayerTurn, Trigger::Hit),
+    t(PhaseKind::PlayerTurn, PhaseKind::PlayerTurn, Trigger::Stand),
+    t(PhaseKind::PlayerTurn, PhaseKind::PlayerTurn, Trigger::LeaveSeat),
+    t(PhaseKind::PlayerTurn, PhaseKind::PlayerTurn, Trigger::LeaveTable),
+    t(PhaseKind::PlayerTurn, PhaseKind::PlayerTurn, Trigger::PlayerTimeout),
+    t(PhaseKind::PlayerTurn, PhaseKind::DealerTurn, Trigger::Hit),
+    t(PhaseKind::PlayerTurn, PhaseKind::DealerTurn, Trigger::Stand),
+    t(PhaseKind::PlayerTurn, PhaseKind::DealerTurn, Trigger::LeaveSeat),
+    t(PhaseKind::PlayerTurn, PhaseKind::DealerTurn, Trigger::LeaveTable),
+    t(PhaseKind::PlayerTurn, PhaseKind::DealerTurn, Trigger::PlayerTimeout),
+    t(PhaseKind::DealerTurn, PhaseKind::Payouts, Trigger::PlayHand),
+    t(PhaseKind::Payouts, PhaseKind::Finished, Trigger::SettleRound),
+    t(PhaseKind::Finished, PhaseKind::WaitingForBets, Trigger::OpenBetting),
+    t(PhaseKind::Finished, PhaseKind::WaitingForBets, Trigger::NewRound),
+    // A voided round returns to betting from wherever it was.
+    t(PhaseKind::InitialDealing, PhaseKind::WaitingForBets, Trigger::VoidRound),
+    t(PhaseKind::PlayerTurn, PhaseKind::WaitingForBets, Trigger::VoidRound),
+    t(PhaseKind::DealerTurn, PhaseKind::WaitingForBets, Trigger::VoidRound),
+    t(PhaseKind::Payouts, PhaseKind::WaitingForBets, Trigger::VoidRound),
+    t(PhaseKind::Finished, PhaseKind::WaitingForBets, Trigger::VoidRound),
+];
+
+#[derive(Debug, thiserror::Error, PartialEq, Clone)]
+#[error("illegal phase transition {from:?} -> {to:?}")]
+pub struct IllegalTransition {
+    pub from: Phase,
+    pub to: Phase,
+}
+
+/// True if `from -> to` is listed for `trigger`.
+pub fn is_legal(from: &Phase, to: &Phase, trigger: Trigger) -> bool {
+    TRANSITIONS
+        .iter()
+        .any(|t| t.from == from.kind() && t.to == to.kind() && t.trigger == trigger)
+}
+
+/// True if `from -> to` is listed for any trigger. Used when applying events,
+/// where the trigger is no longer known.
+pub fn is_legal_any(from: &Phase, to: &Phase) -> bool {
+    TRANSITIONS
+        .iter()
+        .any(|t| t.from == from.kind() && t.to == to.kind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::player::PlayerId;
+
+    #[test]
+    fn stand_may_pass_turn_to_next_player() {
+        let from = Phase::PlayerTurn(PlayerId::new());
+        let to = Phase::PlayerTurn(PlayerId::new());
+        assert!(is_legal(&from, &to, Trigger::Stand));
+    }
+
+    #[test]
+    fn place_bet_never_changes_phase() {
+        assert!(!TRANSITIONS.iter().any(|t| t.trigger == Trigger::PlaceBet));
+    }
+
+    #[test]
+    fn settle_cannot_skip_to_waiting() {
+        assert!(!is_legal(
+            &Phase::Payouts,
+            &Phase::WaitingForBets,
+            Trigger::SettleRound
+        ));
+        // Only a void may go straight back to betting.
+        assert!(TRANSITIONS
+            .iter()
+            .filter(|t| t.from == PhaseKind::Payouts && t.to == PhaseKind::WaitingForBets)
+            .all(|t| t.trigger == Trigger::VoidRound));
+    }
+
+    #[test]
+    fn apply_rejects_phase_change_not_starting_from_current_phase() {
+        use crate::domain::{
+            dealer::DealerId, engine::event::EventPayload, engine::game_id::GameId,
+            engine::game_state::GameState, Shoe,
+        };
+        let mut state = GameState::new(GameId::new(), Shoe::shuffled(), vec![], DealerId::new());
+        let err = state
+            .apply_event(&EventPayload::PhaseChanged {
+                from: Phase::Payouts,
+                to: Phase::Finished,
+            })
+            .unwrap_err();
+        assert_eq!(err.from, Phase::Payouts);
+        assert_eq!(state.phase, Phase::WaitingForBets);
+    }
+
+    #[test]
+    fn new_round_only_from_finished() {
+        assert!(is_legal(
+            &Phase::Finished,
+            &Phase::WaitingForBets,
+            Trigger::NewRound
+        ));
+        assert!(!is_legal(
+            &Phase::DealerTurn,
+            &Phase::WaitingForBets,
+            Trigger::NewRound
+        ));
+    }
+}
@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::{fmt, str::FromStr};
+use thiserror::Error;
 use ulid::Ulid;
 use utoipa::ToSchema;
 
+use crate::domain::{PlayerId, Seat};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct TableId(pub Ulid);
 
@@ -31,10 +34,130 @@ impl FromStr for TableId {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct TableSettings {
     pub min_bet: u32,
     pub max_bet: u32,
     pub max_players: usize,
     pub max_observers: usize,
+    #[serde(default)]
+    pub dealer: DealerMode,
+    /// The accounts that may deal a `Manual` table; nobody else can.
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub dealers: Vec<PlayerId>,
+}
+
+/// Who runs the house side of a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+pub enum DealerMode {
+    /// [`DealerPolicy`](crate::domain::engine::DealerPolicy) deals, plays
+    /// the dealer hand and settles, on the table timers.
+    #[default]
+    Automatic,
+    /// A human dealer issues every dealer command. The table has no timers:
+    /// betting stays open and a player's turn lasts until they act.
+    Manual,
+}
+
+/// Why a [`TableSettings`] cannot be used for a table.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InvalidSettings {
+    #[error("min_bet must be at least 1")]
+    ZeroMinBet,
+    #[error("min_bet {min} is above max_bet {max}")]
+    BetRange { min: u32, max: u32 },
+    #[error("max_players must be 1 to {max}, got {got}", max = Seat::ALL.len())]
+    MaxPlayers { got: usize },
+}
+
+impl TableSettings {
+    pub fn validate(&self) -> Result<(), InvalidSettings> {
+        if self.min_bet == 0 {
+            return Err(InvalidSettings::ZeroMinBet);
+        }
+        if self.min_bet > self.max_bet {
+            return Err(InvalidSettings::BetRange {
+                min: self.min_bet,
+                max: self.max_bet,
+            });
+        }
+        if !(1..=Seat::ALL.len()).contains(&self.max_players) {
+            return Err(InvalidSettings::MaxPlayers {
+                got: self.max_players,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Where a table is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+pub enum TableStatus {
+    #[default]
+    Open,
+    /// The current round plays out; joins, seats and bets are rejected.
+    Closing,
+    /// Everyone has been removed and the table accepts no more commands.
+    Closed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> TableSettings {
+        TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        }
+    }
+
+    #[test]
+    fn valid_settings_pass() {
+        assert_eq!(settings().validate(), Ok(()));
+        let single_stake = TableSettings {
+            min_bet: 100,
+            max_bet: 100,
+            max_players: 7,
+            ..settings()
+        };
+        assert_eq!(single_stake.validate(), Ok(()));
+    }
+
+    #[test]
+    fn min_bet_above_max_bet_is_rejected() {
+        let s = TableSettings {
+            min_bet: 600,
+            ..settings()
+        };
+        assert_eq!(
+            s.validate(),
+            Err(InvalidSettings::BetRange { min: 600, max: 500 })
+        );
+    }
+
+    #[test]
+    fn zero_min_bet_is_rejected() {
+        let s = TableSettings {
+            min_bet: 0,
+            ..settings()
+        };
+        assert_eq!(s.validate(), Err(InvalidSettings::ZeroMinBet));
+    }
+
+    #[test]
+    fn max_players_must_fit_the_seats() {
+        for got in [0, 8] {
+            let s = TableSettings {
+                max_players: got,
+                ..settings()
+            };
+            assert_eq!(s.validate(), Err(InvalidSettings::MaxPlayers { got }));
+        }
+    }
 }
@@ -1,8 +1,8 @@
-CREATE TYPE table_status AS ENUM ('open', 'closed');
-
+-- Plain types only, so the schema runs on PostgreSQL and SQLite alike. Ids
+-- are ULID strings and settings are JSON text.
 CREATE TABLE IF NOT EXISTS tables (
-  id UUID PRIMARY KEY,
-  name VARCHAR(255) NOT NULL,
-  status table_status NOT NULL,
-  settings JSONB NOT NULL
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  status TEXT NOT NULL CHECK (status IN ('open', 'closed')),
+  settings TEXT NOT NULL
 );
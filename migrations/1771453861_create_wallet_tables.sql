@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS wallet_accounts (
+  player_id TEXT PRIMARY KEY,
+  balance BIGINT NOT NULL CHECK (balance >= 0)
+);
+
+-- Append-only chip ledger. `wallet_accounts.balance` is always the sum of a
+-- player's entries; both are written in the same transaction. `id` numbers a
+-- player's entries from 1, under the lock on their account row.
+CREATE TABLE IF NOT EXISTS wallet_transactions (
+  player_id TEXT NOT NULL REFERENCES wallet_accounts (player_id),
+  id BIGINT NOT NULL,
+  kind TEXT NOT NULL CHECK (kind IN ('bet', 'payout', 'refund', 'grant', 'adjustment')),
+  amount BIGINT NOT NULL,
+  balance_after BIGINT NOT NULL CHECK (balance_after >= 0),
+  table_id TEXT,
+  game_id TEXT,
+  created_at BIGINT NOT NULL,
+  PRIMARY KEY (player_id, id)
+);
@@ -0,0 +1,16 @@
+-- Stakes escrowed for a round. One row per (game_id, player_id) makes every
+-- reserve, commit and release idempotent.
+CREATE TABLE IF NOT EXISTS wallet_reservations (
+  game_id TEXT NOT NULL,
+  player_id TEXT NOT NULL REFERENCES wallet_accounts (player_id),
+  table_id TEXT NOT NULL,
+  amount BIGINT NOT NULL CHECK (amount >= 0),
+  status TEXT NOT NULL CHECK (status IN ('held', 'committed', 'released')),
+  payout BIGINT,
+  created_at BIGINT NOT NULL,
+  settled_at BIGINT,
+  PRIMARY KEY (game_id, player_id)
+);
+
+CREATE INDEX IF NOT EXISTS wallet_reservations_held_idx
+  ON wallet_reservations (status) WHERE status = 'held';
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS users (
+  player_id TEXT PRIMARY KEY,
+  username TEXT NOT NULL UNIQUE,
+  -- Argon2id PHC string; carries its own salt and parameters.
+  password_hash TEXT NOT NULL,
+  created_at BIGINT NOT NULL
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS auth_sessions (
+  id TEXT PRIMARY KEY,
+  player_id TEXT NOT NULL REFERENCES users (player_id),
+  -- SHA-256 of the current refresh token; the token itself is never stored.
+  refresh_hash TEXT NOT NULL UNIQUE,
+  refresh_expires_at BIGINT NOT NULL,
+  revoked BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS auth_sessions_player_idx ON auth_sessions (player_id);
@@ -0,0 +1,3 @@
+-- What an account may do: play, deal at manual-dealer tables, or administer.
+ALTER TABLE users
+  ADD COLUMN role TEXT NOT NULL DEFAULT 'player' CHECK (role IN ('player', 'dealer', 'admin'));
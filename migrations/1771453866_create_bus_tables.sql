@@ -0,0 +1,42 @@
+-- Shared state of instances running with `bus.kind: postgres`. `cluster`
+-- names a group of instances, so several can share one database.
+
+-- Commands waiting for the instance that owns their partition. A row is
+-- deleted once its command has been handled; `id` is a ULID, so commands
+-- are taken in the order they were sent.
+CREATE TABLE IF NOT EXISTS bus_commands (
+  id TEXT PRIMARY KEY,
+  cluster TEXT NOT NULL,
+  partition_no INTEGER NOT NULL,
+  envelope TEXT NOT NULL,
+  created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS bus_commands_partition_idx ON bus_commands (cluster, partition_no, id);
+
+-- The outcome of a command, until the instance that sent it picks it up.
+CREATE TABLE IF NOT EXISTS bus_replies (
+  id TEXT PRIMARY KEY,
+  instance TEXT NOT NULL,
+  reply TEXT NOT NULL,
+  created_at BIGINT NOT NULL
+);
+
+-- Which instance hosts the tables of each partition, until `expires_at`
+-- (milliseconds since the epoch, database clock) unless renewed.
+CREATE TABLE IF NOT EXISTS partition_leases (
+  cluster TEXT NOT NULL,
+  partition_no INTEGER NOT NULL,
+  owner TEXT NOT NULL,
+  expires_at BIGINT NOT NULL,
+  PRIMARY KEY (cluster, partition_no)
+);
+
+-- The latest summary of every running table, as published by its host.
+CREATE TABLE IF NOT EXISTS table_directory (
+  cluster TEXT NOT NULL,
+  table_id TEXT NOT NULL,
+  summary TEXT NOT NULL,
+  updated_at BIGINT NOT NULL,
+  PRIMARY KEY (cluster, table_id)
+);
@@ -0,0 +1,14 @@
+-- Every command a table ran and its outcome. Rows are only ever inserted.
+-- `record` is the whole entry as JSON; the other columns are what it is
+-- looked up by. `id` is a ULID.
+CREATE TABLE IF NOT EXISTS audit_log (
+  id TEXT PRIMARY KEY,
+  at BIGINT NOT NULL,
+  table_id TEXT NOT NULL,
+  sender TEXT NOT NULL CHECK (sender IN ('player', 'dealer', 'system')),
+  player_id TEXT,
+  request_id BIGINT,
+  record TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_player_idx ON audit_log (player_id, at);
@@ -0,0 +1,6 @@
+-- SHA-256 of every refresh token a session has already traded in. Seeing
+-- one again means the token was stolen, so its session is revoked.
+CREATE TABLE IF NOT EXISTS auth_spent_refresh (
+  refresh_hash TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL REFERENCES auth_sessions (id) ON DELETE CASCADE
+);
@@ -0,0 +1,33 @@
+use super::{AuditError, AuditQuery, AuditRecord, AuditSink};
+use async_trait::async_trait;
+use std::sync::RwLock;
+
+/// The audit trail of a server that keeps nothing on disk, lost on restart.
+#[derive(Default)]
+pub struct InMemoryAuditLog {
+    records: RwLock<Vec<AuditRecord>>,
+}
+
+impl InMemoryAuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AuditSink for InMemoryAuditLog {
+    async fn append(&self, record: &AuditRecord) -> Result<(), AuditError> {
+        self.records.write().unwrap().push(record.clone());
+        Ok(())
+    }
+
+    async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, AuditError> {
+        let records = self.records.read().unwrap();
+        Ok(records
+            .iter()
+            .filter(|r| query.matches(r))
+            .take(query.limit)
+            .cloned()
+            .collect())
+    }
+}
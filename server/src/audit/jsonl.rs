@@ -0,0 +1,97 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use super::{AuditError, AuditQuery, AuditRecord, AuditSink};
+
+/// The audit trail as a file of one JSON [`AuditRecord`] per line.
+///
+/// The file is only ever appended to, and every record is on disk before
+/// `append` returns; `append_all` syncs once for the lot. Queries read the
+/// whole file.
+pub struct JsonlAuditLog {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl JsonlAuditLog {
+    /// Opens the log at `path`, creating it and its directory as needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+}
+
+#[async_trait]
+impl AuditSink for JsonlAuditLog {
+    async fn append(&self, record: &AuditRecord) -> Result<(), AuditError> {
+        self.append_all(std::slice::from_ref(record)).await
+    }
+
+    async fn append_all(&self, records: &[AuditRecord]) -> Result<(), AuditError> {
+        let mut lines = vec![];
+        for record in records {
+            serde_json::to_writer(&mut lines, record)?;
+            lines.push(b'\n');
+        }
+        let file = self.file.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut file = file.lock().unwrap();
+            file.write_all(&lines)?;
+            file.sync_data()
+        })
+        .await
+        .map_err(|e| AuditError::Backend(e.to_string()))??;
+        Ok(())
+    }
+
+    async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, AuditError> {
+        let path = self.path.clone();
+        let query = *query;
+        let records = tokio::task::spawn_blocking(move || read_matching(&path, &query))
+            .await
+            .map_err(|e| AuditError::Backend(e.to_string()))??;
+        Ok(records)
+    }
+}
+
+/// The records matching `query`, in file order. A line that does not parse,
+/// such as one cut short by a crash, is skipped.
+fn read_matching(path: &Path, query: &AuditQuery) -> io::Result<Vec<AuditRecord>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let mut records = vec![];
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<AuditRecord>(&line) {
+            Ok(record) if query.matches(&record) => {
+                records.push(record);
+                if records.len() == query.limit {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("{}: skipping unreadable record: {e}", path.display()),
+        }
+    }
+    Ok(records)
+}
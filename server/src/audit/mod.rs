@@ -0,0 +1,196 @@
+//! The audit trail: every command a table was sent, who sent it, and what
+//! came of it.
+//!
+//! Table actors append an [`AuditRecord`] for each player, dealer and system
+//! command they run, timeouts included, with the events it produced or the
+//! error it was rejected with, through an [`AuditWriter`](writer::AuditWriter)
+//! that appends them in batches. Records are never changed or removed.
+//! Admins read them back by player and time range at `GET /admin/audit`.
+
+pub mod in_memory;
+pub mod jsonl;
+pub mod postgres;
+pub mod sqlite;
+pub mod writer;
+
+use async_trait::async_trait;
+use bj_core::domain::{
+    engine::{
+        command::{system::SystemCommand, GameCommand},
+        event::GameEvent,
+        Timestamp,
+    },
+    PlayerId, TableId,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::session::RequestId;
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("audit backend error: {0}")]
+    Backend(String),
+}
+
+impl From<std::io::Error> for AuditError {
+    fn from(e: std::io::Error) -> Self {
+        AuditError::Backend(e.to_string())
+    }
+}
+
+impl From<sqlx::Error> for AuditError {
+    fn from(e: sqlx::Error) -> Self {
+        AuditError::Backend(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AuditError {
+    fn from(e: serde_json::Error) -> Self {
+        AuditError::Backend(e.to_string())
+    }
+}
+
+/// Who a command came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Sender {
+    Player,
+    /// The dealer of a manual-dealer table.
+    Dealer,
+    /// The table itself: the automatic dealer, timeouts, closing and
+    /// voiding.
+    System,
+}
+
+impl Sender {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Player => "player",
+            Self::Dealer => "dealer",
+            Self::System => "system",
+        }
+    }
+}
+
+/// Where a command a table runs came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Origin {
+    pub sender: Sender,
+    pub player_id: Option<PlayerId>,
+    pub request_id: Option<RequestId>,
+}
+
+impl Origin {
+    pub fn player(player_id: PlayerId, request_id: RequestId) -> Self {
+        Self {
+            sender: Sender::Player,
+            player_id: Some(player_id),
+            request_id: Some(request_id),
+        }
+    }
+
+    pub fn dealer(dealer_id: PlayerId, request_id: RequestId) -> Self {
+        Self {
+            sender: Sender::Dealer,
+            player_id: Some(dealer_id),
+            request_id: Some(request_id),
+        }
+    }
+
+    pub fn system() -> Self {
+        Self {
+            sender: Sender::System,
+            player_id: None,
+            request_id: None,
+        }
+    }
+}
+
+/// What came of a command.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "result", rename_all = "lowercase")]
+pub enum Outcome {
+    /// The events the command produced, in order.
+    Accepted {
+        #[schema(value_type = Vec<Object>)]
+        events: Vec<GameEvent>,
+    },
+    /// The `CommandError`, or the wallet's reason, it was turned down with.
+    Rejected { error: String },
+}
+
+/// One command run by a table.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditRecord {
+    /// Unix time in milliseconds.
+    #[schema(value_type = u64)]
+    pub at: Timestamp,
+    #[schema(value_type = String)]
+    pub table_id: TableId,
+    pub sender: Sender,
+    /// The player or dealer who sent the command, or the player a system
+    /// command acted for, such as the one whose turn timed out.
+    #[schema(value_type = Option<String>)]
+    pub player_id: Option<PlayerId>,
+    /// The client's id for the request, for player and dealer commands.
+    pub request_id: Option<u64>,
+    #[schema(value_type = Object)]
+    pub command: GameCommand,
+    pub outcome: Outcome,
+}
+
+impl AuditRecord {
+    pub fn new(
+        at: Timestamp,
+        table_id: TableId,
+        origin: Origin,
+        command: GameCommand,
+        outcome: Outcome,
+    ) -> Self {
+        let acted_for = match &command {
+            GameCommand::System(SystemCommand::PlayerTimeout(timeout)) => Some(timeout.player_id),
+            _ => None,
+        };
+        Self {
+            at,
+            table_id,
+            sender: origin.sender,
+            player_id: origin.player_id.or(acted_for),
+            request_id: origin.request_id.map(|r| r.0),
+            command,
+            outcome,
+        }
+    }
+}
+
+/// The records about one player from `from` up to, but not including, `to`.
+#[derive(Debug, Clone, Copy)]
+pub struct AuditQuery {
+    pub player_id: PlayerId,
+    pub from: Timestamp,
+    pub to: Timestamp,
+    pub limit: usize,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        record.player_id == Some(self.player_id) && record.at >= self.from && record.at < self.to
+    }
+}
+
+/// Append-only storage for the audit trail.
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    async fn append(&self, record: &AuditRecord) -> Result<(), AuditError>;
+    /// Appends `records` in order.
+    async fn append_all(&self, records: &[AuditRecord]) -> Result<(), AuditError> {
+        for record in records {
+            self.append(record).await?;
+        }
+        Ok(())
+    }
+    /// Records matching `query`, oldest first, at most `query.limit`.
+    async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, AuditError>;
+}
@@ -0,0 +1,53 @@
+use super::{AuditError, AuditQuery, AuditRecord, AuditSink};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use ulid::Ulid;
+
+/// The audit trail in SQLite, in the same table as
+/// [`PostgresAuditLog`](super::postgres::PostgresAuditLog).
+pub struct SqliteAuditLog {
+    pool: SqlitePool,
+}
+
+impl SqliteAuditLog {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AuditSink for SqliteAuditLog {
+    async fn append(&self, record: &AuditRecord) -> Result<(), AuditError> {
+        sqlx::query(
+            "INSERT INTO audit_log (id, at, table_id, sender, player_id, request_id, record) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(Ulid::new().to_string())
+        .bind(record.at.as_millis() as i64)
+        .bind(record.table_id.to_string())
+        .bind(record.sender.as_str())
+        .bind(record.player_id.map(|p| p.to_string()))
+        .bind(record.request_id.map(|r| r as i64))
+        .bind(serde_json::to_string(record)?)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, AuditError> {
+        let rows: Vec<String> = sqlx::query_scalar(
+            "SELECT record FROM audit_log \
+             WHERE player_id = $1 AND at >= $2 AND at < $3 \
+             ORDER BY at, id LIMIT $4",
+        )
+        .bind(query.player_id.to_string())
+        .bind(query.from.as_millis() as i64)
+        .bind(query.to.as_millis().min(i64::MAX as u64) as i64)
+        .bind(i64::try_from(query.limit).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|record| Ok(serde_json::from_str(record)?))
+            .collect()
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::{mpsc, oneshot};
+use tracing::error;
+
+use super::{AuditError, AuditQuery, AuditRecord, AuditSink};
+
+/// Records a writer holds before appending has to wait for it.
+pub const AUDIT_QUEUE: usize = 1024;
+/// Records handed to the sink at once.
+const BATCH: usize = 256;
+
+enum Queued {
+    Record(Box<AuditRecord>),
+    Flushed(oneshot::Sender<()>),
+}
+
+/// Appends to a sink from a task of its own, in batches, so a table only
+/// waits for its record to be queued. The queue is bounded: a sink that
+/// falls behind slows the tables down rather than losing records.
+///
+/// Queries see every record queued before them. A batch the sink refuses
+/// is logged and lost; the commands have already happened.
+pub struct AuditWriter {
+    tx: mpsc::Sender<Queued>,
+    sink: Arc<dyn AuditSink>,
+}
+
+impl AuditWriter {
+    /// Starts the writer task for `sink`. It runs until the writer is
+    /// dropped, appending what is still queued first.
+    pub fn spawn(sink: Arc<dyn AuditSink>) -> Self {
+        let (tx, rx) = mpsc::channel(AUDIT_QUEUE);
+        tokio::spawn(write(sink.clone(), rx));
+        Self { tx, sink }
+    }
+
+    /// Waits until everything queued so far is appended.
+    pub async fn flush(&self) {
+        let (tx, rx) = oneshot::channel();
+        if self.tx.send(Queued::Flushed(tx)).await.is_ok() {
+            let _ = rx.await;
+        }
+    }
+}
+
+#[async_trait]
+impl AuditSink for AuditWriter {
+    async fn append(&self, record: &AuditRecord) -> Result<(), AuditError> {
+        self.tx
+            .send(Queued::Record(Box::new(record.clone())))
+            .await
+            .map_err(|_| AuditError::Backend("audit writer has stopped".into()))
+    }
+
+    async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, AuditError> {
+        self.flush().await;
+        self.sink.query(query).await
+    }
+}
+
+async fn write(sink: Arc<dyn AuditSink>, mut rx: mpsc::Receiver<Queued>) {
+    let mut queued = Vec::with_capacity(BATCH);
+    while rx.recv_many(&mut queued, BATCH).await > 0 {
+        let mut records = Vec::with_capacity(queued.len());
+        let mut flushed = vec![];
+        for item in queued.drain(..) {
+            match item {
+                Queued::Record(record) => records.push(*record),
+                Queued::Flushed(tx) => flushed.push(tx),
+            }
+        }
+        if !records.is_empty() {
+            if let Err(e) = sink.append_all(&records).await {
+                error!("{} audit records not written: {e}", records.len());
+            }
+        }
+        for tx in flushed {
+            let _ = tx.send(());
+        }
+    }
+}
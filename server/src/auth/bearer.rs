@@ -0,0 +1,91 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bj_core::domain::PlayerId;
+use ulid::Ulid;
+
+use super::Role;
+use crate::AppState;
+
+/// The player behind the request's `Authorization: Bearer` access token.
+///
+/// Handlers take this as an argument to require authentication; a missing,
+/// expired or revoked token is answered with `401 Unauthorized`.
+pub struct AuthenticatedPlayer {
+    pub player_id: PlayerId,
+    pub session_id: Ulid,
+}
+
+pub struct Unauthorized;
+
+impl IntoResponse for Unauthorized {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+        )
+            .into_response()
+    }
+}
+
+/// An [`AuthenticatedPlayer`] whose account has the [`Role::Admin`] role.
+/// Anyone else is answered with `403 Forbidden`.
+pub struct AdminPlayer {
+    pub player_id: PlayerId,
+    pub username: String,
+}
+
+impl FromRequestParts<AppState> for AdminPlayer {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let player = AuthenticatedPlayer::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let forbidden = || StatusCode::FORBIDDEN.into_response();
+        if state.auth.role(player.player_id).await.ok() != Some(Role::Admin) {
+            return Err(forbidden());
+        }
+        let username = state
+            .auth
+            .lookup_username(player.player_id)
+            .await
+            .ok()
+            .flatten()
+            .ok_or_else(forbidden)?;
+        Ok(AdminPlayer {
+            player_id: player.player_id,
+            username,
+        })
+    }
+}
+
+impl FromRequestParts<AppState> for AuthenticatedPlayer {
+    type Rejection = Unauthorized;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or(Unauthorized)?;
+        let claims = state
+            .sessions
+            .verify(token)
+            .await
+            .map_err(|_| Unauthorized)?;
+        Ok(AuthenticatedPlayer {
+            player_id: claims.player_id,
+            session_id: claims.session_id,
+        })
+    }
+}
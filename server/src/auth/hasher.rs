@@ -0,0 +1,84 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+
+use super::{AuthError, Password};
+use crate::config::Argon2Settings;
+
+/// Salt and output of [`PasswordHasher::dummy_hash`]: 16 and 32 bytes, as
+/// in a real hash.
+const DUMMY_SALT: &str = "ZHVtbXlzYWx0ZHVtbXlzYQ";
+const DUMMY_OUTPUT: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+
+/// Argon2id password hashing with configurable cost.
+///
+/// Hashes are stored as PHC strings, which carry their own parameters, so a
+/// hash made with older settings still verifies and can be upgraded with
+/// [`needs_rehash`](Self::needs_rehash).
+#[derive(Clone)]
+pub struct PasswordHasher {
+    params: Params,
+}
+
+impl PasswordHasher {
+    pub fn new(settings: &Argon2Settings) -> Result<Self, AuthError> {
+        let params = Params::new(
+            settings.memory_kib,
+            settings.iterations,
+            settings.parallelism,
+            None,
+        )
+        .map_err(|e| AuthError::Failed(format!("invalid argon2 parameters: {e}")))?;
+        Ok(Self { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+
+    pub fn hash(&self, password: &Password) -> Result<String, AuthError> {
+        let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+        self.argon2()
+            .hash_password(password.expose().as_bytes(), &salt)
+            .map(|h| h.to_string())
+            .map_err(|e| AuthError::Failed(format!("hashing failed: {e}")))
+    }
+
+    pub fn verify(&self, password: &Password, hash: &str) -> Result<bool, AuthError> {
+        let parsed = PasswordHash::new(hash)
+            .map_err(|e| AuthError::Failed(format!("stored hash is malformed: {e}")))?;
+        Ok(self
+            .argon2()
+            .verify_password(password.expose().as_bytes(), &parsed)
+            .is_ok())
+    }
+
+    /// A hash no password matches, with this hasher's cost, for a login to
+    /// [`verify`](Self::verify) against when its username has no account:
+    /// turning it away then takes as long as a wrong password does.
+    pub fn dummy_hash(&self) -> String {
+        format!(
+            "$argon2id$v=19$m={},t={},p={}${DUMMY_SALT}${DUMMY_OUTPUT}",
+            self.params.m_cost(),
+            self.params.t_cost(),
+            self.params.p_cost(),
+        )
+    }
+
+    /// Whether `hash` was made with a different algorithm, version or cost
+    /// than this hasher uses today.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return true;
+        };
+        let Ok(params) = Params::try_from(&parsed) else {
+            return true;
+        };
+        parsed.algorithm != Algorithm::Argon2id.ident()
+            || parsed.version != Some(Version::V0x13.into())
+            || params.m_cost() != self.params.m_cost()
+            || params.t_cost() != self.params.t_cost()
+            || params.p_cost() != self.params.p_cost()
+    }
+}
@@ -1,12 +1,25 @@
+mod bearer;
+mod hasher;
 mod password;
+pub mod policy;
+pub mod postgres;
+mod role;
+pub mod session_store;
+pub mod sqlite;
+mod tokens;
 
+pub use bearer::{AdminPlayer, AuthenticatedPlayer};
+pub use hasher::PasswordHasher;
 pub use password::Password;
+pub use role::Role;
+pub use tokens::{SessionClaims, Sessions, TokenPair};
 
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::Mutex;
 
 use async_trait::async_trait;
 use bj_core::domain::PlayerId;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Debug)]
@@ -17,91 +30,291 @@ pub struct AuthPayload {
 
 #[derive(Debug, Error)]
 pub enum AuthError {
-    #[error("wrong password")]
+    #[error("wrong username or password")]
     WrongPassword,
+    #[error("wrong username or password")]
+    UnknownUser,
+    #[error("username is already taken")]
+    UsernameTaken,
+    #[error("username {0}")]
+    InvalidUsername(String),
+    #[error("password {0}")]
+    WeakPassword(String),
+    #[error("invalid token")]
+    InvalidToken,
+    #[error("token expired")]
+    TokenExpired,
+    #[error("session has been revoked")]
+    SessionRevoked,
+    #[error("too many failed logins; try again in {retry_after_secs}s")]
+    TooManyAttempts { retry_after_secs: u64 },
+    #[error("account locked after repeated failed logins; try again in {retry_after_secs}s")]
+    AccountLocked { retry_after_secs: u64 },
+    #[error("too many sign-ups from this address; try again in {retry_after_secs}s")]
+    TooManySignups { retry_after_secs: u64 },
+    #[error("too many logins from this address; try again in {retry_after_secs}s")]
+    TooManyLogins { retry_after_secs: u64 },
     #[error("authentication failed: {0}")]
     Failed(String),
 }
 
 #[async_trait]
 pub trait Authenticator: Send + Sync {
-    /// Returns the PlayerId on success. Auto-creates the user on first login (signup).
+    /// Returns the PlayerId on success. Unknown usernames are rejected; accounts
+    /// are created by [`register`](Self::register).
     async fn authenticate(&self, payload: &AuthPayload) -> Result<PlayerId, AuthError>;
+    /// Creates an account after checking it against [`policy`]. Fails with
+    /// `UsernameTaken` if the username exists.
+    async fn register(&self, payload: &AuthPayload) -> Result<PlayerId, AuthError>;
+    /// Pre-register a user and return their PlayerId. Idempotent: returns existing id if username taken.
+    async fn seed_user(&self, username: &str, password: &str) -> Result<PlayerId, AuthError>;
+    /// Resolve a PlayerId to the username used at login.
+    async fn lookup_username(&self, player_id: PlayerId) -> Result<Option<String>, AuthError>;
+    /// The account's role. Fails with `UnknownUser` if there is no account.
+    async fn role(&self, player_id: PlayerId) -> Result<Role, AuthError>;
+    /// Gives the account `username` a new role and returns its PlayerId.
+    async fn set_role(&self, username: &str, role: Role) -> Result<PlayerId, AuthError>;
+    /// True if any account has the admin role.
+    async fn has_admin(&self) -> Result<bool, AuthError>;
+    /// Creates `username` as an admin while no account is one, and returns
+    /// its PlayerId; returns `None` once an admin exists. Never touches an
+    /// existing account: fails with `UsernameTaken` if `username` is one.
+    async fn bootstrap_admin(
+        &self,
+        username: &str,
+        password: &Password,
+    ) -> Result<Option<PlayerId>, AuthError>;
 }
 
 struct UserRecord {
-    password: Password,
+    password_hash: String,
     player_id: PlayerId,
+    role: Role,
+}
+
+/// An account as saved by [`InMemoryAuthenticator::export`], with its
+/// Argon2 password hash.
+#[derive(Serialize, Deserialize)]
+pub struct SavedUser {
+    pub username: String,
+    pub password_hash: String,
+    pub player_id: PlayerId,
+    pub role: Role,
+}
+
+/// Accounts by username, and usernames by PlayerId for display-name lookup.
+/// One lock guards both so they never disagree.
+#[derive(Default)]
+struct Accounts {
+    by_name: HashMap<String, UserRecord>,
+    names: HashMap<PlayerId, String>,
+}
+
+impl Accounts {
+    fn insert(&mut self, username: String, record: UserRecord) {
+        self.names.insert(record.player_id, username.clone());
+        self.by_name.insert(username, record);
+    }
 }
 
+/// Accounts in process memory. Passwords are hashed as the database
+/// backends hash them, and never kept in the clear.
 pub struct InMemoryAuthenticator {
-    users: RwLock<HashMap<String, UserRecord>>,
-    /// Reverse index: PlayerId → username, for O(1) display-name lookup.
-    reverse: RwLock<HashMap<PlayerId, String>>,
+    accounts: Mutex<Accounts>,
+    hasher: PasswordHasher,
 }
 
-impl Default for InMemoryAuthenticator {
-    fn default() -> Self {
+impl InMemoryAuthenticator {
+    pub fn new(hasher: PasswordHasher) -> Self {
         Self {
-            users: RwLock::new(HashMap::new()),
-            reverse: RwLock::new(HashMap::new()),
+            accounts: Mutex::default(),
+            hasher,
         }
     }
-}
 
-impl InMemoryAuthenticator {
-    pub fn new() -> Self {
-        Self::default()
+    /// An authenticator holding `users`, as saved by
+    /// [`export`](Self::export).
+    pub fn restore(users: Vec<SavedUser>, hasher: PasswordHasher) -> Self {
+        let auth = Self::new(hasher);
+        {
+            let mut accounts = auth.accounts.lock().unwrap();
+            for user in users {
+                accounts.insert(
+                    user.username,
+                    UserRecord {
+                        password_hash: user.password_hash,
+                        player_id: user.player_id,
+                        role: user.role,
+                    },
+                );
+            }
+        }
+        auth
     }
 
-    /// Pre-register a user and return their PlayerId. Idempotent: returns existing id if username taken.
-    pub fn seed_user(&self, username: &str, password: &str) -> PlayerId {
-        let mut users = self.users.write().unwrap();
-        if let Some(record) = users.get(username) {
-            return record.player_id;
+    pub fn export(&self) -> Vec<SavedUser> {
+        self.accounts
+            .lock()
+            .unwrap()
+            .by_name
+            .iter()
+            .map(|(username, record)| SavedUser {
+                username: username.clone(),
+                password_hash: record.password_hash.clone(),
+                player_id: record.player_id,
+                role: record.role,
+            })
+            .collect()
+    }
+
+    async fn hash(&self, password: Password) -> Result<String, AuthError> {
+        let hasher = self.hasher.clone();
+        tokio::task::spawn_blocking(move || hasher.hash(&password))
+            .await
+            .map_err(|e| AuthError::Failed(e.to_string()))?
+    }
+
+    async fn verify(&self, password: Password, hash: String) -> Result<bool, AuthError> {
+        let hasher = self.hasher.clone();
+        tokio::task::spawn_blocking(move || hasher.verify(&password, &hash))
+            .await
+            .map_err(|e| AuthError::Failed(e.to_string()))?
+    }
+
+    /// Creates the account unless the username is taken. Returns `None` if
+    /// it was, including by a signup that hashed at the same time.
+    async fn insert(
+        &self,
+        username: &str,
+        password: Password,
+        role: Role,
+    ) -> Result<Option<PlayerId>, AuthError> {
+        let password_hash = self.hash(password).await?;
+        let mut accounts = self.accounts.lock().unwrap();
+        if accounts.by_name.contains_key(username) {
+            return Ok(None);
         }
         let pid = PlayerId::new();
-        let mut reverse = self.reverse.write().unwrap();
-        users.insert(
+        accounts.insert(
             username.to_string(),
             UserRecord {
-                password: Password::new(password.to_string()),
+                password_hash,
                 player_id: pid,
+                role,
             },
         );
-        reverse.insert(pid, username.to_string());
-        pid
+        Ok(Some(pid))
     }
 
-    /// Resolve a PlayerId to the username used at login.
-    pub fn lookup_username(&self, player_id: PlayerId) -> Option<String> {
-        self.reverse.read().unwrap().get(&player_id).cloned()
+    fn find(&self, username: &str) -> Option<(PlayerId, String)> {
+        let accounts = self.accounts.lock().unwrap();
+        accounts
+            .by_name
+            .get(username)
+            .map(|record| (record.player_id, record.password_hash.clone()))
     }
 }
 
 #[async_trait]
 impl Authenticator for InMemoryAuthenticator {
     async fn authenticate(&self, payload: &AuthPayload) -> Result<PlayerId, AuthError> {
-        let mut users = self.users.write().unwrap();
-        if let Some(record) = users.get(&payload.username) {
-            if record.password == payload.password {
-                let pid = record.player_id;
-                return Ok(pid);
-            } else {
-                return Err(AuthError::WrongPassword);
+        let Some((pid, hash)) = self.find(&payload.username) else {
+            // As slow as a wrong password, so the answer does not tell
+            // whether the username exists.
+            self.verify(payload.password.clone(), self.hasher.dummy_hash())
+                .await?;
+            return Err(AuthError::UnknownUser);
+        };
+        if !self.verify(payload.password.clone(), hash.clone()).await? {
+            return Err(AuthError::WrongPassword);
+        }
+        if self.hasher.needs_rehash(&hash) {
+            let rehashed = self.hash(payload.password.clone()).await?;
+            let mut accounts = self.accounts.lock().unwrap();
+            if let Some(record) = accounts.by_name.get_mut(&payload.username) {
+                record.password_hash = rehashed;
             }
         }
-        // Auto-register on first login — hold both locks together to keep maps in sync.
+        Ok(pid)
+    }
+
+    async fn register(&self, payload: &AuthPayload) -> Result<PlayerId, AuthError> {
+        policy::validate(payload)?;
+        self.insert(&payload.username, payload.password.clone(), Role::Player)
+            .await?
+            .ok_or(AuthError::UsernameTaken)
+    }
+
+    async fn seed_user(&self, username: &str, password: &str) -> Result<PlayerId, AuthError> {
+        if let Some((pid, _)) = self.find(username) {
+            return Ok(pid);
+        }
+        let password = Password::new(password.to_string());
+        match self.insert(username, password, Role::Player).await? {
+            Some(pid) => Ok(pid),
+            None => self
+                .find(username)
+                .map(|(pid, _)| pid)
+                .ok_or_else(|| AuthError::Failed("could not seed account".into())),
+        }
+    }
+
+    async fn lookup_username(&self, player_id: PlayerId) -> Result<Option<String>, AuthError> {
+        Ok(self.accounts.lock().unwrap().names.get(&player_id).cloned())
+    }
+
+    async fn role(&self, player_id: PlayerId) -> Result<Role, AuthError> {
+        let accounts = self.accounts.lock().unwrap();
+        accounts
+            .names
+            .get(&player_id)
+            .and_then(|username| accounts.by_name.get(username))
+            .map(|record| record.role)
+            .ok_or(AuthError::UnknownUser)
+    }
+
+    async fn has_admin(&self) -> Result<bool, AuthError> {
+        let accounts = self.accounts.lock().unwrap();
+        Ok(accounts.by_name.values().any(|r| r.role == Role::Admin))
+    }
+
+    async fn bootstrap_admin(
+        &self,
+        username: &str,
+        password: &Password,
+    ) -> Result<Option<PlayerId>, AuthError> {
+        if self.has_admin().await? {
+            return Ok(None);
+        }
+        policy::validate_password(password.expose(), username)?;
+        let password_hash = self.hash(password.clone()).await?;
+        let mut accounts = self.accounts.lock().unwrap();
+        // Checked again under the lock, as hashing let others in.
+        if accounts.by_name.values().any(|r| r.role == Role::Admin) {
+            return Ok(None);
+        }
+        if accounts.by_name.contains_key(username) {
+            return Err(AuthError::UsernameTaken);
+        }
         let pid = PlayerId::new();
-        let mut reverse = self.reverse.write().unwrap();
-        users.insert(
-            payload.username.clone(),
+        accounts.insert(
+            username.to_string(),
             UserRecord {
-                password: payload.password.clone(),
+                password_hash,
                 player_id: pid,
+                role: Role::Admin,
             },
         );
-        reverse.insert(pid, payload.username.clone());
-        Ok(pid)
+        Ok(Some(pid))
+    }
+
+    async fn set_role(&self, username: &str, role: Role) -> Result<PlayerId, AuthError> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let record = accounts
+            .by_name
+            .get_mut(username)
+            .ok_or(AuthError::UnknownUser)?;
+        record.role = role;
+        Ok(record.player_id)
     }
 }
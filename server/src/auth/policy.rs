@@ -0,0 +1,74 @@
+use super::{AuthError, AuthPayload};
+
+pub const USERNAME_MIN_LEN: usize = 3;
+pub const USERNAME_MAX_LEN: usize = 32;
+pub const PASSWORD_MIN_LEN: usize = 8;
+pub const PASSWORD_MAX_LEN: usize = 128;
+
+/// Names that would pass for staff or the house at a table. Compared without
+/// case, so `Admin` is taken too.
+const RESERVED_USERNAMES: &[&str] = &[
+    "admin",
+    "administrator",
+    "root",
+    "system",
+    "server",
+    "dealer",
+    "house",
+    "moderator",
+    "support",
+];
+
+/// Checks a signup against the username and password rules. Seeded accounts
+/// skip this, which is how the reserved names get created.
+pub fn validate(payload: &AuthPayload) -> Result<(), AuthError> {
+    validate_username(&payload.username)?;
+    validate_password(payload.password.expose(), &payload.username)
+}
+
+/// 3–32 ASCII letters, digits, `_` or `-`, starting with a letter.
+pub fn validate_username(username: &str) -> Result<(), AuthError> {
+    let invalid = |reason: &str| Err(AuthError::InvalidUsername(reason.to_string()));
+    let len = username.chars().count();
+    if !(USERNAME_MIN_LEN..=USERNAME_MAX_LEN).contains(&len) {
+        return invalid(&format!(
+            "must be {USERNAME_MIN_LEN} to {USERNAME_MAX_LEN} characters"
+        ));
+    }
+    if !username.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return invalid("must start with a letter");
+    }
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return invalid("may only contain letters, digits, '_' and '-'");
+    }
+    if RESERVED_USERNAMES
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(username))
+    {
+        return invalid("is reserved");
+    }
+    Ok(())
+}
+
+/// At least 8 characters mixing letters with digits or symbols, and not the
+/// username.
+pub fn validate_password(password: &str, username: &str) -> Result<(), AuthError> {
+    let weak = |reason: &str| Err(AuthError::WeakPassword(reason.to_string()));
+    let len = password.chars().count();
+    if len < PASSWORD_MIN_LEN {
+        return weak(&format!("must be at least {PASSWORD_MIN_LEN} characters"));
+    }
+    if len > PASSWORD_MAX_LEN {
+        return weak(&format!("must be at most {PASSWORD_MAX_LEN} characters"));
+    }
+    if !password.chars().any(char::is_alphabetic) || password.chars().all(char::is_alphabetic) {
+        return weak("must mix letters with digits or symbols");
+    }
+    if password.to_lowercase().contains(&username.to_lowercase()) {
+        return weak("must not contain the username");
+    }
+    Ok(())
+}
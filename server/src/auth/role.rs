@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use utoipa::ToSchema;
+
+/// What an account may do. New accounts are players; roles are granted by an
+/// admin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Joins tables, takes seats and plays.
+    #[default]
+    Player,
+    /// Drives rounds at manual-dealer tables; does not play.
+    Dealer,
+    /// Manages tables and roles, and may both play and deal.
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Player => "player",
+            Self::Dealer => "dealer",
+            Self::Admin => "admin",
+        }
+    }
+
+    pub fn can_play(&self) -> bool {
+        matches!(self, Self::Player | Self::Admin)
+    }
+
+    pub fn can_deal(&self) -> bool {
+        matches!(self, Self::Dealer | Self::Admin)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "player" => Ok(Self::Player),
+            "dealer" => Ok(Self::Dealer),
+            "admin" => Ok(Self::Admin),
+            other => Err(format!("unknown role '{other}'")),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use bj_core::domain::{
+    engine::{Clock, SystemClock, Timestamp},
+    PlayerId,
+};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use super::AuthError;
+
+/// A login. Access tokens name it by `id`; the refresh token is stored only
+/// as a SHA-256 hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub id: Ulid,
+    pub player_id: PlayerId,
+    pub refresh_hash: String,
+    pub refresh_expires_at: Timestamp,
+    pub revoked: bool,
+}
+
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn insert(&self, session: SessionRecord) -> Result<(), AuthError>;
+    async fn get(&self, id: Ulid) -> Result<Option<SessionRecord>, AuthError>;
+    async fn find_by_refresh(&self, refresh_hash: &str)
+        -> Result<Option<SessionRecord>, AuthError>;
+    /// Swaps the refresh token of a live session and remembers `expected` as
+    /// spent. Returns `false` if the session is revoked or its refresh token
+    /// is no longer `expected`, i.e. it was already used.
+    async fn rotate(
+        &self,
+        id: Ulid,
+        expected: &str,
+        refresh_hash: String,
+        refresh_expires_at: Timestamp,
+    ) -> Result<bool, AuthError>;
+    /// The session that already traded in the refresh token hashed as
+    /// `refresh_hash`, if any.
+    async fn find_by_spent_refresh(&self, refresh_hash: &str) -> Result<Option<Ulid>, AuthError>;
+    async fn revoke(&self, id: Ulid) -> Result<(), AuthError>;
+    /// Revokes every session of `player_id`.
+    async fn revoke_player(&self, player_id: PlayerId) -> Result<(), AuthError>;
+}
+
+/// What [`InMemorySessionStore::export`] saves.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionsState {
+    pub sessions: Vec<SessionRecord>,
+    /// Spent refresh token hashes, by the session that spent them.
+    pub spent: HashMap<String, Ulid>,
+}
+
+#[derive(Default)]
+pub struct InMemorySessionStore {
+    sessions: DashMap<Ulid, SessionRecord>,
+    spent: DashMap<String, Ulid>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A store holding `state`, as saved by [`export`](Self::export).
+    pub fn restore(state: SessionsState) -> Self {
+        Self {
+            sessions: state.sessions.into_iter().map(|s| (s.id, s)).collect(),
+            spent: state.spent.into_iter().collect(),
+        }
+    }
+
+    /// The sessions that can still be refreshed, with the tokens they
+    /// spent. Revoked and expired ones are left out: nothing they hold
+    /// would be accepted again.
+    pub fn export(&self) -> SessionsState {
+        let now = SystemClock.now();
+        let sessions: Vec<SessionRecord> = self
+            .sessions
+            .iter()
+            .filter(|s| !s.revoked && now < s.refresh_expires_at)
+            .map(|s| s.clone())
+            .collect();
+        let kept: HashSet<Ulid> = sessions.iter().map(|s| s.id).collect();
+        SessionsState {
+            spent: self
+                .spent
+                .iter()
+                .filter(|s| kept.contains(s.value()))
+                .map(|s| (s.key().clone(), *s.value()))
+                .collect(),
+            sessions,
+        }
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn insert(&self, session: SessionRecord) -> Result<(), AuthError> {
+        self.sessions.insert(session.id, session);
+        Ok(())
+    }
+
+    async fn get(&self, id: Ulid) -> Result<Option<SessionRecord>, AuthError> {
+        Ok(self.sessions.get(&id).map(|s| s.clone()))
+    }
+
+    async fn find_by_refresh(
+        &self,
+        refresh_hash: &str,
+    ) -> Result<Option<SessionRecord>, AuthError> {
+        Ok(self
+            .sessions
+            .iter()
+            .find(|s| s.refresh_hash == refresh_hash)
+            .map(|s| s.clone()))
+    }
+
+    async fn rotate(
+        &self,
+        id: Ulid,
+        expected: &str,
+        refresh_hash: String,
+        refresh_expires_at: Timestamp,
+    ) -> Result<bool, AuthError> {
+        // The entry guard is held until the swap is done, so a refresh token
+        // can only be used once.
+        let Some(mut session) = self.sessions.get_mut(&id) else {
+            return Ok(false);
+        };
+        if session.revoked || session.refresh_hash != expected {
+            return Ok(false);
+        }
+        session.refresh_hash = refresh_hash;
+        session.refresh_expires_at = refresh_expires_at;
+        self.spent.insert(expected.to_string(), id);
+        Ok(true)
+    }
+
+    async fn find_by_spent_refresh(&self, refresh_hash: &str) -> Result<Option<Ulid>, AuthError> {
+        Ok(self.spent.get(refresh_hash).map(|id| *id))
+    }
+
+    async fn revoke(&self, id: Ulid) -> Result<(), AuthError> {
+        if let Some(mut session) = self.sessions.get_mut(&id) {
+            session.revoked = true;
+        }
+        Ok(())
+    }
+
+    async fn revoke_player(&self, player_id: PlayerId) -> Result<(), AuthError> {
+        for mut session in self.sessions.iter_mut() {
+            if session.player_id == player_id {
+                session.revoked = true;
+            }
+        }
+        Ok(())
+    }
+}
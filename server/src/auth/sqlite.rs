@@ -0,0 +1,335 @@
+use async_trait::async_trait;
+use bj_core::domain::{
+    engine::{Clock, SystemClock, Timestamp},
+    PlayerId,
+};
+use sqlx::{Row, SqlitePool};
+use tracing::{info, warn};
+use ulid::Ulid;
+
+use super::{
+    policy,
+    session_store::{SessionRecord, SessionStore},
+    AuthError, AuthPayload, Authenticator, Password, PasswordHasher, Role,
+};
+
+/// Accounts in the SQLite `users` table. Works like
+/// [`PostgresAuthenticator`](super::postgres::PostgresAuthenticator).
+pub struct SqliteAuthenticator {
+    pool: SqlitePool,
+    hasher: PasswordHasher,
+}
+
+impl SqliteAuthenticator {
+    pub fn new(pool: SqlitePool, hasher: PasswordHasher) -> Self {
+        Self { pool, hasher }
+    }
+
+    async fn hash(&self, password: Password) -> Result<String, AuthError> {
+        let hasher = self.hasher.clone();
+        tokio::task::spawn_blocking(move || hasher.hash(&password))
+            .await
+            .map_err(|e| AuthError::Failed(e.to_string()))?
+    }
+
+    async fn verify(&self, password: Password, hash: String) -> Result<bool, AuthError> {
+        let hasher = self.hasher.clone();
+        tokio::task::spawn_blocking(move || hasher.verify(&password, &hash))
+            .await
+            .map_err(|e| AuthError::Failed(e.to_string()))?
+    }
+
+    async fn find(&self, username: &str) -> Result<Option<(PlayerId, String)>, AuthError> {
+        let row = sqlx::query("SELECT player_id, password_hash FROM users WHERE username = $1")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(backend)?;
+        row.map(|row| {
+            let pid: String = row.try_get("player_id").map_err(backend)?;
+            let hash: String = row.try_get("password_hash").map_err(backend)?;
+            Ok((pid.parse().map_err(backend)?, hash))
+        })
+        .transpose()
+    }
+
+    /// Creates the account unless the username is taken. Returns `None` if it
+    /// was, including by a concurrent signup.
+    async fn insert(
+        &self,
+        username: &str,
+        password: Password,
+        role: Role,
+    ) -> Result<Option<PlayerId>, AuthError> {
+        let pid = PlayerId::new();
+        let hash = self.hash(password).await?;
+        let inserted = sqlx::query(
+            "INSERT INTO users (player_id, username, password_hash, role, created_at) \
+             VALUES ($1, $2, $3, $4, $5) ON CONFLICT (username) DO NOTHING",
+        )
+        .bind(pid.to_string())
+        .bind(username)
+        .bind(hash)
+        .bind(role.as_str())
+        .bind(SystemClock.now().as_millis() as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(backend)?
+        .rows_affected();
+        Ok((inserted == 1).then_some(pid))
+    }
+
+    async fn rehash(&self, player_id: PlayerId, password: Password) -> Result<(), AuthError> {
+        let hash = self.hash(password).await?;
+        sqlx::query("UPDATE users SET password_hash = $2 WHERE player_id = $1")
+            .bind(player_id.to_string())
+            .bind(hash)
+            .execute(&self.pool)
+            .await
+            .map_err(backend)?;
+        Ok(())
+    }
+}
+
+fn backend(e: impl ToString) -> AuthError {
+    AuthError::Failed(e.to_string())
+}
+
+#[async_trait]
+impl Authenticator for SqliteAuthenticator {
+    async fn authenticate(&self, payload: &AuthPayload) -> Result<PlayerId, AuthError> {
+        let Some((pid, hash)) = self.find(&payload.username).await? else {
+            // As slow as a wrong password, so the answer does not tell
+            // whether the username exists.
+            self.verify(payload.password.clone(), self.hasher.dummy_hash())
+                .await?;
+            return Err(AuthError::UnknownUser);
+        };
+        if !self.verify(payload.password.clone(), hash.clone()).await? {
+            return Err(AuthError::WrongPassword);
+        }
+        if self.hasher.needs_rehash(&hash) {
+            match self.rehash(pid, payload.password.clone()).await {
+                Ok(()) => info!("player={pid} password rehashed with current parameters"),
+                Err(e) => warn!("player={pid} password rehash failed: {e}"),
+            }
+        }
+        Ok(pid)
+    }
+
+    async fn register(&self, payload: &AuthPayload) -> Result<PlayerId, AuthError> {
+        policy::validate(payload)?;
+        self.insert(&payload.username, payload.password.clone(), Role::Player)
+            .await?
+            .ok_or(AuthError::UsernameTaken)
+    }
+
+    async fn seed_user(&self, username: &str, password: &str) -> Result<PlayerId, AuthError> {
+        if let Some((pid, _)) = self.find(username).await? {
+            return Ok(pid);
+        }
+        let password = Password::new(password.to_string());
+        match self.insert(username, password, Role::Player).await? {
+            Some(pid) => Ok(pid),
+            None => self
+                .find(username)
+                .await?
+                .map(|(pid, _)| pid)
+                .ok_or_else(|| AuthError::Failed("could not seed account".into())),
+        }
+    }
+
+    async fn lookup_username(&self, player_id: PlayerId) -> Result<Option<String>, AuthError> {
+        sqlx::query_scalar("SELECT username FROM users WHERE player_id = $1")
+            .bind(player_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(backend)
+    }
+
+    async fn role(&self, player_id: PlayerId) -> Result<Role, AuthError> {
+        let role: String = sqlx::query_scalar("SELECT role FROM users WHERE player_id = $1")
+            .bind(player_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(backend)?
+            .ok_or(AuthError::UnknownUser)?;
+        role.parse().map_err(backend)
+    }
+
+    async fn has_admin(&self) -> Result<bool, AuthError> {
+        let admins: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE role = $1")
+            .bind(Role::Admin.as_str())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(backend)?;
+        Ok(admins > 0)
+    }
+
+    async fn bootstrap_admin(
+        &self,
+        username: &str,
+        password: &Password,
+    ) -> Result<Option<PlayerId>, AuthError> {
+        if self.has_admin().await? {
+            return Ok(None);
+        }
+        policy::validate_password(password.expose(), username)?;
+        match self.insert(username, password.clone(), Role::Admin).await? {
+            Some(pid) => Ok(Some(pid)),
+            // Another node may have just created it.
+            None if self.has_admin().await? => Ok(None),
+            None => Err(AuthError::UsernameTaken),
+        }
+    }
+
+    async fn set_role(&self, username: &str, role: Role) -> Result<PlayerId, AuthError> {
+        let pid: String = sqlx::query_scalar(
+            "UPDATE users SET role = $2 WHERE username = $1 RETURNING player_id",
+        )
+        .bind(username)
+        .bind(role.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(backend)?
+        .ok_or(AuthError::UnknownUser)?;
+        pid.parse().map_err(backend)
+    }
+}
+
+/// Login sessions in the SQLite `auth_sessions` table.
+pub struct SqliteSessionStore {
+    pool: SqlitePool,
+}
+
+impl SqliteSessionStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+fn session_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<SessionRecord, AuthError> {
+    let id: String = row.try_get("id").map_err(backend)?;
+    let player_id: String = row.try_get("player_id").map_err(backend)?;
+    let expires_at: i64 = row.try_get("refresh_expires_at").map_err(backend)?;
+    Ok(SessionRecord {
+        id: id.parse().map_err(backend)?,
+        player_id: player_id.parse().map_err(backend)?,
+        refresh_hash: row.try_get("refresh_hash").map_err(backend)?,
+        refresh_expires_at: Timestamp::from_millis(expires_at as u64),
+        revoked: row.try_get("revoked").map_err(backend)?,
+    })
+}
+
+const SESSION_COLUMNS: &str = "id, player_id, refresh_hash, refresh_expires_at, revoked";
+
+#[async_trait]
+impl SessionStore for SqliteSessionStore {
+    async fn insert(&self, session: SessionRecord) -> Result<(), AuthError> {
+        sqlx::query(
+            "INSERT INTO auth_sessions \
+             (id, player_id, refresh_hash, refresh_expires_at, revoked, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(session.id.to_string())
+        .bind(session.player_id.to_string())
+        .bind(session.refresh_hash)
+        .bind(session.refresh_expires_at.as_millis() as i64)
+        .bind(session.revoked)
+        .bind(SystemClock.now().as_millis() as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(backend)?;
+        Ok(())
+    }
+
+    async fn get(&self, id: Ulid) -> Result<Option<SessionRecord>, AuthError> {
+        sqlx::query(&format!(
+            "SELECT {SESSION_COLUMNS} FROM auth_sessions WHERE id = $1"
+        ))
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(backend)?
+        .as_ref()
+        .map(session_from_row)
+        .transpose()
+    }
+
+    async fn find_by_refresh(
+        &self,
+        refresh_hash: &str,
+    ) -> Result<Option<SessionRecord>, AuthError> {
+        sqlx::query(&format!(
+            "SELECT {SESSION_COLUMNS} FROM auth_sessions WHERE refresh_hash = $1"
+        ))
+        .bind(refresh_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(backend)?
+        .as_ref()
+        .map(session_from_row)
+        .transpose()
+    }
+
+    async fn rotate(
+        &self,
+        id: Ulid,
+        expected: &str,
+        refresh_hash: String,
+        refresh_expires_at: Timestamp,
+    ) -> Result<bool, AuthError> {
+        let mut tx = self.pool.begin().await.map_err(backend)?;
+        let updated = sqlx::query(
+            "UPDATE auth_sessions SET refresh_hash = $3, refresh_expires_at = $4 \
+             WHERE id = $1 AND refresh_hash = $2 AND NOT revoked",
+        )
+        .bind(id.to_string())
+        .bind(expected)
+        .bind(refresh_hash)
+        .bind(refresh_expires_at.as_millis() as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(backend)?
+        .rows_affected();
+        if updated != 1 {
+            return Ok(false);
+        }
+        sqlx::query("INSERT INTO auth_spent_refresh (refresh_hash, session_id) VALUES ($1, $2)")
+            .bind(expected)
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(backend)?;
+        tx.commit().await.map_err(backend)?;
+        Ok(true)
+    }
+
+    async fn find_by_spent_refresh(&self, refresh_hash: &str) -> Result<Option<Ulid>, AuthError> {
+        let id: Option<String> =
+            sqlx::query_scalar("SELECT session_id FROM auth_spent_refresh WHERE refresh_hash = $1")
+                .bind(refresh_hash)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(backend)?;
+        id.map(|id| id.parse().map_err(backend)).transpose()
+    }
+
+    async fn revoke(&self, id: Ulid) -> Result<(), AuthError> {
+        sqlx::query("UPDATE auth_sessions SET revoked = TRUE WHERE id = $1")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(backend)?;
+        Ok(())
+    }
+
+    async fn revoke_player(&self, player_id: PlayerId) -> Result<(), AuthError> {
+        sqlx::query("UPDATE auth_sessions SET revoked = TRUE WHERE player_id = $1")
+            .bind(player_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(backend)?;
+        Ok(())
+    }
+}
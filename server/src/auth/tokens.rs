@@ -0,0 +1,245 @@
+use std::{sync::Arc, time::Duration};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bj_core::domain::{
+    engine::{Clock, SystemClock, Timestamp},
+    PlayerId,
+};
+use jsonwebtoken::{errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use ulid::Ulid;
+use utoipa::ToSchema;
+
+use super::{
+    session_store::{SessionRecord, SessionStore},
+    AuthError,
+};
+use crate::config::TokenSettings;
+
+/// What a client gets from logging in or refreshing.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenPair {
+    pub player_id: String,
+    /// Signed, short-lived token for `Authorization: Bearer` and WS `Auth`.
+    pub access_token: String,
+    /// Unix time in milliseconds.
+    pub access_expires_at: u64,
+    /// Single-use token for `POST /auth/refresh`.
+    pub refresh_token: String,
+    /// Unix time in milliseconds.
+    pub refresh_expires_at: u64,
+}
+
+/// A verified access token.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionClaims {
+    pub player_id: PlayerId,
+    pub session_id: Ulid,
+    /// When the access token expires.
+    pub expires_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    sid: String,
+    /// Seconds since the epoch, as JWT requires.
+    exp: u64,
+}
+
+/// Issues, refreshes, verifies and revokes login sessions.
+///
+/// Access tokens are HS256 JWTs naming the player and the session. They are
+/// checked against the [`SessionStore`] on every use, so revoking a session
+/// takes effect before the token expires. Refresh tokens are random, stored
+/// hashed, and replaced on every refresh.
+pub struct Sessions {
+    store: Arc<dyn SessionStore>,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    access_ttl: Duration,
+    refresh_ttl: Duration,
+    recheck: Duration,
+}
+
+impl Sessions {
+    pub fn new(store: Arc<dyn SessionStore>, settings: &TokenSettings) -> Self {
+        let secret = match &settings.secret {
+            Some(secret) => secret.expose_secret().as_bytes().to_vec(),
+            None => {
+                warn!("auth.tokens.secret is not set; sessions will not survive a restart");
+                random_bytes().to_vec()
+            }
+        };
+        Self {
+            store,
+            encoding: EncodingKey::from_secret(&secret),
+            decoding: DecodingKey::from_secret(&secret),
+            access_ttl: Duration::from_secs(settings.access_ttl_secs),
+            refresh_ttl: Duration::from_secs(settings.refresh_ttl_secs),
+            recheck: Duration::from_secs(settings.recheck_secs.max(1)),
+        }
+    }
+
+    /// Starts a new session for an authenticated player.
+    pub async fn issue(&self, player_id: PlayerId) -> Result<TokenPair, AuthError> {
+        let session_id = Ulid::new();
+        let refresh_token = URL_SAFE_NO_PAD.encode(random_bytes());
+        let refresh_expires_at = SystemClock.now().plus(self.refresh_ttl);
+        self.store
+            .insert(SessionRecord {
+                id: session_id,
+                player_id,
+                refresh_hash: hash(&refresh_token),
+                refresh_expires_at,
+                revoked: false,
+            })
+            .await?;
+        self.pair(player_id, session_id, refresh_token, refresh_expires_at)
+    }
+
+    /// Trades a refresh token for a new pair. The old refresh token stops
+    /// working.
+    ///
+    /// A refresh token used twice was copied: whoever holds the other copy
+    /// may be the thief, so the whole session is revoked and neither can
+    /// refresh again.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<TokenPair, AuthError> {
+        let old_hash = hash(refresh_token);
+        let Some(session) = self.store.find_by_refresh(&old_hash).await? else {
+            return match self.store.find_by_spent_refresh(&old_hash).await? {
+                Some(session_id) => self.revoke_reused(session_id).await,
+                None => Err(AuthError::InvalidToken),
+            };
+        };
+        if session.revoked {
+            return Err(AuthError::SessionRevoked);
+        }
+        if SystemClock.now() >= session.refresh_expires_at {
+            return Err(AuthError::TokenExpired);
+        }
+
+        let refresh_token = URL_SAFE_NO_PAD.encode(random_bytes());
+        let refresh_expires_at = SystemClock.now().plus(self.refresh_ttl);
+        let rotated = self
+            .store
+            .rotate(
+                session.id,
+                &old_hash,
+                hash(&refresh_token),
+                refresh_expires_at,
+            )
+            .await?;
+        if !rotated {
+            // Spent by a concurrent refresh, or revoked meanwhile.
+            return self.revoke_reused(session.id).await;
+        }
+        self.pair(
+            session.player_id,
+            session.id,
+            refresh_token,
+            refresh_expires_at,
+        )
+    }
+
+    async fn revoke_reused(&self, session_id: Ulid) -> Result<TokenPair, AuthError> {
+        warn!("session={session_id} refresh token reused; revoking the session");
+        self.store.revoke(session_id).await?;
+        Err(AuthError::SessionRevoked)
+    }
+
+    /// Checks an access token's signature, expiry and session.
+    pub async fn verify(&self, access_token: &str) -> Result<SessionClaims, AuthError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+        let claims = jsonwebtoken::decode::<Claims>(access_token, &self.decoding, &validation)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::TokenExpired,
+                _ => AuthError::InvalidToken,
+            })?
+            .claims;
+        let player_id: PlayerId = claims.sub.parse().map_err(|_| AuthError::InvalidToken)?;
+        let session_id: Ulid = claims.sid.parse().map_err(|_| AuthError::InvalidToken)?;
+
+        match self.store.get(session_id).await? {
+            Some(s) if s.revoked => Err(AuthError::SessionRevoked),
+            Some(s) if s.player_id == player_id => Ok(SessionClaims {
+                player_id,
+                session_id,
+                expires_at: Timestamp::from_millis(claims.exp * 1000),
+            }),
+            _ => Err(AuthError::InvalidToken),
+        }
+    }
+
+    /// Checks again a token [`verify`](Self::verify) accepted, for a
+    /// connection that outlives it: its session must not have been revoked
+    /// since, nor the token have expired.
+    pub async fn recheck(&self, claims: &SessionClaims) -> Result<(), AuthError> {
+        if SystemClock.now() >= claims.expires_at {
+            return Err(AuthError::TokenExpired);
+        }
+        match self.store.get(claims.session_id).await? {
+            Some(s) if s.revoked => Err(AuthError::SessionRevoked),
+            Some(_) => Ok(()),
+            None => Err(AuthError::InvalidToken),
+        }
+    }
+
+    /// How long until `claims` are due for a [`recheck`](Self::recheck):
+    /// when the token expires, or sooner to catch a revocation.
+    pub fn recheck_in(&self, claims: &SessionClaims) -> Duration {
+        claims
+            .expires_at
+            .saturating_duration_since(SystemClock.now())
+            .min(self.recheck)
+    }
+
+    /// Logs out one session.
+    pub async fn revoke(&self, session_id: Ulid) -> Result<(), AuthError> {
+        self.store.revoke(session_id).await
+    }
+
+    /// Logs a player out everywhere.
+    pub async fn revoke_player(&self, player_id: PlayerId) -> Result<(), AuthError> {
+        self.store.revoke_player(player_id).await
+    }
+
+    fn pair(
+        &self,
+        player_id: PlayerId,
+        session_id: Ulid,
+        refresh_token: String,
+        refresh_expires_at: Timestamp,
+    ) -> Result<TokenPair, AuthError> {
+        let exp = SystemClock.now().plus(self.access_ttl).as_millis() / 1000;
+        let claims = Claims {
+            sub: player_id.to_string(),
+            sid: session_id.to_string(),
+            exp,
+        };
+        let access_token = jsonwebtoken::encode(&Header::default(), &claims, &self.encoding)
+            .map_err(|e| AuthError::Failed(format!("could not sign token: {e}")))?;
+        Ok(TokenPair {
+            player_id: player_id.to_string(),
+            access_token,
+            access_expires_at: exp * 1000,
+            refresh_token,
+            refresh_expires_at: refresh_expires_at.as_millis(),
+        })
+    }
+}
+
+fn random_bytes() -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn hash(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,376 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use bj_core::domain::TableId;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+use ulid::Ulid;
+
+use super::{
+    partition_of, BusError, CommandBus, CommandConsumer, CommandEnvelope, Delivery, Replies, Reply,
+};
+use crate::{session::table_actor::TableCommand, telemetry::TraceContext};
+
+/// Commands in an append-only log per partition, under one directory.
+///
+/// Every command is on disk before `send` returns, and stays there until
+/// a consumer acknowledges it. A consumer that stops before acknowledging,
+/// or a process that dies, gets the rest of the log again from the
+/// partition's next consumer: delivery is at least once.
+///
+/// Files per partition `n`: `partition-n.log` holds one JSON [`Delivery`]
+/// per line; `partition-n.ack` holds one line per acknowledged command, with
+/// its offset and envelope id. Acknowledgements are written as they come
+/// but only synced every [`ACK_BATCH`] commands or when the consumer runs
+/// out of work, so a machine that loses power may deliver that many again;
+/// a process that dies does not. A command whose envelope the ack file
+/// already holds is not delivered again, and acknowledged commands are
+/// dropped from the log when a consumer starts.
+pub struct FileCommandBus {
+    partitions: Vec<Arc<Partition>>,
+    replies: Arc<Replies>,
+}
+
+/// Acknowledgements written to an ack file before it is synced.
+pub const ACK_BATCH: usize = 64;
+
+struct Partition {
+    index: u32,
+    log_path: PathBuf,
+    ack_path: PathBuf,
+    log: Mutex<Log>,
+}
+
+struct Log {
+    file: File,
+    next_offset: u64,
+    /// Feeds the partition's consumer, if it has one.
+    live: Option<mpsc::UnboundedSender<Delivery>>,
+}
+
+impl FileCommandBus {
+    /// Opens the logs in `dir`, creating it and them as needed. Commands
+    /// left unacknowledged by a previous run are delivered to the first
+    /// consumer of their partition.
+    pub fn open(dir: impl AsRef<Path>, partitions: u32) -> Result<Self, BusError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let partitions = (0..partitions.max(1))
+            .map(|index| {
+                let log_path = dir.join(format!("partition-{index}.log"));
+                let ack_path = dir.join(format!("partition-{index}.ack"));
+                let acked = read_ack(&ack_path)?;
+                let last = read_log(&log_path)?.last().map_or(0, |d| d.offset);
+                Ok(Arc::new(Partition {
+                    index,
+                    log: Mutex::new(Log {
+                        file: append_to(&log_path)?,
+                        next_offset: last.max(acked.offset) + 1,
+                        live: None,
+                    }),
+                    log_path,
+                    ack_path,
+                }))
+            })
+            .collect::<Result<_, BusError>>()?;
+        Ok(Self {
+            partitions,
+            replies: Arc::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl CommandBus for FileCommandBus {
+    fn partitions(&self) -> u32 {
+        self.partitions.len() as u32
+    }
+
+    async fn send(
+        &self,
+        table_id: TableId,
+        command: TableCommand,
+    ) -> Result<oneshot::Receiver<Reply>, BusError> {
+        let (id, reply) = self.replies.register();
+        let envelope = CommandEnvelope {
+            id,
+            origin: None,
+            table_id,
+            command,
+            trace: TraceContext::current(),
+        };
+        let partition = self.partitions[partition_of(table_id, self.partitions()) as usize].clone();
+        let appended = tokio::task::spawn_blocking(move || partition.append(envelope))
+            .await
+            .map_err(|_| BusError::Closed)
+            .and_then(|appended| appended);
+        if let Err(e) = appended {
+            self.replies.cancel(id);
+            return Err(e);
+        }
+        Ok(reply)
+    }
+
+    fn subscribe(&self, partition: u32) -> Result<Box<dyn CommandConsumer>, BusError> {
+        let partition = self
+            .partitions
+            .get(partition as usize)
+            .ok_or(BusError::UnknownPartition(partition))?;
+        let (rx, acks) = partition.attach()?;
+        Ok(Box::new(FileConsumer {
+            rx,
+            acks,
+            taken: HashMap::new(),
+            unsynced: 0,
+            replies: self.replies.clone(),
+        }))
+    }
+}
+
+impl Partition {
+    fn append(&self, envelope: CommandEnvelope) -> Result<(), BusError> {
+        let mut log = self.log.lock().unwrap();
+        let delivery = Delivery {
+            offset: log.next_offset,
+            envelope,
+        };
+        let mut line = serde_json::to_vec(&delivery).map_err(io::Error::from)?;
+        line.push(b'\n');
+        log.file.write_all(&line)?;
+        log.file.sync_data()?;
+        log.next_offset += 1;
+        if let Some(live) = &log.live {
+            if live.send(delivery).is_err() {
+                log.live = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts a consumer: queues everything not yet acknowledged, then
+    /// whatever is published from now on, and hands back the ack file to
+    /// acknowledge them in. The log lock keeps publishers out in between,
+    /// so nothing is missed or delivered twice.
+    fn attach(&self) -> Result<(mpsc::UnboundedReceiver<Delivery>, File), BusError> {
+        let mut log = self.log.lock().unwrap();
+        if log.live.as_ref().is_some_and(|live| !live.is_closed()) {
+            return Err(BusError::PartitionTaken(self.index));
+        }
+        let acked = read_ack(&self.ack_path)?;
+        let backlog: Vec<Delivery> = read_log(&self.log_path)?
+            .into_iter()
+            .filter(|d| !acked.applied(d.offset, &d.envelope))
+            .collect();
+
+        let tmp = self.log_path.with_extension("log.tmp");
+        let mut compacted = File::create(&tmp)?;
+        for delivery in &backlog {
+            serde_json::to_writer(&mut compacted, delivery).map_err(io::Error::from)?;
+            compacted.write_all(b"\n")?;
+        }
+        compacted.sync_all()?;
+        fs::rename(&tmp, &self.log_path)?;
+        log.file = append_to(&self.log_path)?;
+        let acks = acked.compact(&self.ack_path)?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        for delivery in backlog {
+            let _ = tx.send(delivery);
+        }
+        log.live = Some(tx);
+        Ok((rx, acks))
+    }
+}
+
+struct FileConsumer {
+    rx: mpsc::UnboundedReceiver<Delivery>,
+    /// The partition's ack file, open for appending.
+    acks: File,
+    /// The command handed out at each offset not yet acknowledged.
+    taken: HashMap<u64, (TableId, Ulid)>,
+    /// Acknowledgements written since the ack file was last synced.
+    unsynced: usize,
+    replies: Arc<Replies>,
+}
+
+#[async_trait]
+impl CommandConsumer for FileConsumer {
+    async fn next(&mut self) -> Option<Delivery> {
+        let delivery = self.rx.recv().await?;
+        let envelope = &delivery.envelope;
+        self.taken
+            .insert(delivery.offset, (envelope.table_id, envelope.id));
+        Some(delivery)
+    }
+
+    async fn reply(&mut self, id: Ulid, _origin: Option<&str>, reply: Reply) {
+        self.replies.complete(id, reply);
+    }
+
+    async fn ack(&mut self, offset: u64) -> Result<(), BusError> {
+        let taken = self.taken.remove(&offset);
+        let line = AckLine {
+            offset,
+            table_id: taken.map(|(table_id, _)| table_id),
+            id: taken.map(|(_, id)| id),
+        };
+        line.write_to(&mut self.acks)?;
+        self.unsynced += 1;
+        if self.unsynced >= ACK_BATCH || self.rx.is_empty() {
+            let acks = self.acks.try_clone()?;
+            tokio::task::spawn_blocking(move || acks.sync_data())
+                .await
+                .map_err(|_| BusError::Closed)??;
+            self.unsynced = 0;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for FileConsumer {
+    fn drop(&mut self) {
+        if self.unsynced > 0 {
+            if let Err(e) = self.acks.sync_data() {
+                warn!("ack file not synced: {e}");
+            }
+        }
+    }
+}
+
+/// One line of an ack file.
+#[derive(Serialize, Deserialize)]
+struct AckLine {
+    offset: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    table_id: Option<TableId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<Ulid>,
+}
+
+impl AckLine {
+    fn write_to(&self, file: &mut File) -> io::Result<()> {
+        let mut line = serde_json::to_vec(self).map_err(io::Error::from)?;
+        line.push(b'\n');
+        file.write_all(&line)
+    }
+}
+
+/// What an ack file says has been handled.
+#[derive(Default)]
+struct Acked {
+    /// The highest offset acknowledged.
+    offset: u64,
+    /// The last command acknowledged for each table.
+    last: HashMap<TableId, (u64, Ulid)>,
+}
+
+impl Acked {
+    /// Whether the command at `offset` was handled already. One whose
+    /// envelope was the last handled for its table counts too, even at
+    /// another offset.
+    fn applied(&self, offset: u64, envelope: &CommandEnvelope) -> bool {
+        offset <= self.offset
+            || self
+                .last
+                .get(&envelope.table_id)
+                .is_some_and(|(_, id)| *id == envelope.id)
+    }
+
+    /// Rewrites the ack file down to the last command of each table, and
+    /// opens it for appending.
+    fn compact(&self, path: &Path) -> io::Result<File> {
+        let tmp = path.with_extension("ack.tmp");
+        let mut file = File::create(&tmp)?;
+        AckLine {
+            offset: self.offset,
+            table_id: None,
+            id: None,
+        }
+        .write_to(&mut file)?;
+        for (table_id, (offset, id)) in &self.last {
+            AckLine {
+                offset: *offset,
+                table_id: Some(*table_id),
+                id: Some(*id),
+            }
+            .write_to(&mut file)?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        append_to(path)
+    }
+}
+
+fn append_to(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// The log's entries, oldest first. A line that does not parse, such as one
+/// cut short by a crash, is skipped.
+fn read_log(path: &Path) -> io::Result<Vec<Delivery>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let mut entries = vec![];
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(delivery) => entries.push(delivery),
+            Err(e) => warn!("{}: skipping unreadable entry: {e}", path.display()),
+        }
+    }
+    Ok(entries)
+}
+
+/// Everything the ack file acknowledges. A line that does not parse, such
+/// as one cut short by a crash, is skipped; a bare number, as older ack
+/// files hold, is an offset.
+fn read_ack(path: &Path) -> io::Result<Acked> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Acked::default()),
+        Err(e) => return Err(e),
+    };
+    let mut acked = Acked::default();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let ack = match serde_json::from_str::<AckLine>(line) {
+            Ok(ack) => ack,
+            Err(e) => match line.parse() {
+                Ok(offset) => AckLine {
+                    offset,
+                    table_id: None,
+                    id: None,
+                },
+                Err(_) => {
+                    warn!("{}: skipping unreadable ack: {e}", path.display());
+                    continue;
+                }
+            },
+        };
+        acked.offset = acked.offset.max(ack.offset);
+        if let (Some(table_id), Some(id)) = (ack.table_id, ack.id) {
+            let last = acked.last.entry(table_id).or_insert((ack.offset, id));
+            if ack.offset >= last.0 {
+                *last = (ack.offset, id);
+            }
+        }
+    }
+    Ok(acked)
+}
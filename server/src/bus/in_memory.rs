@@ -0,0 +1,201 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bj_core::domain::{engine::event::GameEvent, TableId};
+use dashmap::DashMap;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use ulid::Ulid;
+
+use super::{
+    partition_of, BusError, CommandBus, CommandConsumer, CommandEnvelope, Delivery, EventBus,
+    Replies, Reply, TableDirectory,
+};
+use crate::{
+    session::{summary::TableSummary, table_actor::TableCommand},
+    telemetry::TraceContext,
+};
+
+const PARTITION_CAPACITY: usize = 1024;
+const EVENT_CAPACITY: usize = 256;
+
+type Slot = Arc<Mutex<Option<mpsc::Receiver<CommandEnvelope>>>>;
+
+/// Commands in process memory, one channel per partition.
+///
+/// Delivery is at most once: a command taken by a consumer that stops
+/// before handling it is gone.
+pub struct InMemoryCommandBus {
+    senders: Vec<mpsc::Sender<CommandEnvelope>>,
+    receivers: Vec<Slot>,
+    replies: Arc<Replies>,
+}
+
+impl InMemoryCommandBus {
+    pub fn new(partitions: u32) -> Self {
+        let (senders, receivers) = (0..partitions.max(1))
+            .map(|_| {
+                let (tx, rx) = mpsc::channel(PARTITION_CAPACITY);
+                (tx, Arc::new(Mutex::new(Some(rx))))
+            })
+            .unzip();
+        Self {
+            senders,
+            receivers,
+            replies: Arc::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl CommandBus for InMemoryCommandBus {
+    fn partitions(&self) -> u32 {
+        self.senders.len() as u32
+    }
+
+    async fn send(
+        &self,
+        table_id: TableId,
+        command: TableCommand,
+    ) -> Result<oneshot::Receiver<Reply>, BusError> {
+        let (id, reply) = self.replies.register();
+        let envelope = CommandEnvelope {
+            id,
+            origin: None,
+            table_id,
+            command,
+            trace: TraceContext::current(),
+        };
+        let partition = partition_of(table_id, self.partitions());
+        if self.senders[partition as usize]
+            .send(envelope)
+            .await
+            .is_err()
+        {
+            self.replies.cancel(id);
+            return Err(BusError::Closed);
+        }
+        Ok(reply)
+    }
+
+    fn subscribe(&self, partition: u32) -> Result<Box<dyn CommandConsumer>, BusError> {
+        let slot = self
+            .receivers
+            .get(partition as usize)
+            .ok_or(BusError::UnknownPartition(partition))?;
+        let rx = slot
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or(BusError::PartitionTaken(partition))?;
+        Ok(Box::new(InMemoryConsumer {
+            rx: Some(rx),
+            slot: slot.clone(),
+            replies: self.replies.clone(),
+            offset: 0,
+        }))
+    }
+}
+
+struct InMemoryConsumer {
+    /// Only `None` while being dropped.
+    rx: Option<mpsc::Receiver<CommandEnvelope>>,
+    slot: Slot,
+    replies: Arc<Replies>,
+    offset: u64,
+}
+
+#[async_trait]
+impl CommandConsumer for InMemoryConsumer {
+    async fn next(&mut self) -> Option<Delivery> {
+        let envelope = self.rx.as_mut()?.recv().await?;
+        self.offset += 1;
+        Some(Delivery {
+            offset: self.offset,
+            envelope,
+        })
+    }
+
+    async fn reply(&mut self, id: Ulid, _origin: Option<&str>, reply: Reply) {
+        self.replies.complete(id, reply);
+    }
+
+    async fn ack(&mut self, _offset: u64) -> Result<(), BusError> {
+        Ok(())
+    }
+}
+
+impl Drop for InMemoryConsumer {
+    /// Hands the partition back for the next consumer.
+    fn drop(&mut self) {
+        if let Some(rx) = self.rx.take() {
+            *self.slot.lock().unwrap() = Some(rx);
+        }
+    }
+}
+
+/// Per-table broadcast channels. A subscriber that falls too far behind
+/// sees `Lagged` and catches up from the table's backlog.
+#[derive(Default)]
+pub struct InMemoryEventBus {
+    channels: DashMap<TableId, broadcast::Sender<GameEvent>>,
+}
+
+impl InMemoryEventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EventBus for InMemoryEventBus {
+    fn open(&self, table_id: TableId) {
+        self.channels
+            .entry(table_id)
+            .or_insert_with(|| broadcast::channel(EVENT_CAPACITY).0);
+    }
+
+    fn publish(&self, table_id: TableId, event: GameEvent) {
+        if let Some(tx) = self.channels.get(&table_id) {
+            let _ = tx.send(event);
+        }
+    }
+
+    fn subscribe(&self, table_id: TableId) -> Option<broadcast::Receiver<GameEvent>> {
+        self.channels.get(&table_id).map(|tx| tx.subscribe())
+    }
+
+    fn close(&self, table_id: TableId) {
+        self.channels.remove(&table_id);
+    }
+}
+
+/// Table summaries in process memory.
+#[derive(Default)]
+pub struct InMemoryTableDirectory {
+    tables: DashMap<TableId, TableSummary>,
+}
+
+impl InMemoryTableDirectory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TableDirectory for InMemoryTableDirectory {
+    fn put(&self, summary: TableSummary) {
+        self.tables.insert(summary.id, summary);
+    }
+
+    fn remove(&self, table_id: TableId) {
+        self.tables.remove(&table_id);
+    }
+
+    fn get(&self, table_id: TableId) -> Option<TableSummary> {
+        self.tables.get(&table_id).map(|s| s.value().clone())
+    }
+
+    fn list(&self) -> Vec<TableSummary> {
+        let mut tables: Vec<TableSummary> = self.tables.iter().map(|r| r.value().clone()).collect();
+        tables.sort_by_key(|s| s.id);
+        tables
+    }
+}
@@ -0,0 +1,163 @@
+//! Transport between the request side (WebSocket, HTTP) and the partition
+//! workers that host the tables.
+//!
+//! Commands are keyed by table: every command for a table lands on the same
+//! partition, in order, and one worker consumes each partition. Replies go
+//! back to whoever sent the command. Events go the other way, per table, to
+//! whoever is watching it, and the table directory tells every instance
+//! which tables exist.
+pub mod file;
+pub mod in_memory;
+pub mod postgres;
+pub mod replies;
+
+pub use file::FileCommandBus;
+pub use in_memory::{InMemoryCommandBus, InMemoryEventBus, InMemoryTableDirectory};
+pub use postgres::PostgresBus;
+pub use replies::{Replies, Reply};
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bj_core::domain::{engine::event::GameEvent, TableId};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::{broadcast, oneshot};
+use ulid::Ulid;
+
+use crate::{
+    session::{summary::TableSummary, table_actor::TableCommand},
+    telemetry::TraceContext,
+};
+
+/// Partitions a bus has unless configured otherwise.
+pub const DEFAULT_PARTITIONS: u32 = 16;
+
+/// A command for one table as it travels on the [`CommandBus`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandEnvelope {
+    /// Correlates the command with the caller waiting for its outcome.
+    pub id: Ulid,
+    /// The instance the caller is on, when the bus spans several. `None`
+    /// for this process.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    pub table_id: TableId,
+    pub command: TableCommand,
+    /// The span that sent the command, for the worker to continue.
+    #[serde(default, skip_serializing_if = "TraceContext::is_empty")]
+    pub trace: TraceContext,
+}
+
+/// A command handed to a partition's consumer, with its position in the
+/// partition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    pub offset: u64,
+    pub envelope: CommandEnvelope,
+}
+
+#[derive(Debug, Error)]
+pub enum BusError {
+    #[error("partition {0} does not exist")]
+    UnknownPartition(u32),
+    #[error("partition {0} already has a consumer")]
+    PartitionTaken(u32),
+    #[error("command bus is closed")]
+    Closed,
+    #[error("command log: {0}")]
+    Log(#[from] std::io::Error),
+    #[error("command store: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// The partition `table_id` belongs to. ULIDs end in random bits, so tables
+/// spread evenly.
+pub fn partition_of(table_id: TableId, partitions: u32) -> u32 {
+    (table_id.0 .0 % u128::from(partitions)) as u32
+}
+
+#[async_trait]
+pub trait CommandBus: Send + Sync {
+    fn partitions(&self) -> u32;
+    /// Queues `command` on its table's partition, with the trace context of
+    /// the current span. The receiver gets the table's reply once the
+    /// command is handled.
+    async fn send(
+        &self,
+        table_id: TableId,
+        command: TableCommand,
+    ) -> Result<oneshot::Receiver<Reply>, BusError>;
+    /// Starts consuming `partition` at its first unacknowledged command. A
+    /// partition has one consumer at a time; it is free again once the
+    /// consumer is dropped.
+    fn subscribe(&self, partition: u32) -> Result<Box<dyn CommandConsumer>, BusError>;
+}
+
+#[async_trait]
+pub trait CommandConsumer: Send {
+    /// The next command, or `None` once the bus is gone.
+    async fn next(&mut self) -> Option<Delivery>;
+    /// Answers the sender of the command `id`. Nobody waiting, as for a
+    /// command delivered again after a restart, is not an error.
+    async fn reply(&mut self, id: Ulid, origin: Option<&str>, reply: Reply);
+    /// Marks the command at `offset` handled. Consumers acknowledge in
+    /// delivery order; whatever is not acknowledged is delivered again to
+    /// the partition's next consumer, if the bus keeps it.
+    async fn ack(&mut self, offset: u64) -> Result<(), BusError>;
+}
+
+/// Per-table event streams.
+pub trait EventBus: Send + Sync {
+    /// Starts a stream for a table that has just opened.
+    fn open(&self, table_id: TableId);
+    /// Sends `event` to everyone subscribed to the table. Nobody listening
+    /// is not an error.
+    fn publish(&self, table_id: TableId, event: GameEvent);
+    /// Follows the table's events from now on, or `None` if the table has
+    /// no stream.
+    fn subscribe(&self, table_id: TableId) -> Option<broadcast::Receiver<GameEvent>>;
+    /// Ends the table's stream; subscribers see it close.
+    fn close(&self, table_id: TableId);
+}
+
+/// The summary of every running table, kept by the workers hosting them.
+pub trait TableDirectory: Send + Sync {
+    fn put(&self, summary: TableSummary);
+    fn remove(&self, table_id: TableId);
+    fn get(&self, table_id: TableId) -> Option<TableSummary>;
+    fn contains(&self, table_id: TableId) -> bool {
+        self.get(table_id).is_some()
+    }
+    /// Every table, in id order.
+    fn list(&self) -> Vec<TableSummary>;
+}
+
+/// The three channels a session runs on.
+#[derive(Clone)]
+pub struct Buses {
+    pub commands: Arc<dyn CommandBus>,
+    pub events: Arc<dyn EventBus>,
+    pub directory: Arc<dyn TableDirectory>,
+}
+
+impl Buses {
+    /// `commands`, with events and the directory in process memory.
+    pub fn local(commands: Arc<dyn CommandBus>) -> Self {
+        Self {
+            commands,
+            events: Arc::new(InMemoryEventBus::new()),
+            directory: Arc::new(InMemoryTableDirectory::new()),
+        }
+    }
+}
+
+impl From<Arc<PostgresBus>> for Buses {
+    fn from(bus: Arc<PostgresBus>) -> Self {
+        Self {
+            commands: bus.clone(),
+            events: bus.clone(),
+            directory: bus,
+        }
+    }
+}
@@ -0,0 +1,596 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use bj_core::domain::{
+    engine::{event::GameEvent, Clock, SystemClock},
+    TableId,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgListener, PgPool, Row};
+use tokio::sync::{broadcast, mpsc, oneshot, Notify};
+use tracing::{error, warn};
+use ulid::Ulid;
+
+use super::{
+    partition_of, BusError, CommandBus, CommandConsumer, CommandEnvelope, Delivery, EventBus,
+    InMemoryEventBus, InMemoryTableDirectory, Replies, Reply, TableDirectory,
+};
+use crate::{
+    session::{summary::TableSummary, table_actor::TableCommand},
+    telemetry::TraceContext,
+};
+
+/// How often a consumer looks for commands whose notification it missed.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const FETCH_BATCH: i64 = 64;
+/// Replies nobody picked up within this long are deleted.
+const REPLY_TTL: Duration = Duration::from_secs(60);
+
+/// Commands, replies, events and the table directory, shared by every
+/// instance of a cluster through one PostgreSQL database.
+///
+/// A command waits in `bus_commands` until the instance consuming its
+/// partition has handled it; `NOTIFY` wakes that instance, which also polls
+/// in case a notification was lost. A reply for another instance goes
+/// through `bus_replies`. Events and directory changes are sent to every
+/// instance with `NOTIFY`, in the order they happened, and each hands them
+/// to its own subscribers.
+///
+/// Delivery of commands is at least once. Events are at most once: an
+/// instance that loses its listening connection misses what was sent in
+/// the meantime.
+pub struct PostgresBus {
+    pool: PgPool,
+    cluster: String,
+    instance: String,
+    partitions: u32,
+    replies: Arc<Replies>,
+    /// Per partition: wakes its consumer when a command arrives.
+    wake: Vec<Arc<Notify>>,
+    /// Per partition: whether this instance has a consumer for it.
+    taken: Vec<Arc<AtomicBool>>,
+    events: InMemoryEventBus,
+    directory: InMemoryTableDirectory,
+    outbox: mpsc::UnboundedSender<Fanout>,
+    me: Weak<PostgresBus>,
+    /// Stops the listener once the bus is dropped.
+    _shutdown: oneshot::Sender<()>,
+}
+
+/// What instances tell each other about tables, on the `tables` channel.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+enum Fanout {
+    Event {
+        origin: String,
+        table_id: TableId,
+        event: GameEvent,
+    },
+    Closed {
+        origin: String,
+        table_id: TableId,
+    },
+    Put {
+        origin: String,
+        summary: TableSummary,
+    },
+    Removed {
+        origin: String,
+        table_id: TableId,
+    },
+}
+
+impl Fanout {
+    fn origin(&self) -> &str {
+        match self {
+            Self::Event { origin, .. }
+            | Self::Closed { origin, .. }
+            | Self::Put { origin, .. }
+            | Self::Removed { origin, .. } => origin,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplyNotice {
+    instance: String,
+    id: Ulid,
+}
+
+impl PostgresBus {
+    /// Joins `cluster` as `instance`: loads the table directory and starts
+    /// listening for the other instances. Every instance of a cluster must
+    /// use the same number of partitions.
+    pub async fn connect(
+        pool: PgPool,
+        cluster: &str,
+        instance: &str,
+        partitions: u32,
+    ) -> Result<Arc<Self>, BusError> {
+        let partitions = partitions.max(1);
+        let mut listener = PgListener::connect_with(&pool).await?;
+        let channels = Channels::new(cluster);
+        listener
+            .listen_all([
+                channels.commands.as_str(),
+                channels.replies.as_str(),
+                channels.tables.as_str(),
+            ])
+            .await?;
+
+        let (outbox, outgoing) = mpsc::unbounded_channel();
+        let (shutdown, stopped) = oneshot::channel();
+        let bus = Arc::new_cyclic(|me| Self {
+            pool: pool.clone(),
+            cluster: cluster.to_string(),
+            instance: instance.to_string(),
+            partitions,
+            replies: Arc::default(),
+            wake: (0..partitions).map(|_| Arc::default()).collect(),
+            taken: (0..partitions).map(|_| Arc::default()).collect(),
+            events: InMemoryEventBus::new(),
+            directory: InMemoryTableDirectory::new(),
+            outbox,
+            me: me.clone(),
+            _shutdown: shutdown,
+        });
+
+        let rows = sqlx::query("SELECT summary FROM table_directory WHERE cluster = $1")
+            .bind(cluster)
+            .fetch_all(&pool)
+            .await?;
+        for row in rows {
+            match serde_json::from_str(row.get("summary")) {
+                Ok(summary) => bus.directory.put(summary),
+                Err(e) => warn!("cluster={cluster} skipping unreadable table summary: {e}"),
+            }
+        }
+
+        tokio::spawn(send_outgoing(
+            pool.clone(),
+            cluster.to_string(),
+            channels.tables.clone(),
+            outgoing,
+        ));
+        tokio::spawn(listen(Arc::downgrade(&bus), listener, channels, stopped));
+        Ok(bus)
+    }
+
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    fn channels(&self) -> Channels {
+        Channels::new(&self.cluster)
+    }
+
+    fn handle_notification(&self, channel: &str, payload: &str) {
+        let channels = self.channels();
+        if channel == channels.commands {
+            match payload.parse::<usize>().ok().and_then(|p| self.wake.get(p)) {
+                Some(wake) => wake.notify_one(),
+                None => warn!(
+                    "cluster={} bad command notification: {payload}",
+                    self.cluster
+                ),
+            }
+        } else if channel == channels.replies {
+            match serde_json::from_str::<ReplyNotice>(payload) {
+                Ok(notice) if notice.instance == self.instance => {
+                    tokio::spawn(pick_up_reply(
+                        self.pool.clone(),
+                        self.replies.clone(),
+                        notice.id,
+                    ));
+                }
+                Ok(_) => {}
+                Err(e) => warn!("cluster={} bad reply notification: {e}", self.cluster),
+            }
+        } else if channel == channels.tables {
+            let fanout: Fanout = match serde_json::from_str(payload) {
+                Ok(fanout) => fanout,
+                Err(e) => {
+                    warn!("cluster={} bad table notification: {e}", self.cluster);
+                    return;
+                }
+            };
+            if fanout.origin() == self.instance {
+                return;
+            }
+            match fanout {
+                Fanout::Event {
+                    table_id, event, ..
+                } => self.events.publish(table_id, event),
+                Fanout::Closed { table_id, .. } => self.events.close(table_id),
+                Fanout::Put { summary, .. } => self.directory.put(summary),
+                Fanout::Removed { table_id, .. } => self.directory.remove(table_id),
+            }
+        }
+    }
+
+    fn fan_out(&self, fanout: Fanout) {
+        // Only fails once the sending task is gone, with the runtime.
+        let _ = self.outbox.send(fanout);
+    }
+}
+
+/// Notification channel names of a cluster.
+#[derive(Clone)]
+struct Channels {
+    commands: String,
+    replies: String,
+    tables: String,
+}
+
+impl Channels {
+    fn new(cluster: &str) -> Self {
+        Self {
+            commands: format!("{cluster}.commands"),
+            replies: format!("{cluster}.replies"),
+            tables: format!("{cluster}.tables"),
+        }
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemClock.now().as_millis() as i64
+}
+
+async fn listen(
+    bus: Weak<PostgresBus>,
+    mut listener: PgListener,
+    channels: Channels,
+    mut stopped: oneshot::Receiver<()>,
+) {
+    let mut janitor = tokio::time::interval(REPLY_TTL);
+    loop {
+        tokio::select! {
+            notification = listener.recv() => {
+                let Some(bus) = bus.upgrade() else { break };
+                match notification {
+                    Ok(n) => bus.handle_notification(n.channel(), n.payload()),
+                    Err(e) => {
+                        warn!("{}: listener failed, reconnecting: {e}", channels.tables);
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                }
+            }
+            _ = janitor.tick() => {
+                let Some(bus) = bus.upgrade() else { break };
+                let cutoff = now_millis() - REPLY_TTL.as_millis() as i64;
+                if let Err(e) = sqlx::query("DELETE FROM bus_replies WHERE created_at < $1")
+                    .bind(cutoff)
+                    .execute(&bus.pool)
+                    .await
+                {
+                    warn!("could not purge stale replies: {e}");
+                }
+            }
+            _ = &mut stopped => break,
+        }
+    }
+}
+
+/// Sends table notifications one at a time, so every instance sees them in
+/// the order they happened here.
+async fn send_outgoing(
+    pool: PgPool,
+    cluster: String,
+    channel: String,
+    mut outgoing: mpsc::UnboundedReceiver<Fanout>,
+) {
+    while let Some(fanout) = outgoing.recv().await {
+        let stored = match &fanout {
+            Fanout::Put { summary, .. } => sqlx::query(
+                "INSERT INTO table_directory (cluster, table_id, summary, updated_at) \
+                 VALUES ($1, $2, $3, $4) \
+                 ON CONFLICT (cluster, table_id) \
+                 DO UPDATE SET summary = EXCLUDED.summary, updated_at = EXCLUDED.updated_at",
+            )
+            .bind(&cluster)
+            .bind(summary.id.to_string())
+            .bind(serde_json::to_string(summary).unwrap_or_default())
+            .bind(now_millis())
+            .execute(&pool)
+            .await
+            .map(|_| ()),
+            Fanout::Removed { table_id, .. } => {
+                sqlx::query("DELETE FROM table_directory WHERE cluster = $1 AND table_id = $2")
+                    .bind(&cluster)
+                    .bind(table_id.to_string())
+                    .execute(&pool)
+                    .await
+                    .map(|_| ())
+            }
+            Fanout::Event { .. } | Fanout::Closed { .. } => Ok(()),
+        };
+        if let Err(e) = stored {
+            error!("cluster={cluster} table directory not updated: {e}");
+        }
+        let payload = match serde_json::to_string(&fanout) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("cluster={cluster} unencodable table notification: {e}");
+                continue;
+            }
+        };
+        // NOTIFY payloads are limited to 8000 bytes; a larger one fails
+        // here and is lost.
+        if let Err(e) = sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(&channel)
+            .bind(&payload)
+            .execute(&pool)
+            .await
+        {
+            error!("cluster={cluster} table notification not sent: {e}");
+        }
+    }
+}
+
+async fn pick_up_reply(pool: PgPool, replies: Arc<Replies>, id: Ulid) {
+    let row = sqlx::query("DELETE FROM bus_replies WHERE id = $1 RETURNING reply")
+        .bind(id.to_string())
+        .fetch_optional(&pool)
+        .await;
+    match row {
+        Ok(Some(row)) => match serde_json::from_str(row.get("reply")) {
+            Ok(reply) => replies.complete(id, reply),
+            Err(e) => {
+                error!("command={id} unreadable reply: {e}");
+                replies.complete(id, Err(crate::session::SessionError::Internal));
+            }
+        },
+        Ok(None) => {}
+        Err(e) => error!("command={id} reply not picked up: {e}"),
+    }
+}
+
+#[async_trait]
+impl CommandBus for PostgresBus {
+    fn partitions(&self) -> u32 {
+        self.partitions
+    }
+
+    async fn send(
+        &self,
+        table_id: TableId,
+        command: TableCommand,
+    ) -> Result<oneshot::Receiver<Reply>, BusError> {
+        let (id, reply) = self.replies.register();
+        let partition = partition_of(table_id, self.partitions);
+        let envelope = CommandEnvelope {
+            id,
+            origin: Some(self.instance.clone()),
+            table_id,
+            command,
+            trace: TraceContext::current(),
+        };
+        let queued = sqlx::query(
+            "WITH queued AS ( \
+               INSERT INTO bus_commands (id, cluster, partition_no, envelope, created_at) \
+               VALUES ($1, $2, $3, $4, $5) RETURNING partition_no \
+             ) \
+             SELECT pg_notify($6, partition_no::text) FROM queued",
+        )
+        .bind(id.to_string())
+        .bind(&self.cluster)
+        .bind(partition as i32)
+        .bind(serde_json::to_string(&envelope).map_err(std::io::Error::from)?)
+        .bind(now_millis())
+        .bind(&self.channels().commands)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = queued {
+            self.replies.cancel(id);
+            return Err(e.into());
+        }
+        Ok(reply)
+    }
+
+    fn subscribe(&self, partition: u32) -> Result<Box<dyn CommandConsumer>, BusError> {
+        let index = partition as usize;
+        let taken = self
+            .taken
+            .get(index)
+            .ok_or(BusError::UnknownPartition(partition))?;
+        if taken.swap(true, Ordering::AcqRel) {
+            return Err(BusError::PartitionTaken(partition));
+        }
+        Ok(Box::new(PostgresConsumer {
+            bus: self.me.clone(),
+            partition,
+            wake: self.wake[index].clone(),
+            taken: taken.clone(),
+            buffer: VecDeque::new(),
+            in_flight: HashMap::new(),
+            offset: 0,
+        }))
+    }
+}
+
+struct PostgresConsumer {
+    bus: Weak<PostgresBus>,
+    partition: u32,
+    wake: Arc<Notify>,
+    taken: Arc<AtomicBool>,
+    buffer: VecDeque<(String, CommandEnvelope)>,
+    /// Row id of every command delivered but not yet acknowledged, by
+    /// offset.
+    in_flight: HashMap<u64, String>,
+    offset: u64,
+}
+
+impl PostgresConsumer {
+    /// Queues the partition's waiting commands, oldest first, other than
+    /// those already delivered.
+    async fn fetch(&mut self, bus: &PostgresBus) -> Result<(), sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, envelope FROM bus_commands \
+             WHERE cluster = $1 AND partition_no = $2 \
+             ORDER BY id LIMIT $3",
+        )
+        .bind(&bus.cluster)
+        .bind(self.partition as i32)
+        .bind(FETCH_BATCH + self.in_flight.len() as i64)
+        .fetch_all(&bus.pool)
+        .await?;
+        for row in rows {
+            let id: String = row.get("id");
+            if self.in_flight.values().any(|delivered| *delivered == id) {
+                continue;
+            }
+            match serde_json::from_str(row.get("envelope")) {
+                Ok(envelope) => self.buffer.push_back((id, envelope)),
+                Err(e) => {
+                    // It would block the partition for good.
+                    error!("command={id} unreadable, dropping it: {e}");
+                    sqlx::query("DELETE FROM bus_commands WHERE id = $1")
+                        .bind(&id)
+                        .execute(&bus.pool)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CommandConsumer for PostgresConsumer {
+    async fn next(&mut self) -> Option<Delivery> {
+        loop {
+            if let Some((id, envelope)) = self.buffer.pop_front() {
+                self.offset += 1;
+                self.in_flight.insert(self.offset, id);
+                return Some(Delivery {
+                    offset: self.offset,
+                    envelope,
+                });
+            }
+            {
+                let bus = self.bus.upgrade()?;
+                if let Err(e) = self.fetch(&bus).await {
+                    warn!("partition={} fetch failed: {e}", self.partition);
+                }
+            }
+            if self.buffer.is_empty() {
+                let _ = tokio::time::timeout(POLL_INTERVAL, self.wake.notified()).await;
+            }
+        }
+    }
+
+    async fn reply(&mut self, id: Ulid, origin: Option<&str>, reply: Reply) {
+        let Some(bus) = self.bus.upgrade() else {
+            return;
+        };
+        let origin = match origin {
+            Some(origin) if origin != bus.instance => origin,
+            _ => return bus.replies.complete(id, reply),
+        };
+        let notice = ReplyNotice {
+            instance: origin.to_string(),
+            id,
+        };
+        let sent = sqlx::query(
+            "WITH stored AS ( \
+               INSERT INTO bus_replies (id, instance, reply, created_at) \
+               VALUES ($1, $2, $3, $4) RETURNING id \
+             ) \
+             SELECT pg_notify($5, $6) FROM stored",
+        )
+        .bind(id.to_string())
+        .bind(origin)
+        .bind(serde_json::to_string(&reply).unwrap_or_default())
+        .bind(now_millis())
+        .bind(&bus.channels().replies)
+        .bind(serde_json::to_string(&notice).unwrap_or_default())
+        .execute(&bus.pool)
+        .await;
+        if let Err(e) = sent {
+            error!("command={id} reply to instance={origin} not sent: {e}");
+        }
+    }
+
+    async fn ack(&mut self, offset: u64) -> Result<(), BusError> {
+        let Some(id) = self.in_flight.remove(&offset) else {
+            return Ok(());
+        };
+        let bus = self.bus.upgrade().ok_or(BusError::Closed)?;
+        sqlx::query("DELETE FROM bus_commands WHERE id = $1")
+            .bind(id)
+            .execute(&bus.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+impl Drop for PostgresConsumer {
+    fn drop(&mut self) {
+        self.taken.store(false, Ordering::Release);
+    }
+}
+
+impl EventBus for PostgresBus {
+    fn open(&self, table_id: TableId) {
+        self.events.open(table_id);
+    }
+
+    fn publish(&self, table_id: TableId, event: GameEvent) {
+        self.events.publish(table_id, event.clone());
+        self.fan_out(Fanout::Event {
+            origin: self.instance.clone(),
+            table_id,
+            event,
+        });
+    }
+
+    /// Any table in the cluster can be followed from any instance.
+    fn subscribe(&self, table_id: TableId) -> Option<broadcast::Receiver<GameEvent>> {
+        self.events.open(table_id);
+        self.events.subscribe(table_id)
+    }
+
+    fn close(&self, table_id: TableId) {
+        self.events.close(table_id);
+        self.fan_out(Fanout::Closed {
+            origin: self.instance.clone(),
+            table_id,
+        });
+    }
+}
+
+impl TableDirectory for PostgresBus {
+    fn put(&self, summary: TableSummary) {
+        if self.directory.get(summary.id).as_ref() == Some(&summary) {
+            return;
+        }
+        self.directory.put(summary.clone());
+        self.fan_out(Fanout::Put {
+            origin: self.instance.clone(),
+            summary,
+        });
+    }
+
+    fn remove(&self, table_id: TableId) {
+        self.directory.remove(table_id);
+        self.fan_out(Fanout::Removed {
+            origin: self.instance.clone(),
+            table_id,
+        });
+    }
+
+    fn get(&self, table_id: TableId) -> Option<TableSummary> {
+        self.directory.get(table_id)
+    }
+
+    fn list(&self) -> Vec<TableSummary> {
+        self.directory.list()
+    }
+}
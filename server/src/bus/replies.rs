@@ -0,0 +1,38 @@
+use dashmap::DashMap;
+use tokio::sync::oneshot;
+use ulid::Ulid;
+
+use crate::session::{table_actor::TableReply, SessionError};
+
+pub type Reply = Result<TableReply, SessionError>;
+
+/// Callers waiting for the outcome of a command they put on the bus, by
+/// envelope id.
+#[derive(Default)]
+pub struct Replies {
+    pending: DashMap<Ulid, oneshot::Sender<Reply>>,
+}
+
+impl Replies {
+    /// A fresh envelope id and the receiver its outcome will arrive on.
+    pub fn register(&self) -> (Ulid, oneshot::Receiver<Reply>) {
+        let id = Ulid::new();
+        let (tx, rx) = oneshot::channel();
+        self.pending.insert(id, tx);
+        (id, rx)
+    }
+
+    /// Forgets a command that never made it onto the bus.
+    pub fn cancel(&self, id: Ulid) {
+        self.pending.remove(&id);
+    }
+
+    /// Hands `reply` to whoever waits for command `id`. A command
+    /// redelivered after a restart has nobody waiting; its outcome is
+    /// dropped.
+    pub fn complete(&self, id: Ulid, reply: Reply) {
+        if let Some((_, tx)) = self.pending.remove(&id) {
+            let _ = tx.send(reply);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Several instances sharing tables, with `bus.kind: postgres`.
+//!
+//! Every partition is leased to one instance at a time. The holder hosts
+//! the partition's tables and renews the lease; when it stops renewing,
+//! another instance takes the partition over once the lease runs out and
+//! reopens its tables from the [`TableStore`]. A holder that cannot renew
+//! stops hosting a third of a term before its lease runs out, so no
+//! partition runs on two instances at once.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bj_core::domain::{TableId, TableStatus};
+use sqlx::PgPool;
+use thiserror::Error;
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
+
+use crate::{
+    bus::{partition_of, BusError, TableDirectory},
+    session::in_memory::InMemoryGameSession,
+    store::{TableStore, TableStoreError},
+    wallet::{Wallet, WalletError},
+};
+
+#[derive(Debug, Error)]
+pub enum ClusterError {
+    #[error("lease: {0}")]
+    Lease(#[from] sqlx::Error),
+    #[error("bus: {0}")]
+    Bus(#[from] BusError),
+    #[error("wallet: {0}")]
+    Wallet(#[from] WalletError),
+    #[error("tables: {0}")]
+    Tables(#[from] TableStoreError),
+}
+
+/// Leases on the partitions of a cluster, timed by the database clock so
+/// instances need not agree on the time.
+pub struct PartitionLeases {
+    pool: PgPool,
+    cluster: String,
+    instance: String,
+    ttl: Duration,
+}
+
+impl PartitionLeases {
+    pub fn new(pool: PgPool, cluster: &str, instance: &str, ttl: Duration) -> Self {
+        Self {
+            pool,
+            cluster: cluster.to_string(),
+            instance: instance.to_string(),
+            ttl,
+        }
+    }
+
+    /// Takes or renews the lease on `partition`. `false` while another
+    /// instance holds it.
+    pub async fn acquire(&self, partition: u32) -> Result<bool, sqlx::Error> {
+        let owner = sqlx::query(
+            "INSERT INTO partition_leases (cluster, partition_no, owner, expires_at) \
+             VALUES ($1, $2, $3, (extract(epoch FROM clock_timestamp()) * 1000)::bigint + $4) \
+             ON CONFLICT (cluster, partition_no) DO UPDATE \
+             SET owner = EXCLUDED.owner, expires_at = EXCLUDED.expires_at \
+             WHERE partition_leases.owner = EXCLUDED.owner \
+                OR partition_leases.expires_at < (extract(epoch FROM clock_timestamp()) * 1000)::bigint \
+             RETURNING owner",
+        )
+        .bind(&self.cluster)
+        .bind(partition as i32)
+        .bind(&self.instance)
+        .bind(self.ttl.as_millis() as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(owner.is_some())
+    }
+
+    /// Gives `partition` up, if this instance holds it.
+    pub async fn release(&self, partition: u32) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "DELETE FROM partition_leases \
+             WHERE cluster = $1 AND partition_no = $2 AND owner = $3",
+        )
+        .bind(&self.cluster)
+        .bind(partition as i32)
+        .bind(&self.instance)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Keeps this instance's share of the partitions: renews the leases it
+/// holds, takes free or expired ones, and stops hosting those it lost.
+pub struct Coordinator {
+    leases: PartitionLeases,
+    session: Arc<InMemoryGameSession>,
+    wallet: Arc<dyn Wallet>,
+    tables: Arc<dyn TableStore>,
+    directory: Arc<dyn TableDirectory>,
+    partitions: u32,
+    /// Partitions hosted here, with when their lease was last renewed, as
+    /// of the renewal being asked for.
+    owned: HashMap<u32, Instant>,
+}
+
+impl Coordinator {
+    pub fn new(
+        leases: PartitionLeases,
+        session: Arc<InMemoryGameSession>,
+        wallet: Arc<dyn Wallet>,
+        tables: Arc<dyn TableStore>,
+        directory: Arc<dyn TableDirectory>,
+        partitions: u32,
+    ) -> Self {
+        Self {
+            leases,
+            session,
+            wallet,
+            tables,
+            directory,
+            partitions,
+            owned: HashMap::new(),
+        }
+    }
+
+    /// Ticks three times per lease period until `stop` fires or is dropped,
+    /// then [releases](Self::release_all) everything it holds.
+    pub async fn run(mut self, mut stop: oneshot::Receiver<()>) {
+        let mut interval = tokio::time::interval(self.leases.ttl / 3);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => self.tick().await,
+                _ = &mut stop => break,
+            }
+        }
+        self.release_all().await;
+    }
+
+    /// Stops every partition this instance hosts and gives up its lease, so
+    /// another instance takes it over without waiting for it to expire.
+    pub async fn release_all(&mut self) {
+        let owned: Vec<u32> = self.owned.drain().map(|(partition, _)| partition).collect();
+        for &partition in &owned {
+            self.session.stop_partition(partition).await;
+            if let Err(e) = self.leases.release(partition).await {
+                warn!("partition={partition} lease not released: {e}");
+            }
+        }
+        info!("released {} partitions", owned.len());
+    }
+
+    /// One pass over every partition.
+    pub async fn tick(&mut self) {
+        for partition in 0..self.partitions {
+            self.stop_expiring().await;
+            // The database starts the lease after this, so it runs out no
+            // sooner than a term from now.
+            let asked = Instant::now();
+            let acquired =
+                tokio::time::timeout(self.leases.ttl / 6, self.leases.acquire(partition))
+                    .await
+                    .unwrap_or(Err(sqlx::Error::PoolTimedOut));
+            match acquired {
+                Ok(true) => {
+                    let held = self.owned.insert(partition, asked).is_some();
+                    if !held {
+                        if let Err(e) = self.take_over(partition).await {
+                            error!("partition={partition} not taken over: {e}");
+                            self.owned.remove(&partition);
+                            self.session.stop_partition(partition).await;
+                            if let Err(e) = self.leases.release(partition).await {
+                                warn!("partition={partition} lease not released: {e}");
+                            }
+                        }
+                    }
+                }
+                Ok(false) => {
+                    if self.owned.remove(&partition).is_some() {
+                        warn!("partition={partition} lease lost to another instance");
+                        self.session.stop_partition(partition).await;
+                    }
+                }
+                Err(e) => warn!("partition={partition} lease not renewed: {e}"),
+            }
+        }
+        self.stop_expiring().await;
+    }
+
+    /// Stops hosting the partitions whose lease has gone unrenewed for two
+    /// thirds of its term. Another instance may take one over as soon as
+    /// its lease runs out, and by then its worker must be gone here.
+    async fn stop_expiring(&mut self) {
+        let cutoff = self.leases.ttl - self.leases.ttl / 3;
+        let expiring: Vec<u32> = self
+            .owned
+            .iter()
+            .filter(|(_, renewed)| renewed.elapsed() >= cutoff)
+            .map(|(partition, _)| *partition)
+            .collect();
+        for partition in expiring {
+            warn!("partition={partition} lease about to run out unrenewed; stopping it");
+            self.owned.remove(&partition);
+            self.session.stop_partition(partition).await;
+        }
+    }
+
+    /// Whether this instance hosts `partition`.
+    pub fn owns(&self, partition: u32) -> bool {
+        self.owned.contains_key(&partition)
+    }
+
+    /// Hosts a partition that was free, or whose previous holder went
+    /// away: the rounds it was playing are gone, so their stakes go back
+    /// to the players and the tables start over from the store.
+    async fn take_over(&mut self, partition: u32) -> Result<(), ClusterError> {
+        let in_partition = |table_id: TableId| partition_of(table_id, self.partitions) == partition;
+
+        for r in self.wallet.held().await? {
+            if in_partition(r.table_id) {
+                self.wallet.release(r.player_id, r.game_id).await?;
+                warn!(
+                    "table={} game={} player={} released stake of {} left by the previous host",
+                    r.table_id, r.game_id, r.player_id, r.amount
+                );
+            }
+        }
+
+        let tables: Vec<_> = self
+            .tables
+            .list_tables()
+            .await?
+            .into_iter()
+            .filter(|t| t.status == TableStatus::Open && in_partition(t.id))
+            .collect();
+        let hosted: HashSet<TableId> = tables.iter().map(|t| t.id).collect();
+        let count = tables.len();
+        self.session.start_partition(partition, tables)?;
+
+        for summary in self.directory.list() {
+            if in_partition(summary.id) && !hosted.contains(&summary.id) {
+                self.directory.remove(summary.id);
+            }
+        }
+        info!("partition={partition} taken over with {count} tables");
+        Ok(())
+    }
+}
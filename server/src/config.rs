@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+use secrecy::SecretString;
 use serde::Deserialize;
 use serde_aux::field_attributes::deserialize_number_from_string;
 
@@ -7,6 +8,20 @@ use serde_aux::field_attributes::deserialize_number_from_string;
 pub struct Settings {
     pub application: ApplicationSettings,
     pub database: DatabaseSettings,
+    #[serde(default)]
+    pub auth: AuthSettings,
+    #[serde(default)]
+    pub websocket: WebSocketSettings,
+    #[serde(default)]
+    pub bus: BusSettings,
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    #[serde(default)]
+    pub rate_limits: RateLimitSettings,
+    #[serde(default)]
+    pub audit: AuditSettings,
+    #[serde(default)]
+    pub state: StateSettings,
 }
 
 impl Settings {
@@ -36,10 +51,32 @@ pub struct ApplicationSettings {
     pub host: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
+    /// How long a shutdown lets rounds in play finish before voiding them.
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+}
+
+fn default_shutdown_grace_secs() -> u64 {
+    30
+}
+
+/// Where persistent state (wallet ledger, ...) lives.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseKind {
+    /// Process memory only; everything is lost on restart.
+    #[default]
+    Memory,
+    Postgres,
+    /// A single database file at `database.sqlite_path`, for single-node
+    /// deployments.
+    Sqlite,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct DatabaseSettings {
+    #[serde(default)]
+    pub kind: DatabaseKind,
     pub host: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
@@ -47,6 +84,13 @@ pub struct DatabaseSettings {
     pub password: String,
     pub database_name: String,
     pub max_connections: u32,
+    /// Database file for `kind: sqlite`; created if missing.
+    #[serde(default = "default_sqlite_path")]
+    pub sqlite_path: String,
+}
+
+fn default_sqlite_path() -> String {
+    "blackjack.db".into()
 }
 
 impl DatabaseSettings {
@@ -58,6 +102,278 @@ impl DatabaseSettings {
     }
 }
 
+#[derive(Deserialize, Debug, Default)]
+pub struct AuthSettings {
+    #[serde(default)]
+    pub argon2: Argon2Settings,
+    #[serde(default)]
+    pub tokens: TokenSettings,
+    /// The first admin, created at boot while no account is one. Required
+    /// until then; ignored after.
+    pub bootstrap_admin: Option<BootstrapAdmin>,
+    /// Seeds the `qa` and `dev` player accounts, for development.
+    #[serde(default)]
+    pub seed_dev_accounts: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BootstrapAdmin {
+    pub username: String,
+    pub password: SecretString,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TokenSettings {
+    /// HMAC key for access tokens. When unset a random key is generated at
+    /// startup and every access token dies with the process.
+    pub secret: Option<SecretString>,
+    pub access_ttl_secs: u64,
+    pub refresh_ttl_secs: u64,
+    /// How often an open socket checks that its session was not revoked.
+    pub recheck_secs: u64,
+}
+
+impl Default for TokenSettings {
+    fn default() -> Self {
+        Self {
+            secret: None,
+            access_ttl_secs: 15 * 60,
+            refresh_ttl_secs: 30 * 24 * 60 * 60,
+            recheck_secs: 30,
+        }
+    }
+}
+
+/// Cost of password hashing. Changing these upgrades each stored hash the
+/// next time its owner logs in.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Argon2Settings {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Settings {
+    /// The OWASP-recommended minimum for Argon2id.
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WebSocketSettings {
+    /// How long a dropped player keeps their seat before they are removed
+    /// from the table. `0` removes them at once.
+    pub reconnect_grace_secs: u64,
+    /// How often the server pings each connection. `0` turns pings off.
+    #[serde(default = "default_ping_interval_secs")]
+    pub ping_interval_secs: u64,
+    /// Pings in a row a connection may leave unanswered before it is
+    /// treated as dropped.
+    #[serde(default = "default_missed_pongs")]
+    pub missed_pongs: u32,
+}
+
+fn default_ping_interval_secs() -> u64 {
+    15
+}
+
+fn default_missed_pongs() -> u32 {
+    3
+}
+
+impl Default for WebSocketSettings {
+    fn default() -> Self {
+        Self {
+            reconnect_grace_secs: 60,
+            ping_interval_secs: default_ping_interval_secs(),
+            missed_pongs: default_missed_pongs(),
+        }
+    }
+}
+
+/// Token buckets hold `*_burst` messages and refill at `*_per_sec`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RateLimitSettings {
+    /// What one WebSocket connection may send.
+    pub messages_per_sec: f64,
+    pub message_burst: u32,
+    /// What one player may send over all their connections.
+    pub player_messages_per_sec: f64,
+    pub player_message_burst: u32,
+    /// Open WebSocket connections per client address.
+    pub connections_per_ip: usize,
+    /// The wait after the first failed login for a username; it doubles
+    /// with each further failure.
+    pub login_backoff_ms: u64,
+    /// Failed logins in a row after which the username is locked.
+    pub login_lockout_after: u32,
+    pub login_lockout_secs: u64,
+    /// Sign-ups per client address; each one hashes a password.
+    pub signups_per_min: f64,
+    pub signup_burst: u32,
+    /// Login attempts per client address, whatever the username.
+    pub logins_per_min: f64,
+    pub login_burst: u32,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            messages_per_sec: 10.0,
+            message_burst: 20,
+            player_messages_per_sec: 15.0,
+            player_message_burst: 30,
+            connections_per_ip: 20,
+            login_backoff_ms: 500,
+            login_lockout_after: 10,
+            login_lockout_secs: 900,
+            signups_per_min: 5.0,
+            signup_burst: 5,
+            logins_per_min: 30.0,
+            login_burst: 10,
+        }
+    }
+}
+
+/// Where the audit trail of table commands is kept.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSinkKind {
+    /// With the rest of the data, per `database.kind`; in memory for
+    /// `memory`.
+    #[default]
+    Database,
+    /// One JSON record per line in `audit.path`.
+    Jsonl,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AuditSettings {
+    pub sink: AuditSinkKind,
+    pub path: String,
+}
+
+impl Default for AuditSettings {
+    fn default() -> Self {
+        Self {
+            sink: AuditSinkKind::default(),
+            path: "audit/audit.jsonl".into(),
+        }
+    }
+}
+
+/// Hot-restart persistence for `database.kind: memory`. The other kinds keep
+/// everything in their database already.
+#[derive(Deserialize, Debug)]
+pub struct StateSettings {
+    /// Where tables, chips and accounts are saved and restored from. Unset,
+    /// they are kept in memory only and a restart starts from the seeds.
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// How often the state is saved while running, besides at shutdown.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub save_interval_secs: u64,
+}
+
+impl Default for StateSettings {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            save_interval_secs: 30,
+        }
+    }
+}
+
+/// How commands reach the partition workers hosting the tables.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BusKind {
+    /// Channels in process memory; queued commands die with the process.
+    #[default]
+    Memory,
+    /// An append-only log per partition under `bus.log_dir`. Commands not
+    /// yet handled when the process stops are delivered again on boot.
+    File,
+    /// Shared by every instance of `bus.cluster` through the PostgreSQL
+    /// database; requires `database.kind: postgres`. Each instance hosts
+    /// the partitions it holds a lease on.
+    Postgres,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BusSettings {
+    #[serde(default)]
+    pub kind: BusKind,
+    /// Tables are spread over this many partitions, one worker each.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub partitions: u32,
+    /// Directory for `kind: file`; created if missing.
+    pub log_dir: String,
+    /// For `kind: postgres`: the instances sharing tables. Instances of
+    /// different clusters can use the same database.
+    pub cluster: String,
+    /// For `kind: postgres`: this instance's name in the cluster. A random
+    /// one if unset; two running instances must not share one.
+    #[serde(default)]
+    pub instance_id: Option<String>,
+    /// For `kind: postgres`: how long a partition stays with an instance
+    /// that stops renewing its lease.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub lease_ttl_secs: u64,
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        Self {
+            kind: BusKind::default(),
+            partitions: crate::bus::DEFAULT_PARTITIONS,
+            log_dir: "command-log".into(),
+            cluster: "blackjack".into(),
+            instance_id: None,
+            lease_ttl_secs: 10,
+        }
+    }
+}
+
+/// Where spans go besides the log.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceExporter {
+    /// Spans only show as log context.
+    #[default]
+    None,
+    /// Finished spans printed to stdout, for local debugging.
+    Stdout,
+    /// Batched to an OTLP/HTTP collector at `telemetry.otlp_endpoint`.
+    Otlp,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TelemetrySettings {
+    #[serde(default)]
+    pub exporter: TraceExporter,
+    /// The collector's trace endpoint, e.g.
+    /// `http://localhost:4318/v1/traces`.
+    pub otlp_endpoint: String,
+    /// `service.name` on every span.
+    pub service_name: String,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            exporter: TraceExporter::default(),
+            otlp_endpoint: "http://localhost:4318/v1/traces".into(),
+            service_name: "blackjack-server".into(),
+        }
+    }
+}
+
 pub enum Environment {
     Local,
     Production,
@@ -0,0 +1,109 @@
+//! WebSocket heartbeat.
+//!
+//! The server pings every connection on an interval and times the pongs. A
+//! half-open TCP connection never answers, so once `missed_pongs` pings in a
+//! row go unanswered the connection is dropped as if the client had gone,
+//! and its seat is held for the reconnect grace period.
+
+use std::{collections::VecDeque, time::Duration};
+
+use tokio::time::{self, Instant, Interval, MissedTickBehavior};
+
+use crate::config::WebSocketSettings;
+
+/// How often to ping, and how many pings may go unanswered.
+#[derive(Debug, Clone, Copy)]
+pub struct Heartbeat {
+    /// `None` turns pings off.
+    pub interval: Option<Duration>,
+    pub missed_pongs: u32,
+}
+
+impl Heartbeat {
+    pub fn new(settings: &WebSocketSettings) -> Self {
+        Self {
+            interval: (settings.ping_interval_secs > 0)
+                .then(|| Duration::from_secs(settings.ping_interval_secs)),
+            missed_pongs: settings.missed_pongs.max(1),
+        }
+    }
+
+    /// How long a connection may go without a sign of life: used before it
+    /// has authenticated, when nothing is pinged yet.
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.interval.map(|i| i * self.missed_pongs)
+    }
+
+    pub fn pinger(&self) -> Pinger {
+        Pinger {
+            ticks: self.interval.map(|period| {
+                let mut ticks = time::interval_at(Instant::now() + period, period);
+                ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                ticks
+            }),
+            missed_pongs: self.missed_pongs,
+            pending: VecDeque::new(),
+            next: 0,
+        }
+    }
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new(&WebSocketSettings::default())
+    }
+}
+
+/// The pings of one connection still waiting for their pong.
+pub struct Pinger {
+    ticks: Option<Interval>,
+    missed_pongs: u32,
+    /// Payload and send time of each unanswered ping, oldest first.
+    pending: VecDeque<(u64, Instant)>,
+    next: u64,
+}
+
+/// The connection let `missed_pongs` pings go unanswered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unresponsive {
+    pub missed: u32,
+}
+
+impl Pinger {
+    /// Resolves when the next ping is due; never, with pings off.
+    pub async fn due(&mut self) {
+        match &mut self.ticks {
+            Some(ticks) => {
+                ticks.tick().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    /// The payload of the next ping, or the error once too many pings have
+    /// gone unanswered.
+    pub fn ping(&mut self) -> Result<Vec<u8>, Unresponsive> {
+        let missed = self.pending.len() as u32;
+        if missed >= self.missed_pongs {
+            return Err(Unresponsive { missed });
+        }
+        let id = self.next;
+        self.next += 1;
+        self.pending.push_back((id, Instant::now()));
+        Ok(id.to_be_bytes().to_vec())
+    }
+
+    /// Books the pong carrying `payload` and returns the round trip of its
+    /// ping. Every older ping counts as answered too. `None` for a pong
+    /// that answers no ping of ours.
+    pub fn pong(&mut self, payload: &[u8]) -> Option<Duration> {
+        let id = u64::from_be_bytes(payload.try_into().ok()?);
+        let at = self
+            .pending
+            .iter()
+            .position(|&(pending, _)| pending == id)?;
+        let (_, sent) = self.pending[at];
+        self.pending.drain(..=at);
+        Some(sent.elapsed())
+    }
+}
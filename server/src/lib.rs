@@ -1,14 +1,30 @@
+pub mod audit;
 pub mod auth;
+pub mod bus;
+pub mod cluster;
 pub mod config;
+pub mod heartbeat;
+pub mod metrics;
 pub mod protocol;
+pub mod rate_limit;
 pub mod routes;
 pub mod session;
+pub mod shutdown;
+pub mod state_dir;
 pub mod store;
+pub mod telemetry;
 pub mod wallet;
 
-use auth::InMemoryAuthenticator;
-use session::GameSession;
+use audit::{in_memory::InMemoryAuditLog, AuditSink};
+use auth::{Authenticator, Sessions};
+use config::RateLimitSettings;
+use heartbeat::Heartbeat;
+use metrics::Metrics;
+use rate_limit::RateLimits;
+use session::{grace::SeatHolds, GameSession};
+use shutdown::Shutdown;
 use std::sync::Arc;
+use store::TableStore;
 use wallet::Wallet;
 
 pub type AppState = Arc<App>;
@@ -16,19 +32,56 @@ pub type AppState = Arc<App>;
 pub struct App {
     pub session: Arc<dyn GameSession>,
     pub wallet: Arc<dyn Wallet>,
-    pub auth: Arc<InMemoryAuthenticator>,
+    pub auth: Arc<dyn Authenticator>,
+    pub sessions: Arc<Sessions>,
+    pub seat_holds: SeatHolds,
+    pub tables: Arc<dyn TableStore>,
+    pub metrics: Arc<Metrics>,
+    pub shutdown: Shutdown,
+    pub rate_limits: RateLimits,
+    pub heartbeat: Heartbeat,
+    /// The trail the tables write; read by `GET /admin/audit`.
+    pub audit: Arc<dyn AuditSink>,
 }
 
 impl App {
     pub fn new(
         session: Arc<dyn GameSession>,
         wallet: Arc<dyn Wallet>,
-        auth: Arc<InMemoryAuthenticator>,
+        auth: Arc<dyn Authenticator>,
+        sessions: Arc<Sessions>,
+        seat_holds: SeatHolds,
+        tables: Arc<dyn TableStore>,
+        metrics: Arc<Metrics>,
     ) -> Self {
         Self {
             session,
             wallet,
             auth,
+            sessions,
+            seat_holds,
+            tables,
+            rate_limits: RateLimits::new(&RateLimitSettings::default(), metrics.clone()),
+            metrics,
+            shutdown: Shutdown::new(),
+            heartbeat: Heartbeat::default(),
+            audit: Arc::new(InMemoryAuditLog::new()),
         }
     }
+
+    pub fn with_audit(mut self, audit: Arc<dyn AuditSink>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    pub fn with_heartbeat(mut self, heartbeat: Heartbeat) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Replaces the default limits.
+    pub fn with_rate_limits(mut self, settings: &RateLimitSettings) -> Self {
+        self.rate_limits = RateLimits::new(settings, self.metrics.clone());
+        self
+    }
 }
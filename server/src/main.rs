@@ -1,48 +1,289 @@
-use server::auth::InMemoryAuthenticator;
-use server::config::Settings;
-use server::session::in_memory::InMemoryGameSession;
-use server::wallet::in_memory::InMemoryWallet;
-use server::{routes::create_router, App, AppState};
-use std::sync::Arc;
-use tokio::net::TcpListener;
-use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-
-const SEED_ACCOUNTS: &[(&str, &str)] = &[
-    ("admin", "famly1234"),
-    ("qa", "famly1234"),
-    ("dev", "famly1234"),
-];
+use bj_core::domain::PlayerId;
+use secrecy::ExposeSecret;
+use server::audit::{
+    in_memory::InMemoryAuditLog, jsonl::JsonlAuditLog, postgres::PostgresAuditLog,
+    sqlite::SqliteAuditLog, writer::AuditWriter, AuditSink,
+};
+use server::auth::{
+    postgres::{PostgresAuthenticator, PostgresSessionStore},
+    session_store::SessionStore,
+    sqlite::{SqliteAuthenticator, SqliteSessionStore},
+    Authenticator, Password, PasswordHasher, Sessions,
+};
+use server::bus::{Buses, FileCommandBus, InMemoryCommandBus, PostgresBus};
+use server::cluster::{Coordinator, PartitionLeases};
+use server::config::{AuditSinkKind, BootstrapAdmin, BusKind, DatabaseKind, Settings};
+use server::heartbeat::Heartbeat;
+use server::metrics::Metrics;
+use server::rate_limit::SWEEP_INTERVAL;
+use server::session::{
+    grace::SeatHolds, in_memory::InMemoryGameSession, table_actor::TableActorConfig,
+};
+use server::state_dir::{open_saved_tables, MemoryBackends, StateDir, StateSaver};
+use server::store::{
+    connect_postgres, connect_sqlite, open_stored_tables, PostgresTableStore, SqliteTableStore,
+    TableStore,
+};
+use server::wallet::{
+    metered::MeteredWallet, postgres::PostgresWallet, release_held, sqlite::SqliteWallet, Posting,
+    Wallet,
+};
+use server::{routes::create_router, shutdown, telemetry, App, AppState};
+use sqlx::PgPool;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{net::TcpListener, sync::oneshot};
+use tracing::{error, info, warn};
+
+/// Player accounts for development, seeded with `auth.seed_dev_accounts`.
+const DEV_ACCOUNTS: &[(&str, &str)] = &[("qa", "famly1234"), ("dev", "famly1234")];
 const SEED_BALANCE: u32 = 1000;
 
+/// Where accounts, chips and tables are kept, per `database.kind`.
+struct Backends {
+    wallet: Arc<dyn Wallet>,
+    auth: Arc<dyn Authenticator>,
+    session_store: Arc<dyn SessionStore>,
+    tables: Arc<dyn TableStore>,
+    /// Used with `audit.sink: database`.
+    audit: Arc<dyn AuditSink>,
+    /// Only for `database.kind: postgres`.
+    pool: Option<PgPool>,
+    /// Only for `database.kind: memory`, to be saved to `state.dir`.
+    memory: Option<MemoryBackends>,
+}
+
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "server=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
     let config = Settings::load().expect("Failed to load configuration");
+    let _telemetry = telemetry::init(&config.telemetry).expect("failed to set up tracing");
     info!("Loaded configuration: {:?}", config);
 
-    let wallet = Arc::new(InMemoryWallet::new());
-    let auth = Arc::new(InMemoryAuthenticator::new());
+    let state_dir = match (&config.state.dir, config.database.kind) {
+        (Some(dir), DatabaseKind::Memory) => {
+            Some(StateDir::open(dir).expect("failed to open the state directory"))
+        }
+        (Some(_), _) => {
+            warn!("state.dir only applies to database.kind memory; ignoring it");
+            None
+        }
+        (None, _) => None,
+    };
+    let mut saved = state_dir
+        .as_ref()
+        .and_then(|dir| dir.load().expect("failed to read the saved state"));
+
+    let Backends {
+        wallet,
+        auth,
+        session_store,
+        tables,
+        audit,
+        pool,
+        memory,
+    } = match config.database.kind {
+        DatabaseKind::Memory => {
+            let hasher =
+                PasswordHasher::new(&config.auth.argon2).expect("invalid auth configuration");
+            let memory = match &mut saved {
+                Some(saved) => {
+                    info!(
+                        "Restoring the state saved at {} in {}",
+                        saved.saved_at.as_millis(),
+                        config.state.dir.as_deref().unwrap_or_default()
+                    );
+                    MemoryBackends::restore(saved, hasher)
+                }
+                None => MemoryBackends::seeded(hasher),
+            };
+            Backends {
+                wallet: memory.wallet.clone(),
+                auth: memory.auth.clone(),
+                session_store: memory.sessions.clone(),
+                tables: memory.tables.clone(),
+                audit: Arc::new(InMemoryAuditLog::new()),
+                pool: None,
+                memory: Some(memory),
+            }
+        }
+        DatabaseKind::Postgres => {
+            let pool = connect_postgres(&config.database)
+                .await
+                .expect("failed to connect to postgres");
+            let hasher =
+                PasswordHasher::new(&config.auth.argon2).expect("invalid auth configuration");
+            info!("Using PostgreSQL wallet, accounts and tables");
+            Backends {
+                wallet: Arc::new(PostgresWallet::new(pool.clone())),
+                auth: Arc::new(PostgresAuthenticator::new(pool.clone(), hasher)),
+                session_store: Arc::new(PostgresSessionStore::new(pool.clone())),
+                tables: Arc::new(PostgresTableStore::new(pool.clone())),
+                audit: Arc::new(PostgresAuditLog::new(pool.clone())),
+                pool: Some(pool),
+                memory: None,
+            }
+        }
+        DatabaseKind::Sqlite => {
+            let pool = connect_sqlite(
+                &config.database.sqlite_path,
+                config.database.max_connections,
+            )
+            .await
+            .expect("failed to open sqlite database");
+            let hasher =
+                PasswordHasher::new(&config.auth.argon2).expect("invalid auth configuration");
+            info!(
+                "Using SQLite wallet, accounts and tables in {}",
+                config.database.sqlite_path
+            );
+            Backends {
+                wallet: Arc::new(SqliteWallet::new(pool.clone())),
+                auth: Arc::new(SqliteAuthenticator::new(pool.clone(), hasher)),
+                session_store: Arc::new(SqliteSessionStore::new(pool.clone())),
+                tables: Arc::new(SqliteTableStore::new(pool.clone())),
+                audit: Arc::new(SqliteAuditLog::new(pool)),
+                pool: None,
+                memory: None,
+            }
+        }
+    };
+    let audit = match config.audit.sink {
+        AuditSinkKind::Database => audit,
+        AuditSinkKind::Jsonl => {
+            info!("Writing the audit trail to {}", config.audit.path);
+            Arc::new(JsonlAuditLog::open(&config.audit.path).expect("failed to open the audit log"))
+        }
+    };
+    let audit = Arc::new(AuditWriter::spawn(audit));
+    let metrics = Arc::new(Metrics::new());
+    let wallet: Arc<dyn Wallet> = Arc::new(MeteredWallet::new(wallet, metrics.clone()));
+    let actor_config = TableActorConfig {
+        metrics: metrics.clone(),
+        audit: audit.clone(),
+        ..TableActorConfig::default()
+    };
+    let clustered = config.bus.kind == BusKind::Postgres;
+    // In a cluster, held stakes may belong to rounds another instance is
+    // playing; each partition's are released when it is taken over.
+    if !clustered {
+        let released = release_held(wallet.as_ref())
+            .await
+            .expect("failed to release stakes held by the previous run");
+        if released > 0 {
+            info!("Released {released} stakes from rounds interrupted by the last shutdown");
+        }
+    }
 
-    for (username, password) in SEED_ACCOUNTS {
-        let pid = auth.seed_user(username, password);
-        wallet.seed(pid, SEED_BALANCE);
-        info!("Seeded account '{}' with {} chips", username, SEED_BALANCE);
+    if let Some(pid) = bootstrap_admin(auth.as_ref(), config.auth.bootstrap_admin.as_ref()).await {
+        info!("player={pid} created as the first admin");
+    }
+    // A restored server already has its accounts, and may have changed them.
+    if config.auth.seed_dev_accounts && saved.is_none() {
+        for &(username, password) in DEV_ACCOUNTS {
+            let pid = auth
+                .seed_user(username, password)
+                .await
+                .expect("failed to seed account");
+            if wallet.balance(pid).await.is_err() {
+                wallet
+                    .credit(pid, SEED_BALANCE, Posting::grant())
+                    .await
+                    .expect("failed to seed wallet");
+            }
+            info!("Seeded account '{}' with {} chips", username, SEED_BALANCE);
+        }
     }
 
-    let wallet_dyn: Arc<dyn server::wallet::Wallet> = wallet;
-    let session = InMemoryGameSession::new(wallet_dyn.clone());
-    let session: Arc<dyn server::session::GameSession> = session;
+    // In a cluster, the coordinator's stop switch and task; with a state
+    // directory, what saves to it.
+    let (session, coordinator, saver) = match config.bus.kind {
+        BusKind::Memory | BusKind::File => {
+            let buses = if config.bus.kind == BusKind::File {
+                info!("Using the command log in {}", config.bus.log_dir);
+                Buses::local(Arc::new(
+                    FileCommandBus::open(&config.bus.log_dir, config.bus.partitions)
+                        .expect("failed to open the command log"),
+                ))
+            } else {
+                Buses::local(Arc::new(InMemoryCommandBus::new(config.bus.partitions)))
+            };
+            let session = InMemoryGameSession::with_buses(wallet.clone(), actor_config, buses)
+                .expect("failed to start partition workers");
+            let opened = match saved.take() {
+                Some(saved) => open_saved_tables(saved.tables, session.as_ref()).await,
+                None => open_stored_tables(tables.as_ref(), session.as_ref())
+                    .await
+                    .expect("failed to load tables"),
+            };
+            info!("Opened {opened} tables");
+            let saver = state_dir
+                .zip(memory)
+                .map(|(dir, memory)| Arc::new(StateSaver::new(dir, memory, session.clone())));
+            (session, None, saver)
+        }
+        BusKind::Postgres => {
+            let pool = pool.expect("bus.kind postgres requires database.kind postgres");
+            let instance = config
+                .bus
+                .instance_id
+                .clone()
+                .unwrap_or_else(|| ulid::Ulid::new().to_string());
+            let bus = PostgresBus::connect(
+                pool.clone(),
+                &config.bus.cluster,
+                &instance,
+                config.bus.partitions,
+            )
+            .await
+            .expect("failed to join the cluster");
+            info!(
+                "Joined cluster '{}' as instance {instance}",
+                config.bus.cluster
+            );
+            let directory = bus.clone();
+            let session = InMemoryGameSession::standby(wallet.clone(), actor_config, bus.into());
+            let leases = PartitionLeases::new(
+                pool,
+                &config.bus.cluster,
+                &instance,
+                Duration::from_secs(config.bus.lease_ttl_secs.max(1)),
+            );
+            let coordinator = Coordinator::new(
+                leases,
+                session.clone(),
+                wallet.clone(),
+                tables.clone(),
+                directory,
+                config.bus.partitions,
+            );
+            let (stop, stopped) = oneshot::channel();
+            let coordinator = tokio::spawn(coordinator.run(stopped));
+            (session, Some((stop, coordinator)), None)
+        }
+    };
 
-    let state: AppState = Arc::new(App::new(session, wallet_dyn, auth));
-    let app = create_router(state);
+    let sessions = Arc::new(Sessions::new(session_store, &config.auth.tokens));
+    let seat_holds = SeatHolds::new(Duration::from_secs(config.websocket.reconnect_grace_secs));
+    let state: AppState = Arc::new(
+        App::new(
+            session.clone(),
+            wallet,
+            auth,
+            sessions,
+            seat_holds,
+            tables,
+            metrics,
+        )
+        .with_rate_limits(&config.rate_limits)
+        .with_heartbeat(Heartbeat::new(&config.websocket))
+        .with_audit(audit.clone()),
+    );
+    let app = create_router(state.clone());
+    let sweeping = state.clone();
+    tokio::spawn(async move { sweeping.rate_limits.sweep_every(SWEEP_INTERVAL).await });
+    if let Some(saver) = &saver {
+        let interval = Duration::from_secs(config.state.save_interval_secs.max(1));
+        tokio::spawn(saver.clone().run(interval, state.shutdown.watch()));
+    }
 
     let listener = TcpListener::bind(format!(
         "{}:{}",
@@ -55,7 +296,54 @@ async fn main() {
         "Server running on http://{}:{}",
         config.application.host, config.application.port
     );
-    axum::serve(listener, app)
+    let grace = Duration::from_secs(config.application.shutdown_grace_secs);
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown::signal().await;
+        shutdown::drain(&state, &session, grace).await;
+        // Drained tables keep their seats in the save, with the chips as
+        // the drain left them.
+        if let Some(saver) = saver {
+            match saver.save_final().await {
+                Ok(()) => info!("Saved the state for the next boot"),
+                Err(e) => error!("state not saved at shutdown: {e}"),
+            }
+        }
+    })
+    .await
+    .expect("failed to run server");
+
+    if let Some((stop, coordinator)) = coordinator {
+        let _ = stop.send(());
+        if let Err(e) = coordinator.await {
+            error!("cluster coordinator failed: {e}");
+        }
+    }
+    audit.flush().await;
+    info!("Server stopped");
+}
+
+/// Creates the first admin from `auth.bootstrap_admin` unless an admin
+/// exists. Without one the server cannot be administered, so the setting is
+/// required until then.
+async fn bootstrap_admin(
+    auth: &dyn Authenticator,
+    admin: Option<&BootstrapAdmin>,
+) -> Option<PlayerId> {
+    let Some(admin) = admin else {
+        let exists = auth.has_admin().await.expect("failed to look for an admin");
+        assert!(
+            exists,
+            "no admin account exists: set auth.bootstrap_admin.username and .password \
+             (APP_AUTH__BOOTSTRAP_ADMIN__USERNAME, APP_AUTH__BOOTSTRAP_ADMIN__PASSWORD)"
+        );
+        return None;
+    };
+    let password = Password::new(admin.password.expose_secret().to_string());
+    auth.bootstrap_admin(&admin.username, &password)
         .await
-        .expect("failed to run server");
+        .unwrap_or_else(|e| panic!("failed to create admin '{}': {e}", admin.username))
 }
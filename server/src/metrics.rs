@@ -0,0 +1,260 @@
+//! Prometheus metrics, served at `/metrics`.
+//!
+//! One [`Metrics`] per process, shared by the WebSocket handler, the table
+//! actors (through [`TableActorConfig`](crate::session::table_actor::TableActorConfig))
+//! and the wallet (through [`MeteredWallet`](crate::wallet::metered::MeteredWallet)).
+//! Rounds are a counter: rounds per minute is
+//! `rate(blackjack_rounds_total[1m]) * 60`.
+
+use std::time::Duration;
+
+use bj_core::domain::{engine::CommandError, TableId};
+use prometheus::{
+    exponential_buckets, Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+use crate::session::summary::TableSummary;
+
+/// Every phase a table reports, so phases with no table read 0.
+const PHASES: [&str; 6] = [
+    "WaitingForBets",
+    "InitialDealing",
+    "PlayerTurn",
+    "DealerTurn",
+    "Payouts",
+    "Finished",
+];
+
+pub struct Metrics {
+    registry: Registry,
+    sockets: IntGauge,
+    players: IntGauge,
+    rtt: Histogram,
+    heartbeat_timeouts: IntCounter,
+    tables: IntGaugeVec,
+    rounds: IntCounter,
+    restarts: IntCounter,
+    commands: HistogramVec,
+    rejections: IntCounterVec,
+    rate_limited: IntCounterVec,
+    login_failures: IntCounter,
+    lag_events: IntCounter,
+    lagged_messages: IntCounter,
+    wallet: HistogramVec,
+    house: IntGaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("blackjack".into()), None).expect("metric prefix is valid");
+        // 100µs to ~3s.
+        let latency = exponential_buckets(0.0001, 2.0, 16).expect("buckets are valid");
+        let metrics = Self {
+            sockets: IntGauge::new("ws_connections", "Open WebSocket connections").unwrap(),
+            players: IntGauge::new(
+                "ws_authenticated_players",
+                "WebSocket connections past authentication",
+            )
+            .unwrap(),
+            rtt: Histogram::with_opts(
+                HistogramOpts::new(
+                    "ws_round_trip_seconds",
+                    "Time from a heartbeat ping to its pong",
+                )
+                // 1ms to ~4s.
+                .buckets(exponential_buckets(0.001, 2.0, 13).expect("buckets are valid")),
+            )
+            .unwrap(),
+            heartbeat_timeouts: IntCounter::new(
+                "ws_heartbeat_timeouts_total",
+                "Connections dropped for not answering pings",
+            )
+            .unwrap(),
+            tables: IntGaugeVec::new(Opts::new("tables", "Running tables by phase"), &["phase"])
+                .unwrap(),
+            rounds: IntCounter::new("rounds_total", "Rounds played to the end").unwrap(),
+            restarts: IntCounter::new("table_restarts_total", "Tables rebuilt after panicking")
+                .unwrap(),
+            commands: HistogramVec::new(
+                HistogramOpts::new(
+                    "command_duration_seconds",
+                    "Time a table takes to handle a player command",
+                )
+                .buckets(latency.clone()),
+                &["action"],
+            )
+            .unwrap(),
+            rejections: IntCounterVec::new(
+                Opts::new("command_rejections_total", "Commands the game refused"),
+                &["error"],
+            )
+            .unwrap(),
+            rate_limited: IntCounterVec::new(
+                Opts::new(
+                    "rate_limited_total",
+                    "Messages, connections and logins refused by a rate limit",
+                ),
+                &["scope"],
+            )
+            .unwrap(),
+            login_failures: IntCounter::new("login_failures_total", "Logins with a wrong password")
+                .unwrap(),
+            lag_events: IntCounter::new(
+                "broadcast_lag_events_total",
+                "Times an event forwarder fell behind its table and resynced",
+            )
+            .unwrap(),
+            lagged_messages: IntCounter::new(
+                "broadcast_lagged_messages_total",
+                "Events dropped by forwarders that fell behind",
+            )
+            .unwrap(),
+            wallet: HistogramVec::new(
+                HistogramOpts::new("wallet_duration_seconds", "Wallet call latency")
+                    .buckets(latency),
+                &["operation"],
+            )
+            .unwrap(),
+            house: IntGaugeVec::new(
+                Opts::new(
+                    "house_net_chips",
+                    "Chips the house has won (negative: lost) per table since start",
+                ),
+                &["table"],
+            )
+            .unwrap(),
+            registry,
+        };
+        let collectors: [Box<dyn prometheus::core::Collector>; 15] = [
+            Box::new(metrics.sockets.clone()),
+            Box::new(metrics.players.clone()),
+            Box::new(metrics.rtt.clone()),
+            Box::new(metrics.heartbeat_timeouts.clone()),
+            Box::new(metrics.tables.clone()),
+            Box::new(metrics.rounds.clone()),
+            Box::new(metrics.restarts.clone()),
+            Box::new(metrics.commands.clone()),
+            Box::new(metrics.rejections.clone()),
+            Box::new(metrics.rate_limited.clone()),
+            Box::new(metrics.login_failures.clone()),
+            Box::new(metrics.lag_events.clone()),
+            Box::new(metrics.lagged_messages.clone()),
+            Box::new(metrics.wallet.clone()),
+            Box::new(metrics.house.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+        metrics
+    }
+
+    /// Counts an open connection until the guard drops.
+    pub fn connection(&self) -> GaugeGuard {
+        GaugeGuard::new(&self.sockets)
+    }
+
+    /// Counts an authenticated connection until the guard drops.
+    pub fn authenticated(&self) -> GaugeGuard {
+        GaugeGuard::new(&self.players)
+    }
+
+    pub fn round_trip(&self, took: Duration) {
+        self.rtt.observe(took.as_secs_f64());
+    }
+
+    pub fn heartbeat_timed_out(&self) {
+        self.heartbeat_timeouts.inc();
+    }
+
+    /// A player command handled in `took`; `action` is the
+    /// [`PlayerAction`](bj_core::domain::engine::command::player::PlayerAction)
+    /// variant.
+    pub fn command(&self, action: &str, took: Duration) {
+        self.commands
+            .with_label_values(&[action])
+            .observe(took.as_secs_f64());
+    }
+
+    pub fn rejected(&self, error: &CommandError) {
+        let error: &'static str = error.into();
+        self.rejections.with_label_values(&[error]).inc();
+    }
+
+    /// A request refused by the [`Limit`](crate::rate_limit::Limit) named
+    /// `scope`.
+    pub fn rate_limited(&self, scope: &str) {
+        self.rate_limited.with_label_values(&[scope]).inc();
+    }
+
+    pub fn login_failed(&self) {
+        self.login_failures.inc();
+    }
+
+    /// A forwarder skipped `missed` events.
+    pub fn lagged(&self, missed: u64) {
+        self.lag_events.inc();
+        self.lagged_messages.inc_by(missed);
+    }
+
+    pub fn wallet_call(&self, operation: &str, took: Duration) {
+        self.wallet
+            .with_label_values(&[operation])
+            .observe(took.as_secs_f64());
+    }
+
+    pub fn round_finished(&self) {
+        self.rounds.inc();
+    }
+
+    pub fn table_restarted(&self) {
+        self.restarts.inc();
+    }
+
+    /// Books the house's side of a settlement at `table_id`: `staked` went
+    /// in, `paid` went back out.
+    pub fn settled(&self, table_id: TableId, staked: u32, paid: u32) {
+        self.house
+            .with_label_values(&[&table_id.to_string()])
+            .add(i64::from(staked) - i64::from(paid));
+    }
+
+    /// The text exposition of every metric, with `tables` counted by phase.
+    pub fn render(&self, tables: &[TableSummary]) -> String {
+        for phase in PHASES {
+            let count = tables.iter().filter(|t| t.phase == phase).count();
+            self.tables.with_label_values(&[phase]).set(count as i64);
+        }
+        let mut out = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut out)
+            .expect("text encoding does not fail");
+        String::from_utf8(out).expect("text exposition is UTF-8")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Holds a gauge one higher for as long as it lives.
+pub struct GaugeGuard(IntGauge);
+
+impl GaugeGuard {
+    fn new(gauge: &IntGauge) -> Self {
+        gauge.inc();
+        Self(gauge.clone())
+    }
+}
+
+impl Drop for GaugeGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
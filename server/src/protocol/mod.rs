@@ -1,13 +1,17 @@
-use crate::session::summary::TableSummary;
-use bj_core::domain::engine::snapshot::{GameEventDto, GameStateSnapshot};
+use crate::{auth::Role, rate_limit::Limit, session::summary::TableSummary};
+use bj_core::domain::engine::{
+    snapshot::{GameEventDto, GameStateSnapshot},
+    Timestamp,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 pub enum ClientMessage {
+    /// First frame on every connection; `token` is an access token from
+    /// `POST /auth/login` or `POST /auth/refresh`.
     Auth {
-        username: String,
-        password: String,
+        token: String,
     },
     JoinTable {
         table_id: String,
@@ -40,6 +44,17 @@ pub enum ClientMessage {
         #[serde(default)]
         seat: Option<u8>,
     },
+    /// Picks up the event stream of a table the player is already at,
+    /// after the last event they saw. The server replays what came after it
+    /// as `Event`s, or sends a fresh `Snapshot` if the gap is too old.
+    Resume {
+        table_id: String,
+        last_seq: u64,
+        #[serde(default)]
+        request_id: u64,
+    },
+    /// Dealer commands drive the round at a manual-dealer table. They need
+    /// the dealer or admin role.
     DealerOpenBetting {
         table_id: String,
         request_id: u64,
@@ -58,11 +73,76 @@ pub enum ClientMessage {
     },
 }
 
+impl ClientMessage {
+    pub fn request_id(&self) -> u64 {
+        match self {
+            Self::Auth { .. } => 0,
+            Self::JoinTable { request_id, .. }
+            | Self::LeaveSeat { request_id, .. }
+            | Self::LeaveTable { request_id, .. }
+            | Self::PlaceBet { request_id, .. }
+            | Self::Hit { request_id, .. }
+            | Self::Stand { request_id, .. }
+            | Self::TakeSeat { request_id, .. }
+            | Self::Resume { request_id, .. }
+            | Self::DealerOpenBetting { request_id, .. }
+            | Self::DealerDealCards { request_id, .. }
+            | Self::DealerPlayHand { request_id, .. }
+            | Self::DealerSettle { request_id, .. } => *request_id,
+        }
+    }
+
+    /// Whether this starts something a shutdown would cut short: joining a
+    /// table, sitting down, betting or opening betting.
+    pub fn starts_play(&self) -> bool {
+        matches!(
+            self,
+            Self::JoinTable { .. }
+                | Self::TakeSeat { .. }
+                | Self::PlaceBet { .. }
+                | Self::DealerOpenBetting { .. }
+        )
+    }
+
+    pub fn is_dealer_command(&self) -> bool {
+        matches!(
+            self,
+            Self::DealerOpenBetting { .. }
+                | Self::DealerDealCards { .. }
+                | Self::DealerPlayHand { .. }
+                | Self::DealerSettle { .. }
+        )
+    }
+
+    /// Whether an account with `role` may send this message. Anyone may
+    /// watch a table; only players sit and bet, and only dealers deal.
+    pub fn allowed_for(&self, role: Role) -> bool {
+        match self {
+            Self::Auth { .. }
+            | Self::JoinTable { .. }
+            | Self::LeaveTable { .. }
+            | Self::Resume { .. } => true,
+            Self::TakeSeat { .. }
+            | Self::LeaveSeat { .. }
+            | Self::PlaceBet { .. }
+            | Self::Hit { .. }
+            | Self::Stand { .. } => role.can_play(),
+            Self::DealerOpenBetting { .. }
+            | Self::DealerDealCards { .. }
+            | Self::DealerPlayHand { .. }
+            | Self::DealerSettle { .. } => role.can_deal(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type")]
 pub enum ServerMessage {
     AuthOk {
         player_id: String,
+        /// What the account may do; other commands are answered with
+        /// `CommandError`.
+        role: Role,
     },
     AuthError {
         reason: String,
@@ -78,6 +158,13 @@ pub enum ServerMessage {
         table_id: String,
         event: GameEventDto,
     },
+    /// Sent after the `Snapshot` that re-attaches a reconnecting player.
+    /// The snapshot already reflects these events; they are for the
+    /// player's history only.
+    MissedEvents {
+        table_id: String,
+        events: Vec<GameEventDto>,
+    },
     CommandAck {
         request_id: u64,
     },
@@ -88,4 +175,24 @@ pub enum ServerMessage {
     Balance {
         amount: u32,
     },
+    /// Sent on each heartbeat pong: how long the last ping took to come
+    /// back, and how often pings are sent.
+    Latency {
+        rtt_ms: u64,
+        ping_interval_ms: u64,
+    },
+    /// The message with `request_id` was dropped unhandled: the connection
+    /// or the player is sending faster than `limit` allows. Sending again
+    /// after `retry_after_ms` goes through.
+    RateLimited {
+        request_id: u64,
+        limit: Limit,
+        retry_after_ms: u64,
+    },
+    /// The server goes down at `deadline`, in milliseconds since the Unix
+    /// epoch. Joins and bets are refused from now on; rounds in play finish
+    /// or are voided by then, and the socket closes with code 1001.
+    ServerShuttingDown {
+        deadline: Timestamp,
+    },
 }
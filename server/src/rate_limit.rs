@@ -0,0 +1,325 @@
+//! Rate limiting and brute-force protection.
+//!
+//! Messages are metered by token buckets, one per connection and one per
+//! player across all their connections. Each client address may hold a
+//! limited number of WebSocket connections, and try a limited number of
+//! logins and sign-ups a minute. Failed logins make the next attempt for
+//! that username wait, twice as long each time, until the account is locked
+//! for a while. Every refusal is counted on `/metrics`.
+
+use std::{
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bj_core::domain::PlayerId;
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::{config::RateLimitSettings, metrics::Metrics};
+
+/// How often [`RateLimits::sweep_every`] forgets what no longer limits
+/// anyone.
+pub const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What a request was refused by; the `scope` label on `/metrics`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Limit {
+    Connection,
+    Player,
+    Ip,
+    Signup,
+    LoginIp,
+    LoginBackoff,
+    LoginLocked,
+}
+
+impl Limit {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Connection => "connection",
+            Self::Player => "player",
+            Self::Ip => "ip",
+            Self::Signup => "signup",
+            Self::LoginIp => "login_ip",
+            Self::LoginBackoff => "login_backoff",
+            Self::LoginLocked => "login_locked",
+        }
+    }
+}
+
+/// A refusal, and how long until the same request would be let through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limited {
+    pub limit: Limit,
+    pub retry_after: Duration,
+}
+
+/// Holds up to `capacity` tokens, refilled at `per_sec`.
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    per_sec: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket.
+    pub fn new(capacity: u32, per_sec: f64) -> Self {
+        let capacity = f64::from(capacity.max(1));
+        Self {
+            capacity,
+            per_sec: per_sec.max(f64::MIN_POSITIVE),
+            tokens: capacity,
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Takes a token, or says how long until there is one.
+    pub fn try_take(&mut self) -> Result<(), Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.per_sec))
+        }
+    }
+
+    /// Whether the bucket has refilled, so forgetting it changes nothing.
+    pub fn is_full(&mut self) -> bool {
+        self.refill();
+        self.tokens >= self.capacity
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.refilled = now;
+    }
+}
+
+/// Failed logins in a row for one username.
+struct Failures {
+    count: u32,
+    /// No attempt is checked before this.
+    blocked_until: Instant,
+}
+
+/// The limits shared by every connection and login.
+pub struct RateLimits {
+    settings: RateLimitSettings,
+    metrics: Arc<Metrics>,
+    players: DashMap<PlayerId, TokenBucket>,
+    connections: Arc<DashMap<IpAddr, usize>>,
+    signups: DashMap<IpAddr, TokenBucket>,
+    login_ips: DashMap<IpAddr, TokenBucket>,
+    logins: DashMap<String, Failures>,
+}
+
+impl RateLimits {
+    pub fn new(settings: &RateLimitSettings, metrics: Arc<Metrics>) -> Self {
+        Self {
+            settings: settings.clone(),
+            metrics,
+            players: DashMap::new(),
+            connections: Arc::new(DashMap::new()),
+            signups: DashMap::new(),
+            login_ips: DashMap::new(),
+            logins: DashMap::new(),
+        }
+    }
+
+    /// The bucket for one connection's messages.
+    pub fn connection_bucket(&self) -> TokenBucket {
+        TokenBucket::new(self.settings.message_burst, self.settings.messages_per_sec)
+    }
+
+    /// Lets one message through `connection` from `player` (if they have
+    /// authenticated), or says which limit it hit.
+    pub fn message(
+        &self,
+        connection: &mut TokenBucket,
+        player: Option<PlayerId>,
+    ) -> Result<(), Limited> {
+        let refused = |limit, retry_after| {
+            self.refused(limit);
+            Limited { limit, retry_after }
+        };
+        connection
+            .try_take()
+            .map_err(|wait| refused(Limit::Connection, wait))?;
+        let Some(player) = player else {
+            return Ok(());
+        };
+        self.players
+            .entry(player)
+            .or_insert_with(|| {
+                TokenBucket::new(
+                    self.settings.player_message_burst,
+                    self.settings.player_messages_per_sec,
+                )
+            })
+            .try_take()
+            .map_err(|wait| refused(Limit::Player, wait))
+    }
+
+    /// Forgets the buckets that have refilled and the failed logins that
+    /// no longer hold anyone back.
+    pub fn sweep(&self) {
+        self.players.retain(|_, bucket| !bucket.is_full());
+        self.signups.retain(|_, bucket| !bucket.is_full());
+        self.login_ips.retain(|_, bucket| !bucket.is_full());
+        let now = Instant::now();
+        self.logins.retain(|_, f| {
+            f.blocked_until + Duration::from_secs(self.settings.login_lockout_secs) > now
+        });
+    }
+
+    /// Sweeps every `interval`, for as long as the server runs.
+    pub async fn sweep_every(&self, interval: Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.tick().await;
+        loop {
+            ticks.tick().await;
+            self.sweep();
+        }
+    }
+
+    /// Takes one of `ip`'s connection slots until the returned guard drops.
+    pub fn connect(&self, ip: IpAddr) -> Result<ConnectionSlot, Limited> {
+        let mut open = self.connections.entry(ip).or_insert(0);
+        if *open >= self.settings.connections_per_ip {
+            drop(open);
+            self.refused(Limit::Ip);
+            return Err(Limited {
+                limit: Limit::Ip,
+                // Slots free up when a connection closes, not on a timer.
+                retry_after: Duration::from_secs(1),
+            });
+        }
+        *open += 1;
+        Ok(ConnectionSlot {
+            ip,
+            connections: self.connections.clone(),
+        })
+    }
+
+    /// Lets `ip` sign up one more account, or says how long until it may.
+    pub fn signup(&self, ip: IpAddr) -> Result<(), Limited> {
+        let bucket = || {
+            TokenBucket::new(
+                self.settings.signup_burst,
+                self.settings.signups_per_min / 60.0,
+            )
+        };
+        self.take(&self.signups, ip, bucket, Limit::Signup)
+    }
+
+    /// Lets `ip` try one more login, under any username, or says how long
+    /// until it may.
+    pub fn login_from(&self, ip: IpAddr) -> Result<(), Limited> {
+        let bucket = || {
+            TokenBucket::new(
+                self.settings.login_burst,
+                self.settings.logins_per_min / 60.0,
+            )
+        };
+        self.take(&self.login_ips, ip, bucket, Limit::LoginIp)
+    }
+
+    fn take(
+        &self,
+        buckets: &DashMap<IpAddr, TokenBucket>,
+        ip: IpAddr,
+        bucket: impl FnOnce() -> TokenBucket,
+        limit: Limit,
+    ) -> Result<(), Limited> {
+        buckets
+            .entry(ip)
+            .or_insert_with(bucket)
+            .try_take()
+            .map_err(|retry_after| {
+                self.refused(limit);
+                Limited { limit, retry_after }
+            })
+    }
+
+    /// Whether `username` may try to log in now.
+    pub fn login_allowed(&self, username: &str) -> Result<(), Limited> {
+        let Some(failures) = self.logins.get(username) else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if failures.blocked_until <= now {
+            return Ok(());
+        }
+        let limit = if failures.count >= self.settings.login_lockout_after {
+            Limit::LoginLocked
+        } else {
+            Limit::LoginBackoff
+        };
+        let retry_after = failures.blocked_until - now;
+        drop(failures);
+        self.refused(limit);
+        Err(Limited { limit, retry_after })
+    }
+
+    /// Books a wrong password for `username`; the next attempt waits
+    /// `login_backoff_ms`, doubling with each failure, and the account is
+    /// locked for `login_lockout_secs` once `login_lockout_after` are
+    /// reached. Returns the failures in a row.
+    pub fn login_failed(&self, username: &str) -> u32 {
+        self.metrics.login_failed();
+        let now = Instant::now();
+        let mut failures = self.logins.entry(username.to_owned()).or_insert(Failures {
+            count: 0,
+            blocked_until: now,
+        });
+        // A lockout served starts the count again.
+        if failures.count >= self.settings.login_lockout_after {
+            failures.count = 0;
+        }
+        failures.count += 1;
+        let wait = if failures.count >= self.settings.login_lockout_after {
+            Duration::from_secs(self.settings.login_lockout_secs)
+        } else {
+            let doublings = (failures.count - 1).min(31);
+            Duration::from_millis(
+                self.settings
+                    .login_backoff_ms
+                    .saturating_mul(1 << doublings),
+            )
+            .min(Duration::from_secs(self.settings.login_lockout_secs))
+        };
+        failures.blocked_until = now + wait;
+        failures.count
+    }
+
+    pub fn login_succeeded(&self, username: &str) {
+        self.logins.remove(username);
+    }
+
+    fn refused(&self, limit: Limit) {
+        self.metrics.rate_limited(limit.as_str());
+    }
+}
+
+/// One of an address's connections; gives the slot back when dropped.
+pub struct ConnectionSlot {
+    ip: IpAddr,
+    connections: Arc<DashMap<IpAddr, usize>>,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.connections.remove_if_mut(&self.ip, |_, open| {
+            *open = open.saturating_sub(1);
+            *open == 0
+        });
+    }
+}
@@ -0,0 +1,368 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use bj_core::domain::{
+    engine::{Clock, SystemClock, Timestamp},
+    PlayerId, TableId, TableSettings, TableStatus,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    audit::{AuditQuery, AuditRecord},
+    auth::{AdminPlayer, AuthError, Role},
+    session::SessionError,
+    store::{TableRecord, TableStoreError},
+    AppState,
+};
+
+const TABLE_NAME_MAX_LEN: usize = 64;
+
+/// Name and settings of a table to create, or to replace an open table's.
+#[derive(Deserialize, ToSchema)]
+pub struct TableSpec {
+    name: String,
+    settings: TableSettings,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RoleRequest {
+    role: Role,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PlayerRole {
+    #[schema(value_type = String)]
+    player_id: PlayerId,
+    username: String,
+    role: Role,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminErrorBody {
+    error: String,
+}
+
+type AdminResult<T> = Result<T, (StatusCode, Json<AdminErrorBody>)>;
+
+fn reject(status: StatusCode, error: impl ToString) -> (StatusCode, Json<AdminErrorBody>) {
+    (
+        status,
+        Json(AdminErrorBody {
+            error: error.to_string(),
+        }),
+    )
+}
+
+fn store_failed(e: TableStoreError) -> (StatusCode, Json<AdminErrorBody>) {
+    match e {
+        TableStoreError::NotFound => reject(StatusCode::NOT_FOUND, e),
+        TableStoreError::UnexpectedError(_) => {
+            error!("table store error: {e:?}");
+            reject(StatusCode::INTERNAL_SERVER_ERROR, e)
+        }
+    }
+}
+
+fn parse_id(id: &str) -> AdminResult<TableId> {
+    id.parse()
+        .map_err(|_| reject(StatusCode::NOT_FOUND, "table not found"))
+}
+
+/// Trims the name and checks it and the settings.
+fn validate(spec: TableSpec) -> AdminResult<(String, TableSettings)> {
+    let name = spec.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > TABLE_NAME_MAX_LEN {
+        return Err(reject(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("name must be 1 to {TABLE_NAME_MAX_LEN} characters"),
+        ));
+    }
+    spec.settings
+        .validate()
+        .map_err(|e| reject(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    Ok((name, spec.settings))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/tables",
+    responses(
+        (status = 200, description = "Every table, closed ones included", body = [TableRecord]),
+        (status = 401, description = "Missing or invalid access token"),
+        (status = 403, description = "Caller is not an admin")
+    )
+)]
+pub async fn list_all_tables(
+    _admin: AdminPlayer,
+    State(state): State<AppState>,
+) -> AdminResult<Json<Vec<TableRecord>>> {
+    let mut tables = state.tables.list_tables().await.map_err(store_failed)?;
+    // The store only knows open or closed; a table finishing its last round
+    // shows as closing.
+    for live in state.session.list_tables().await {
+        if let Some(table) = tables.iter_mut().find(|t| t.id == live.id) {
+            table.status = live.status;
+        }
+    }
+    Ok(Json(tables))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/tables",
+    request_body = TableSpec,
+    responses(
+        (status = 201, description = "Table created and open for players", body = TableRecord),
+        (status = 401, description = "Missing or invalid access token"),
+        (status = 403, description = "Caller is not an admin"),
+        (status = 422, description = "Invalid name or settings", body = AdminErrorBody)
+    )
+)]
+pub async fn create_table(
+    admin: AdminPlayer,
+    State(state): State<AppState>,
+    Json(spec): Json<TableSpec>,
+) -> AdminResult<(StatusCode, Json<TableRecord>)> {
+    let (name, settings) = validate(spec)?;
+    let table = TableRecord {
+        id: TableId::new(),
+        name,
+        status: TableStatus::Open,
+        settings,
+    };
+    state
+        .tables
+        .insert_table(&table)
+        .await
+        .map_err(store_failed)?;
+    state.session.open_table(&table).await.map_err(|e| {
+        error!("table={} could not open: {e}", table.id);
+        reject(StatusCode::INTERNAL_SERVER_ERROR, e)
+    })?;
+    info!(
+        "admin='{}' opened table={} '{}'",
+        admin.username, table.id, table.name
+    );
+    Ok((StatusCode::CREATED, Json(table)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/tables/{id}",
+    params(("id" = String, Path, description = "Table id")),
+    request_body = TableSpec,
+    responses(
+        (status = 200, description = "Table renamed and its settings replaced", body = TableRecord),
+        (status = 401, description = "Missing or invalid access token"),
+        (status = 403, description = "Caller is not an admin"),
+        (status = 404, description = "No such table", body = AdminErrorBody),
+        (status = 409, description = "Table is closed, or seated players conflict with the settings", body = AdminErrorBody),
+        (status = 422, description = "Invalid name or settings", body = AdminErrorBody)
+    )
+)]
+pub async fn update_table(
+    admin: AdminPlayer,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(spec): Json<TableSpec>,
+) -> AdminResult<Json<TableRecord>> {
+    let id = parse_id(&id)?;
+    let (name, settings) = validate(spec)?;
+    let mut table = state.tables.get_table(id).await.map_err(store_failed)?;
+    if table.status != TableStatus::Open {
+        return Err(reject(StatusCode::CONFLICT, "table is closed"));
+    }
+    table.name = name;
+    table.settings = settings;
+    // The running table gets the last word: it knows who is seated.
+    state
+        .session
+        .update_table(&table)
+        .await
+        .map_err(|e| match e {
+            SessionError::Internal => reject(StatusCode::INTERNAL_SERVER_ERROR, e),
+            SessionError::TableNotFound => reject(StatusCode::CONFLICT, "table is closed"),
+            SessionError::CommandRejected(reason) => reject(StatusCode::CONFLICT, reason),
+        })?;
+    state
+        .tables
+        .update_table(&table)
+        .await
+        .map_err(store_failed)?;
+    info!(
+        "admin='{}' updated table={} '{}' {:?}",
+        admin.username, table.id, table.name, table.settings
+    );
+    Ok(Json(table))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/tables/{id}/close",
+    params(("id" = String, Path, description = "Table id")),
+    responses(
+        (status = 202, description = "Table closes once its current round is over", body = TableRecord),
+        (status = 401, description = "Missing or invalid access token"),
+        (status = 403, description = "Caller is not an admin"),
+        (status = 404, description = "No such table", body = AdminErrorBody),
+        (status = 409, description = "Table is already closed", body = AdminErrorBody)
+    )
+)]
+pub async fn close_table(
+    admin: AdminPlayer,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AdminResult<(StatusCode, Json<TableRecord>)> {
+    let id = parse_id(&id)?;
+    let mut table = state.tables.get_table(id).await.map_err(store_failed)?;
+    if table.status != TableStatus::Open {
+        return Err(reject(StatusCode::CONFLICT, "table is already closed"));
+    }
+    // Stored first, so a restart mid-round does not bring the table back.
+    table.status = TableStatus::Closed;
+    state
+        .tables
+        .update_table(&table)
+        .await
+        .map_err(store_failed)?;
+    match state.session.close_table(id).await {
+        // Between rounds the table is closed already; otherwise it is
+        // finishing the round in play.
+        Ok(()) => {
+            if let Some(live) = state
+                .session
+                .list_tables()
+                .await
+                .iter()
+                .find(|t| t.id == id)
+            {
+                table.status = live.status;
+            }
+        }
+        // Not running, or closing already: the stored status is all there is.
+        Err(e @ (SessionError::TableNotFound | SessionError::CommandRejected(_))) => {
+            warn!("table={id} close: {e}")
+        }
+        Err(e) => return Err(reject(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+    info!(
+        "admin='{}' closed table={} '{}'",
+        admin.username, table.id, table.name
+    );
+    Ok((StatusCode::ACCEPTED, Json(table)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/players/{username}/role",
+    params(("username" = String, Path, description = "Account username")),
+    request_body = RoleRequest,
+    responses(
+        (status = 200, description = "Role changed; it applies from the account's next connection", body = PlayerRole),
+        (status = 401, description = "Missing or invalid access token"),
+        (status = 403, description = "Caller is not an admin"),
+        (status = 404, description = "No such account", body = AdminErrorBody),
+        (status = 409, description = "Admins cannot change their own role", body = AdminErrorBody)
+    )
+)]
+pub async fn set_role(
+    admin: AdminPlayer,
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    Json(req): Json<RoleRequest>,
+) -> AdminResult<Json<PlayerRole>> {
+    // Otherwise the last admin could lock everyone out of this endpoint.
+    if username == admin.username {
+        return Err(reject(
+            StatusCode::CONFLICT,
+            "admins cannot change their own role",
+        ));
+    }
+    let player_id = state
+        .auth
+        .set_role(&username, req.role)
+        .await
+        .map_err(|e| match e {
+            AuthError::UnknownUser => reject(StatusCode::NOT_FOUND, "account not found"),
+            e => {
+                error!("auth backend error: {e}");
+                reject(StatusCode::INTERNAL_SERVER_ERROR, e)
+            }
+        })?;
+    info!(
+        "admin='{}' gave user='{username}' player_id={player_id} the {} role",
+        admin.username, req.role
+    );
+    Ok(Json(PlayerRole {
+        player_id,
+        username,
+        role: req.role,
+    }))
+}
+
+const DEFAULT_AUDIT_LIMIT: usize = 100;
+const MAX_AUDIT_LIMIT: usize = 1000;
+
+#[derive(Deserialize, IntoParams)]
+pub struct AuditParams {
+    /// The player whose commands, and timeouts, to return.
+    player_id: String,
+    /// From this Unix time in milliseconds (default: the beginning).
+    from: Option<u64>,
+    /// Up to, but not including, this Unix time in milliseconds (default:
+    /// now).
+    to: Option<u64>,
+    /// Number of records to return, oldest first (default 100, max 1000).
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/audit",
+    params(AuditParams),
+    responses(
+        (status = 200, description = "The player's commands and their outcomes, oldest first", body = [AuditRecord]),
+        (status = 401, description = "Missing or invalid access token"),
+        (status = 403, description = "Caller is not an admin"),
+        (status = 422, description = "Invalid player id or time range", body = AdminErrorBody)
+    )
+)]
+pub async fn audit_trail(
+    _admin: AdminPlayer,
+    Query(params): Query<AuditParams>,
+    State(state): State<AppState>,
+) -> AdminResult<Json<Vec<AuditRecord>>> {
+    let player_id: PlayerId = params
+        .player_id
+        .parse()
+        .map_err(|_| reject(StatusCode::UNPROCESSABLE_ENTITY, "invalid player_id"))?;
+    let from = Timestamp::from_millis(params.from.unwrap_or(0));
+    let to = params.to.map_or_else(
+        || SystemClock.now().plus(Duration::from_millis(1)),
+        Timestamp::from_millis,
+    );
+    if to < from {
+        return Err(reject(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "`to` is before `from`",
+        ));
+    }
+    let query = AuditQuery {
+        player_id,
+        from,
+        to,
+        limit: params
+            .limit
+            .unwrap_or(DEFAULT_AUDIT_LIMIT)
+            .clamp(1, MAX_AUDIT_LIMIT),
+    };
+    state.audit.query(&query).await.map(Json).map_err(|e| {
+        error!("player={player_id} audit trail not read: {e}");
+        reject(StatusCode::INTERNAL_SERVER_ERROR, e)
+    })
+}
@@ -0,0 +1,259 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::StatusCode,
+    Json,
+};
+use bj_core::domain::PlayerId;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+use crate::{
+    auth::{AuthError, AuthPayload, AuthenticatedPlayer, Password, TokenPair},
+    rate_limit::{Limit, Limited},
+    wallet::Posting,
+    AppState,
+};
+
+/// Chips granted to a new account.
+const NEW_PLAYER_CHIPS: u32 = 1_000;
+
+#[derive(Deserialize, ToSchema)]
+pub struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuthErrorBody {
+    error: String,
+    /// Seconds to wait before trying again, when refused for too many
+    /// failed logins or sign-ups.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_secs: Option<u64>,
+}
+
+type AuthResult<T> = Result<T, (StatusCode, Json<AuthErrorBody>)>;
+
+fn reject(e: AuthError) -> (StatusCode, Json<AuthErrorBody>) {
+    let status = match e {
+        AuthError::Failed(_) => {
+            error!("auth backend error: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+        AuthError::UsernameTaken => StatusCode::CONFLICT,
+        AuthError::TooManyAttempts { .. }
+        | AuthError::TooManySignups { .. }
+        | AuthError::TooManyLogins { .. } => StatusCode::TOO_MANY_REQUESTS,
+        AuthError::AccountLocked { .. } => StatusCode::LOCKED,
+        AuthError::InvalidUsername(_) | AuthError::WeakPassword(_) => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        _ => StatusCode::UNAUTHORIZED,
+    };
+    let retry_after_secs = match e {
+        AuthError::TooManyAttempts { retry_after_secs }
+        | AuthError::AccountLocked { retry_after_secs }
+        | AuthError::TooManySignups { retry_after_secs }
+        | AuthError::TooManyLogins { retry_after_secs } => Some(retry_after_secs),
+        _ => None,
+    };
+    (
+        status,
+        Json(AuthErrorBody {
+            error: e.to_string(),
+            retry_after_secs,
+        }),
+    )
+}
+
+/// The error for a login or sign-up refused before its password was
+/// checked.
+fn blocked(limited: Limited) -> AuthError {
+    // Rounded up, so a client waiting this long is let through.
+    let retry_after_secs = limited.retry_after.as_millis().div_ceil(1000) as u64;
+    match limited.limit {
+        Limit::LoginLocked => AuthError::AccountLocked { retry_after_secs },
+        Limit::Signup => AuthError::TooManySignups { retry_after_secs },
+        Limit::LoginIp => AuthError::TooManyLogins { retry_after_secs },
+        _ => AuthError::TooManyAttempts { retry_after_secs },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Session started", body = TokenPair),
+        (status = 401, description = "Wrong username or password", body = AuthErrorBody),
+        (status = 423, description = "Account locked after repeated failed logins", body = AuthErrorBody),
+        (status = 429, description = "Too soon after a failed login, or too many logins from this address; wait `retry_after_secs`", body = AuthErrorBody)
+    )
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Json(req): Json<LoginRequest>,
+) -> AuthResult<Json<TokenPair>> {
+    let payload = AuthPayload {
+        username: req.username,
+        password: Password::new(req.password),
+    };
+    let limits = &state.rate_limits;
+    limits.login_from(peer.ip()).map_err(|limited| {
+        warn!(
+            "login refused user='{}' ip={} for {:?}",
+            payload.username,
+            peer.ip(),
+            limited.retry_after
+        );
+        reject(blocked(limited))
+    })?;
+    limits.login_allowed(&payload.username).map_err(|limited| {
+        warn!(
+            "login refused user='{}': {:?} for {:?}",
+            payload.username, limited.limit, limited.retry_after
+        );
+        reject(blocked(limited))
+    })?;
+    let pid = state.auth.authenticate(&payload).await.map_err(|e| {
+        if matches!(e, AuthError::WrongPassword | AuthError::UnknownUser) {
+            let failures = limits.login_failed(&payload.username);
+            warn!(
+                "login failed user='{}' failures={failures}: {e}",
+                payload.username
+            );
+        } else {
+            warn!("login failed user='{}': {e}", payload.username);
+        }
+        reject(e)
+    })?;
+    limits.login_succeeded(&payload.username);
+    // Seeded accounts get their chips on first login.
+    open_wallet(&state, pid).await;
+    info!("user='{}' player_id={pid} logged in", payload.username);
+    state.sessions.issue(pid).await.map(Json).map_err(reject)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    request_body = LoginRequest,
+    responses(
+        (status = 201, description = "Account created and logged in", body = TokenPair),
+        (status = 409, description = "Username already taken", body = AuthErrorBody),
+        (status = 422, description = "Username or password does not meet the policy", body = AuthErrorBody),
+        (status = 429, description = "Too many sign-ups from this address; wait `retry_after_secs`", body = AuthErrorBody)
+    )
+)]
+pub async fn register(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Json(req): Json<LoginRequest>,
+) -> AuthResult<(StatusCode, Json<TokenPair>)> {
+    let payload = AuthPayload {
+        username: req.username,
+        password: Password::new(req.password),
+    };
+    state.rate_limits.signup(peer.ip()).map_err(|limited| {
+        warn!(
+            "signup refused user='{}' ip={} for {:?}",
+            payload.username,
+            peer.ip(),
+            limited.retry_after
+        );
+        reject(blocked(limited))
+    })?;
+    let pid = state.auth.register(&payload).await.map_err(|e| {
+        warn!("signup failed user='{}': {e}", payload.username);
+        reject(e)
+    })?;
+    open_wallet(&state, pid).await;
+    info!("user='{}' player_id={pid} signed up", payload.username);
+    let tokens = state.sessions.issue(pid).await.map_err(reject)?;
+    Ok((StatusCode::CREATED, Json(tokens)))
+}
+
+/// Grants the starting chips unless the player already has a wallet account.
+async fn open_wallet(state: &AppState, pid: PlayerId) {
+    if state.wallet.balance(pid).await.is_err() {
+        if let Err(e) = state
+            .wallet
+            .credit(pid, NEW_PLAYER_CHIPS, Posting::grant())
+            .await
+        {
+            error!("player_id={pid} could not open wallet: {e}");
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "New token pair; the old refresh token is spent", body = TokenPair),
+        (status = 401, description = "Unknown, expired or revoked refresh token; reusing a spent one revokes its session", body = AuthErrorBody)
+    )
+)]
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(req): Json<RefreshRequest>,
+) -> AuthResult<Json<TokenPair>> {
+    state
+        .sessions
+        .refresh(&req.refresh_token)
+        .await
+        .map(Json)
+        .map_err(reject)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 401, description = "Missing or invalid access token")
+    )
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    caller: AuthenticatedPlayer,
+) -> AuthResult<StatusCode> {
+    state
+        .sessions
+        .revoke(caller.session_id)
+        .await
+        .map_err(reject)?;
+    info!("player_id={} logged out", caller.player_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/revoke",
+    responses(
+        (status = 204, description = "Every session of the caller revoked"),
+        (status = 401, description = "Missing or invalid access token")
+    )
+)]
+pub async fn revoke_all(
+    State(state): State<AppState>,
+    caller: AuthenticatedPlayer,
+) -> AuthResult<StatusCode> {
+    state
+        .sessions
+        .revoke_player(caller.player_id)
+        .await
+        .map_err(reject)?;
+    info!("player_id={} logged out everywhere", caller.player_id);
+    Ok(StatusCode::NO_CONTENT)
+}
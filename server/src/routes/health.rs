@@ -1,12 +1,47 @@
-use axum::http::StatusCode;
+use crate::AppState;
+use axum::{extract::State, Json};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    /// Serving, but some tables were rebuilt after a panic and are still
+    /// recovering.
+    Degraded,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Health {
+    pub status: HealthStatus,
+    /// Ids of the tables still recovering from a panic.
+    pub degraded_tables: Vec<String>,
+}
 
 #[utoipa::path(
     get,
     path = "/health",
     responses(
-        (status = 200, description = "Service is healthy")
+        (status = 200, description = "Service is up; `status` says whether every table is healthy", body = Health)
     )
 )]
-pub async fn health_check() -> StatusCode {
-    StatusCode::OK
+pub async fn health_check(State(state): State<AppState>) -> Json<Health> {
+    let degraded_tables: Vec<String> = state
+        .session
+        .list_tables()
+        .await
+        .into_iter()
+        .filter(|t| t.degraded)
+        .map(|t| t.id.to_string())
+        .collect();
+    let status = if degraded_tables.is_empty() {
+        HealthStatus::Ok
+    } else {
+        HealthStatus::Degraded
+    };
+    Json(Health {
+        status,
+        degraded_tables,
+    })
 }
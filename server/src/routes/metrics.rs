@@ -0,0 +1,13 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+
+use crate::AppState;
+
+/// Prometheus text exposition. Not part of the OpenAPI spec: it is for
+/// scrapers, not clients.
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let tables = state.session.list_tables().await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(&tables),
+    )
+}
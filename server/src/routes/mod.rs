@@ -1,8 +1,12 @@
+mod admin;
+mod auth;
 mod health;
+mod metrics;
+mod player;
 mod table;
 mod ws;
 
-use axum::Router;
+use axum::{routing::get, Router};
 use utoipa::OpenApi;
 use utoipa_axum::router::OpenApiRouter;
 use utoipa_redoc::{Redoc, Servable};
@@ -15,7 +19,21 @@ struct ApiDoc;
 fn api_router() -> (Router<AppState>, utoipa::openapi::OpenApi) {
     OpenApiRouter::with_openapi(ApiDoc::openapi())
         .routes(utoipa_axum::routes!(health::health_check))
+        .routes(utoipa_axum::routes!(auth::register))
+        .routes(utoipa_axum::routes!(auth::login))
+        .routes(utoipa_axum::routes!(auth::refresh))
+        .routes(utoipa_axum::routes!(auth::logout))
+        .routes(utoipa_axum::routes!(auth::revoke_all))
         .routes(utoipa_axum::routes!(table::list_tables))
+        .routes(utoipa_axum::routes!(
+            admin::list_all_tables,
+            admin::create_table
+        ))
+        .routes(utoipa_axum::routes!(admin::update_table))
+        .routes(utoipa_axum::routes!(admin::close_table))
+        .routes(utoipa_axum::routes!(admin::set_role))
+        .routes(utoipa_axum::routes!(admin::audit_trail))
+        .routes(utoipa_axum::routes!(player::my_transactions))
         .routes(utoipa_axum::routes!(ws::ws_handler))
         .split_for_parts()
 }
@@ -30,6 +48,7 @@ pub fn create_router(state: AppState) -> Router {
     let (router, api) = api_router();
 
     router
+        .route("/metrics", get(metrics::metrics))
         .merge(Redoc::with_url("/redoc", api))
         .with_state(state)
 }
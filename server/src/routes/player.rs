@@ -0,0 +1,47 @@
+use crate::{
+    auth::AuthenticatedPlayer,
+    wallet::{Transaction, WalletError},
+    AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use tracing::error;
+use utoipa::IntoParams;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+#[derive(Deserialize, IntoParams)]
+pub struct TransactionsQuery {
+    /// Number of entries to return, newest first (default 50, max 500).
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/players/me/transactions",
+    params(TransactionsQuery),
+    responses(
+        (status = 200, description = "The caller's chip ledger, newest first", body = [Transaction]),
+        (status = 401, description = "Missing or invalid access token")
+    )
+)]
+pub async fn my_transactions(
+    AuthenticatedPlayer { player_id, .. }: AuthenticatedPlayer,
+    Query(query): Query<TransactionsQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Transaction>>, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    match state.wallet.transactions(player_id, limit).await {
+        Ok(entries) => Ok(Json(entries)),
+        Err(WalletError::PlayerNotFound) => Ok(Json(vec![])),
+        Err(e) => {
+            error!("player={player_id} could not read transactions: {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
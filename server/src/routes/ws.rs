@@ -1,43 +1,104 @@
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, State,
     },
-    response::IntoResponse,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
 };
-use tokio::sync::mpsc;
-use tracing::{error, info, warn};
+use serde::Serialize;
+use std::{net::SocketAddr, ops::ControlFlow, sync::Arc};
+use tokio::{
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+    time::Instant,
+};
+use tracing::{debug, error, info, warn};
 use ulid::Ulid;
+use utoipa::ToSchema;
 
 use crate::{
-    auth::{AuthPayload, Authenticator, Password},
+    auth::{AuthError, Role},
+    heartbeat::Unresponsive,
+    metrics::Metrics,
     protocol::{ClientMessage, ServerMessage},
-    session::RequestId,
+    rate_limit::{ConnectionSlot, Limited, TokenBucket},
+    session::{grace::Reclaimed, Catchup, GameSession, RequestId, SessionError},
+    shutdown::ShutdownState,
+    wallet::Wallet,
     AppState,
 };
 
-/// Chips granted to a first-time player on their initial login.
-const NEW_PLAYER_CHIPS: u32 = 1_000;
 use bj_core::domain::{
-    engine::command::player::{
-        Hit, JoinTable, LeaveSeat, LeaveTable, PlaceBet, PlayerAction, Stand, TakeSeat,
+    engine::command::{
+        dealer::{DealInitialCards, DealerAction, OpenBetting, PlayHand, SettleRound},
+        player::{Hit, JoinTable, LeaveSeat, LeaveTable, PlaceBet, PlayerAction, Stand, TakeSeat},
+    },
+    engine::{
+        event::{payload::EventPayload, GameEvent},
+        snapshot::GameEventDto,
     },
-    engine::snapshot::GameEventDto,
-    Seat, TableId,
+    PlayerId, Seat, TableId,
 };
 
-#[utoipa::path(get, path = "/ws", responses((status = 101, description = "WebSocket upgrade")))]
-pub async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+#[derive(Serialize, ToSchema)]
+pub struct TooManyConnections {
+    error: String,
+    retry_after_secs: u64,
 }
 
-async fn handle_socket(mut socket: WebSocket, state: AppState) {
+#[utoipa::path(
+    get,
+    path = "/ws",
+    responses(
+        (status = 101, description = "WebSocket upgrade"),
+        (status = 429, description = "Too many open connections from this address", body = TooManyConnections)
+    )
+)]
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    State(state): State<AppState>,
+) -> Response {
+    let slot = match state.rate_limits.connect(peer.ip()) {
+        Ok(slot) => slot,
+        Err(limited) => {
+            warn!(
+                "ws upgrade from {} refused: too many connections",
+                peer.ip()
+            );
+            let body = TooManyConnections {
+                error: "too many open connections from this address".into(),
+                retry_after_secs: limited.retry_after.as_secs(),
+            };
+            return (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+        }
+    };
+    ws.on_upgrade(move |socket| handle_socket(socket, state, slot))
+}
+
+async fn handle_socket(mut socket: WebSocket, state: AppState, _slot: ConnectionSlot) {
     let conn_id = Ulid::new();
+    let _connection = state.metrics.connection();
+    let mut bucket = state.rate_limits.connection_bucket();
     info!("WS connection {conn_id} opened");
 
     // Auth phase
-    let (player_id, authed_username) = loop {
-        match socket.recv().await {
+    let idle_timeout = state.heartbeat.idle_timeout();
+    let (player_id, authed_username, role, claims) = loop {
+        let received = match idle_timeout {
+            Some(idle) => match tokio::time::timeout(idle, socket.recv()).await {
+                Ok(received) => received,
+                Err(_) => {
+                    info!("conn={conn_id} idle for {idle:?} before auth; closing");
+                    let _ = socket.send(Message::Close(None)).await;
+                    return;
+                }
+            },
+            None => socket.recv().await,
+        };
+        match received {
             None => {
                 info!("conn={conn_id} disconnected before auth");
                 return;
@@ -46,80 +107,159 @@ async fn handle_socket(mut socket: WebSocket, state: AppState) {
                 error!("conn={conn_id} recv error before auth: {e}");
                 return;
             }
-            Some(Ok(Message::Text(text))) => {
-                match serde_json::from_str::<ClientMessage>(&text) {
-                    Ok(ClientMessage::Auth { username, password }) => {
-                        match state
+            Some(Ok(Message::Text(_)))
+                if too_fast(&mut socket, &state, &mut bucket, None, 0).await =>
+            {
+                continue;
+            }
+            Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(ClientMessage::Auth { token }) => match state.sessions.verify(&token).await {
+                    Ok(claims) => {
+                        let pid = claims.player_id;
+                        let username = state
                             .auth
-                            .authenticate(&AuthPayload {
-                                username: username.clone(),
-                                password: Password::new(password),
-                            })
+                            .lookup_username(pid)
                             .await
+                            .ok()
+                            .flatten()
+                            .unwrap_or_else(|| pid.to_string());
+                        let role = state.auth.role(pid).await.unwrap_or_default();
+                        info!(
+                            "conn={conn_id} authenticated user='{}' player_id={} role={}",
+                            username, pid, role
+                        );
+                        if send_msg(
+                            &mut socket,
+                            &ServerMessage::AuthOk {
+                                player_id: pid.to_string(),
+                                role,
+                            },
+                        )
+                        .await
+                        .is_err()
                         {
-                            Ok(pid) => {
-                                // Only seed chips for new players (wallet returns Err if player unknown)
-                                if state.wallet.balance(pid).await.is_err() {
-                                    let _ = state.wallet.credit(pid, NEW_PLAYER_CHIPS).await;
-                                }
-                                info!(
-                                    "conn={conn_id} authenticated user='{}' player_id={}",
-                                    username, pid
-                                );
-                                if send_msg(
-                                    &mut socket,
-                                    &ServerMessage::AuthOk {
-                                        player_id: pid.to_string(),
-                                    },
-                                )
-                                .await
-                                .is_err()
-                                {
-                                    return;
-                                }
-                                let balance = state.wallet.balance(pid).await.unwrap_or(0);
-                                let _ = send_msg(
-                                    &mut socket,
-                                    &ServerMessage::Balance { amount: balance },
-                                )
-                                .await;
-                                break (pid, username);
-                            }
-                            Err(e) => {
-                                warn!("conn={conn_id} auth failed user='{}': {e}", username);
-                                let _ = send_msg(
-                                    &mut socket,
-                                    &ServerMessage::AuthError {
-                                        reason: e.to_string(),
-                                    },
-                                )
-                                .await;
-                                return;
-                            }
+                            return;
                         }
+                        let balance = state.wallet.balance(pid).await.unwrap_or(0);
+                        let _ = send_msg(&mut socket, &ServerMessage::Balance { amount: balance })
+                            .await;
+                        break (pid, username, role, claims);
                     }
-                    _ => {
-                        warn!("conn={conn_id} sent non-Auth message before authenticating");
+                    Err(e) => {
+                        warn!("conn={conn_id} auth failed: {e}");
                         let _ = send_msg(
                             &mut socket,
                             &ServerMessage::AuthError {
-                                reason: "send Auth first".into(),
+                                reason: e.to_string(),
                             },
                         )
                         .await;
+                        return;
                     }
+                },
+                _ => {
+                    warn!("conn={conn_id} sent non-Auth message before authenticating");
+                    let _ = send_msg(
+                        &mut socket,
+                        &ServerMessage::AuthError {
+                            reason: "send Auth first".into(),
+                        },
+                    )
+                    .await;
                 }
-            }
+            },
             Some(Ok(_)) => {}
         }
     };
 
+    let _authenticated = state.metrics.authenticated();
+    // A client connecting mid-shutdown hears about it straight away.
+    let mut shutdown = state.shutdown.watch();
+    let now = *shutdown.borrow_and_update();
+    if follow_shutdown(&mut socket, now).await.is_break() {
+        return;
+    }
     let mut current_table: Option<TableId> = None;
     let (event_fwd_tx, mut event_fwd_rx) = mpsc::channel::<String>(64);
-    let mut fwd_abort: Option<tokio::task::JoinHandle<()>> = None;
+    let mut fwd_abort: Option<JoinHandle<()>> = None;
+
+    let (reclaimed, mut superseded) = state.seat_holds.attach(player_id, conn_id).await;
+    if let Some(reclaimed) = reclaimed {
+        let tid = reclaimed.table_id;
+        match reattach(&mut socket, &state, player_id, reclaimed, &event_fwd_tx).await {
+            Ok(handle) => {
+                info!(
+                    "conn={conn_id} user='{}' back at table={tid}",
+                    authed_username
+                );
+                fwd_abort = Some(handle);
+                current_table = Some(tid);
+            }
+            Err(e) => warn!(
+                "conn={conn_id} user='{}' could not rejoin table={tid}: {e}",
+                authed_username
+            ),
+        }
+    }
+
+    // A client that closes on purpose gives up its seat; a dropped one keeps
+    // it for the grace period.
+    let mut hung_up = false;
+    // Set when a newer connection for the same player takes over.
+    let mut handover = None;
+    let mut pinger = state.heartbeat.pinger();
+    let mut recheck = Instant::now() + state.sessions.recheck_in(&claims);
+    let ping_interval_ms = state.heartbeat.interval.map_or(0, |i| i.as_millis() as u64);
 
     loop {
         tokio::select! {
+            Ok(()) = shutdown.changed() => {
+                let now = *shutdown.borrow_and_update();
+                if follow_shutdown(&mut socket, now).await.is_break() {
+                    info!("conn={conn_id} user='{}' closed for shutdown", authed_username);
+                    // The tables are closed; there is no seat to hold.
+                    hung_up = true;
+                    break;
+                }
+            }
+            Ok(h) = &mut superseded => {
+                info!("conn={conn_id} user='{}' replaced by a new connection", authed_username);
+                handover = Some(h);
+                break;
+            }
+            () = pinger.due() => {
+                match pinger.ping() {
+                    Ok(payload) => {
+                        if socket.send(Message::Ping(payload.into())).await.is_err() {
+                            error!("conn={conn_id} user='{}' send failed (ping)", authed_username);
+                            break;
+                        }
+                    }
+                    Err(Unresponsive { missed }) => {
+                        // Treated like a dropped socket: the seat is held.
+                        warn!("conn={conn_id} user='{}' missed {missed} pongs; dropping", authed_username);
+                        state.metrics.heartbeat_timed_out();
+                        break;
+                    }
+                }
+            }
+            () = tokio::time::sleep_until(recheck) => {
+                match state.sessions.recheck(&claims).await {
+                    Ok(()) => recheck = Instant::now() + state.sessions.recheck_in(&claims),
+                    Err(e) => {
+                        info!("conn={conn_id} user='{}' closed: {e}", authed_username);
+                        let frame = CloseFrame {
+                            code: close_code::POLICY,
+                            reason: e.to_string().into(),
+                        };
+                        let _ = socket.send(Message::Close(Some(frame))).await;
+                        // An expired token is refreshed by reconnecting, which
+                        // takes the seat back; a revoked session is over.
+                        hung_up = !matches!(e, AuthError::TokenExpired);
+                        break;
+                    }
+                }
+            }
             Some(json) = event_fwd_rx.recv() => {
                 if socket.send(Message::Text(json.into())).await.is_err() {
                     error!("conn={conn_id} user='{}' send failed (event forward)", authed_username);
@@ -138,10 +278,26 @@ async fn handle_socket(mut socket: WebSocket, state: AppState) {
                     }
                     Some(Ok(Message::Close(_))) => {
                         info!("conn={conn_id} user='{}' sent close frame", authed_username);
+                        hung_up = true;
                         break;
                     }
+                    Some(Ok(Message::Pong(payload))) => {
+                        if let Some(rtt) = pinger.pong(&payload) {
+                            debug!("conn={conn_id} user='{}' rtt={rtt:?}", authed_username);
+                            state.metrics.round_trip(rtt);
+                            let _ = send_msg(&mut socket, &ServerMessage::Latency {
+                                rtt_ms: rtt.as_millis() as u64,
+                                ping_interval_ms,
+                            }).await;
+                        }
+                    }
                     Some(Ok(Message::Text(text))) => {
-                        match serde_json::from_str::<ClientMessage>(&text) {
+                        let parsed = serde_json::from_str::<ClientMessage>(&text);
+                        let request_id = parsed.as_ref().map_or(0, ClientMessage::request_id);
+                        if too_fast(&mut socket, &state, &mut bucket, Some(player_id), request_id).await {
+                            continue;
+                        }
+                        match parsed {
                             Err(e) => {
                                 warn!("conn={conn_id} user='{}' parse error: {e}", authed_username);
                                 let _ = send_msg(&mut socket, &ServerMessage::CommandError {
@@ -151,7 +307,7 @@ async fn handle_socket(mut socket: WebSocket, state: AppState) {
                             }
                             Ok(msg) => {
                                 if handle_client_msg(
-                                    msg, player_id, &state,
+                                    msg, player_id, role, &state,
                                     &mut socket, &mut current_table,
                                     &event_fwd_tx, &mut fwd_abort,
                                 ).await.is_err() { break; }
@@ -164,38 +320,118 @@ async fn handle_socket(mut socket: WebSocket, state: AppState) {
         }
     }
 
+    // Stop forwarding only once a hold is listening to the table.
+    state
+        .seat_holds
+        .detach(
+            state.session.clone(),
+            player_id,
+            conn_id,
+            current_table,
+            hung_up,
+        )
+        .await;
     if let Some(h) = fwd_abort {
         h.abort();
     }
-    if let Some(tid) = current_table {
-        if let Err(e) = state
-            .session
-            .send_command(
-                tid,
-                player_id,
-                RequestId(0),
-                PlayerAction::LeaveTable(LeaveTable { player_id }),
-            )
-            .await
-        {
-            warn!(
-                "conn={conn_id} user='{}' leave-table on disconnect failed: {e}",
-                authed_username
-            );
-        }
+    if let Some(handover) = handover {
+        handover.done();
+        let _ = socket.send(Message::Close(None)).await;
     }
     info!("conn={conn_id} user='{}' session ended", authed_username);
 }
 
+/// Meters one message; a message over the limits is answered with
+/// `RateLimited` instead of being handled.
+async fn too_fast(
+    socket: &mut WebSocket,
+    state: &AppState,
+    bucket: &mut TokenBucket,
+    player_id: Option<PlayerId>,
+    request_id: u64,
+) -> bool {
+    let Err(Limited { limit, retry_after }) = state.rate_limits.message(bucket, player_id) else {
+        return false;
+    };
+    warn!("player={player_id:?} request_id={request_id} over the {limit:?} rate limit");
+    let _ = send_msg(
+        socket,
+        &ServerMessage::RateLimited {
+            request_id,
+            limit,
+            retry_after_ms: retry_after.as_millis().max(1) as u64,
+        },
+    )
+    .await;
+    true
+}
+
+/// Passes the server's shutdown on to the client. `Break` once the socket
+/// has been closed.
+async fn follow_shutdown(socket: &mut WebSocket, state: ShutdownState) -> ControlFlow<()> {
+    match state {
+        ShutdownState::Running => ControlFlow::Continue(()),
+        ShutdownState::Draining { deadline } => {
+            let _ = send_msg(socket, &ServerMessage::ServerShuttingDown { deadline }).await;
+            ControlFlow::Continue(())
+        }
+        ShutdownState::Closing => {
+            let frame = CloseFrame {
+                code: close_code::AWAY,
+                reason: "server shutting down".into(),
+            };
+            let _ = socket.send(Message::Close(Some(frame))).await;
+            ControlFlow::Break(())
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(
+    name = "client_message",
+    skip_all,
+    fields(player = %player_id, request_id = msg.request_id())
+)]
 async fn handle_client_msg(
     msg: ClientMessage,
-    player_id: bj_core::domain::PlayerId,
+    player_id: PlayerId,
+    role: Role,
     state: &AppState,
     socket: &mut WebSocket,
     current_table: &mut Option<TableId>,
     event_fwd_tx: &mpsc::Sender<String>,
-    fwd_abort: &mut Option<tokio::task::JoinHandle<()>>,
+    fwd_abort: &mut Option<JoinHandle<()>>,
 ) -> Result<(), ()> {
+    // A role can change while the socket is open; dealing rights are looked
+    // up again for every dealer command.
+    let role = if msg.is_dealer_command() {
+        state.auth.role(player_id).await.unwrap_or_default()
+    } else {
+        role
+    };
+    if !msg.allowed_for(role) {
+        warn!("player={player_id} role={role} not allowed to send {msg:?}");
+        let _ = send_msg(
+            socket,
+            &ServerMessage::CommandError {
+                request_id: msg.request_id(),
+                reason: format!("not allowed for the {role} role"),
+            },
+        )
+        .await;
+        return Ok(());
+    }
+    if msg.starts_play() && state.shutdown.is_draining() {
+        let _ = send_msg(
+            socket,
+            &ServerMessage::CommandError {
+                request_id: msg.request_id(),
+                reason: "the server is shutting down".into(),
+            },
+        )
+        .await;
+        return Ok(());
+    }
     match msg {
         ClientMessage::JoinTable {
             table_id,
@@ -233,7 +469,7 @@ async fn handle_client_msg(
             }
 
             // Subscribe before joining so PlayerJoined event is never missed.
-            let mut rx = match state.session.subscribe(tid).await {
+            let rx = match state.session.subscribe(tid).await {
                 Ok(rx) => rx,
                 Err(e) => {
                     error!("player={player_id} subscribe table={tid} failed: {e}");
@@ -274,8 +510,9 @@ async fn handle_client_msg(
             info!("player={player_id} joined table={tid}");
 
             // Snapshot — on failure, undo the join so the actor state stays consistent.
-            match state.session.snapshot(tid, player_id).await {
+            let last_seq = match state.session.snapshot(tid, player_id).await {
                 Ok(snap) => {
+                    let last_seq = snap.last_seq;
                     let _ = send_msg(
                         socket,
                         &ServerMessage::Snapshot {
@@ -284,6 +521,7 @@ async fn handle_client_msg(
                         },
                     )
                     .await;
+                    last_seq
                 }
                 Err(e) => {
                     error!("player={player_id} snapshot table={tid} failed: {e}");
@@ -306,64 +544,14 @@ async fn handle_client_msg(
                     .await;
                     return Ok(());
                 }
-            }
+            };
 
             // Start forwarder with the already-subscribed receiver
             if let Some(h) = fwd_abort.take() {
                 h.abort();
             }
-            let tx = event_fwd_tx.clone();
-            let tid_str = tid.to_string();
-            let wallet = state.wallet.clone();
-            let handle = tokio::spawn(async move {
-                loop {
-                    match rx.recv().await {
-                        Ok(event) => {
-                            let is_finished = matches!(
-                                event.payload,
-                                bj_core::domain::engine::event::payload::EventPayload::GameFinished { .. }
-                            );
-                            let dto = GameEventDto {
-                                game_id: event.game_id,
-                                seq: event.event_seq_id.0,
-                                payload: event.payload,
-                            };
-                            let msg = ServerMessage::Event {
-                                table_id: tid_str.clone(),
-                                event: dto,
-                            };
-                            if let Ok(json) = serde_json::to_string(&msg) {
-                                if tx.send(json).await.is_err() {
-                                    break;
-                                }
-                            }
-                            if is_finished {
-                                let balance = wallet.balance(player_id).await.unwrap_or(0);
-                                if let Ok(json) = serde_json::to_string(&ServerMessage::Balance {
-                                    amount: balance,
-                                }) {
-                                    let _ = tx.send(json).await;
-                                }
-                            }
-                        }
-                        Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
-                            warn!("table={tid_str} event forwarder lagged by {n} messages");
-                            let err = ServerMessage::CommandError {
-                                request_id: 0,
-                                reason: "event stream lagged, please rejoin".into(),
-                            };
-                            if let Ok(json) = serde_json::to_string(&err) {
-                                let _ = tx.send(json).await;
-                            }
-                            break;
-                        }
-                        Err(e) => {
-                            error!("table={tid_str} event forwarder recv error: {e}");
-                            break;
-                        }
-                    }
-                }
-            });
+            let handle =
+                Forwarder::new(tid, player_id, last_seq, state, event_fwd_tx).spawn(rx, None);
             *fwd_abort = Some(handle);
             *current_table = Some(tid);
             let _ = send_msg(socket, &ServerMessage::CommandAck { request_id }).await;
@@ -416,6 +604,52 @@ async fn handle_client_msg(
             }
         }
 
+        ClientMessage::Resume {
+            table_id,
+            last_seq,
+            request_id,
+        } => {
+            let reject = |reason: String| ServerMessage::CommandError { request_id, reason };
+            let tid = match table_id.parse::<TableId>() {
+                Ok(t) => t,
+                Err(_) => {
+                    let _ = send_msg(socket, &reject("invalid table_id".into())).await;
+                    return Ok(());
+                }
+            };
+            if current_table.is_some_and(|t| t != tid) {
+                let _ = send_msg(socket, &reject("leave your current table first".into())).await;
+                return Ok(());
+            }
+            // Subscribe first so nothing falls between the catch-up and the
+            // stream; the forwarder drops whatever overlaps.
+            let catchup = match state.session.subscribe(tid).await {
+                Ok(rx) => state
+                    .session
+                    .resume(tid, player_id, last_seq)
+                    .await
+                    .map(|catchup| (rx, catchup)),
+                Err(e) => Err(e),
+            };
+            let (rx, catchup) = match catchup {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!("player={player_id} resume table={tid} failed: {e}");
+                    let _ = send_msg(socket, &reject(e.to_string())).await;
+                    return Ok(());
+                }
+            };
+            if let Some(h) = fwd_abort.take() {
+                h.abort();
+            }
+            info!("player={player_id} resumed table={tid} after seq {last_seq}");
+            let _ = send_msg(socket, &ServerMessage::CommandAck { request_id }).await;
+            let handle = Forwarder::new(tid, player_id, last_seq, state, event_fwd_tx)
+                .spawn(rx, Some(catchup));
+            *fwd_abort = Some(handle);
+            *current_table = Some(tid);
+        }
+
         ClientMessage::PlaceBet {
             table_id,
             request_id,
@@ -508,18 +742,33 @@ async fn handle_client_msg(
             .await?;
         }
 
-        ClientMessage::DealerOpenBetting { request_id, .. }
-        | ClientMessage::DealerDealCards { request_id, .. }
-        | ClientMessage::DealerPlayHand { request_id, .. }
-        | ClientMessage::DealerSettle { request_id, .. } => {
-            let _ = send_msg(
-                socket,
-                &ServerMessage::CommandError {
-                    request_id,
-                    reason: "dealer commands not supported via WS in this version".into(),
-                },
-            )
-            .await;
+        ClientMessage::DealerOpenBetting {
+            table_id,
+            request_id,
+        } => {
+            let action = DealerAction::OpenBetting(OpenBetting);
+            send_dealer_cmd(socket, state, player_id, &table_id, request_id, action).await?;
+        }
+        ClientMessage::DealerDealCards {
+            table_id,
+            request_id,
+        } => {
+            let action = DealerAction::DealInitialCards(DealInitialCards);
+            send_dealer_cmd(socket, state, player_id, &table_id, request_id, action).await?;
+        }
+        ClientMessage::DealerPlayHand {
+            table_id,
+            request_id,
+        } => {
+            let action = DealerAction::PlayHand(PlayHand);
+            send_dealer_cmd(socket, state, player_id, &table_id, request_id, action).await?;
+        }
+        ClientMessage::DealerSettle {
+            table_id,
+            request_id,
+        } => {
+            let action = DealerAction::SettleRound(SettleRound);
+            send_dealer_cmd(socket, state, player_id, &table_id, request_id, action).await?;
         }
 
         ClientMessage::Auth { .. } => {
@@ -536,10 +785,220 @@ async fn handle_client_msg(
     Ok(())
 }
 
+/// Forwards a table's events to one connection, followed by the player's
+/// balance whenever a round ends.
+struct Forwarder {
+    tid: TableId,
+    player_id: PlayerId,
+    /// The last event the client has, from the stream or a snapshot.
+    /// Anything at or below it is not sent again.
+    last_seq: u64,
+    session: Arc<dyn GameSession>,
+    wallet: Arc<dyn Wallet>,
+    metrics: Arc<Metrics>,
+    tx: mpsc::Sender<String>,
+}
+
+impl Forwarder {
+    fn new(
+        tid: TableId,
+        player_id: PlayerId,
+        last_seq: u64,
+        state: &AppState,
+        tx: &mpsc::Sender<String>,
+    ) -> Self {
+        Self {
+            tid,
+            player_id,
+            last_seq,
+            session: state.session.clone(),
+            wallet: state.wallet.clone(),
+            metrics: state.metrics.clone(),
+            tx: tx.clone(),
+        }
+    }
+
+    /// Sends `catchup` first, if any, then follows `rx`.
+    fn spawn(self, rx: broadcast::Receiver<GameEvent>, catchup: Option<Catchup>) -> JoinHandle<()> {
+        tokio::spawn(self.run(rx, catchup))
+    }
+
+    async fn run(mut self, mut rx: broadcast::Receiver<GameEvent>, catchup: Option<Catchup>) {
+        if let Some(catchup) = catchup {
+            if self.catch_up(catchup).await.is_break() {
+                return;
+            }
+        }
+        loop {
+            let flow = match rx.recv().await {
+                Ok(event) => self.deliver(event).await,
+                // The receiver skips ahead to what the channel still holds;
+                // the table's backlog fills in what it dropped.
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    self.metrics.lagged(n);
+                    warn!(
+                        "table={} player={} event forwarder lagged by {n} messages, resyncing",
+                        self.tid, self.player_id
+                    );
+                    self.resync().await
+                }
+                Err(e) => {
+                    error!("table={} event forwarder recv error: {e}", self.tid);
+                    ControlFlow::Break(())
+                }
+            };
+            if flow.is_break() {
+                break;
+            }
+        }
+    }
+
+    /// Sends a live event. One that skips ahead of the last event sent
+    /// means the stream lost some on the way, as a cross-instance bus can
+    /// when a notification is too large or its listener reconnects: the
+    /// table fills the gap first.
+    async fn deliver(&mut self, event: GameEvent) -> ControlFlow<()> {
+        let seq = event.event_seq_id.0;
+        if seq > self.last_seq + 1 {
+            self.metrics.lagged(seq - self.last_seq - 1);
+            warn!(
+                "table={} player={} event stream skipped from seq {} to {seq}, resyncing",
+                self.tid, self.player_id, self.last_seq
+            );
+            self.resync().await?;
+        }
+        self.forward(event).await
+    }
+
+    async fn forward(&mut self, event: GameEvent) -> ControlFlow<()> {
+        if event.event_seq_id.0 <= self.last_seq {
+            return ControlFlow::Continue(());
+        }
+        self.last_seq = event.event_seq_id.0;
+        let is_closed = matches!(event.payload, EventPayload::TableClosed);
+        let is_finished = is_closed
+            || matches!(
+                event.payload,
+                EventPayload::GameFinished { .. } | EventPayload::RoundVoided { .. }
+            );
+        self.send(&ServerMessage::Event {
+            table_id: self.tid.to_string(),
+            event: event_dto(event),
+        })
+        .await?;
+        if is_finished {
+            let balance = self.wallet.balance(self.player_id).await.unwrap_or(0);
+            self.send(&ServerMessage::Balance { amount: balance })
+                .await?;
+        }
+        if is_closed {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Asks the table for whatever came after the last event sent.
+    async fn resync(&mut self) -> ControlFlow<()> {
+        match self
+            .session
+            .resume(self.tid, self.player_id, self.last_seq)
+            .await
+        {
+            Ok(catchup) => self.catch_up(catchup).await,
+            Err(e) => {
+                warn!(
+                    "table={} player={} resync failed: {e}",
+                    self.tid, self.player_id
+                );
+                let _ = self
+                    .send(&ServerMessage::CommandError {
+                        request_id: 0,
+                        reason: format!("event stream lost: {e}"),
+                    })
+                    .await;
+                ControlFlow::Break(())
+            }
+        }
+    }
+
+    async fn catch_up(&mut self, catchup: Catchup) -> ControlFlow<()> {
+        match catchup {
+            Catchup::Events(events) => {
+                for event in events {
+                    self.forward(event).await?;
+                }
+                ControlFlow::Continue(())
+            }
+            Catchup::Snapshot(snap) => {
+                self.last_seq = snap.last_seq;
+                self.send(&ServerMessage::Snapshot {
+                    table_id: self.tid.to_string(),
+                    state: snap,
+                })
+                .await
+            }
+        }
+    }
+
+    /// Breaks once the connection is gone.
+    async fn send(&self, msg: &ServerMessage) -> ControlFlow<()> {
+        let Ok(json) = serde_json::to_string(msg) else {
+            return ControlFlow::Continue(());
+        };
+        if self.tx.send(json).await.is_err() {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+fn event_dto(event: GameEvent) -> GameEventDto {
+    GameEventDto {
+        game_id: event.game_id,
+        seq: event.event_seq_id.0,
+        payload: event.payload,
+    }
+}
+
+/// Puts a reconnecting player back at the table they were holding: a fresh
+/// snapshot, the events they missed, then the live event stream.
+async fn reattach(
+    socket: &mut WebSocket,
+    state: &AppState,
+    player_id: PlayerId,
+    reclaimed: Reclaimed,
+    event_fwd_tx: &mpsc::Sender<String>,
+) -> Result<JoinHandle<()>, SessionError> {
+    let tid = reclaimed.table_id;
+    // Subscribe first so nothing falls between the snapshot and the stream.
+    let rx = state.session.subscribe(tid).await?;
+    let snap = state.session.snapshot(tid, player_id).await?;
+    let last_seq = snap.last_seq;
+    let _ = send_msg(
+        socket,
+        &ServerMessage::Snapshot {
+            table_id: tid.to_string(),
+            state: snap,
+        },
+    )
+    .await;
+    if let Some(missed) = reclaimed.missed.filter(|m| !m.is_empty()) {
+        let _ = send_msg(
+            socket,
+            &ServerMessage::MissedEvents {
+                table_id: tid.to_string(),
+                events: missed.into_iter().map(event_dto).collect(),
+            },
+        )
+        .await;
+    }
+    Ok(Forwarder::new(tid, player_id, last_seq, state, event_fwd_tx).spawn(rx, None))
+}
+
 async fn send_player_cmd(
     socket: &mut WebSocket,
     state: &AppState,
-    player_id: bj_core::domain::PlayerId,
+    player_id: PlayerId,
     table_id_str: &str,
     request_id: u64,
     action: PlayerAction,
@@ -581,6 +1040,33 @@ async fn send_player_cmd(
     Ok(())
 }
 
+async fn send_dealer_cmd(
+    socket: &mut WebSocket,
+    state: &AppState,
+    dealer_id: PlayerId,
+    table_id_str: &str,
+    request_id: u64,
+    action: DealerAction,
+) -> Result<(), ()> {
+    let result = match table_id_str.parse::<TableId>() {
+        Err(_) => Err("invalid table_id".to_string()),
+        Ok(tid) => state
+            .session
+            .dealer_command(tid, dealer_id, RequestId(request_id), action)
+            .await
+            .map_err(|e| {
+                warn!("dealer={dealer_id} command rejected on table={tid}: {e}");
+                e.to_string()
+            }),
+    };
+    let reply = match result {
+        Ok(_) => ServerMessage::CommandAck { request_id },
+        Err(reason) => ServerMessage::CommandError { request_id, reason },
+    };
+    let _ = send_msg(socket, &reply).await;
+    Ok(())
+}
+
 async fn send_msg(socket: &mut WebSocket, msg: &ServerMessage) -> Result<(), ()> {
     let json = serde_json::to_string(msg).map_err(|_| ())?;
     socket
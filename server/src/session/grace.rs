@@ -0,0 +1,219 @@
+use crate::session::{GameSession, RequestId};
+use bj_core::domain::{
+    engine::{
+        command::player::{LeaveTable, PlayerAction},
+        event::GameEvent,
+    },
+    PlayerId, TableId,
+};
+use dashmap::{mapref::entry::Entry, DashMap};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{broadcast::error::RecvError, oneshot},
+    task::JoinHandle,
+};
+use tracing::{info, warn};
+use ulid::Ulid;
+
+/// How long a new connection waits for the one it replaces to hand over.
+const HANDOVER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The most events kept for a player who is away. Past it, they get only
+/// the snapshot when they come back.
+pub const MAX_MISSED_EVENTS: usize = 1024;
+
+/// Seats kept for players whose connection dropped.
+///
+/// While a player is away the table plays on without them: their turns are
+/// stood on timeout by the dealer policy and they simply miss the betting
+/// window. If they authenticate again within the grace period,
+/// [`attach`](Self::attach) hands back the table and every event they
+/// missed; otherwise they are removed from the table.
+///
+/// Each player has at most one live connection. Authenticating again
+/// supersedes the old one, which may not have noticed yet that its socket is
+/// dead; it holds its seat on the way out so the new connection can take it.
+pub struct SeatHolds {
+    grace: Duration,
+    held: Arc<DashMap<PlayerId, Hold>>,
+    live: DashMap<PlayerId, Live>,
+}
+
+struct Hold {
+    /// Tells this hold apart from a later one for the same player.
+    id: Ulid,
+    table_id: TableId,
+    /// `None` once more was missed than can be replayed.
+    missed: Arc<Mutex<Option<Vec<GameEvent>>>>,
+    task: JoinHandle<()>,
+}
+
+struct Live {
+    conn_id: Ulid,
+    supersede: oneshot::Sender<Handover>,
+}
+
+/// Given to a superseded connection, which calls [`done`](Self::done) once it
+/// has let go of its table.
+pub struct Handover(oneshot::Sender<()>);
+
+impl Handover {
+    pub fn done(self) {
+        let _ = self.0.send(());
+    }
+}
+
+/// A held seat given back to its player.
+pub struct Reclaimed {
+    pub table_id: TableId,
+    /// Events broadcast at the table while the player was away, oldest
+    /// first, or `None` if there were too many to replay, or some were lost;
+    /// the snapshot sent on reattaching covers them.
+    pub missed: Option<Vec<GameEvent>>,
+}
+
+impl SeatHolds {
+    pub fn new(grace: Duration) -> Self {
+        Self {
+            grace,
+            held: Arc::new(DashMap::new()),
+            live: DashMap::new(),
+        }
+    }
+
+    /// Makes `conn_id` the player's live connection and returns their held
+    /// seat, if any, with a receiver that fires if a later connection
+    /// supersedes this one.
+    pub async fn attach(
+        &self,
+        player: PlayerId,
+        conn_id: Ulid,
+    ) -> (Option<Reclaimed>, oneshot::Receiver<Handover>) {
+        let (supersede, superseded) = oneshot::channel();
+        let previous = self.live.insert(player, Live { conn_id, supersede });
+        if let Some(previous) = previous {
+            let (done_tx, done_rx) = oneshot::channel();
+            if previous.supersede.send(Handover(done_tx)).is_ok() {
+                info!("player={player} conn={} superseded", previous.conn_id);
+                let _ = tokio::time::timeout(HANDOVER_TIMEOUT, done_rx).await;
+            }
+        }
+        (self.reclaim(player), superseded)
+    }
+
+    /// Called when a connection ends while seated or watching at `table`.
+    /// A player who hung up on purpose leaves at once; anyone else keeps
+    /// their place for the grace period.
+    pub async fn detach(
+        &self,
+        session: Arc<dyn GameSession>,
+        player: PlayerId,
+        conn_id: Ulid,
+        table: Option<TableId>,
+        hung_up: bool,
+    ) {
+        self.live
+            .remove_if(&player, |_, live| live.conn_id == conn_id);
+        match table {
+            Some(table_id) if hung_up => leave(session.as_ref(), player, table_id).await,
+            Some(table_id) => self.hold(session, player, table_id).await,
+            None => {}
+        }
+    }
+
+    /// Keeps `player`'s place at `table_id` for the grace period, then has
+    /// them leave the table. With no grace period they leave at once.
+    async fn hold(&self, session: Arc<dyn GameSession>, player: PlayerId, table_id: TableId) {
+        let mut rx = match session.subscribe(table_id).await {
+            Ok(rx) if !self.grace.is_zero() => rx,
+            _ => return leave(session.as_ref(), player, table_id).await,
+        };
+        let id = Ulid::new();
+        let missed = Arc::new(Mutex::new(Some(Vec::new())));
+        let grace = self.grace;
+        let held = self.held.clone();
+        let log = missed.clone();
+        let table_session = session.clone();
+        // The player's entry stays locked until the hold is in it, so the
+        // expiry cannot look for the hold before it is there.
+        let entry = self.held.entry(player);
+        let task = tokio::spawn(async move {
+            let expired = tokio::time::sleep(grace);
+            tokio::pin!(expired);
+            loop {
+                tokio::select! {
+                    _ = &mut expired => break,
+                    event = rx.recv() => match event {
+                        Ok(event) => {
+                            let mut log = log.lock().unwrap();
+                            if log.as_ref().is_some_and(|l| l.len() >= MAX_MISSED_EVENTS) {
+                                info!("table={table_id} player={player} missed too much to replay");
+                                *log = None;
+                            }
+                            if let Some(log) = log.as_mut() {
+                                log.push(event);
+                            }
+                        }
+                        Err(RecvError::Lagged(n)) => {
+                            warn!("table={table_id} player={player} missed-event log lagged by {n}");
+                            *log.lock().unwrap() = None;
+                        }
+                        // Table gone: nothing to come back to.
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+            }
+            if held.remove_if(&player, |_, h| h.id == id).is_some() {
+                info!("table={table_id} player={player} did not reconnect in time");
+                leave(table_session.as_ref(), player, table_id).await;
+            }
+        });
+        let hold = Hold {
+            id,
+            table_id,
+            missed,
+            task,
+        };
+        let previous = match entry {
+            Entry::Occupied(mut held) => Some(held.insert(hold)),
+            Entry::Vacant(held) => {
+                held.insert(hold);
+                None
+            }
+        };
+        if let Some(previous) = previous {
+            previous.task.abort();
+            if previous.table_id != table_id {
+                leave(session.as_ref(), player, previous.table_id).await;
+            }
+        }
+        info!("table={table_id} player={player} seat held for {grace:?}");
+    }
+
+    fn reclaim(&self, player: PlayerId) -> Option<Reclaimed> {
+        let (_, hold) = self.held.remove(&player)?;
+        hold.task.abort();
+        let missed = hold.missed.lock().unwrap().take();
+        Some(Reclaimed {
+            table_id: hold.table_id,
+            missed,
+        })
+    }
+}
+
+async fn leave(session: &dyn GameSession, player: PlayerId, table_id: TableId) {
+    if let Err(e) = session
+        .send_command(
+            table_id,
+            player,
+            RequestId(0),
+            PlayerAction::LeaveTable(LeaveTable { player_id: player }),
+        )
+        .await
+    {
+        warn!("table={table_id} player={player} leave after disconnect failed: {e}");
+    }
+}
@@ -1,141 +1,278 @@
 use crate::{
+    bus::{partition_of, BusError, Buses, InMemoryCommandBus, Reply, DEFAULT_PARTITIONS},
     session::{
+        partition::{Hosting, PartitionWorker},
         summary::TableSummary,
-        table_actor::{run_table_actor, TableCommand},
-        CommandAck, GameSession, RequestId, SessionError,
+        table_actor::{TableActorConfig, TableCommand, TableReply},
+        Catchup, CommandAck, GameSession, RequestId, SessionError,
     },
+    store::TableRecord,
     wallet::Wallet,
 };
 use async_trait::async_trait;
 use bj_core::domain::{
     engine::{
-        command::player::PlayerAction, event::GameEvent, game_id::GameId, game_state::GameState,
+        command::{dealer::DealerAction, player::PlayerAction},
+        event::{EventSeqId, GameEvent},
+        game_state::GameState,
         snapshot::GameStateSnapshot,
     },
-    DealerId, PlayerId, Shoe, TableId, TableSettings,
+    PlayerId, TableId, TableStatus,
 };
 use dashmap::DashMap;
-use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc, RwLock};
-use ulid::Ulid;
-
-struct TableHandle {
-    cmd_tx: mpsc::Sender<TableCommand>,
-    event_tx: broadcast::Sender<GameEvent>,
-    summary: Arc<RwLock<TableSummary>>,
-}
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{broadcast, oneshot, RwLock, RwLockWriteGuard},
+    task::JoinHandle,
+    time::Instant,
+};
+use tracing::{error, info, warn};
 
-pub struct InMemoryGameSession {
-    tables: DashMap<TableId, TableHandle>,
-    wallet: Arc<dyn Wallet>,
-}
+/// How often [`InMemoryGameSession::drain`] checks whether its tables have
+/// closed.
+const DRAIN_POLL: Duration = Duration::from_millis(100);
 
-struct SeedTable {
-    name: &'static str,
-    settings: TableSettings,
+/// Runs tables in this process, spread over partition workers that take
+/// their commands from a [`CommandBus`](crate::bus::CommandBus) and publish
+/// events on an [`EventBus`](crate::bus::EventBus).
+pub struct InMemoryGameSession {
+    buses: Buses,
+    hosting: Arc<Hosting>,
+    /// The worker of each partition this session hosts.
+    workers: Mutex<HashMap<u32, Worker>>,
 }
 
-fn seeds() -> Vec<SeedTable> {
-    vec![
-        SeedTable {
-            name: "Cool Kids #1",
-            settings: TableSettings {
-                min_bet: 10,
-                max_bet: 500,
-                max_players: 5,
-                max_observers: 10,
-            },
-        },
-        SeedTable {
-            name: "Big Sharks #2",
-            settings: TableSettings {
-                min_bet: 25,
-                max_bet: 1000,
-                max_players: 6,
-                max_observers: 10,
-            },
-        },
-        SeedTable {
-            name: "Sopranos #3",
-            settings: TableSettings {
-                min_bet: 100,
-                max_bet: 5000,
-                max_players: 4,
-                max_observers: 10,
-            },
-        },
-    ]
+/// A running partition worker.
+struct Worker {
+    stop: oneshot::Sender<()>,
+    running: JoinHandle<()>,
 }
 
 impl InMemoryGameSession {
+    /// A session with no tables; open them with
+    /// [`open_table`](GameSession::open_table).
     pub fn new(wallet: Arc<dyn Wallet>) -> Arc<Self> {
-        let session = Arc::new(Self {
-            tables: DashMap::new(),
+        Self::with_config(wallet, TableActorConfig::default())
+    }
+
+    /// Like [`new`](Self::new), with every table running on `config`.
+    pub fn with_config(wallet: Arc<dyn Wallet>, config: TableActorConfig) -> Arc<Self> {
+        let commands = Arc::new(InMemoryCommandBus::new(DEFAULT_PARTITIONS));
+        Self::with_buses(wallet, config, Buses::local(commands))
+            .expect("a new in-memory bus has every partition free")
+    }
+
+    /// A session running every partition of `buses`. Fails if a partition
+    /// already has a consumer.
+    pub fn with_buses(
+        wallet: Arc<dyn Wallet>,
+        config: TableActorConfig,
+        buses: Buses,
+    ) -> Result<Arc<Self>, BusError> {
+        let session = Self::standby(wallet, config, buses);
+        for partition in 0..session.buses.commands.partitions() {
+            session.start_partition(partition, vec![])?;
+        }
+        Ok(session)
+    }
+
+    /// A session that hosts no partition until told to with
+    /// [`start_partition`](Self::start_partition). Commands for tables it
+    /// does not host wait on the bus for whichever instance does.
+    pub fn standby(wallet: Arc<dyn Wallet>, config: TableActorConfig, buses: Buses) -> Arc<Self> {
+        let hosting = Arc::new(Hosting {
             wallet,
+            events: buses.events.clone(),
+            directory: buses.directory.clone(),
+            config,
+            games: DashMap::new(),
+            pause: RwLock::new(()),
         });
-        for seed in seeds() {
-            session.seed_table(seed.name, seed.settings);
+        Arc::new(Self {
+            buses,
+            hosting,
+            workers: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Starts a worker for `partition` hosting `tables`.
+    pub fn start_partition(
+        &self,
+        partition: u32,
+        tables: Vec<TableRecord>,
+    ) -> Result<(), BusError> {
+        let mut workers = self.workers.lock().unwrap();
+        if workers.contains_key(&partition) {
+            return Err(BusError::PartitionTaken(partition));
         }
-        session
-    }
-
-    fn seed_table(&self, name: &str, settings: TableSettings) {
-        let table_id = TableId::new();
-        let dealer_id = DealerId(Ulid::new());
-        let game_id = GameId::new();
-        let shoe = Shoe::shuffled();
-        let state = GameState::new(game_id, shoe, vec![], dealer_id);
-
-        let (cmd_tx, cmd_rx) = mpsc::channel::<TableCommand>(128);
-        let (event_tx, _) = broadcast::channel::<GameEvent>(256);
-        let summary = Arc::new(RwLock::new(TableSummary {
-            id: table_id,
-            name: name.to_string(),
-            settings: settings.clone(),
-            player_count: 0,
-            phase: "WaitingForBets".into(),
-            is_joinable: true,
-        }));
-
-        let wallet = self.wallet.clone();
-        let summary_clone = summary.clone();
-        let event_tx_clone = event_tx.clone();
-        tokio::spawn(run_table_actor(
-            table_id,
-            settings,
-            state,
-            cmd_rx,
-            event_tx_clone,
-            summary_clone,
-            wallet,
-        ));
-
-        self.tables.insert(
-            table_id,
-            TableHandle {
-                cmd_tx,
-                event_tx,
-                summary,
-            },
+        let consumer = self.buses.commands.subscribe(partition)?;
+        let (stop, stopped) = oneshot::channel();
+        let mut worker = PartitionWorker::new(partition, consumer, self.hosting.clone(), stopped);
+        worker.host(tables);
+        let running = tokio::spawn(worker.run());
+        workers.insert(partition, Worker { stop, running });
+        Ok(())
+    }
+
+    /// Stops the worker of `partition` and waits for it to exit, which it
+    /// does once the command it is running, if any, is done. Its tables are
+    /// dropped without settling the round in play.
+    pub async fn stop_partition(&self, partition: u32) {
+        let Some(worker) = self.workers.lock().unwrap().remove(&partition) else {
+            return;
+        };
+        let _ = worker.stop.send(());
+        if let Err(e) = worker.running.await {
+            error!("partition={partition} worker failed: {e}");
+        }
+    }
+
+    /// Closes every table this session hosts, as a shutdown does: joins and
+    /// bets are refused at once, and rounds in play get until `grace` to
+    /// finish. Those still going then are voided, which closes their table
+    /// too. Returns the tables that were hosted.
+    ///
+    /// Only the live tables close; the table store is left alone, so they
+    /// open again on the next boot or on whichever instance takes over.
+    pub async fn drain(&self, grace: Duration) -> Vec<TableId> {
+        let deadline = Instant::now() + grace;
+        let hosted: HashSet<u32> = self.workers.lock().unwrap().keys().copied().collect();
+        let partitions = self.buses.commands.partitions();
+        let tables: Vec<_> = self
+            .buses
+            .directory
+            .list()
+            .into_iter()
+            .filter(|t| hosted.contains(&partition_of(t.id, partitions)))
+            .collect();
+        for table in tables.iter().filter(|t| t.status == TableStatus::Open) {
+            if let Err(e) = self.close_table(table.id).await {
+                warn!("table={} not closed for shutdown: {e}", table.id);
+            }
+        }
+
+        let open = || {
+            tables
+                .iter()
+                .map(|t| t.id)
+                .filter(|id| self.buses.directory.contains(*id))
+                .collect::<Vec<_>>()
+        };
+        while !open().is_empty() && Instant::now() < deadline {
+            tokio::time::sleep(DRAIN_POLL).await;
+        }
+        for table_id in open() {
+            warn!("table={table_id} still in a round at the shutdown deadline; voiding it");
+            let reason = "the server is shutting down".to_string();
+            if let Err(e) = self.void_round(table_id, reason).await {
+                error!("table={table_id} round not voided: {e}");
+            }
+        }
+        info!("drained {} tables", tables.len());
+        tables.into_iter().map(|t| t.id).collect()
+    }
+
+    /// Starts hosting `table` from the game state it was saved with, as a
+    /// boot from a state directory does. Fails if the table is already
+    /// running.
+    pub async fn restore_table(
+        &self,
+        table: &TableRecord,
+        game: GameState,
+    ) -> Result<(), SessionError> {
+        let command = TableCommand::Restore {
+            table: table.clone(),
+            game: Box::new(game),
+        };
+        expect(self.request(table.id, command).await, done)
+    }
+
+    /// Holds every table of this session between commands until the guard
+    /// drops.
+    pub async fn pause(&self) -> RwLockWriteGuard<'_, ()> {
+        self.hosting.pause.write().await
+    }
+
+    /// The game of every table this session hosts, and of those that
+    /// closed since, as of their last command.
+    pub fn games(&self) -> HashMap<TableId, GameState> {
+        self.hosting
+            .games
+            .iter()
+            .map(|game| (*game.key(), game.value().clone()))
+            .collect()
+    }
+
+    /// Puts `command` on the bus and waits for the table's answer.
+    #[tracing::instrument(
+        name = "session.request",
+        skip_all,
+        fields(table = %table_id, command = command.name())
+    )]
+    async fn request(&self, table_id: TableId, command: TableCommand) -> Reply {
+        // Spare the bus a round trip for a table nobody hosts.
+        let opens = matches!(
+            command,
+            TableCommand::Open { .. } | TableCommand::Restore { .. }
         );
+        if !opens && !self.buses.directory.contains(table_id) {
+            return Err(SessionError::TableNotFound);
+        }
+        let reply = self
+            .buses
+            .commands
+            .send(table_id, command)
+            .await
+            .map_err(|e| {
+                error!("table={table_id} command not sent: {e}");
+                SessionError::Internal
+            })?;
+        reply.await.map_err(|_| SessionError::Internal)?
+    }
+}
+
+/// Unpacks the reply a command is expected to get.
+fn expect<T>(reply: Reply, pick: impl FnOnce(TableReply) -> Option<T>) -> Result<T, SessionError> {
+    pick(reply?).ok_or_else(|| {
+        error!("table answered with the wrong kind of reply");
+        SessionError::Internal
+    })
+}
+
+fn done(reply: TableReply) -> Option<()> {
+    matches!(reply, TableReply::Done).then_some(())
+}
+
+fn ack(reply: TableReply) -> Option<CommandAck> {
+    match reply {
+        TableReply::Ack(ack) => Some(ack),
+        _ => None,
     }
 }
 
 #[async_trait]
 impl GameSession for InMemoryGameSession {
     async fn list_tables(&self) -> Vec<TableSummary> {
-        // Collect Arc handles first, then drop the DashMap guard before awaiting
-        let summaries: Vec<Arc<RwLock<TableSummary>>> = self
-            .tables
-            .iter()
-            .map(|r| r.value().summary.clone())
-            .collect();
-        let mut out = Vec::with_capacity(summaries.len());
-        for s in summaries {
-            out.push(s.read().await.clone());
-        }
-        out.sort_by(|a, b| a.id.cmp(&b.id));
-        out
+        self.buses.directory.list()
+    }
+
+    async fn open_table(&self, table: &TableRecord) -> Result<(), SessionError> {
+        let command = TableCommand::Open {
+            table: table.clone(),
+        };
+        expect(self.request(table.id, command).await, done)
+    }
+
+    async fn update_table(&self, table: &TableRecord) -> Result<(), SessionError> {
+        let command = TableCommand::Update {
+            name: table.name.clone(),
+            settings: table.settings.clone(),
+        };
+        expect(self.request(table.id, command).await, done)
     }
 
     async fn snapshot(
@@ -143,22 +280,13 @@ impl GameSession for InMemoryGameSession {
         table_id: TableId,
         player: PlayerId,
     ) -> Result<GameStateSnapshot, SessionError> {
-        // Clone the sender out of the guard before awaiting
-        let cmd_tx = self
-            .tables
-            .get(&table_id)
-            .ok_or(SessionError::TableNotFound)?
-            .cmd_tx
-            .clone();
-        let (tx, rx) = tokio::sync::oneshot::channel();
-        cmd_tx
-            .send(TableCommand::Snapshot {
-                requesting_player: player,
-                reply: tx,
-            })
-            .await
-            .map_err(|_| SessionError::Internal)?;
-        rx.await.map_err(|_| SessionError::Internal)?
+        let command = TableCommand::Snapshot {
+            requesting_player: player,
+        };
+        expect(self.request(table_id, command).await, |reply| match reply {
+            TableReply::Snapshot(snapshot) => Some(*snapshot),
+            _ => None,
+        })
     }
 
     async fn send_command(
@@ -168,37 +296,64 @@ impl GameSession for InMemoryGameSession {
         request_id: RequestId,
         action: PlayerAction,
     ) -> Result<CommandAck, SessionError> {
-        // Clone the sender out of the guard before awaiting
-        let cmd_tx = self
-            .tables
-            .get(&table_id)
-            .ok_or(SessionError::TableNotFound)?
-            .cmd_tx
-            .clone();
-        let (tx, rx) = tokio::sync::oneshot::channel();
-        cmd_tx
-            .send(TableCommand::Execute {
-                player_id,
-                request_id,
-                action,
-                reply: tx,
-            })
-            .await
-            .map_err(|_| SessionError::Internal)?;
-        rx.await.map_err(|_| SessionError::Internal)?
+        let command = TableCommand::Execute {
+            player_id,
+            request_id,
+            action,
+        };
+        expect(self.request(table_id, command).await, ack)
+    }
+
+    async fn dealer_command(
+        &self,
+        table_id: TableId,
+        dealer_id: PlayerId,
+        request_id: RequestId,
+        action: DealerAction,
+    ) -> Result<CommandAck, SessionError> {
+        let command = TableCommand::DealerExecute {
+            dealer_id,
+            request_id,
+            action,
+        };
+        expect(self.request(table_id, command).await, ack)
     }
 
     async fn subscribe(
         &self,
         table_id: TableId,
     ) -> Result<broadcast::Receiver<GameEvent>, SessionError> {
-        // Clone the sender out of the guard before awaiting
-        let event_tx = self
-            .tables
-            .get(&table_id)
-            .ok_or(SessionError::TableNotFound)?
-            .event_tx
-            .clone();
-        Ok(event_tx.subscribe())
+        if !self.buses.directory.contains(table_id) {
+            return Err(SessionError::TableNotFound);
+        }
+        self.buses
+            .events
+            .subscribe(table_id)
+            .ok_or(SessionError::TableNotFound)
+    }
+
+    async fn resume(
+        &self,
+        table_id: TableId,
+        player: PlayerId,
+        last_seq: u64,
+    ) -> Result<Catchup, SessionError> {
+        let command = TableCommand::Resume {
+            player_id: player,
+            after: EventSeqId(last_seq),
+        };
+        expect(self.request(table_id, command).await, |reply| match reply {
+            TableReply::Catchup(catchup) => Some(*catchup),
+            _ => None,
+        })
+    }
+
+    async fn close_table(&self, table_id: TableId) -> Result<(), SessionError> {
+        expect(self.request(table_id, TableCommand::Close).await, done)
+    }
+
+    async fn void_round(&self, table_id: TableId, reason: String) -> Result<(), SessionError> {
+        let command = TableCommand::VoidRound { reason };
+        expect(self.request(table_id, command).await, done)
     }
 }
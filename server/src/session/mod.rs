@@ -1,10 +1,18 @@
+pub mod grace;
 pub mod in_memory;
+pub mod partition;
 pub mod summary;
 pub mod table_actor;
 
+use crate::store::TableRecord;
 use async_trait::async_trait;
 use bj_core::domain::{
-    engine::{command::player::PlayerAction, event::GameEvent, snapshot::GameStateSnapshot},
+    engine::{
+        command::{dealer::DealerAction, player::PlayerAction},
+        event::GameEvent,
+        snapshot::GameStateSnapshot,
+        CommandError,
+    },
     PlayerId, TableId,
 };
 use serde::{Deserialize, Serialize};
@@ -16,12 +24,22 @@ pub use summary::TableSummary;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct RequestId(pub u64);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandAck {
     pub request_id: RequestId,
 }
 
-#[derive(Debug, Error)]
+/// What a client whose event stream broke off needs to catch up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Catchup {
+    /// The events it missed, oldest first. Empty if it missed nothing.
+    Events(Vec<GameEvent>),
+    /// The gap reaches further back than the table remembers: start over
+    /// from the table as it is now.
+    Snapshot(GameStateSnapshot),
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
 pub enum SessionError {
     #[error("table not found")]
     TableNotFound,
@@ -31,9 +49,20 @@ pub enum SessionError {
     Internal,
 }
 
+impl From<CommandError> for SessionError {
+    fn from(e: CommandError) -> Self {
+        SessionError::CommandRejected(e.to_string())
+    }
+}
+
 #[async_trait]
 pub trait GameSession: Send + Sync {
+    /// Tables that are open or still closing.
     async fn list_tables(&self) -> Vec<TableSummary>;
+    /// Starts hosting `table`. Fails if the table is already running.
+    async fn open_table(&self, table: &TableRecord) -> Result<(), SessionError>;
+    /// Renames a running table and changes its settings.
+    async fn update_table(&self, table: &TableRecord) -> Result<(), SessionError>;
     async fn snapshot(
         &self,
         table_id: TableId,
@@ -46,8 +75,30 @@ pub trait GameSession: Send + Sync {
         request_id: RequestId,
         action: PlayerAction,
     ) -> Result<CommandAck, SessionError>;
+    /// Runs a round step for the dealer of a manual-dealer table. Rejected
+    /// at a table with an automatic dealer.
+    async fn dealer_command(
+        &self,
+        table_id: TableId,
+        dealer_id: PlayerId,
+        request_id: RequestId,
+        action: DealerAction,
+    ) -> Result<CommandAck, SessionError>;
     async fn subscribe(
         &self,
         table_id: TableId,
     ) -> Result<broadcast::Receiver<GameEvent>, SessionError>;
+    /// Catches up a player at the table on the events after `last_seq`.
+    /// Fails if the player has not joined the table.
+    async fn resume(
+        &self,
+        table_id: TableId,
+        player: PlayerId,
+        last_seq: u64,
+    ) -> Result<Catchup, SessionError>;
+    /// Starts closing a table. The table finishes its current round, then
+    /// stops and disappears from [`list_tables`](Self::list_tables).
+    async fn close_table(&self, table_id: TableId) -> Result<(), SessionError>;
+    /// Cancels the round in play at a table and refunds every bet.
+    async fn void_round(&self, table_id: TableId, reason: String) -> Result<(), SessionError>;
 }
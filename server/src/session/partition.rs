@@ -0,0 +1,314 @@
+use std::{any::Any, collections::HashMap, panic::AssertUnwindSafe, sync::Arc, time::Duration};
+
+use bj_core::domain::{engine::game_state::GameState, TableId};
+use dashmap::DashMap;
+use futures::FutureExt;
+use tokio::sync::{oneshot, RwLock};
+use tracing::{
+    error,
+    field::{display, Empty},
+    info, info_span, Instrument, Span,
+};
+
+use crate::{
+    bus::{CommandConsumer, CommandEnvelope, Delivery, EventBus, TableDirectory},
+    session::{
+        table_actor::{TableActor, TableActorConfig, TableCommand, TableReply},
+        SessionError,
+    },
+    store::TableRecord,
+    wallet::Wallet,
+};
+
+/// What every partition worker of a session shares with the session.
+pub struct Hosting {
+    pub wallet: Arc<dyn Wallet>,
+    pub events: Arc<dyn EventBus>,
+    pub directory: Arc<dyn TableDirectory>,
+    pub config: TableActorConfig,
+    /// The game of every table, as of its last command, for a state
+    /// directory to save. A table that closed keeps the game it had just
+    /// before, seats and all.
+    pub games: DashMap<TableId, GameState>,
+    /// Held for reading while a worker handles a command or a deadline. A
+    /// save holds it for writing, so the games and the wallet it reads are
+    /// of one moment.
+    pub pause: RwLock<()>,
+}
+
+/// Hosts the tables of one bus partition: applies their commands in order
+/// and wakes each table when its deadline comes, all from one task.
+///
+/// It also supervises them. A table that panics is rebuilt in place by
+/// [`TableActor::recover`] and keeps its id; the other tables of the
+/// partition never notice.
+pub struct PartitionWorker {
+    partition: u32,
+    consumer: Box<dyn CommandConsumer>,
+    tables: HashMap<TableId, TableActor>,
+    hosting: Arc<Hosting>,
+    stop: oneshot::Receiver<()>,
+}
+
+enum Wake {
+    Command(Option<Box<Delivery>>),
+    Deadline,
+    Stop,
+}
+
+impl PartitionWorker {
+    /// A worker with no tables. It runs until `stop` fires or is dropped,
+    /// or the bus goes away.
+    pub fn new(
+        partition: u32,
+        consumer: Box<dyn CommandConsumer>,
+        hosting: Arc<Hosting>,
+        stop: oneshot::Receiver<()>,
+    ) -> Self {
+        Self {
+            partition,
+            consumer,
+            tables: HashMap::new(),
+            hosting,
+            stop,
+        }
+    }
+
+    /// Opens `tables` before any command is taken, so commands waiting on
+    /// the bus find them.
+    pub fn host(&mut self, tables: Vec<TableRecord>) {
+        for table in tables {
+            let table_id = table.id;
+            if !self.tables.contains_key(&table_id) {
+                self.open(table);
+                self.refresh(table_id);
+            }
+        }
+    }
+
+    pub async fn run(mut self) {
+        loop {
+            // Deadlines live in the game states; the worker only sleeps
+            // until the earliest and lets the dealer policy decide what it
+            // means.
+            let now = self.hosting.config.clock.now();
+            let wake = self
+                .tables
+                .values()
+                .filter_map(TableActor::deadline)
+                .min()
+                .map(|at| at.saturating_duration_since(now));
+
+            let wake = tokio::select! {
+                delivery = self.consumer.next() => Wake::Command(delivery.map(Box::new)),
+                _ = sleep_for(wake) => Wake::Deadline,
+                _ = &mut self.stop => Wake::Stop,
+            };
+            let hosting = self.hosting.clone();
+            let _running = hosting.pause.read().await;
+            match wake {
+                Wake::Command(Some(delivery)) => self.deliver(*delivery).await,
+                Wake::Deadline => self.advance_due().await,
+                Wake::Command(None) | Wake::Stop => break,
+            }
+        }
+        // The tables go with the worker. Their directory entries stay for
+        // whoever hosts the partition next.
+        info!(
+            "partition={} stopped with {} tables",
+            self.partition,
+            self.tables.len()
+        );
+    }
+
+    /// Applies one command, answers whoever waits for it, and only then
+    /// acknowledges it: a crash in between means it is delivered again.
+    async fn deliver(&mut self, delivery: Delivery) {
+        let CommandEnvelope {
+            id,
+            origin,
+            table_id,
+            command,
+            trace,
+        } = delivery.envelope;
+        let span = command_span(table_id, &command);
+        trace.attach(&span);
+        let reply = async {
+            let reply = match command {
+                TableCommand::Open { table } if !self.tables.contains_key(&table.id) => {
+                    Ok(self.open(table))
+                }
+                TableCommand::Restore { table, game } if !self.tables.contains_key(&table.id) => {
+                    Ok(self.restore(table, *game).await)
+                }
+                command => match self.tables.get_mut(&table_id) {
+                    Some(table) => {
+                        match AssertUnwindSafe(table.handle(command)).catch_unwind().await {
+                            Ok(reply) => reply,
+                            Err(panic) => {
+                                self.restart(table_id, panic).await;
+                                Err(SessionError::Internal)
+                            }
+                        }
+                    }
+                    None => Err(SessionError::TableNotFound),
+                },
+            };
+            self.advance(table_id).await;
+            self.refresh(table_id);
+            reply
+        }
+        .instrument(span)
+        .await;
+        self.consumer.reply(id, origin.as_deref(), reply).await;
+        if let Err(e) = self.consumer.ack(delivery.offset).await {
+            error!(
+                "partition={} offset={} ack failed: {e}",
+                self.partition, delivery.offset
+            );
+        }
+    }
+
+    fn open(&mut self, table: TableRecord) -> TableReply {
+        let actor = TableActor::new(
+            &table,
+            self.hosting.wallet.clone(),
+            self.hosting.events.clone(),
+            self.hosting.config.clone(),
+        );
+        info!(
+            "partition={} hosting table={} '{}'",
+            self.partition, table.id, table.name
+        );
+        self.tables.insert(table.id, actor);
+        TableReply::Done
+    }
+
+    /// Opens `table` and hands it the game it was saved with.
+    async fn restore(&mut self, table: TableRecord, game: GameState) -> TableReply {
+        let table_id = table.id;
+        self.open(table);
+        if let Some(table) = self.tables.get_mut(&table_id) {
+            if let Err(panic) = AssertUnwindSafe(table.restore(game)).catch_unwind().await {
+                self.restart(table_id, panic).await;
+            }
+        }
+        TableReply::Done
+    }
+
+    async fn advance_due(&mut self) {
+        let now = self.hosting.config.clock.now();
+        let due: Vec<TableId> = self
+            .tables
+            .iter()
+            .filter(|(_, table)| table.deadline().is_some_and(|at| at <= now))
+            .map(|(id, _)| *id)
+            .collect();
+        for table_id in due {
+            self.advance(table_id).await;
+            self.refresh(table_id);
+        }
+    }
+
+    async fn advance(&mut self, table_id: TableId) {
+        let Some(table) = self.tables.get_mut(&table_id) else {
+            return;
+        };
+        if let Err(panic) = AssertUnwindSafe(table.advance()).catch_unwind().await {
+            self.restart(table_id, panic).await;
+        }
+    }
+
+    /// Rebuilds a table that panicked. One that panics again while
+    /// recovering is no longer hosted.
+    async fn restart(&mut self, table_id: TableId, panic: Box<dyn Any + Send>) {
+        let Some(table) = self.tables.get_mut(&table_id) else {
+            return;
+        };
+        error!(
+            "partition={} table={table_id} panicked: {}",
+            self.partition,
+            panic_message(&*panic)
+        );
+        if let Err(panic) = AssertUnwindSafe(table.recover()).catch_unwind().await {
+            error!(
+                "partition={} table={table_id} panicked while recovering, dropping it: {}",
+                self.partition,
+                panic_message(&*panic)
+            );
+            self.tables.remove(&table_id);
+            self.hosting.games.remove(&table_id);
+            self.hosting.directory.remove(table_id);
+            self.hosting.events.close(table_id);
+        }
+    }
+
+    /// Publishes the table's summary, or stops hosting it once it has
+    /// closed.
+    fn refresh(&mut self, table_id: TableId) {
+        let Some(table) = self.tables.get(&table_id) else {
+            return;
+        };
+        if table.is_closed() {
+            self.tables.remove(&table_id);
+            self.hosting.directory.remove(table_id);
+            self.hosting.events.close(table_id);
+            info!("table={table_id} closed");
+        } else {
+            self.hosting.games.insert(table_id, table.game().clone());
+            self.hosting.directory.put(table.summary());
+        }
+    }
+}
+
+/// The span a command is handled in, with who sent it when that is known.
+fn command_span(table_id: TableId, command: &TableCommand) -> Span {
+    let span = info_span!(
+        "table.command",
+        table = %table_id,
+        command = command.name(),
+        player = Empty,
+        request_id = Empty,
+    );
+    match command {
+        TableCommand::Execute {
+            player_id,
+            request_id,
+            ..
+        } => {
+            span.record("player", display(player_id));
+            span.record("request_id", request_id.0);
+        }
+        TableCommand::DealerExecute {
+            dealer_id,
+            request_id,
+            ..
+        } => {
+            span.record("player", display(dealer_id));
+            span.record("request_id", request_id.0);
+        }
+        TableCommand::Snapshot { requesting_player } => {
+            span.record("player", display(requesting_player));
+        }
+        TableCommand::Resume { player_id, .. } => {
+            span.record("player", display(player_id));
+        }
+        _ => {}
+    }
+    span
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+async fn sleep_for(wake: Option<Duration>) {
+    match wake {
+        Some(d) => tokio::time::sleep(d).await,
+        None => std::future::pending().await,
+    }
+}
@@ -1,7 +1,7 @@
-use bj_core::domain::{TableId, TableSettings};
-use serde::Serialize;
+use bj_core::domain::{TableId, TableSettings, TableStatus};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TableSummary {
     pub id: TableId,
     pub name: String,
@@ -9,4 +9,9 @@ pub struct TableSummary {
     pub player_count: usize,
     pub phase: String,
     pub is_joinable: bool,
+    pub status: TableStatus,
+    /// The table panicked and was rebuilt recently; see
+    /// [`TableActor::recover`](super::table_actor::TableActor::recover).
+    #[serde(default)]
+    pub degraded: bool,
 }
@@ -1,387 +1,725 @@
 use crate::{
-    session::{summary::TableSummary, CommandAck, RequestId, SessionError},
-    wallet::Wallet,
+    audit::{in_memory::InMemoryAuditLog, AuditRecord, AuditSink, Origin, Outcome},
+    bus::EventBus,
+    metrics::Metrics,
+    session::{summary::TableSummary, Catchup, CommandAck, RequestId, SessionError},
+    store::TableRecord,
+    wallet::{Wallet, WalletError},
 };
 use bj_core::domain::{
     engine::{
         command::{
-            dealer::{
-                DealInitialCards, DealerAction, DealerCommand, OpenBetting, PlayHand, SettleRound,
-            },
+            dealer::{DealerAction, DealerCommand},
             player::{PlayerAction, PlayerCommand},
-            system::{PlayerTimeout, SystemCommand},
+            system::{CloseTable, SystemCommand, VoidRound},
             CommandId, GameCommand,
         },
-        event::{EventSeqId, GameEvent},
+        event::{EventPayload, EventSeqId, GameEvent},
         game_id::GameId,
         game_state::GameState,
         phase::Phase,
         snapshot::GameStateSnapshot,
-        GameEngine,
+        Clock, CommandError, DealerPolicy, GameEngine, SystemClock, TableTimers, Timestamp,
     },
-    PlayerId, Shoe, TableId, TableSettings,
+    DealerId, DealerMode, Hand, PlayerId, PlayerState, Shoe, TableId, TableSettings, TableStatus,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use std::{sync::Arc, time::Duration};
-use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
-use tracing::{info, warn};
+use tracing::{error, info, info_span, warn, Instrument};
+use ulid::Ulid;
 
+/// A command for one table. Commands travel on the
+/// [`CommandBus`](crate::bus::CommandBus), so they carry no reply channel:
+/// the outcome goes back through the session's reply registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TableCommand {
+    /// Starts hosting `table`. Handled by the partition worker.
+    Open {
+        table: TableRecord,
+    },
+    /// Starts hosting `table` from the game state it was saved with. Handled
+    /// by the partition worker.
+    Restore {
+        table: TableRecord,
+        game: Box<GameState>,
+    },
     Execute {
         player_id: PlayerId,
         request_id: RequestId,
         action: PlayerAction,
-        reply: oneshot::Sender<Result<CommandAck, SessionError>>,
     },
-    #[allow(dead_code)]
-    DealerExecute { action: DealerAction },
+    /// A round step from the dealer of a manual-dealer table.
+    DealerExecute {
+        dealer_id: PlayerId,
+        request_id: RequestId,
+        action: DealerAction,
+    },
     Snapshot {
         requesting_player: PlayerId,
-        reply: oneshot::Sender<Result<GameStateSnapshot, SessionError>>,
     },
+    /// Everything broadcast after `after`, for a player at the table whose
+    /// event stream broke off.
+    Resume {
+        player_id: PlayerId,
+        after: EventSeqId,
+    },
+    /// A new name and limits for the table. Bets already placed stand;
+    /// later ones are checked against the new limits.
+    Update {
+        name: String,
+        settings: TableSettings,
+    },
+    Close,
+    VoidRound {
+        reason: String,
+    },
+}
+
+impl TableCommand {
+    /// The variant name, for spans and logs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Open { .. } => "Open",
+            Self::Restore { .. } => "Restore",
+            Self::Execute { action, .. } => action.into(),
+            Self::DealerExecute { .. } => "DealerExecute",
+            Self::Snapshot { .. } => "Snapshot",
+            Self::Resume { .. } => "Resume",
+            Self::Update { .. } => "Update",
+            Self::Close => "Close",
+            Self::VoidRound { .. } => "VoidRound",
+        }
+    }
+}
+
+/// The outcome of a [`TableCommand`] that succeeded.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum TableReply {
+    Done,
+    Ack(CommandAck),
+    Snapshot(Box<GameStateSnapshot>),
+    Catchup(Box<Catchup>),
 }
 
-/// Timing parameters for a `TableActor`.
+/// Events a table keeps for [`TableCommand::Resume`] by default. Four times
+/// the broadcast channel, so a forwarder that lagged can usually catch up
+/// without a snapshot.
+pub const DEFAULT_EVENT_BACKLOG: usize = 1024;
+
+/// How long a table reports itself degraded after a panic by default.
+pub const DEFAULT_RECOVERY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Timing parameters and time source for a `TableActor`.
 ///
 /// Production code uses [`TableActorConfig::default`].
-/// Tests and load benchmarks can supply shorter timers.
-#[derive(Debug, Clone)]
+/// Tests and load benchmarks can supply shorter timers or a manual clock.
+#[derive(Clone)]
 pub struct TableActorConfig {
-    pub betting_timeout: Duration,
-    pub player_turn_timeout: Duration,
-    pub round_delay: Duration,
+    pub timers: TableTimers,
+    pub clock: Arc<dyn Clock>,
+    /// How many recent events the table keeps for clients resuming their
+    /// stream. Older gaps are answered with a snapshot.
+    pub event_backlog: usize,
+    pub metrics: Arc<Metrics>,
+    /// How long a table rebuilt after a panic shows as degraded.
+    pub recovery_period: Duration,
+    /// Where every command the table runs, and its outcome, is recorded.
+    pub audit: Arc<dyn AuditSink>,
 }
 
 impl Default for TableActorConfig {
     fn default() -> Self {
         Self {
-            betting_timeout: Duration::from_secs(30),
-            player_turn_timeout: Duration::from_secs(30),
-            round_delay: Duration::from_secs(5),
+            timers: TableTimers::default(),
+            clock: Arc::new(SystemClock),
+            event_backlog: DEFAULT_EVENT_BACKLOG,
+            metrics: Arc::default(),
+            recovery_period: DEFAULT_RECOVERY_PERIOD,
+            audit: Arc::new(InMemoryAuditLog::new()),
         }
     }
 }
 
-pub async fn run_table_actor(
+/// One table: its game state, the events it keeps for resuming clients, and
+/// the wallet postings its rounds call for. A [`PartitionWorker`] hosts it
+/// and drives it by command and by deadline.
+///
+/// [`PartitionWorker`]: super::partition::PartitionWorker
+pub struct TableActor {
     table_id: TableId,
+    name: String,
     settings: TableSettings,
-    initial_state: GameState,
-    cmd_rx: mpsc::Receiver<TableCommand>,
-    event_tx: broadcast::Sender<GameEvent>,
-    summary: Arc<RwLock<TableSummary>>,
+    state: GameState,
+    /// The most recent events, oldest first, at most
+    /// `config.event_backlog` of them.
+    backlog: VecDeque<GameEvent>,
+    events: Arc<dyn EventBus>,
     wallet: Arc<dyn Wallet>,
-) {
-    run_table_actor_with_config(
-        table_id,
-        settings,
-        initial_state,
-        cmd_rx,
-        event_tx,
-        summary,
-        wallet,
-        TableActorConfig::default(),
-    )
-    .await
+    config: TableActorConfig,
+    /// Set by [`recover`](Self::recover): until then the table shows as
+    /// degraded.
+    degraded_until: Option<Timestamp>,
 }
 
-pub async fn run_table_actor_with_config(
-    table_id: TableId,
-    settings: TableSettings,
-    initial_state: GameState,
-    mut cmd_rx: mpsc::Receiver<TableCommand>,
-    event_tx: broadcast::Sender<GameEvent>,
-    summary: Arc<RwLock<TableSummary>>,
-    wallet: Arc<dyn Wallet>,
-    config: TableActorConfig,
-) {
-    let mut state = initial_state;
-    let mut seq = 0u64;
+impl TableActor {
+    /// A fresh table with a shuffled shoe and nobody at it. Its event
+    /// stream opens on `events`.
+    pub fn new(
+        table: &TableRecord,
+        wallet: Arc<dyn Wallet>,
+        events: Arc<dyn EventBus>,
+        config: TableActorConfig,
+    ) -> Self {
+        let state = GameState::new(
+            GameId::new(),
+            Shoe::shuffled(),
+            vec![],
+            DealerId(Ulid::new()),
+        );
+        events.open(table.id);
+        Self {
+            table_id: table.id,
+            name: table.name.clone(),
+            settings: table.settings.clone(),
+            state,
+            backlog: VecDeque::with_capacity(config.event_backlog),
+            events,
+            wallet,
+            config,
+            degraded_until: None,
+        }
+    }
+
+    /// When the table next needs [`advance`](Self::advance) without a
+    /// command, if ever.
+    pub fn deadline(&self) -> Option<Timestamp> {
+        match (self.state.deadline, self.degraded_until) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
 
-    let betting_timeout = config.betting_timeout;
-    let player_turn_timeout = config.player_turn_timeout;
-    let round_delay = config.round_delay;
+    pub fn game(&self) -> &GameState {
+        &self.state
+    }
 
-    let betting_dl = tokio::time::sleep(betting_timeout);
-    tokio::pin!(betting_dl);
+    /// Whether the table has closed and should no longer be hosted.
+    pub fn is_closed(&self) -> bool {
+        self.state.status == TableStatus::Closed
+    }
 
-    let mut player_dl: Option<std::pin::Pin<Box<tokio::time::Sleep>>> = None;
-    let mut round_dl: Option<std::pin::Pin<Box<tokio::time::Sleep>>> = None;
+    pub fn summary(&self) -> TableSummary {
+        let state = &self.state;
+        let phase = match &state.phase {
+            Phase::WaitingForBets => "WaitingForBets",
+            Phase::InitialDealing => "InitialDealing",
+            Phase::PlayerTurn(_) => "PlayerTurn",
+            Phase::DealerTurn => "DealerTurn",
+            Phase::Payouts => "Payouts",
+            Phase::Finished => "Finished",
+        };
+        TableSummary {
+            id: self.table_id,
+            name: self.name.clone(),
+            settings: self.settings.clone(),
+            player_count: state.players.len(),
+            phase: phase.to_string(),
+            is_joinable: state.status == TableStatus::Open
+                && state.players.len() < self.settings.max_players,
+            status: state.status,
+            degraded: self.degraded_until.is_some(),
+        }
+    }
 
-    loop {
-        tokio::select! {
-            cmd = cmd_rx.recv() => {
-                let Some(cmd) = cmd else { break };
-                match cmd {
-                    TableCommand::Execute { player_id, request_id, action, reply } => {
-                        let game_cmd = GameCommand::Player(PlayerCommand {
-                            game_id: state.game_id,
-                            command_id: CommandId(request_id.0),
-                            action,
-                        });
-                        match GameEngine::handle(&state, &settings, &game_cmd) {
-                            Err(e) => {
-                                warn!("table={table_id} player={player_id} command rejected: {e}");
-                                let _ = reply.send(Err(SessionError::CommandRejected(e.to_string())));
-                            }
-                            Ok(events) => {
-                                apply_and_broadcast(&mut state, &events, &event_tx, &mut seq);
-                                // Load wallet balance for any player that just joined
-                                for payload in &events {
-                                    if let bj_core::domain::engine::event::payload::EventPayload::PlayerJoined { player, .. } = payload {
-                                        if let Ok(balance) = wallet.balance(*player).await {
-                                            if let Some(ps) = state.players.iter_mut().find(|p| p.player_id == *player) {
-                                                ps.balance = balance;
-                                            }
-                                        }
-                                    }
-                                }
-                                update_summary(&summary, &state, &settings).await;
-                                if matches!(state.phase, Phase::Finished) {
-                                    handle_game_finished(&state, &wallet, &mut round_dl, round_delay).await;
-                                }
-                                reset_player_timer(&state, &mut player_dl, player_turn_timeout);
-                                let _ = reply.send(Ok(CommandAck { request_id }));
-                            }
-                        }
-                        maybe_advance_dealer(&mut state, &settings, &event_tx, &mut seq, &summary, &wallet, &mut round_dl, round_delay, &mut player_dl, player_turn_timeout).await;
-                    }
-                    TableCommand::DealerExecute { action } => {
-                        let game_cmd = GameCommand::Dealer(DealerCommand {
-                            game_id: state.game_id,
-                            command_id: CommandId(0),
-                            action,
-                        });
-                        match GameEngine::handle(&state, &settings, &game_cmd) {
-                            Err(e) => warn!("table={table_id} dealer command rejected: {e}"),
-                            Ok(events) => {
-                                apply_and_broadcast(&mut state, &events, &event_tx, &mut seq);
-                                update_summary(&summary, &state, &settings).await;
-                                if matches!(state.phase, Phase::Finished) {
-                                    handle_game_finished(&state, &wallet, &mut round_dl, round_delay).await;
-                                }
-                                reset_player_timer(&state, &mut player_dl, player_turn_timeout);
-                            }
-                        }
-                        maybe_advance_dealer(&mut state, &settings, &event_tx, &mut seq, &summary, &wallet, &mut round_dl, round_delay, &mut player_dl, player_turn_timeout).await;
-                    }
-                    TableCommand::Snapshot { requesting_player, reply } => {
-                        let snap = GameStateSnapshot::from_state(&state, requesting_player);
-                        let _ = reply.send(Ok(snap));
+    pub async fn handle(&mut self, cmd: TableCommand) -> Result<TableReply, SessionError> {
+        let table_id = self.table_id;
+        match cmd {
+            TableCommand::Open { .. } | TableCommand::Restore { .. } => Err(
+                SessionError::CommandRejected(format!("table {table_id} is already open")),
+            ),
+            TableCommand::Execute {
+                player_id,
+                request_id,
+                action,
+            } => {
+                let started = Instant::now();
+                let action_name: &'static str = (&action).into();
+                let game_cmd = GameCommand::Player(PlayerCommand {
+                    game_id: self.state.game_id,
+                    command_id: CommandId(request_id.0),
+                    action,
+                });
+                let result = self
+                    .execute(&game_cmd, Origin::player(player_id, request_id))
+                    .await
+                    .map(|()| TableReply::Ack(CommandAck { request_id }))
+                    .inspect_err(|e| warn!("table={table_id} player={player_id} {e}"));
+                self.config.metrics.command(action_name, started.elapsed());
+                result
+            }
+            TableCommand::DealerExecute {
+                dealer_id,
+                request_id,
+                action,
+            } => {
+                let origin = Origin::dealer(dealer_id, request_id);
+                let game_cmd = GameCommand::Dealer(DealerCommand {
+                    game_id: self.state.game_id,
+                    command_id: CommandId(request_id.0),
+                    action,
+                });
+                let refused = if self.settings.dealer != DealerMode::Manual {
+                    Some("table has an automatic dealer")
+                } else if !self.settings.dealers.contains(&dealer_id) {
+                    Some("not a dealer at this table")
+                } else {
+                    None
+                };
+                let result = match refused {
+                    None => self
+                        .execute(&game_cmd, origin)
+                        .await
+                        .map(|()| TableReply::Ack(CommandAck { request_id })),
+                    Some(reason) => {
+                        let e = SessionError::CommandRejected(reason.into());
+                        self.audit(&game_cmd, origin, Err(&e)).await;
+                        Err(e)
                     }
-                }
+                };
+                result.inspect_err(|e| warn!("table={table_id} dealer={dealer_id} {e}"))
+            }
+            TableCommand::Snapshot { requesting_player } => Ok(TableReply::Snapshot(Box::new(
+                GameStateSnapshot::from_state(&self.state, requesting_player),
+            ))),
+            TableCommand::Resume { player_id, after } => self
+                .catch_up(player_id, after)
+                .map(|catchup| TableReply::Catchup(Box::new(catchup))),
+            TableCommand::Update { name, settings } => {
+                self.update_settings(settings)
+                    .inspect_err(|e| warn!("table={table_id} settings {e}"))?;
+                self.name = name;
+                Ok(TableReply::Done)
+            }
+            TableCommand::Close => {
+                let cmd = GameCommand::System(SystemCommand::CloseTable(CloseTable));
+                self.execute(&cmd, Origin::system())
+                    .await
+                    .map(|()| TableReply::Done)
+                    .inspect_err(|e| warn!("table={table_id} close {e}"))
+            }
+            TableCommand::VoidRound { reason } => {
+                info!("table={table_id} voiding round: {reason}");
+                let cmd = GameCommand::System(SystemCommand::VoidRound(VoidRound { reason }));
+                self.execute(&cmd, Origin::system())
+                    .await
+                    .map(|()| TableReply::Done)
+                    .inspect_err(|e| warn!("table={table_id} void {e}"))
             }
+        }
+    }
 
-            // Betting phase timeout
-            _ = &mut betting_dl, if matches!(state.phase, Phase::WaitingForBets) => {
-                let has_bets = state.players.iter().any(|p| p.bet.is_some());
-                if has_bets {
-                    fire_dealer(&mut state, &settings, DealerAction::DealInitialCards(DealInitialCards), &event_tx, &mut seq, &summary, &wallet, &mut round_dl, round_delay, &mut player_dl, player_turn_timeout).await;
-                }
-                betting_dl.as_mut().reset(tokio::time::Instant::now() + betting_timeout);
+    /// Issues dealer-policy commands until the table is waiting on players or
+    /// on a future deadline.
+    pub async fn advance(&mut self) {
+        let now = self.config.clock.now();
+        if self.degraded_until.is_some_and(|until| until <= now) {
+            self.degraded_until = None;
+            info!("table={} recovered", self.table_id);
+        }
+        while let Some(cmd) =
+            DealerPolicy::next_command(&self.state, &self.settings, self.config.clock.now())
+        {
+            if let Err(e) = self.execute(&cmd, Origin::system()).await {
+                error!("table={} dealer policy {e}", self.table_id);
+                // Never spin on a deadline the engine will not act on.
+                self.state.deadline = None;
+                break;
             }
+        }
+    }
+
+    /// Rebuilds the table after a panic in [`handle`](Self::handle) or
+    /// [`advance`](Self::advance) left its state half-applied.
+    ///
+    /// Who sits where, who watches and who waits survive, and so does the
+    /// event sequence, so connected clients keep following the table. The
+    /// round does not: it is voided, which releases every stake and tells
+    /// clients why. Stakes the wallet still holds for the table, such as one
+    /// reserved just before the panic, are released too.
+    pub async fn recover(&mut self) {
+        let table_id = self.table_id;
+        let crashed = &self.state;
+        let mut state = GameState::new(
+            crashed.game_id,
+            Shoe::shuffled(),
+            vec![],
+            DealerId(Ulid::new()),
+        );
+        state.phase = crashed.phase.clone();
+        state.players = crashed
+            .players
+            .iter()
+            .map(|p| PlayerState {
+                hand: Hand::new(),
+                decisions: vec![],
+                ..p.clone()
+            })
+            .collect();
+        state.observers = crashed.observers.clone();
+        state.waiting = crashed.waiting.clone();
+        state.status = crashed.status;
+        state.event_seq = crashed.event_seq;
+        self.state = state;
 
-            // Player turn timeout
-            _ = async {
-                if let Some(ref mut t) = player_dl { t.as_mut().await }
-                else { std::future::pending::<()>().await }
-            }, if player_dl.is_some() && matches!(state.phase, Phase::PlayerTurn(_)) => {
-                if let Phase::PlayerTurn(pid) = state.phase {
-                    let cmd = GameCommand::System(SystemCommand::PlayerTimeout(PlayerTimeout { player_id: pid }));
-                    if let Ok(events) = GameEngine::handle(&state, &settings, &cmd) {
-                        apply_and_broadcast(&mut state, &events, &event_tx, &mut seq);
-                        update_summary(&summary, &state, &settings).await;
-                        maybe_advance_dealer(&mut state, &settings, &event_tx, &mut seq, &summary, &wallet, &mut round_dl, round_delay, &mut player_dl, player_turn_timeout).await;
+        let void = GameCommand::System(SystemCommand::VoidRound(VoidRound {
+            reason: "the table restarted after an internal error".into(),
+        }));
+        if let Err(e) = self.execute(&void, Origin::system()).await {
+            error!("table={table_id} could not void the crashed round: {e}");
+        }
+        match self.wallet.held().await {
+            Ok(held) => {
+                for stake in held.iter().filter(|r| r.table_id == table_id) {
+                    if let Err(e) = self.wallet.release(stake.player_id, stake.game_id).await {
+                        error!(
+                            "table={table_id} player={} stake not released: {e}",
+                            stake.player_id
+                        );
                     }
                 }
-                reset_player_timer(&state, &mut player_dl, player_turn_timeout);
             }
+            Err(e) => error!("table={table_id} held stakes unknown: {e}"),
+        }
+        self.degraded_until = Some(self.config.clock.now().plus(self.config.recovery_period));
+        self.config.metrics.table_restarted();
+        warn!("table={table_id} restarted; round voided");
+    }
 
-            // New round delay
-            _ = async {
-                if let Some(ref mut t) = round_dl { t.as_mut().await }
-                else { std::future::pending::<()>().await }
-            }, if round_dl.is_some() => {
-                round_dl = None;
-                // Preserve player seats and balances across rounds.
-                let preserved: Vec<_> = state
-                    .players
-                    .iter()
-                    .map(|p| (p.player_id, p.seat, p.balance))
-                    .collect();
-                debug_assert!(
-                    {
-                        let mut seats = preserved.iter().map(|(_, s, _)| *s).collect::<Vec<_>>();
-                        seats.sort();
-                        seats.windows(2).all(|w| w[0] != w[1])
-                    },
-                    "duplicate seats detected in round reset"
-                );
-                let dealer_id = state.dealer.dealer_id;
-                let shoe = Shoe::shuffled();
-                let mut next =
-                    GameState::new(GameId::new(), shoe, vec![], dealer_id);
-                for (pid, seat, balance) in preserved {
-                    next.players.push(
-                        bj_core::domain::PlayerState::with_balance_at_seat(
-                            pid, seat, balance,
-                        ),
-                    );
-                }
-                state = next;
-                // Broadcast new round notification so subscribed clients know the round reset
-                seq += 1;
-                let _ = event_tx.send(GameEvent {
-                    game_id: state.game_id,
-                    event_seq_id: EventSeqId(seq),
-                    payload: bj_core::domain::engine::event::payload::EventPayload::PhaseChanged {
-                        from: bj_core::domain::engine::phase::Phase::Finished,
-                        to: bj_core::domain::engine::phase::Phase::WaitingForBets,
-                    },
-                });
-                fire_dealer(&mut state, &settings, DealerAction::OpenBetting(OpenBetting), &event_tx, &mut seq, &summary, &wallet, &mut round_dl, round_delay, &mut player_dl, player_turn_timeout).await;
-                betting_dl.as_mut().reset(tokio::time::Instant::now() + betting_timeout);
+    /// Takes up the game `saved` from a state directory, in place of the
+    /// fresh one the table opened with.
+    ///
+    /// Seats, observers, the waiting list and the event sequence carry on.
+    /// A round that was in play is voided: its stakes were refunded when the
+    /// wallet released what it held at boot, so voiding only clears the
+    /// hands and reopens betting. Every seated balance is read again from
+    /// the wallet. A game saved as the shutdown closed its table opens
+    /// again; only the table store keeps a table closed.
+    pub async fn restore(&mut self, saved: GameState) {
+        let table_id = self.table_id;
+        self.state = GameState {
+            shoe: Shoe::shuffled(),
+            dealt: 0,
+            status: TableStatus::Open,
+            ..saved
+        };
+        if !self.state.is_between_rounds() {
+            let void = GameCommand::System(SystemCommand::VoidRound(VoidRound {
+                reason: "the server restarted".into(),
+            }));
+            if let Err(e) = self.execute(&void, Origin::system()).await {
+                error!("table={table_id} could not void the interrupted round: {e}");
             }
         }
+        for player in self.state.players.iter_mut() {
+            match self.wallet.balance(player.player_id).await {
+                Ok(balance) => player.balance = balance,
+                Err(e) => error!(
+                    "table={table_id} player={} balance unknown: {e}",
+                    player.player_id
+                ),
+            }
+        }
+        info!(
+            "table={table_id} restored with {} players",
+            self.state.players.len()
+        );
     }
-}
 
-fn apply_and_broadcast(
-    state: &mut GameState,
-    events: &[bj_core::domain::engine::event::payload::EventPayload],
-    tx: &broadcast::Sender<GameEvent>,
-    seq: &mut u64,
-) {
-    for payload in events {
-        state.apply_event(payload);
-        *seq += 1;
-        let _ = tx.send(GameEvent {
-            game_id: state.game_id,
-            event_seq_id: EventSeqId(*seq),
-            payload: payload.clone(),
-        });
+    /// Runs `cmd`, from `origin`, and adds it and its outcome to the audit
+    /// trail.
+    async fn execute(&mut self, cmd: &GameCommand, origin: Origin) -> Result<(), SessionError> {
+        let result = self.run(cmd).await;
+        self.audit(cmd, origin, result.as_deref()).await;
+        result.map(|_| ())
     }
-}
 
-async fn update_summary(
-    summary: &Arc<RwLock<TableSummary>>,
-    state: &GameState,
-    settings: &TableSettings,
-) {
-    let phase_str = match &state.phase {
-        Phase::WaitingForBets => "WaitingForBets".to_string(),
-        Phase::InitialDealing => "InitialDealing".to_string(),
-        Phase::PlayerTurn(_) => "PlayerTurn".to_string(),
-        Phase::DealerTurn => "DealerTurn".to_string(),
-        Phase::Payouts => "Payouts".to_string(),
-        Phase::Finished => "Finished".to_string(),
-    };
-    let player_count = state.players.len();
-    let is_joinable = state.players.len() < settings.max_players;
-    let mut s = summary.write().await;
-    s.player_count = player_count;
-    s.phase = phase_str;
-    s.is_joinable = is_joinable;
-}
-
-async fn handle_game_finished(
-    state: &GameState,
-    wallet: &Arc<dyn Wallet>,
-    round_dl: &mut Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
-    delay: Duration,
-) {
-    for player in &state.players {
-        wallet.set_balance(player.player_id, player.balance).await;
-        info!(
-            "game={} player={} balance synced to {}",
-            state.game_id, player.player_id, player.balance
+    /// A failure to record is logged; the command has already happened.
+    async fn audit(
+        &self,
+        cmd: &GameCommand,
+        origin: Origin,
+        result: Result<&[GameEvent], &SessionError>,
+    ) {
+        let outcome = match result {
+            Ok(events) => Outcome::Accepted {
+                events: events.to_vec(),
+            },
+            Err(e) => Outcome::Rejected {
+                error: e.to_string(),
+            },
+        };
+        let record = AuditRecord::new(
+            self.config.clock.now(),
+            self.table_id,
+            origin,
+            cmd.clone(),
+            outcome,
         );
+        if let Err(e) = self
+            .config
+            .audit
+            .append(&record)
+            .instrument(info_span!("table.audit"))
+            .await
+        {
+            error!("table={} command not audited: {e}", self.table_id);
+        }
     }
-    *round_dl = Some(Box::pin(tokio::time::sleep(delay)));
-}
 
-fn reset_player_timer(
-    state: &GameState,
-    player_dl: &mut Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
-    timeout: Duration,
-) {
-    if matches!(state.phase, Phase::PlayerTurn(_)) {
-        *player_dl = Some(Box::pin(tokio::time::sleep(timeout)));
-    } else {
-        *player_dl = None;
+    /// Runs `cmd` through the engine, posts the stakes and settlements its
+    /// events call for to the wallet, then applies and broadcasts the events.
+    /// Returns the events as broadcast.
+    ///
+    /// The wallet goes first: a bet the wallet cannot cover is rejected, and
+    /// by the time clients see `GameFinished` their payout is already booked.
+    async fn run(&mut self, cmd: &GameCommand) -> Result<Vec<GameEvent>, SessionError> {
+        let events = info_span!("engine.handle", game = %self.state.game_id)
+            .in_scope(|| {
+                GameEngine::handle_at(
+                    &self.state,
+                    &self.settings,
+                    cmd,
+                    &self.config.timers,
+                    self.config.clock.now(),
+                )
+            })
+            .inspect_err(|e| self.config.metrics.rejected(e))?;
+        let balances = self
+            .post_to_wallet(&events)
+            .instrument(info_span!("table.wallet"))
+            .await?;
+        let broadcast = self.apply_and_broadcast(&events);
+        load_joined_balances(&mut self.state, &events, &self.wallet).await;
+        for player in self.state.players.iter_mut() {
+            if let Some(balance) = balances.get(&player.player_id) {
+                player.balance = *balance;
+            }
+        }
+        Ok(broadcast)
     }
-}
 
-#[allow(clippy::too_many_arguments)]
-async fn fire_dealer(
-    state: &mut GameState,
-    settings: &TableSettings,
-    action: DealerAction,
-    event_tx: &broadcast::Sender<GameEvent>,
-    seq: &mut u64,
-    summary: &Arc<RwLock<TableSummary>>,
-    wallet: &Arc<dyn Wallet>,
-    round_dl: &mut Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
-    round_delay: Duration,
-    player_dl: &mut Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
-    player_timeout: Duration,
-) {
-    let cmd = GameCommand::Dealer(DealerCommand {
-        game_id: state.game_id,
-        command_id: CommandId(0),
-        action,
-    });
-    if let Ok(events) = GameEngine::handle(state, settings, &cmd) {
-        apply_and_broadcast(state, &events, event_tx, seq);
-        update_summary(summary, state, settings).await;
-        if matches!(state.phase, Phase::Finished) {
-            handle_game_finished(state, wallet, round_dl, round_delay).await;
+    /// Applies engine-validated events, keeping each in the backlog before
+    /// broadcasting it.
+    fn apply_and_broadcast(&mut self, events: &[EventPayload]) -> Vec<GameEvent> {
+        let _span = info_span!("table.broadcast", events = events.len()).entered();
+        let mut broadcast = Vec::with_capacity(events.len());
+        for payload in events {
+            if let Err(e) = self.state.apply_event(payload) {
+                // The engine validated these events, so this is a handler bug.
+                error!(
+                    "game={} dropping rest of event batch: {e}",
+                    self.state.game_id
+                );
+                return broadcast;
+            }
+            let event = GameEvent {
+                game_id: self.state.game_id,
+                event_seq_id: self.state.event_seq,
+                payload: payload.clone(),
+            };
+            if self.backlog.len() == self.config.event_backlog {
+                self.backlog.pop_front();
+            }
+            if self.config.event_backlog > 0 {
+                self.backlog.push_back(event.clone());
+            }
+            self.events.publish(self.table_id, event.clone());
+            broadcast.push(event);
+        }
+        broadcast
+    }
+
+    /// Swaps in `settings` unless someone sits, or waits for, a seat the new
+    /// `max_players` would remove. The dealer mode only changes between
+    /// rounds.
+    fn update_settings(&mut self, settings: TableSettings) -> Result<(), SessionError> {
+        if settings.dealer != self.settings.dealer {
+            if !self.state.is_between_rounds() {
+                return Err(SessionError::CommandRejected(
+                    "the dealer mode can only change between rounds".into(),
+                ));
+            }
+            // The automatic dealer starts the next round straight away.
+            let next_round =
+                settings.dealer == DealerMode::Automatic && self.state.phase == Phase::Finished;
+            self.state.deadline = next_round.then(|| self.config.clock.now());
+        }
+        let state = &self.state;
+        let taken = state
+            .players
+            .iter()
+            .map(|p| p.seat)
+            .chain(state.waiting.iter().map(|(_, seat)| *seat))
+            .filter(|seat| seat.number() > settings.max_players)
+            .max();
+        if let Some(seat) = taken {
+            return Err(SessionError::CommandRejected(format!(
+                "seat {seat} is taken; max_players cannot go below {}",
+                seat.number()
+            )));
         }
-        reset_player_timer(state, player_dl, player_timeout);
+        info!("table={} settings now {settings:?}", self.table_id);
+        self.settings = settings;
+        Ok(())
+    }
+
+    /// The events after `after` if the backlog still reaches back that far,
+    /// otherwise a snapshot of the table as it is now.
+    fn catch_up(&self, player_id: PlayerId, after: EventSeqId) -> Result<Catchup, SessionError> {
+        let state = &self.state;
+        let at_table = state.players.iter().any(|p| p.player_id == player_id)
+            || state.observers.contains(&player_id)
+            || state.waiting.iter().any(|(p, _)| *p == player_id);
+        if !at_table {
+            return Err(CommandError::PlayerNotFound(player_id).into());
+        }
+        if after == state.event_seq {
+            return Ok(Catchup::Events(vec![]));
+        }
+        let oldest = self.backlog.front().map(|e| e.event_seq_id);
+        // A client claiming to be ahead of the table has the wrong table or
+        // predates a restart; either way it needs the whole picture.
+        if after < state.event_seq && oldest.is_some_and(|seq| seq <= after.next()) {
+            let missed = self
+                .backlog
+                .iter()
+                .filter(|e| e.event_seq_id > after)
+                .cloned()
+                .collect();
+            return Ok(Catchup::Events(missed));
+        }
+        Ok(Catchup::Snapshot(GameStateSnapshot::from_state(
+            state, player_id,
+        )))
+    }
+
+    /// Escrows new bets and settles the stakes of a finished, voided or
+    /// abandoned round. Returns the wallet balance of every player touched.
+    ///
+    /// Only a failed reservation is an error. A failed commit or release is
+    /// logged and the stake stays held until [`release_held`] runs at the
+    /// next boot; the round itself has already happened.
+    ///
+    /// [`release_held`]: crate::wallet::release_held
+    async fn post_to_wallet(
+        &self,
+        events: &[EventPayload],
+    ) -> Result<HashMap<PlayerId, u32>, SessionError> {
+        let table_id = self.table_id;
+        let game_id = self.state.game_id;
+        // Once the round has finished its bets are settled, though they
+        // are still shown until the next one.
+        let in_play = self.state.phase != Phase::Finished;
+        let staked = |player: &PlayerId| {
+            in_play
+                && self
+                    .state
+                    .players
+                    .iter()
+                    .any(|p| p.player_id == *player && p.bet.is_some())
+        };
+        let mut balances = HashMap::new();
+        let mut settled = vec![];
+
+        for event in events {
+            match event {
+                EventPayload::PlayerPlacedBet { player, amount } => {
+                    let balance = self
+                        .wallet
+                        .reserve(*player, table_id, game_id, *amount)
+                        .await
+                        .map_err(|e| match e {
+                            WalletError::InsufficientBalance { balance, amount } => {
+                                let e = CommandError::InsufficientBalance { balance, amount };
+                                self.config.metrics.rejected(&e);
+                                e.into()
+                            }
+                            e => {
+                                error!("table={table_id} player={player} reserve failed: {e}");
+                                SessionError::Internal
+                            }
+                        })?;
+                    balances.insert(*player, balance);
+                }
+                EventPayload::GameFinished { result } => {
+                    self.config.metrics.round_finished();
+                    for pr in &result.player_results {
+                        let payout = pr.payout.total();
+                        self.config.metrics.settled(table_id, pr.payout.bet, payout);
+                        settled.push((
+                            pr.player,
+                            self.wallet.commit(pr.player, game_id, payout).await,
+                        ));
+                    }
+                }
+                EventPayload::RoundVoided { .. } => {
+                    for p in self.state.players.iter().filter(|p| staked(&p.player_id)) {
+                        settled
+                            .push((p.player_id, self.wallet.release(p.player_id, game_id).await));
+                    }
+                }
+                // Leaving before the deal takes the stake back; leaving once
+                // cards are out forfeits it.
+                EventPayload::PlayerLeft { player } if staked(player) => {
+                    let result = if matches!(self.state.phase, Phase::WaitingForBets) {
+                        self.wallet.release(*player, game_id).await
+                    } else {
+                        let stake = self
+                            .state
+                            .players
+                            .iter()
+                            .find(|p| p.player_id == *player)
+                            .and_then(|p| p.bet)
+                            .unwrap_or(0);
+                        self.config.metrics.settled(table_id, stake, 0);
+                        self.wallet.commit(*player, game_id, 0).await
+                    };
+                    settled.push((*player, result));
+                }
+                _ => {}
+            }
+        }
+        for (player, result) in settled {
+            match result {
+                Ok(balance) => {
+                    balances.insert(player, balance);
+                }
+                Err(e) => {
+                    error!("table={table_id} game={game_id} player={player} stake not settled: {e}")
+                }
+            }
+        }
+        Ok(balances)
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn maybe_advance_dealer(
+/// Load the wallet balance for every player seated by `events`.
+async fn load_joined_balances(
     state: &mut GameState,
-    settings: &TableSettings,
-    event_tx: &broadcast::Sender<GameEvent>,
-    seq: &mut u64,
-    summary: &Arc<RwLock<TableSummary>>,
+    events: &[EventPayload],
     wallet: &Arc<dyn Wallet>,
-    round_dl: &mut Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
-    round_delay: Duration,
-    player_dl: &mut Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
-    player_timeout: Duration,
 ) {
-    if matches!(state.phase, Phase::DealerTurn) {
-        fire_dealer(
-            state,
-            settings,
-            DealerAction::PlayHand(PlayHand),
-            event_tx,
-            seq,
-            summary,
-            wallet,
-            round_dl,
-            round_delay,
-            player_dl,
-            player_timeout,
-        )
-        .await;
-    }
-    if matches!(state.phase, Phase::Payouts) {
-        fire_dealer(
-            state,
-            settings,
-            DealerAction::SettleRound(SettleRound),
-            event_tx,
-            seq,
-            summary,
-            wallet,
-            round_dl,
-            round_delay,
-            player_dl,
-            player_timeout,
-        )
-        .await;
+    for payload in events {
+        if let EventPayload::PlayerJoined { player, .. } = payload {
+            if let Ok(balance) = wallet.balance(*player).await {
+                if let Some(ps) = state.players.iter_mut().find(|p| p.player_id == *player) {
+                    ps.balance = balance;
+                }
+            }
+        }
     }
 }
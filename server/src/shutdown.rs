@@ -0,0 +1,136 @@
+//! Graceful shutdown.
+//!
+//! On SIGTERM or Ctrl-C the server stops taking joins and bets, tells every
+//! client when it will go down, lets the rounds in play finish (voiding
+//! those still going at the deadline), releases any stake left held, and
+//! closes the sockets with 1001 "going away".
+
+use std::time::Duration;
+
+use bj_core::domain::engine::{Clock, SystemClock, Timestamp};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::{session::in_memory::InMemoryGameSession, App};
+
+/// Where the process is in its shutdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownState {
+    Running,
+    /// Tables are finishing their rounds; the server goes down at
+    /// `deadline`.
+    Draining {
+        deadline: Timestamp,
+    },
+    /// Connections should close now.
+    Closing,
+}
+
+/// Lets every connection follow the shutdown.
+pub struct Shutdown {
+    state: watch::Sender<ShutdownState>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            state: watch::Sender::new(ShutdownState::Running),
+        }
+    }
+
+    pub fn watch(&self) -> watch::Receiver<ShutdownState> {
+        self.state.subscribe()
+    }
+
+    /// Whether joins and bets should be refused.
+    pub fn is_draining(&self) -> bool {
+        *self.state.borrow() != ShutdownState::Running
+    }
+
+    pub fn begin(&self, deadline: Timestamp) {
+        self.state
+            .send_replace(ShutdownState::Draining { deadline });
+    }
+
+    /// Tells every connection to close and waits, up to `wait`, until they
+    /// have.
+    pub async fn close_connections(&self, wait: Duration) {
+        self.state.send_replace(ShutdownState::Closing);
+        if tokio::time::timeout(wait, self.state.closed())
+            .await
+            .is_err()
+        {
+            warn!(
+                "{} connections still open after {wait:?}",
+                self.state.receiver_count()
+            );
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM where there is one.
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("cannot listen for Ctrl-C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                error!("cannot listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Runs the shutdown sequence for the tables `session` hosts, giving rounds
+/// in play `grace` to finish. The sockets are closed when it returns.
+pub async fn drain(app: &App, session: &InMemoryGameSession, grace: Duration) {
+    let deadline = SystemClock.now().plus(grace);
+    info!("shutting down; rounds in play have {grace:?} to finish");
+    app.shutdown.begin(deadline);
+
+    let tables = session.drain(grace).await;
+
+    // Stakes are only left held by a round that neither finished nor
+    // voided cleanly.
+    match app.wallet.held().await {
+        Ok(held) => {
+            for stake in held.iter().filter(|r| tables.contains(&r.table_id)) {
+                match app.wallet.release(stake.player_id, stake.game_id).await {
+                    Ok(_) => warn!(
+                        "table={} player={} released stake of {} at shutdown",
+                        stake.table_id, stake.player_id, stake.amount
+                    ),
+                    Err(e) => error!(
+                        "table={} player={} stake not released: {e}",
+                        stake.table_id, stake.player_id
+                    ),
+                }
+            }
+        }
+        Err(e) => error!("held stakes not checked at shutdown: {e}"),
+    }
+
+    app.shutdown.close_connections(Duration::from_secs(5)).await;
+}
@@ -0,0 +1,285 @@
+//! Hot-restart persistence for `database.kind: memory`.
+//!
+//! Every table with its settings and game, the wallet, the accounts and
+//! their logins are saved together as one JSON file in `state.dir`: every
+//! `state.save_interval_secs` and once more at shutdown. On boot the file
+//! is read back in place of the seeds.
+//!
+//! A save writes a new file beside the old one, syncs it and renames it over
+//! the old one, so a crash mid-save leaves the last state whole. Rounds do
+//! not survive a restart: a table saved mid-round comes back with the round
+//! voided and its stakes refunded.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use bj_core::domain::{
+    engine::{game_state::GameState, Clock, SystemClock, Timestamp},
+    TableStatus,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::{watch, Mutex};
+use tracing::{error, info};
+
+use crate::{
+    auth::{
+        session_store::{InMemorySessionStore, SessionsState},
+        InMemoryAuthenticator, PasswordHasher, SavedUser,
+    },
+    session::{in_memory::InMemoryGameSession, GameSession},
+    shutdown::ShutdownState,
+    store::{InMemoryTableStore, TableRecord, TableStore},
+    wallet::in_memory::{InMemoryWallet, WalletState},
+};
+
+/// The file in the state directory holding the last save.
+pub const STATE_FILE: &str = "state.json";
+
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("state directory error: {0}")]
+    Io(#[from] io::Error),
+    #[error("saved state is unreadable: {0}")]
+    Corrupt(#[from] serde_json::Error),
+}
+
+/// A table as saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTable {
+    pub table: TableRecord,
+    /// `None` for a table that was not running, such as a closed one.
+    pub game: Option<GameState>,
+}
+
+/// Everything a save writes.
+#[derive(Serialize, Deserialize)]
+pub struct SavedState {
+    pub saved_at: Timestamp,
+    pub tables: Vec<SavedTable>,
+    pub wallet: WalletState,
+    pub users: Vec<SavedUser>,
+    pub sessions: SessionsState,
+}
+
+/// The directory a server saves its state to.
+#[derive(Debug, Clone)]
+pub struct StateDir {
+    path: PathBuf,
+}
+
+impl StateDir {
+    /// Creates the directory if it does not exist yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StateError> {
+        fs::create_dir_all(&path)?;
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    pub fn file(&self) -> PathBuf {
+        self.path.join(STATE_FILE)
+    }
+
+    /// The last state saved, or `None` if nothing has been saved here yet.
+    pub fn load(&self) -> Result<Option<SavedState>, StateError> {
+        match fs::read(self.file()) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Replaces the saved state with `state`, all at once.
+    pub fn write(&self, state: &SavedState) -> Result<(), StateError> {
+        let bytes = serde_json::to_vec(state)?;
+        let partial = self.path.join(format!("{STATE_FILE}.partial"));
+        let mut file = private_file(&partial)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&partial, self.file())?;
+        // The rename itself is only durable once the directory is synced.
+        #[cfg(unix)]
+        fs::File::open(&self.path)?.sync_all()?;
+        Ok(())
+    }
+}
+
+/// The saved state holds password hashes and refresh-token hashes, so only
+/// its owner may read it, even when a save that died left the file behind
+/// with other permissions.
+fn private_file(path: &Path) -> io::Result<fs::File> {
+    let file = fs::File::create(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(file)
+}
+
+/// The in-memory backends a state directory saves and restores.
+#[derive(Clone)]
+pub struct MemoryBackends {
+    pub wallet: Arc<InMemoryWallet>,
+    pub auth: Arc<InMemoryAuthenticator>,
+    pub sessions: Arc<InMemorySessionStore>,
+    pub tables: Arc<InMemoryTableStore>,
+}
+
+impl MemoryBackends {
+    /// Empty backends with the seeded tables, for a first boot.
+    pub fn seeded(hasher: PasswordHasher) -> Self {
+        Self {
+            wallet: Arc::new(InMemoryWallet::new()),
+            auth: Arc::new(InMemoryAuthenticator::new(hasher)),
+            sessions: Arc::new(InMemorySessionStore::new()),
+            tables: Arc::new(InMemoryTableStore::seeded()),
+        }
+    }
+
+    /// Backends holding what `saved` recorded. Its tables' games are opened
+    /// later, by [`open_saved_tables`].
+    pub fn restore(saved: &mut SavedState, hasher: PasswordHasher) -> Self {
+        Self {
+            wallet: Arc::new(InMemoryWallet::restore(std::mem::take(&mut saved.wallet))),
+            auth: Arc::new(InMemoryAuthenticator::restore(
+                std::mem::take(&mut saved.users),
+                hasher,
+            )),
+            sessions: Arc::new(InMemorySessionStore::restore(std::mem::take(
+                &mut saved.sessions,
+            ))),
+            tables: Arc::new(InMemoryTableStore::restore(
+                saved.tables.iter().map(|t| t.table.clone()).collect(),
+            )),
+        }
+    }
+}
+
+/// Opens every open table in `tables` on `session`, each with the game it
+/// was saved with. Returns how many opened.
+pub async fn open_saved_tables(tables: Vec<SavedTable>, session: &InMemoryGameSession) -> usize {
+    let mut opened = 0;
+    for SavedTable { table, game } in tables {
+        if table.status != TableStatus::Open {
+            continue;
+        }
+        let result = match game {
+            Some(game) => session.restore_table(&table, game).await,
+            None => session.open_table(&table).await,
+        };
+        match result {
+            Ok(()) => opened += 1,
+            Err(e) => error!("table={} '{}' could not open: {e}", table.id, table.name),
+        }
+    }
+    opened
+}
+
+/// Saves the state of a running server to its state directory.
+pub struct StateSaver {
+    dir: StateDir,
+    backends: MemoryBackends,
+    session: Arc<InMemoryGameSession>,
+    /// Set by the save at shutdown; no save may follow it.
+    finished: Mutex<bool>,
+}
+
+impl StateSaver {
+    pub fn new(dir: StateDir, backends: MemoryBackends, session: Arc<InMemoryGameSession>) -> Self {
+        Self {
+            dir,
+            backends,
+            session,
+            finished: Mutex::new(false),
+        }
+    }
+
+    /// Everything as it is now: the tables with their games, the chips,
+    /// accounts and logins, read while no table runs a command so they
+    /// agree with each other. An open table's game is its last one, even
+    /// if it closed for the shutdown since.
+    pub async fn snapshot(&self) -> SavedState {
+        let tables = match self.backends.tables.list_tables().await {
+            Ok(tables) => tables,
+            Err(e) => {
+                error!("tables not listed for saving: {e}");
+                vec![]
+            }
+        };
+        let _paused = self.session.pause().await;
+        let mut games = self.session.games();
+        SavedState {
+            saved_at: SystemClock.now(),
+            tables: tables
+                .into_iter()
+                .map(|table| SavedTable {
+                    game: match table.status {
+                        TableStatus::Open => games.remove(&table.id),
+                        _ => None,
+                    },
+                    table,
+                })
+                .collect(),
+            wallet: self.backends.wallet.export(),
+            users: self.backends.auth.export(),
+            sessions: self.backends.sessions.export(),
+        }
+    }
+
+    /// Saves everything as it is now.
+    pub async fn save(&self) -> Result<(), StateError> {
+        let finished = self.finished.lock().await;
+        if *finished {
+            return Ok(());
+        }
+        let state = self.snapshot().await;
+        self.write(state).await
+    }
+
+    /// The last save, at shutdown, once the tables have drained, so the
+    /// chips include every round settled on the way. Saves after it are
+    /// ignored.
+    pub async fn save_final(&self) -> Result<(), StateError> {
+        let mut finished = self.finished.lock().await;
+        *finished = true;
+        let state = self.snapshot().await;
+        self.write(state).await
+    }
+
+    async fn write(&self, state: SavedState) -> Result<(), StateError> {
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || dir.write(&state))
+            .await
+            .map_err(io::Error::other)??;
+        Ok(())
+    }
+
+    /// Saves every `interval` until the shutdown begins.
+    pub async fn run(
+        self: Arc<Self>,
+        interval: Duration,
+        mut shutdown: watch::Receiver<ShutdownState>,
+    ) {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.tick().await;
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => {
+                    if let Err(e) = self.save().await {
+                        error!("state not saved: {e}");
+                    }
+                }
+                _ = shutdown.changed() => break,
+            }
+        }
+        info!("periodic saves stopped");
+    }
+}
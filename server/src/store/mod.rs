@@ -1,3 +1,36 @@
 mod table_store;
 
 pub use table_store::*;
+
+use crate::config::DatabaseSettings;
+use sqlx::{
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    PgPool, SqlitePool,
+};
+
+/// Connects to PostgreSQL and brings the schema up to date.
+pub async fn connect_postgres(settings: &DatabaseSettings) -> Result<PgPool, sqlx::Error> {
+    let pool = PgPoolOptions::new()
+        .max_connections(settings.max_connections)
+        .connect(&settings.connection_string())
+        .await?;
+    sqlx::migrate!("../migrations").run(&pool).await?;
+    Ok(pool)
+}
+
+/// Opens the SQLite file at `path`, creating it if needed, and brings the
+/// schema up to date. The same migrations as for PostgreSQL apply.
+pub async fn connect_sqlite(path: &str, max_connections: u32) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        // Readers do not wait for the writer.
+        .journal_mode(SqliteJournalMode::Wal);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(max_connections)
+        .connect_with(options)
+        .await?;
+    sqlx::migrate!("../migrations").run(&pool).await?;
+    Ok(pool)
+}
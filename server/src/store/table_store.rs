@@ -1,19 +1,144 @@
-use color_eyre::eyre::Report;
-use sqlx::PgPool;
+use crate::session::GameSession;
+use bj_core::domain::{DealerMode, TableId, TableSettings, TableStatus};
+use color_eyre::eyre::{eyre, Report};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row, SqlitePool};
 use thiserror::Error;
+use tracing::error;
+use utoipa::ToSchema;
+
+/// A table as configured by an admin. Live state (who is seated, the
+/// phase) lives in its `TableActor`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TableRecord {
+    #[schema(value_type = String)]
+    pub id: TableId,
+    pub name: String,
+    /// `Open` or `Closed`: a table being closed is stored as closed, so it
+    /// does not come back after a restart.
+    pub status: TableStatus,
+    pub settings: TableSettings,
+}
 
-// TODO(Task 15): Replace with TableActor / InMemoryGameSession — this is a temporary stub.
 #[async_trait::async_trait]
 pub trait TableStore: Send + Sync {
-    async fn list_tables(&self) -> Result<Vec<()>, TableStoreError>;
+    /// Every table, closed ones included, ordered by id.
+    async fn list_tables(&self) -> Result<Vec<TableRecord>, TableStoreError>;
+    async fn get_table(&self, id: TableId) -> Result<TableRecord, TableStoreError>;
+    async fn insert_table(&self, table: &TableRecord) -> Result<(), TableStoreError>;
+    /// Overwrites the name, status and settings of an existing table.
+    async fn update_table(&self, table: &TableRecord) -> Result<(), TableStoreError>;
+}
+
+/// Starts an actor for every open table in `store`. Called once at boot.
+pub async fn open_stored_tables(
+    store: &dyn TableStore,
+    session: &dyn GameSession,
+) -> Result<usize, TableStoreError> {
+    let mut opened = 0;
+    for table in store.list_tables().await? {
+        if table.status != TableStatus::Open {
+            continue;
+        }
+        match session.open_table(&table).await {
+            Ok(()) => opened += 1,
+            Err(e) => error!("table={} '{}' could not open: {e}", table.id, table.name),
+        }
+    }
+    Ok(opened)
 }
 
 #[derive(Debug, Error)]
 pub enum TableStoreError {
+    #[error("table not found")]
+    NotFound,
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
 
+impl From<sqlx::Error> for TableStoreError {
+    fn from(e: sqlx::Error) -> Self {
+        TableStoreError::UnexpectedError(e.into())
+    }
+}
+
+/// Tables in process memory, for `database.kind: memory`.
+#[derive(Default)]
+pub struct InMemoryTableStore {
+    tables: DashMap<TableId, TableRecord>,
+}
+
+impl InMemoryTableStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A store holding `tables`, as a state directory saved them.
+    pub fn restore(tables: Vec<TableRecord>) -> Self {
+        Self {
+            tables: tables.into_iter().map(|t| (t.id, t)).collect(),
+        }
+    }
+
+    /// The tables a fresh in-memory server opens with.
+    pub fn seeded() -> Self {
+        let store = Self::new();
+        for (name, min_bet, max_bet, max_players) in [
+            ("Cool Kids #1", 10, 500, 5),
+            ("Big Sharks #2", 25, 1000, 6),
+            ("Sopranos #3", 100, 5000, 4),
+        ] {
+            let table = TableRecord {
+                id: TableId::new(),
+                name: name.into(),
+                status: TableStatus::Open,
+                settings: TableSettings {
+                    min_bet,
+                    max_bet,
+                    max_players,
+                    max_observers: 10,
+                    dealer: DealerMode::Automatic,
+                    dealers: vec![],
+                },
+            };
+            store.tables.insert(table.id, table);
+        }
+        store
+    }
+}
+
+#[async_trait::async_trait]
+impl TableStore for InMemoryTableStore {
+    async fn list_tables(&self) -> Result<Vec<TableRecord>, TableStoreError> {
+        let mut tables: Vec<_> = self.tables.iter().map(|t| t.clone()).collect();
+        tables.sort_by_key(|t| t.id);
+        Ok(tables)
+    }
+
+    async fn get_table(&self, id: TableId) -> Result<TableRecord, TableStoreError> {
+        self.tables
+            .get(&id)
+            .map(|t| t.clone())
+            .ok_or(TableStoreError::NotFound)
+    }
+
+    async fn insert_table(&self, table: &TableRecord) -> Result<(), TableStoreError> {
+        self.tables.insert(table.id, table.clone());
+        Ok(())
+    }
+
+    async fn update_table(&self, table: &TableRecord) -> Result<(), TableStoreError> {
+        let mut stored = self
+            .tables
+            .get_mut(&table.id)
+            .ok_or(TableStoreError::NotFound)?;
+        *stored = table.clone();
+        Ok(())
+    }
+}
+
+/// Tables in the `tables` relation of PostgreSQL.
 pub struct PostgresTableStore {
     pool: PgPool,
 }
@@ -24,10 +149,140 @@ impl PostgresTableStore {
     }
 }
 
+fn status_name(status: TableStatus) -> &'static str {
+    match status {
+        TableStatus::Open => "open",
+        TableStatus::Closing | TableStatus::Closed => "closed",
+    }
+}
+
+fn record_from<'r, R>(row: &'r R) -> Result<TableRecord, TableStoreError>
+where
+    R: Row,
+    &'r str: sqlx::ColumnIndex<R>,
+    String: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+{
+    let id: String = row.try_get("id")?;
+    let status: String = row.try_get("status")?;
+    let settings: String = row.try_get("settings")?;
+    Ok(TableRecord {
+        id: id
+            .parse()
+            .map_err(|e| TableStoreError::UnexpectedError(eyre!("bad table id {id}: {e}")))?,
+        name: row.try_get("name")?,
+        status: match status.as_str() {
+            "open" => TableStatus::Open,
+            _ => TableStatus::Closed,
+        },
+        settings: serde_json::from_str(&settings)
+            .map_err(|e| TableStoreError::UnexpectedError(e.into()))?,
+    })
+}
+
+fn settings_json(table: &TableRecord) -> Result<String, TableStoreError> {
+    serde_json::to_string(&table.settings).map_err(|e| TableStoreError::UnexpectedError(e.into()))
+}
+
+const SELECT_TABLES: &str = "SELECT id, name, status, settings FROM tables";
+const INSERT_TABLE: &str =
+    "INSERT INTO tables (id, name, status, settings) VALUES ($1, $2, $3, $4)";
+const UPDATE_TABLE: &str = "UPDATE tables SET name = $2, status = $3, settings = $4 WHERE id = $1";
+
 #[async_trait::async_trait]
 impl TableStore for PostgresTableStore {
-    async fn list_tables(&self) -> Result<Vec<()>, TableStoreError> {
-        let _ = &self.pool;
-        todo!("replaced in Task 15")
+    async fn list_tables(&self) -> Result<Vec<TableRecord>, TableStoreError> {
+        let rows = sqlx::query(&format!("{SELECT_TABLES} ORDER BY id"))
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(record_from).collect()
+    }
+
+    async fn get_table(&self, id: TableId) -> Result<TableRecord, TableStoreError> {
+        let row = sqlx::query(&format!("{SELECT_TABLES} WHERE id = $1"))
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(TableStoreError::NotFound)?;
+        record_from(&row)
+    }
+
+    async fn insert_table(&self, table: &TableRecord) -> Result<(), TableStoreError> {
+        sqlx::query(INSERT_TABLE)
+            .bind(table.id.to_string())
+            .bind(&table.name)
+            .bind(status_name(table.status))
+            .bind(settings_json(table)?)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_table(&self, table: &TableRecord) -> Result<(), TableStoreError> {
+        let updated = sqlx::query(UPDATE_TABLE)
+            .bind(table.id.to_string())
+            .bind(&table.name)
+            .bind(status_name(table.status))
+            .bind(settings_json(table)?)
+            .execute(&self.pool)
+            .await?;
+        if updated.rows_affected() == 0 {
+            return Err(TableStoreError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+/// Tables in the `tables` relation of SQLite.
+pub struct SqliteTableStore {
+    pool: SqlitePool,
+}
+
+impl SqliteTableStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl TableStore for SqliteTableStore {
+    async fn list_tables(&self) -> Result<Vec<TableRecord>, TableStoreError> {
+        let rows = sqlx::query(&format!("{SELECT_TABLES} ORDER BY id"))
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(record_from).collect()
+    }
+
+    async fn get_table(&self, id: TableId) -> Result<TableRecord, TableStoreError> {
+        let row = sqlx::query(&format!("{SELECT_TABLES} WHERE id = $1"))
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(TableStoreError::NotFound)?;
+        record_from(&row)
+    }
+
+    async fn insert_table(&self, table: &TableRecord) -> Result<(), TableStoreError> {
+        sqlx::query(INSERT_TABLE)
+            .bind(table.id.to_string())
+            .bind(&table.name)
+            .bind(status_name(table.status))
+            .bind(settings_json(table)?)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_table(&self, table: &TableRecord) -> Result<(), TableStoreError> {
+        let updated = sqlx::query(UPDATE_TABLE)
+            .bind(table.id.to_string())
+            .bind(&table.name)
+            .bind(status_name(table.status))
+            .bind(settings_json(table)?)
+            .execute(&self.pool)
+            .await?;
+        if updated.rows_affected() == 0 {
+            return Err(TableStoreError::NotFound);
+        }
+        Ok(())
     }
 }
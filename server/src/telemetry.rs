@@ -0,0 +1,109 @@
+//! Logging and distributed tracing.
+//!
+//! Spans carry `table`, `player` and `request_id` from the socket to the
+//! table. A command crosses the bus with a [`TraceContext`], so the worker
+//! handling it, possibly on another instance, continues the caller's trace.
+
+use std::collections::HashMap;
+
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+use serde::{Deserialize, Serialize};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::config::{TelemetrySettings, TraceExporter};
+
+/// Flushes spans not yet exported when dropped. Keep it alive until the
+/// process exits.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("trace exporter did not shut down cleanly: {e}");
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber: the log on stdout, filtered by
+/// `RUST_LOG`, plus the configured trace exporter.
+pub fn init(
+    settings: &TelemetrySettings,
+) -> Result<Telemetry, opentelemetry_otlp::ExporterBuildError> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let resource = Resource::builder()
+        .with_service_name(settings.service_name.clone())
+        .build();
+    let provider = match settings.exporter {
+        TraceExporter::None => None,
+        TraceExporter::Stdout => Some(
+            SdkTracerProvider::builder()
+                .with_resource(resource)
+                .with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+                .build(),
+        ),
+        TraceExporter::Otlp => {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(&settings.otlp_endpoint)
+                .build()?;
+            Some(
+                SdkTracerProvider::builder()
+                    .with_resource(resource)
+                    .with_batch_exporter(exporter)
+                    .build(),
+            )
+        }
+    };
+    let traces = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("blackjack-server"))
+    });
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "server=debug".into()),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .with(traces)
+        .init();
+    Ok(Telemetry { provider })
+}
+
+/// The W3C trace context of a span, in a form that survives the bus.
+/// Empty when tracing is off.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceContext(HashMap<String, String>);
+
+impl TraceContext {
+    /// The context of the span the caller is in.
+    pub fn current() -> Self {
+        let mut carrier = HashMap::new();
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&Span::current().context(), &mut carrier)
+        });
+        Self(carrier)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Makes `span` a child of the span this context was taken from.
+    pub fn attach(&self, span: &Span) {
+        if self.is_empty() {
+            return;
+        }
+        let parent = global::get_text_map_propagator(|propagator| propagator.extract(&self.0));
+        // Fails only when the span is disabled; then there is nothing to
+        // link.
+        let _ = span.set_parent(parent);
+    }
+}
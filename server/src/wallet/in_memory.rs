@@ -1,21 +1,158 @@
-use super::{Wallet, WalletError};
+use super::{
+    Posting, Reservation, ReservationStatus, Transaction, TransactionKind, Wallet, WalletError,
+};
 use async_trait::async_trait;
-use bj_core::domain::PlayerId;
+use bj_core::domain::{
+    engine::{game_id::GameId, Clock, SystemClock},
+    PlayerId, TableId,
+};
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    RwLock,
+};
+
+#[derive(Default)]
+struct Account {
+    balance: u32,
+    ledger: Vec<Transaction>,
+}
+
+impl Account {
+    fn record(&mut self, id: u64, amount: i64, posting: Posting) {
+        self.ledger.push(Transaction {
+            id,
+            kind: posting.kind,
+            amount,
+            balance_after: self.balance,
+            table_id: posting.table_id,
+            game_id: posting.game_id,
+            created_at: SystemClock.now().as_millis(),
+        });
+    }
+}
+
+/// One account as saved by [`InMemoryWallet::export`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedAccount {
+    pub player_id: PlayerId,
+    pub balance: u32,
+    pub ledger: Vec<Transaction>,
+}
+
+/// Everything an [`InMemoryWallet`] holds, for a state directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WalletState {
+    pub accounts: Vec<SavedAccount>,
+    pub reservations: Vec<Reservation>,
+    pub next_id: u64,
+}
 
 pub struct InMemoryWallet {
-    balances: DashMap<PlayerId, u32>,
+    accounts: DashMap<PlayerId, Account>,
+    /// Always locked before `accounts` when both are needed.
+    reservations: DashMap<(GameId, PlayerId), Reservation>,
+    next_id: AtomicU64,
+    /// Shared by every change, taken alone by [`export`](Self::export), so
+    /// an export never sees a stake taken from the balance but not yet held.
+    changing: RwLock<()>,
 }
 
 impl InMemoryWallet {
     pub fn new() -> Self {
         Self {
-            balances: DashMap::new(),
+            accounts: DashMap::new(),
+            reservations: DashMap::new(),
+            next_id: AtomicU64::new(1),
+            changing: RwLock::new(()),
         }
     }
 
-    pub fn seed(&self, player: PlayerId, amount: u32) {
-        self.balances.insert(player, amount);
+    /// A wallet holding what `state` recorded.
+    pub fn restore(state: WalletState) -> Self {
+        let wallet = Self::new();
+        for saved in state.accounts {
+            let account = Account {
+                balance: saved.balance,
+                ledger: saved.ledger,
+            };
+            wallet.accounts.insert(saved.player_id, account);
+        }
+        for reservation in state.reservations {
+            let key = (reservation.game_id, reservation.player_id);
+            wallet.reservations.insert(key, reservation);
+        }
+        wallet
+            .next_id
+            .store(state.next_id.max(1), Ordering::Relaxed);
+        wallet
+    }
+
+    /// Every account, its ledger and every reservation, as of one moment.
+    pub fn export(&self) -> WalletState {
+        let _no_changes = self.changing.write().unwrap();
+        WalletState {
+            accounts: self
+                .accounts
+                .iter()
+                .map(|a| SavedAccount {
+                    player_id: *a.key(),
+                    balance: a.balance,
+                    ledger: a.ledger.clone(),
+                })
+                .collect(),
+            reservations: self
+                .reservations
+                .iter()
+                .map(|r| r.value().clone())
+                .collect(),
+            next_id: self.next_id.load(Ordering::Relaxed),
+        }
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn current_balance(&self, player: PlayerId) -> Result<u32, WalletError> {
+        self.accounts
+            .get(&player)
+            .map(|a| a.balance)
+            .ok_or(WalletError::PlayerNotFound)
+    }
+
+    /// Moves a held reservation to `status`, crediting `amount` as `kind`.
+    fn settle(
+        &self,
+        player: PlayerId,
+        game_id: GameId,
+        status: ReservationStatus,
+        amount: impl FnOnce(&Reservation) -> u32,
+        kind: TransactionKind,
+    ) -> Result<u32, WalletError> {
+        let _changing = self.changing.read().unwrap();
+        let mut reservation = self
+            .reservations
+            .get_mut(&(game_id, player))
+            .ok_or(WalletError::ReservationNotFound)?;
+        match reservation.status {
+            ReservationStatus::Held => {}
+            s if s == status => return self.current_balance(player),
+            s => return Err(WalletError::ReservationSettled(s)),
+        }
+        let amount = amount(&reservation);
+        let mut account = self
+            .accounts
+            .get_mut(&player)
+            .ok_or(WalletError::PlayerNotFound)?;
+        if amount > 0 {
+            account.balance = account.balance.saturating_add(amount);
+            let posting = Posting::round(kind, reservation.table_id, game_id);
+            account.record(self.next_id(), i64::from(amount), posting);
+        }
+        reservation.status = status;
+        Ok(account.balance)
     }
 }
 
@@ -28,34 +165,133 @@ impl Default for InMemoryWallet {
 #[async_trait]
 impl Wallet for InMemoryWallet {
     async fn balance(&self, player: PlayerId) -> Result<u32, WalletError> {
-        self.balances
+        self.accounts
             .get(&player)
-            .map(|b| *b)
+            .map(|a| a.balance)
             .ok_or(WalletError::PlayerNotFound)
     }
 
-    async fn debit(&self, player: PlayerId, amount: u32) -> Result<u32, WalletError> {
-        let mut entry = self
-            .balances
+    async fn debit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError> {
+        let _changing = self.changing.read().unwrap();
+        let mut account = self
+            .accounts
             .get_mut(&player)
             .ok_or(WalletError::PlayerNotFound)?;
-        if *entry < amount {
+        if account.balance < amount {
             return Err(WalletError::InsufficientBalance {
-                balance: *entry,
+                balance: account.balance,
                 amount,
             });
         }
-        *entry -= amount;
-        Ok(*entry)
+        account.balance -= amount;
+        account.record(self.next_id(), -i64::from(amount), posting);
+        Ok(account.balance)
     }
 
-    async fn credit(&self, player: PlayerId, amount: u32) -> Result<u32, WalletError> {
-        let mut entry = self.balances.entry(player).or_insert(0);
-        *entry = entry.saturating_add(amount);
-        Ok(*entry)
+    async fn credit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError> {
+        let _changing = self.changing.read().unwrap();
+        let mut account = self.accounts.entry(player).or_default();
+        account.balance = account.balance.saturating_add(amount);
+        account.record(self.next_id(), i64::from(amount), posting);
+        Ok(account.balance)
     }
 
-    async fn set_balance(&self, player: PlayerId, amount: u32) {
-        self.balances.insert(player, amount);
+    async fn reserve(
+        &self,
+        player: PlayerId,
+        table_id: TableId,
+        game_id: GameId,
+        amount: u32,
+    ) -> Result<u32, WalletError> {
+        let _changing = self.changing.read().unwrap();
+        let slot = match self.reservations.entry((game_id, player)) {
+            dashmap::Entry::Occupied(held) if held.get().amount == amount => {
+                return self.current_balance(player);
+            }
+            dashmap::Entry::Occupied(held) => {
+                return Err(WalletError::ReservationConflict {
+                    held: held.get().amount,
+                    amount,
+                });
+            }
+            dashmap::Entry::Vacant(slot) => slot,
+        };
+        let mut account = self
+            .accounts
+            .get_mut(&player)
+            .ok_or(WalletError::PlayerNotFound)?;
+        if account.balance < amount {
+            return Err(WalletError::InsufficientBalance {
+                balance: account.balance,
+                amount,
+            });
+        }
+        account.balance -= amount;
+        let posting = Posting::round(TransactionKind::Bet, table_id, game_id);
+        account.record(self.next_id(), -i64::from(amount), posting);
+        slot.insert(Reservation {
+            player_id: player,
+            table_id,
+            game_id,
+            amount,
+            status: ReservationStatus::Held,
+        });
+        Ok(account.balance)
+    }
+
+    async fn commit(
+        &self,
+        player: PlayerId,
+        game_id: GameId,
+        payout: u32,
+    ) -> Result<u32, WalletError> {
+        self.settle(
+            player,
+            game_id,
+            ReservationStatus::Committed,
+            |_| payout,
+            TransactionKind::Payout,
+        )
+    }
+
+    async fn release(&self, player: PlayerId, game_id: GameId) -> Result<u32, WalletError> {
+        self.settle(
+            player,
+            game_id,
+            ReservationStatus::Released,
+            |r| r.amount,
+            TransactionKind::Refund,
+        )
+    }
+
+    async fn held(&self) -> Result<Vec<Reservation>, WalletError> {
+        Ok(self
+            .reservations
+            .iter()
+            .filter(|r| r.status == ReservationStatus::Held)
+            .map(|r| r.value().clone())
+            .collect())
+    }
+
+    async fn transactions(
+        &self,
+        player: PlayerId,
+        limit: usize,
+    ) -> Result<Vec<Transaction>, WalletError> {
+        let account = self
+            .accounts
+            .get(&player)
+            .ok_or(WalletError::PlayerNotFound)?;
+        Ok(account.ledger.iter().rev().take(limit).cloned().collect())
     }
 }
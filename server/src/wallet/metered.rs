@@ -0,0 +1,95 @@
+use std::{future::Future, sync::Arc, time::Instant};
+
+use async_trait::async_trait;
+use bj_core::domain::{engine::game_id::GameId, PlayerId, TableId};
+
+use super::{Posting, Reservation, Transaction, Wallet, WalletError};
+use crate::metrics::Metrics;
+
+/// Times every call to the wallet it wraps.
+pub struct MeteredWallet {
+    inner: Arc<dyn Wallet>,
+    metrics: Arc<Metrics>,
+}
+
+impl MeteredWallet {
+    pub fn new(inner: Arc<dyn Wallet>, metrics: Arc<Metrics>) -> Self {
+        Self { inner, metrics }
+    }
+
+    async fn timed<T>(&self, operation: &str, call: impl Future<Output = T>) -> T {
+        let started = Instant::now();
+        let result = call.await;
+        self.metrics.wallet_call(operation, started.elapsed());
+        result
+    }
+}
+
+#[async_trait]
+impl Wallet for MeteredWallet {
+    async fn balance(&self, player: PlayerId) -> Result<u32, WalletError> {
+        self.timed("balance", self.inner.balance(player)).await
+    }
+
+    async fn debit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError> {
+        self.timed("debit", self.inner.debit(player, amount, posting))
+            .await
+    }
+
+    async fn credit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError> {
+        self.timed("credit", self.inner.credit(player, amount, posting))
+            .await
+    }
+
+    async fn reserve(
+        &self,
+        player: PlayerId,
+        table_id: TableId,
+        game_id: GameId,
+        amount: u32,
+    ) -> Result<u32, WalletError> {
+        self.timed(
+            "reserve",
+            self.inner.reserve(player, table_id, game_id, amount),
+        )
+        .await
+    }
+
+    async fn commit(
+        &self,
+        player: PlayerId,
+        game_id: GameId,
+        payout: u32,
+    ) -> Result<u32, WalletError> {
+        self.timed("commit", self.inner.commit(player, game_id, payout))
+            .await
+    }
+
+    async fn release(&self, player: PlayerId, game_id: GameId) -> Result<u32, WalletError> {
+        self.timed("release", self.inner.release(player, game_id))
+            .await
+    }
+
+    async fn held(&self) -> Result<Vec<Reservation>, WalletError> {
+        self.timed("held", self.inner.held()).await
+    }
+
+    async fn transactions(
+        &self,
+        player: PlayerId,
+        limit: usize,
+    ) -> Result<Vec<Transaction>, WalletError> {
+        self.timed("transactions", self.inner.transactions(player, limit))
+            .await
+    }
+}
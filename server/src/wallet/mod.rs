@@ -1,8 +1,14 @@
 pub mod in_memory;
+pub mod metered;
+pub mod postgres;
+pub mod sqlite;
 
 use async_trait::async_trait;
-use bj_core::domain::PlayerId;
+use bj_core::domain::{engine::game_id::GameId, PlayerId, TableId};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 use thiserror::Error;
+use utoipa::ToSchema;
 
 #[derive(Debug, Error)]
 pub enum WalletError {
@@ -10,12 +16,227 @@ pub enum WalletError {
     PlayerNotFound,
     #[error("insufficient balance: have {balance}, need {amount}")]
     InsufficientBalance { balance: u32, amount: u32 },
+    #[error("no reservation for this round")]
+    ReservationNotFound,
+    #[error("reservation already {0}")]
+    ReservationSettled(ReservationStatus),
+    #[error("reservation of {held} already held, cannot reserve {amount}")]
+    ReservationConflict { held: u32, amount: u32 },
+    #[error("wallet backend error: {0}")]
+    Backend(String),
+}
+
+/// Why chips moved. Stored with every ledger entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionKind {
+    /// Stake lost to, or held by, the house for a round.
+    Bet,
+    /// Winnings and returned stake from a settled round.
+    Payout,
+    /// Stake returned because the round never settled.
+    Refund,
+    /// Chips given to a player outside play, e.g. a new account.
+    Grant,
+    /// Manual correction by an operator.
+    Adjustment,
+}
+
+impl TransactionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bet => "bet",
+            Self::Payout => "payout",
+            Self::Refund => "refund",
+            Self::Grant => "grant",
+            Self::Adjustment => "adjustment",
+        }
+    }
+}
+
+impl fmt::Display for TransactionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TransactionKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bet" => Ok(Self::Bet),
+            "payout" => Ok(Self::Payout),
+            "refund" => Ok(Self::Refund),
+            "grant" => Ok(Self::Grant),
+            "adjustment" => Ok(Self::Adjustment),
+            other => Err(format!("unknown transaction kind '{other}'")),
+        }
+    }
+}
+
+/// What a debit or credit is for: its kind and, for game money, the table
+/// and round (`GameId`) it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Posting {
+    pub kind: TransactionKind,
+    pub table_id: Option<TableId>,
+    pub game_id: Option<GameId>,
+}
+
+impl Posting {
+    /// Chips granted outside of play.
+    pub fn grant() -> Self {
+        Self {
+            kind: TransactionKind::Grant,
+            table_id: None,
+            game_id: None,
+        }
+    }
+
+    /// Chips moved by a round at a table.
+    pub fn round(kind: TransactionKind, table_id: TableId, game_id: GameId) -> Self {
+        Self {
+            kind,
+            table_id: Some(table_id),
+            game_id: Some(game_id),
+        }
+    }
+}
+
+/// One entry of a player's chip ledger.
+///
+/// `amount` is signed: debits are negative, credits positive. Entries are
+/// append-only; a mistake is corrected with a new `Adjustment`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Transaction {
+    /// Increases with every entry in the player's ledger.
+    pub id: u64,
+    pub kind: TransactionKind,
+    pub amount: i64,
+    pub balance_after: u32,
+    #[schema(value_type = Option<String>)]
+    pub table_id: Option<TableId>,
+    #[schema(value_type = Option<String>)]
+    pub game_id: Option<GameId>,
+    /// Unix time in milliseconds.
+    pub created_at: u64,
+}
+
+/// Lifecycle of the chips a player has staked on a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReservationStatus {
+    /// Taken from the balance, waiting for the round to settle.
+    Held,
+    /// Settled: the payout, if any, went back to the player.
+    Committed,
+    /// Returned to the player in full.
+    Released,
+}
+
+impl ReservationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Held => "held",
+            Self::Committed => "committed",
+            Self::Released => "released",
+        }
+    }
+}
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReservationStatus {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "held" => Ok(Self::Held),
+            "committed" => Ok(Self::Committed),
+            "released" => Ok(Self::Released),
+            other => Err(format!("unknown reservation status '{other}'")),
+        }
+    }
+}
+
+/// A player's stake on one round, identified by `(game_id, player_id)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reservation {
+    pub player_id: PlayerId,
+    pub table_id: TableId,
+    pub game_id: GameId,
+    pub amount: u32,
+    pub status: ReservationStatus,
 }
 
 #[async_trait]
 pub trait Wallet: Send + Sync {
     async fn balance(&self, player: PlayerId) -> Result<u32, WalletError>;
-    async fn debit(&self, player: PlayerId, amount: u32) -> Result<u32, WalletError>;
-    async fn credit(&self, player: PlayerId, amount: u32) -> Result<u32, WalletError>;
-    async fn set_balance(&self, player: PlayerId, amount: u32);
+    /// Takes `amount` from the player and records it in the ledger.
+    async fn debit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError>;
+    /// Gives `amount` to the player, opening an account if needed, and records
+    /// it in the ledger.
+    async fn credit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError>;
+    /// Escrows a stake for a round: `amount` leaves the balance (a `Bet`
+    /// entry) and is held until [`commit`](Self::commit) or
+    /// [`release`](Self::release). Returns the balance left to play with.
+    ///
+    /// Repeating a reservation for the same `(game_id, player)` and amount is
+    /// a no-op.
+    async fn reserve(
+        &self,
+        player: PlayerId,
+        table_id: TableId,
+        game_id: GameId,
+        amount: u32,
+    ) -> Result<u32, WalletError>;
+    /// Settles a held stake: `payout` (stake included, 0 for a loss) is
+    /// credited as a `Payout` entry. Committing twice is a no-op.
+    async fn commit(
+        &self,
+        player: PlayerId,
+        game_id: GameId,
+        payout: u32,
+    ) -> Result<u32, WalletError>;
+    /// Returns a held stake in full as a `Refund` entry. Releasing twice is a
+    /// no-op.
+    async fn release(&self, player: PlayerId, game_id: GameId) -> Result<u32, WalletError>;
+    /// Every reservation still waiting for its round to settle.
+    async fn held(&self) -> Result<Vec<Reservation>, WalletError>;
+    /// The player's most recent ledger entries, newest first.
+    async fn transactions(
+        &self,
+        player: PlayerId,
+        limit: usize,
+    ) -> Result<Vec<Transaction>, WalletError>;
+}
+
+/// Releases every held stake. Rounds do not survive a restart, so on boot a
+/// held reservation belongs to a round that will never settle.
+pub async fn release_held(wallet: &dyn Wallet) -> Result<usize, WalletError> {
+    let held = wallet.held().await?;
+    for r in &held {
+        wallet.release(r.player_id, r.game_id).await?;
+        tracing::warn!(
+            "table={} game={} player={} released abandoned stake of {}",
+            r.table_id,
+            r.game_id,
+            r.player_id,
+            r.amount
+        );
+    }
+    Ok(held.len())
 }
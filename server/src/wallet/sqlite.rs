@@ -0,0 +1,353 @@
+use super::{
+    Posting, Reservation, ReservationStatus, Transaction, TransactionKind, Wallet, WalletError,
+};
+use async_trait::async_trait;
+use bj_core::domain::{
+    engine::{game_id::GameId, Clock, SystemClock},
+    PlayerId, TableId,
+};
+use sqlx::{Row, Sqlite, SqlitePool};
+
+/// Chip ledger in SQLite, with the same tables as
+/// [`PostgresWallet`](super::postgres::PostgresWallet).
+///
+/// SQLite has no row locks: every write opens an `IMMEDIATE` transaction,
+/// which takes the database write lock up front, so operations on the
+/// wallet are serialized.
+pub struct SqliteWallet {
+    pool: SqlitePool,
+}
+
+impl SqliteWallet {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+async fn append(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    player: PlayerId,
+    amount: i64,
+    balance_after: i64,
+    posting: Posting,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO wallet_transactions \
+         (player_id, id, kind, amount, balance_after, table_id, game_id, created_at) \
+         SELECT $1, COALESCE(MAX(id), 0) + 1, $2, $3, $4, $5, $6, $7 \
+         FROM wallet_transactions WHERE player_id = $1",
+    )
+    .bind(player.to_string())
+    .bind(posting.kind.as_str())
+    .bind(amount)
+    .bind(balance_after)
+    .bind(posting.table_id.map(|t| t.to_string()))
+    .bind(posting.game_id.map(|g| g.to_string()))
+    .bind(now_millis())
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+fn now_millis() -> i64 {
+    SystemClock.now().as_millis() as i64
+}
+
+/// The player's balance, read inside a write transaction.
+async fn read_balance(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    player: PlayerId,
+) -> Result<i64, WalletError> {
+    let balance: Option<i64> =
+        sqlx::query_scalar("SELECT balance FROM wallet_accounts WHERE player_id = $1")
+            .bind(player.to_string())
+            .fetch_optional(&mut **tx)
+            .await?;
+    balance.ok_or(WalletError::PlayerNotFound)
+}
+
+async fn set_balance(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    player: PlayerId,
+    balance: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE wallet_accounts SET balance = $2 WHERE player_id = $1")
+        .bind(player.to_string())
+        .bind(balance)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+impl SqliteWallet {
+    async fn write(&self) -> Result<sqlx::Transaction<'static, Sqlite>, sqlx::Error> {
+        self.pool.begin_with("BEGIN IMMEDIATE").await
+    }
+
+    /// Moves a held reservation to `status`, crediting `amount` (or the
+    /// stake, if `None`) as `kind`.
+    async fn settle(
+        &self,
+        player: PlayerId,
+        game_id: GameId,
+        status: ReservationStatus,
+        amount: Option<u32>,
+        kind: TransactionKind,
+    ) -> Result<u32, WalletError> {
+        let mut tx = self.write().await?;
+        let balance = read_balance(&mut tx, player).await?;
+        let row = sqlx::query(
+            "SELECT table_id, amount, status FROM wallet_reservations \
+             WHERE game_id = $1 AND player_id = $2",
+        )
+        .bind(game_id.to_string())
+        .bind(player.to_string())
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(WalletError::ReservationNotFound)?;
+
+        let current: ReservationStatus = row
+            .try_get::<String, _>("status")?
+            .parse()
+            .map_err(decode)?;
+        match current {
+            ReservationStatus::Held => {}
+            s if s == status => return Ok(to_u32(balance)),
+            s => return Err(WalletError::ReservationSettled(s)),
+        }
+        let table_id: TableId = row
+            .try_get::<String, _>("table_id")?
+            .parse()
+            .map_err(decode)?;
+        let amount = match amount {
+            Some(amount) => i64::from(amount),
+            None => row.try_get("amount")?,
+        };
+
+        let after = balance + amount;
+        if amount > 0 {
+            set_balance(&mut tx, player, after).await?;
+            let posting = Posting::round(kind, table_id, game_id);
+            append(&mut tx, player, amount, after, posting).await?;
+        }
+        sqlx::query(
+            "UPDATE wallet_reservations SET status = $3, payout = $4, settled_at = $5 \
+             WHERE game_id = $1 AND player_id = $2",
+        )
+        .bind(game_id.to_string())
+        .bind(player.to_string())
+        .bind(status.as_str())
+        .bind(amount)
+        .bind(now_millis())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(to_u32(after))
+    }
+}
+
+fn to_u32(balance: i64) -> u32 {
+    u32::try_from(balance).unwrap_or(u32::MAX)
+}
+
+fn decode(e: impl ToString) -> WalletError {
+    WalletError::Backend(e.to_string())
+}
+
+#[async_trait]
+impl Wallet for SqliteWallet {
+    async fn balance(&self, player: PlayerId) -> Result<u32, WalletError> {
+        let balance: Option<i64> =
+            sqlx::query_scalar("SELECT balance FROM wallet_accounts WHERE player_id = $1")
+                .bind(player.to_string())
+                .fetch_optional(&self.pool)
+                .await?;
+        balance.map(to_u32).ok_or(WalletError::PlayerNotFound)
+    }
+
+    async fn debit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError> {
+        let mut tx = self.write().await?;
+        let balance = read_balance(&mut tx, player).await?;
+        if balance < i64::from(amount) {
+            return Err(WalletError::InsufficientBalance {
+                balance: to_u32(balance),
+                amount,
+            });
+        }
+        let after = balance - i64::from(amount);
+        set_balance(&mut tx, player, after).await?;
+        append(&mut tx, player, -i64::from(amount), after, posting).await?;
+        tx.commit().await?;
+        Ok(to_u32(after))
+    }
+
+    async fn credit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError> {
+        let mut tx = self.write().await?;
+        let after: i64 = sqlx::query_scalar(
+            "INSERT INTO wallet_accounts (player_id, balance) VALUES ($1, $2) \
+             ON CONFLICT (player_id) DO UPDATE \
+             SET balance = wallet_accounts.balance + EXCLUDED.balance \
+             RETURNING balance",
+        )
+        .bind(player.to_string())
+        .bind(i64::from(amount))
+        .fetch_one(&mut *tx)
+        .await?;
+        append(&mut tx, player, i64::from(amount), after, posting).await?;
+        tx.commit().await?;
+        Ok(to_u32(after))
+    }
+
+    async fn reserve(
+        &self,
+        player: PlayerId,
+        table_id: TableId,
+        game_id: GameId,
+        amount: u32,
+    ) -> Result<u32, WalletError> {
+        let mut tx = self.write().await?;
+        let balance = read_balance(&mut tx, player).await?;
+        let held: Option<i64> = sqlx::query_scalar(
+            "SELECT amount FROM wallet_reservations WHERE game_id = $1 AND player_id = $2",
+        )
+        .bind(game_id.to_string())
+        .bind(player.to_string())
+        .fetch_optional(&mut *tx)
+        .await?;
+        match held {
+            Some(held) if held == i64::from(amount) => return Ok(to_u32(balance)),
+            Some(held) => {
+                return Err(WalletError::ReservationConflict {
+                    held: to_u32(held),
+                    amount,
+                })
+            }
+            None => {}
+        }
+        if balance < i64::from(amount) {
+            return Err(WalletError::InsufficientBalance {
+                balance: to_u32(balance),
+                amount,
+            });
+        }
+
+        let after = balance - i64::from(amount);
+        set_balance(&mut tx, player, after).await?;
+        sqlx::query(
+            "INSERT INTO wallet_reservations \
+             (game_id, player_id, table_id, amount, status, created_at) \
+             VALUES ($1, $2, $3, $4, 'held', $5)",
+        )
+        .bind(game_id.to_string())
+        .bind(player.to_string())
+        .bind(table_id.to_string())
+        .bind(i64::from(amount))
+        .bind(now_millis())
+        .execute(&mut *tx)
+        .await?;
+        let posting = Posting::round(TransactionKind::Bet, table_id, game_id);
+        append(&mut tx, player, -i64::from(amount), after, posting).await?;
+        tx.commit().await?;
+        Ok(to_u32(after))
+    }
+
+    async fn commit(
+        &self,
+        player: PlayerId,
+        game_id: GameId,
+        payout: u32,
+    ) -> Result<u32, WalletError> {
+        self.settle(
+            player,
+            game_id,
+            ReservationStatus::Committed,
+            Some(payout),
+            TransactionKind::Payout,
+        )
+        .await
+    }
+
+    async fn release(&self, player: PlayerId, game_id: GameId) -> Result<u32, WalletError> {
+        self.settle(
+            player,
+            game_id,
+            ReservationStatus::Released,
+            None,
+            TransactionKind::Refund,
+        )
+        .await
+    }
+
+    async fn held(&self) -> Result<Vec<Reservation>, WalletError> {
+        let rows = sqlx::query(
+            "SELECT player_id, table_id, game_id, amount FROM wallet_reservations \
+             WHERE status = 'held'",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(Reservation {
+                    player_id: row
+                        .try_get::<String, _>("player_id")?
+                        .parse()
+                        .map_err(decode)?,
+                    table_id: row
+                        .try_get::<String, _>("table_id")?
+                        .parse()
+                        .map_err(decode)?,
+                    game_id: row
+                        .try_get::<String, _>("game_id")?
+                        .parse()
+                        .map_err(decode)?,
+                    amount: to_u32(row.try_get("amount")?),
+                    status: ReservationStatus::Held,
+                })
+            })
+            .collect()
+    }
+
+    async fn transactions(
+        &self,
+        player: PlayerId,
+        limit: usize,
+    ) -> Result<Vec<Transaction>, WalletError> {
+        self.balance(player).await?;
+        let rows = sqlx::query(
+            "SELECT id, kind, amount, balance_after, table_id, game_id, created_at \
+             FROM wallet_transactions WHERE player_id = $1 \
+             ORDER BY id DESC LIMIT $2",
+        )
+        .bind(player.to_string())
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let kind: String = row.try_get("kind")?;
+                let table_id: Option<String> = row.try_get("table_id")?;
+                let game_id: Option<String> = row.try_get("game_id")?;
+                Ok(Transaction {
+                    id: row.try_get::<i64, _>("id")? as u64,
+                    kind: kind.parse::<TransactionKind>().map_err(decode)?,
+                    amount: row.try_get("amount")?,
+                    balance_after: to_u32(row.try_get("balance_after")?),
+                    table_id: table_id.map(|t| t.parse()).transpose().map_err(decode)?,
+                    game_id: game_id.map(|g| g.parse()).transpose().map_err(decode)?,
+                    created_at: row.try_get::<i64, _>("created_at")? as u64,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,300 @@
+//! The audit trail: what tables record, and reading it back from each sink.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use bj_core::domain::{
+    engine::{
+        command::{
+            dealer::{DealerAction, PlayHand},
+            player::{JoinTable, PlaceBet, PlayerAction, TakeSeat},
+            system::{NewRound, PlayerTimeout, SystemCommand},
+            GameCommand,
+        },
+        event::payload::EventPayload,
+        TableTimers, Timestamp,
+    },
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    audit::{
+        in_memory::InMemoryAuditLog, jsonl::JsonlAuditLog, postgres::PostgresAuditLog,
+        sqlite::SqliteAuditLog, writer::AuditWriter, AuditQuery, AuditRecord, AuditSink, Origin,
+        Outcome, Sender,
+    },
+    session::{
+        in_memory::InMemoryGameSession, table_actor::TableActorConfig, GameSession, RequestId,
+    },
+    store::TableRecord,
+    wallet::{in_memory::InMemoryWallet, Posting, Wallet},
+};
+
+const TICK: Duration = Duration::from_millis(40);
+
+fn everything(player_id: PlayerId) -> AuditQuery {
+    AuditQuery {
+        player_id,
+        from: Timestamp::from_millis(0),
+        to: Timestamp::from_millis(u64::MAX),
+        limit: 1000,
+    }
+}
+
+async fn send(
+    session: &dyn GameSession,
+    table_id: TableId,
+    player: PlayerId,
+    request: u64,
+    action: PlayerAction,
+) {
+    let _ = session
+        .send_command(table_id, player, RequestId(request), action)
+        .await;
+}
+
+#[tokio::test]
+async fn a_table_records_each_command_and_its_outcome() {
+    let audit = Arc::new(InMemoryAuditLog::new());
+    let wallet: Arc<dyn Wallet> = Arc::new(InMemoryWallet::new());
+    let session: Arc<dyn GameSession> = InMemoryGameSession::with_config(
+        wallet.clone(),
+        TableActorConfig {
+            timers: TableTimers {
+                betting_window: TICK,
+                player_turn: TICK,
+                round_delay: TICK,
+            },
+            audit: audit.clone(),
+            ..TableActorConfig::default()
+        },
+    );
+    let table = TableRecord {
+        id: TableId::new(),
+        name: "Audited".into(),
+        status: TableStatus::Open,
+        settings: TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        },
+    };
+    session.open_table(&table).await.unwrap();
+    let id = table.id;
+    let player = PlayerId::new();
+    wallet
+        .credit(player, 10_000, Posting::grant())
+        .await
+        .unwrap();
+
+    send(
+        session.as_ref(),
+        id,
+        player,
+        1,
+        PlayerAction::JoinTable(JoinTable { player_id: player }),
+    )
+    .await;
+    send(
+        session.as_ref(),
+        id,
+        player,
+        2,
+        PlayerAction::TakeSeat(TakeSeat {
+            player_id: player,
+            seat: None,
+        }),
+    )
+    .await;
+    send(
+        session.as_ref(),
+        id,
+        player,
+        3,
+        PlayerAction::PlaceBet(PlaceBet {
+            player_id: player,
+            amount: 5,
+        }),
+    )
+    .await;
+
+    let records = audit.query(&everything(player)).await.unwrap();
+    let requests: Vec<_> = records.iter().map(|r| r.request_id).collect();
+    assert_eq!(requests, vec![Some(1), Some(2), Some(3)]);
+    assert!(records
+        .iter()
+        .all(|r| r.sender == Sender::Player && r.table_id == id));
+    assert!(matches!(
+        &records[0].outcome,
+        Outcome::Accepted { events } if !events.is_empty()
+    ));
+    assert!(matches!(records[2].outcome, Outcome::Rejected { .. }));
+
+    // Leave the turn to run out; a blackjack has no turn, so it may take a
+    // few rounds.
+    let mut timed_out = None;
+    for request in 4..20 {
+        send(
+            session.as_ref(),
+            id,
+            player,
+            request,
+            PlayerAction::PlaceBet(PlaceBet {
+                player_id: player,
+                amount: 10,
+            }),
+        )
+        .await;
+        tokio::time::sleep(TICK * 6).await;
+        let records = audit.query(&everything(player)).await.unwrap();
+        timed_out = records.into_iter().find(|r| {
+            matches!(
+                r.command,
+                GameCommand::System(SystemCommand::PlayerTimeout(_))
+            )
+        });
+        if timed_out.is_some() {
+            break;
+        }
+    }
+    let timed_out = timed_out.expect("the player's turn timed out");
+    assert_eq!(timed_out.sender, Sender::System);
+    assert_eq!(timed_out.request_id, None);
+    assert!(matches!(
+        &timed_out.outcome,
+        Outcome::Accepted { events }
+            if events.iter().any(|e| matches!(e.payload, EventPayload::PlayerDecisionTaken { .. }))
+    ));
+
+    // Dealing a table with an automatic dealer is turned down, and recorded.
+    let dealer = PlayerId::new();
+    assert!(session
+        .dealer_command(id, dealer, RequestId(9), DealerAction::PlayHand(PlayHand))
+        .await
+        .is_err());
+    let records = audit.query(&everything(dealer)).await.unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].sender, Sender::Dealer);
+    assert!(matches!(records[0].outcome, Outcome::Rejected { .. }));
+}
+
+fn record(player_id: PlayerId, at: u64) -> AuditRecord {
+    AuditRecord::new(
+        Timestamp::from_millis(at),
+        TableId::new(),
+        Origin::player(player_id, RequestId(at)),
+        GameCommand::System(SystemCommand::NewRound(NewRound)),
+        Outcome::Rejected {
+            error: "not now".into(),
+        },
+    )
+}
+
+/// Appends a few records and reads them back by player and time range.
+async fn check_queries(sink: &dyn AuditSink) {
+    let player = PlayerId::new();
+    let other = PlayerId::new();
+    for at in [100, 200, 300, 400] {
+        sink.append(&record(player, at)).await.unwrap();
+    }
+    sink.append(&record(other, 250)).await.unwrap();
+    // A timeout is filed under the player whose turn it was.
+    let timeout = AuditRecord::new(
+        Timestamp::from_millis(350),
+        TableId::new(),
+        Origin::system(),
+        GameCommand::System(SystemCommand::PlayerTimeout(PlayerTimeout {
+            player_id: player,
+        })),
+        Outcome::Accepted { events: vec![] },
+    );
+    sink.append(&timeout).await.unwrap();
+
+    let query = AuditQuery {
+        player_id: player,
+        from: Timestamp::from_millis(200),
+        to: Timestamp::from_millis(400),
+        limit: 10,
+    };
+    let found: Vec<_> = sink
+        .query(&query)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| (r.at.as_millis(), r.sender))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (200, Sender::Player),
+            (300, Sender::Player),
+            (350, Sender::System)
+        ]
+    );
+
+    let first_two = sink.query(&AuditQuery { limit: 2, ..query }).await.unwrap();
+    assert_eq!(first_two.len(), 2);
+    assert_eq!(first_two[0].at.as_millis(), 200);
+}
+
+#[tokio::test]
+async fn the_in_memory_trail_is_queried_by_player_and_time() {
+    check_queries(&InMemoryAuditLog::new()).await;
+}
+
+#[tokio::test]
+async fn the_jsonl_trail_is_queried_by_player_and_time() {
+    let path = std::env::temp_dir()
+        .join(format!("bj-audit-{}", ulid::Ulid::new()))
+        .join("audit.jsonl");
+    check_queries(&JsonlAuditLog::open(&path).unwrap()).await;
+
+    // Reopening appends to what is there.
+    let player = PlayerId::new();
+    JsonlAuditLog::open(&path)
+        .unwrap()
+        .append(&record(player, 1))
+        .await
+        .unwrap();
+    let reopened = JsonlAuditLog::open(&path).unwrap();
+    assert_eq!(reopened.query(&everything(player)).await.unwrap().len(), 1);
+    let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+    assert_eq!(lines, 7);
+}
+
+#[tokio::test]
+async fn the_sqlite_trail_is_queried_by_player_and_time() {
+    check_queries(&SqliteAuditLog::new(common::sqlite_pool().await)).await;
+}
+
+#[tokio::test]
+async fn the_postgres_trail_is_queried_by_player_and_time() {
+    let Some(pool) = common::postgres_pool().await else {
+        eprintln!("DATABASE_URL not set; skipping");
+        return;
+    };
+    check_queries(&PostgresAuditLog::new(pool)).await;
+}
+
+#[tokio::test]
+async fn the_writer_appends_in_the_background() {
+    let path = std::env::temp_dir()
+        .join(format!("bj-audit-{}", ulid::Ulid::new()))
+        .join("audit.jsonl");
+    let writer = AuditWriter::spawn(Arc::new(JsonlAuditLog::open(&path).unwrap()));
+    // Queries see whatever was queued before them.
+    check_queries(&writer).await;
+
+    let player = PlayerId::new();
+    for at in 0..500 {
+        writer.append(&record(player, at)).await.unwrap();
+    }
+    writer.flush().await;
+    let written = JsonlAuditLog::open(&path).unwrap();
+    let records = written.query(&everything(player)).await.unwrap();
+    let order: Vec<u64> = records.iter().map(|r| r.at.as_millis()).collect();
+    assert_eq!(order, (0..500).collect::<Vec<_>>());
+}
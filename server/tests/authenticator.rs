@@ -0,0 +1,337 @@
+//! Account behaviour shared by every `Authenticator` implementation (SQLite
+//! on a temp file; Postgres when `DATABASE_URL` is set), plus password
+//! storage checks for `PostgresAuthenticator`.
+
+mod common;
+
+use bj_core::domain::PlayerId;
+use server::{
+    auth::{
+        postgres::PostgresAuthenticator, sqlite::SqliteAuthenticator, AuthError, AuthPayload,
+        Authenticator, InMemoryAuthenticator, Password, PasswordHasher, Role,
+    },
+    config::Argon2Settings,
+};
+use ulid::Ulid;
+
+/// Cheap parameters so the suite stays fast in debug builds.
+fn cheap(iterations: u32) -> PasswordHasher {
+    PasswordHasher::new(&Argon2Settings {
+        memory_kib: 1024,
+        iterations,
+        parallelism: 1,
+    })
+    .unwrap()
+}
+
+async fn postgres() -> Option<PostgresAuthenticator> {
+    let pool = common::postgres_pool().await?;
+    Some(PostgresAuthenticator::new(pool, cheap(1)))
+}
+
+async fn sqlite() -> SqliteAuthenticator {
+    SqliteAuthenticator::new(common::sqlite_pool().await, cheap(1))
+}
+
+/// Usernames are unique per run so tests can share a database.
+fn username() -> String {
+    format!("user-{}", Ulid::new())
+}
+
+fn login(username: &str, password: &str) -> AuthPayload {
+    AuthPayload {
+        username: username.to_string(),
+        password: Password::new(password.to_string()),
+    }
+}
+
+async fn login_returns_registered_player(auth: &dyn Authenticator) {
+    let name = username();
+    let registered = auth.register(&login(&name, "hunter22")).await.unwrap();
+    let first = auth.authenticate(&login(&name, "hunter22")).await.unwrap();
+    let again = auth.authenticate(&login(&name, "hunter22")).await.unwrap();
+    assert_eq!((first, again), (registered, registered));
+}
+
+async fn wrong_password_is_rejected(auth: &dyn Authenticator) {
+    let name = username();
+    auth.register(&login(&name, "hunter22")).await.unwrap();
+    assert!(matches!(
+        auth.authenticate(&login(&name, "hunter23")).await,
+        Err(AuthError::WrongPassword)
+    ));
+}
+
+/// A typo on the login screen must not open a second account.
+async fn unknown_username_is_rejected(auth: &dyn Authenticator) {
+    let name = username();
+    assert!(matches!(
+        auth.authenticate(&login(&name, "hunter22")).await,
+        Err(AuthError::UnknownUser)
+    ));
+    assert!(auth.register(&login(&name, "hunter22")).await.is_ok());
+}
+
+async fn duplicate_registration_is_rejected(auth: &dyn Authenticator) {
+    let name = username();
+    auth.register(&login(&name, "hunter22")).await.unwrap();
+    assert!(matches!(
+        auth.register(&login(&name, "other-pw-1")).await,
+        Err(AuthError::UsernameTaken)
+    ));
+    // The original password still works.
+    assert!(auth.authenticate(&login(&name, "hunter22")).await.is_ok());
+}
+
+async fn username_policy_is_enforced(auth: &dyn Authenticator) {
+    for name in ["ab", "1player", "has space", "émile", "Admin", "dealer"] {
+        assert!(
+            matches!(
+                auth.register(&login(name, "hunter22")).await,
+                Err(AuthError::InvalidUsername(_))
+            ),
+            "{name}"
+        );
+    }
+    let too_long = format!("u{}", "x".repeat(32));
+    assert!(matches!(
+        auth.register(&login(&too_long, "hunter22")).await,
+        Err(AuthError::InvalidUsername(_))
+    ));
+}
+
+async fn password_policy_is_enforced(auth: &dyn Authenticator) {
+    let name = username();
+    let too_long = format!("a1{}", "x".repeat(127));
+    let containing_name = format!("{name}-1");
+    for password in [
+        "hunt22",
+        "password",
+        "12345678",
+        &too_long,
+        &containing_name,
+    ] {
+        assert!(
+            matches!(
+                auth.register(&login(&name, password)).await,
+                Err(AuthError::WeakPassword(_))
+            ),
+            "{password}"
+        );
+    }
+    assert!(auth
+        .register(&login(&name, "correct horse 1"))
+        .await
+        .is_ok());
+}
+
+async fn seed_user_is_idempotent(auth: &dyn Authenticator) {
+    let name = username();
+    let seeded = auth.seed_user(&name, "famly1234").await.unwrap();
+    assert_eq!(auth.seed_user(&name, "other").await.unwrap(), seeded);
+    assert_eq!(
+        auth.authenticate(&login(&name, "famly1234")).await.unwrap(),
+        seeded
+    );
+}
+
+async fn lookup_username_resolves_player(auth: &dyn Authenticator) {
+    let name = username();
+    let pid = auth.seed_user(&name, "famly1234").await.unwrap();
+    assert_eq!(auth.lookup_username(pid).await.unwrap(), Some(name));
+    assert_eq!(auth.lookup_username(PlayerId::new()).await.unwrap(), None);
+}
+
+async fn new_accounts_are_players(auth: &dyn Authenticator) {
+    let registered = auth
+        .register(&login(&username(), "hunter22"))
+        .await
+        .unwrap();
+    let seeded = auth.seed_user(&username(), "famly1234").await.unwrap();
+    assert_eq!(auth.role(registered).await.unwrap(), Role::Player);
+    assert_eq!(auth.role(seeded).await.unwrap(), Role::Player);
+    assert!(matches!(
+        auth.role(PlayerId::new()).await,
+        Err(AuthError::UnknownUser)
+    ));
+}
+
+async fn set_role_changes_the_role(auth: &dyn Authenticator) {
+    let name = username();
+    let pid = auth.seed_user(&name, "famly1234").await.unwrap();
+    assert_eq!(auth.set_role(&name, Role::Dealer).await.unwrap(), pid);
+    assert_eq!(auth.role(pid).await.unwrap(), Role::Dealer);
+    auth.set_role(&name, Role::Admin).await.unwrap();
+    assert_eq!(auth.role(pid).await.unwrap(), Role::Admin);
+    assert!(matches!(
+        auth.set_role(&username(), Role::Admin).await,
+        Err(AuthError::UnknownUser)
+    ));
+}
+
+/// Postgres shares one database between tests, where an admin may already
+/// exist; the other backends start empty.
+async fn first_admin_is_bootstrapped_once(auth: &dyn Authenticator) {
+    let secret = Password::new("s3cret-admin".to_string());
+    let taken = username();
+    let existing = auth.seed_user(&taken, "famly1234").await.unwrap();
+    if !auth.has_admin().await.unwrap() {
+        // An existing account is never promoted.
+        assert!(matches!(
+            auth.bootstrap_admin(&taken, &secret).await,
+            Err(AuthError::UsernameTaken)
+        ));
+        assert!(matches!(
+            auth.bootstrap_admin(&username(), &Password::new("short".into()))
+                .await,
+            Err(AuthError::WeakPassword(_))
+        ));
+        let name = username();
+        let admin = auth
+            .bootstrap_admin(&name, &secret)
+            .await
+            .unwrap()
+            .expect("no admin yet");
+        assert_eq!(auth.role(admin).await.unwrap(), Role::Admin);
+        assert_eq!(
+            auth.authenticate(&login(&name, "s3cret-admin"))
+                .await
+                .unwrap(),
+            admin
+        );
+    }
+    assert!(auth.has_admin().await.unwrap());
+    assert_eq!(
+        auth.bootstrap_admin(&username(), &secret).await.unwrap(),
+        None
+    );
+    assert_eq!(auth.bootstrap_admin(&taken, &secret).await.unwrap(), None);
+    assert_eq!(auth.role(existing).await.unwrap(), Role::Player);
+}
+
+macro_rules! authenticator_tests {
+    ($($name:ident),* $(,)?) => {
+        mod in_memory {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(&super::InMemoryAuthenticator::new(super::cheap(1))).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    if let Some(auth) = super::postgres().await {
+                        super::$name(&auth).await;
+                    }
+                }
+            )*
+        }
+
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(&super::sqlite().await).await;
+                }
+            )*
+        }
+    };
+}
+
+authenticator_tests!(
+    login_returns_registered_player,
+    wrong_password_is_rejected,
+    unknown_username_is_rejected,
+    duplicate_registration_is_rejected,
+    username_policy_is_enforced,
+    password_policy_is_enforced,
+    seed_user_is_idempotent,
+    lookup_username_resolves_player,
+    new_accounts_are_players,
+    set_role_changes_the_role,
+    first_admin_is_bootstrapped_once,
+);
+
+async fn stored_hash(pool: &sqlx::PgPool, name: &str) -> String {
+    sqlx::query_scalar("SELECT password_hash FROM users WHERE username = $1")
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn postgres_stores_argon2id_hashes() {
+    let Some(pool) = common::postgres_pool().await else {
+        return;
+    };
+    let auth = PostgresAuthenticator::new(pool.clone(), cheap(1));
+    let name = username();
+    auth.seed_user(&name, "famly1234").await.unwrap();
+
+    let hash = stored_hash(&pool, &name).await;
+    assert!(hash.starts_with("$argon2id$"), "{hash}");
+    assert!(!hash.contains("famly1234"));
+}
+
+#[tokio::test]
+async fn postgres_rehashes_when_parameters_change() {
+    let Some(pool) = common::postgres_pool().await else {
+        return;
+    };
+    let name = username();
+    let old = PostgresAuthenticator::new(pool.clone(), cheap(1));
+    let pid = old.seed_user(&name, "famly1234").await.unwrap();
+    let before = stored_hash(&pool, &name).await;
+
+    let new = PostgresAuthenticator::new(pool.clone(), cheap(2));
+    assert_eq!(
+        new.authenticate(&login(&name, "famly1234")).await.unwrap(),
+        pid
+    );
+    let after = stored_hash(&pool, &name).await;
+    assert_ne!(before, after);
+    assert!(after.contains("t=2"), "{after}");
+    assert!(!cheap(2).needs_rehash(&after));
+
+    // A failed login never touches the stored hash.
+    assert!(new.authenticate(&login(&name, "wrong")).await.is_err());
+    assert_eq!(stored_hash(&pool, &name).await, after);
+}
+
+#[test]
+fn no_password_matches_the_dummy_hash() {
+    let hasher = cheap(2);
+    let dummy = hasher.dummy_hash();
+    assert!(!hasher.needs_rehash(&dummy), "{dummy}");
+    for password in ["", "famly1234", "hunter22"] {
+        assert!(!hasher
+            .verify(&Password::new(password.to_string()), &dummy)
+            .unwrap());
+    }
+}
+
+#[tokio::test]
+async fn an_unknown_username_takes_as_long_as_a_wrong_password() {
+    let auth = InMemoryAuthenticator::new(cheap(16));
+    let name = username();
+    auth.register(&login(&name, "hunter22")).await.unwrap();
+
+    let started = std::time::Instant::now();
+    let wrong = auth.authenticate(&login(&name, "hunter23")).await;
+    let wrong_took = started.elapsed();
+    let started = std::time::Instant::now();
+    let unknown = auth.authenticate(&login(&username(), "hunter22")).await;
+    let unknown_took = started.elapsed();
+
+    assert!(matches!(wrong, Err(AuthError::WrongPassword)));
+    assert!(matches!(unknown, Err(AuthError::UnknownUser)));
+    assert!(
+        unknown_took * 2 >= wrong_took,
+        "unknown {unknown_took:?}, wrong password {wrong_took:?}"
+    );
+}
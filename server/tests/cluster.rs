@@ -0,0 +1,228 @@
+//! Two instances sharing tables through PostgreSQL. Skipped without
+//! `DATABASE_URL`.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use bj_core::domain::{
+    engine::command::player::{JoinTable, PlayerAction},
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    bus::PostgresBus,
+    cluster::{Coordinator, PartitionLeases},
+    session::{
+        in_memory::InMemoryGameSession, table_actor::TableActorConfig, GameSession, RequestId,
+    },
+    store::{InMemoryTableStore, TableRecord, TableStore},
+    wallet::{in_memory::InMemoryWallet, Wallet},
+};
+use sqlx::PgPool;
+use ulid::Ulid;
+
+const PARTITIONS: u32 = 4;
+const TTL: Duration = Duration::from_secs(1);
+
+struct Instance {
+    session: Arc<InMemoryGameSession>,
+    coordinator: Coordinator,
+}
+
+async fn join(
+    pool: &PgPool,
+    cluster: &str,
+    wallet: Arc<dyn Wallet>,
+    tables: Arc<dyn TableStore>,
+) -> Instance {
+    let instance = Ulid::new().to_string();
+    let bus = PostgresBus::connect(pool.clone(), cluster, &instance, PARTITIONS)
+        .await
+        .unwrap();
+    let session = InMemoryGameSession::standby(
+        wallet.clone(),
+        TableActorConfig::default(),
+        bus.clone().into(),
+    );
+    let coordinator = Coordinator::new(
+        PartitionLeases::new(pool.clone(), cluster, &instance, TTL),
+        session.clone(),
+        wallet,
+        tables,
+        bus,
+        PARTITIONS,
+    );
+    Instance {
+        session,
+        coordinator,
+    }
+}
+
+fn table() -> TableRecord {
+    TableRecord {
+        id: TableId::new(),
+        name: "Shared".into(),
+        status: TableStatus::Open,
+        settings: TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        },
+    }
+}
+
+/// Waits for a notification from the other instance to arrive.
+async fn lists(session: &InMemoryGameSession, table_id: TableId) -> bool {
+    for _ in 0..50 {
+        if session.list_tables().await.iter().any(|t| t.id == table_id) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn instances_share_tables_and_take_over_partitions() {
+    let Some(pool) = common::postgres_pool().await else {
+        return;
+    };
+    let cluster = format!("test-{}", Ulid::new());
+    let wallet: Arc<dyn Wallet> = Arc::new(InMemoryWallet::new());
+    let tables: Arc<dyn TableStore> = Arc::new(InMemoryTableStore::new());
+
+    let mut a = join(&pool, &cluster, wallet.clone(), tables.clone()).await;
+    a.coordinator.tick().await;
+    let mut b = join(&pool, &cluster, wallet.clone(), tables.clone()).await;
+    b.coordinator.tick().await;
+    assert!((0..PARTITIONS).all(|p| a.coordinator.owns(p) && !b.coordinator.owns(p)));
+
+    // Opened through B, hosted by A, listed by both.
+    let table = table();
+    tables.insert_table(&table).await.unwrap();
+    b.session.open_table(&table).await.unwrap();
+    assert!(lists(&a.session, table.id).await);
+    assert!(lists(&b.session, table.id).await);
+
+    // B forwards commands and follows the table's events.
+    let player = PlayerId::new();
+    let mut events = b.session.subscribe(table.id).await.unwrap();
+    b.session
+        .send_command(
+            table.id,
+            player,
+            RequestId(1),
+            PlayerAction::JoinTable(JoinTable { player_id: player }),
+        )
+        .await
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(2), events.recv())
+        .await
+        .expect("no event reached the other instance")
+        .unwrap();
+    let snapshot = b.session.snapshot(table.id, player).await.unwrap();
+    assert!(snapshot.observers.contains(&player));
+
+    // A stops without giving its leases up, as if it had crashed.
+    for partition in 0..PARTITIONS {
+        a.session.stop_partition(partition).await;
+    }
+    drop(a);
+    tokio::time::sleep(TTL + Duration::from_millis(200)).await;
+    b.coordinator.tick().await;
+    assert!((0..PARTITIONS).all(|p| b.coordinator.owns(p)));
+
+    // The table starts over on B; the round in play is lost.
+    let snapshot = b.session.snapshot(table.id, player).await.unwrap();
+    assert!(!snapshot.observers.contains(&player));
+    assert!(lists(&b.session, table.id).await);
+
+    for table in ["partition_leases", "table_directory", "bus_commands"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE cluster = $1"))
+            .bind(&cluster)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn released_partitions_are_taken_over_at_once() {
+    let Some(pool) = common::postgres_pool().await else {
+        return;
+    };
+    let cluster = format!("test-{}", Ulid::new());
+    let wallet: Arc<dyn Wallet> = Arc::new(InMemoryWallet::new());
+    let tables: Arc<dyn TableStore> = Arc::new(InMemoryTableStore::new());
+
+    let mut a = join(&pool, &cluster, wallet.clone(), tables.clone()).await;
+    a.coordinator.tick().await;
+    let mut b = join(&pool, &cluster, wallet.clone(), tables.clone()).await;
+    b.coordinator.tick().await;
+    assert!((0..PARTITIONS).all(|p| !b.coordinator.owns(p)));
+
+    // A shuts down cleanly; B need not wait for the leases to expire.
+    a.coordinator.release_all().await;
+    assert!((0..PARTITIONS).all(|p| !a.coordinator.owns(p)));
+    b.coordinator.tick().await;
+    assert!((0..PARTITIONS).all(|p| b.coordinator.owns(p)));
+
+    for table in ["partition_leases", "table_directory", "bus_commands"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE cluster = $1"))
+            .bind(&cluster)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn unrenewed_partitions_stop_before_their_lease_runs_out() {
+    let Some(pool) = common::postgres_pool().await else {
+        return;
+    };
+    let Some(lease_pool) = common::postgres_pool().await else {
+        return;
+    };
+    let cluster = format!("test-{}", Ulid::new());
+    let instance = Ulid::new().to_string();
+    let wallet: Arc<dyn Wallet> = Arc::new(InMemoryWallet::new());
+    let bus = PostgresBus::connect(pool.clone(), &cluster, &instance, PARTITIONS)
+        .await
+        .unwrap();
+    let session = InMemoryGameSession::standby(
+        wallet.clone(),
+        TableActorConfig::default(),
+        bus.clone().into(),
+    );
+    let mut coordinator = Coordinator::new(
+        PartitionLeases::new(lease_pool.clone(), &cluster, &instance, TTL),
+        session,
+        wallet,
+        Arc::new(InMemoryTableStore::new()),
+        bus,
+        PARTITIONS,
+    );
+    coordinator.tick().await;
+    assert!((0..PARTITIONS).all(|p| coordinator.owns(p)));
+
+    // The leases can no longer be renewed. Two thirds into their term the
+    // partitions stop, before anyone else may take them.
+    lease_pool.close().await;
+    coordinator.tick().await;
+    assert!((0..PARTITIONS).all(|p| coordinator.owns(p)));
+    tokio::time::sleep(TTL * 2 / 3).await;
+    coordinator.tick().await;
+    assert!((0..PARTITIONS).all(|p| !coordinator.owns(p)));
+
+    for table in ["partition_leases", "table_directory", "bus_commands"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE cluster = $1"))
+            .bind(&cluster)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,239 @@
+//! Command delivery through the buses, and a session running on the
+//! file-backed log.
+
+use std::{fs::OpenOptions, io::Write, path::PathBuf, sync::Arc, time::Duration};
+
+use bj_core::domain::{
+    engine::command::player::{JoinTable, PlayerAction},
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    bus::{
+        partition_of, BusError, Buses, CommandBus, CommandConsumer, Delivery, FileCommandBus,
+        InMemoryCommandBus,
+    },
+    session::{
+        in_memory::InMemoryGameSession,
+        table_actor::{TableActorConfig, TableCommand},
+        GameSession, RequestId,
+    },
+    store::TableRecord,
+    wallet::in_memory::InMemoryWallet,
+};
+use ulid::Ulid;
+
+const PARTITIONS: u32 = 4;
+
+fn log_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("bj-bus-{}", Ulid::new()))
+}
+
+fn snapshot() -> TableCommand {
+    TableCommand::Snapshot {
+        requesting_player: PlayerId::new(),
+    }
+}
+
+async fn next_offset(consumer: &mut Box<dyn CommandConsumer>) -> Option<u64> {
+    tokio::time::timeout(Duration::from_millis(100), consumer.next())
+        .await
+        .ok()
+        .flatten()
+        .map(|d| d.offset)
+}
+
+async fn send_three(bus: &dyn CommandBus, table_id: TableId) {
+    for _ in 0..3 {
+        // Nobody consumes yet, so nobody answers.
+        drop(bus.send(table_id, snapshot()).await.unwrap());
+    }
+}
+
+#[test]
+fn tables_stay_on_one_partition() {
+    for _ in 0..100 {
+        let table_id = TableId::new();
+        let partition = partition_of(table_id, PARTITIONS);
+        assert!(partition < PARTITIONS);
+        assert_eq!(partition_of(table_id, PARTITIONS), partition);
+    }
+}
+
+#[tokio::test]
+async fn in_memory_bus_delivers_in_order() {
+    let bus = InMemoryCommandBus::new(PARTITIONS);
+    let table_id = TableId::new();
+    let partition = partition_of(table_id, PARTITIONS);
+    send_three(&bus, table_id).await;
+
+    let mut consumer = bus.subscribe(partition).unwrap();
+    assert!(matches!(
+        bus.subscribe(partition),
+        Err(BusError::PartitionTaken(_))
+    ));
+    assert!(matches!(
+        bus.subscribe(PARTITIONS),
+        Err(BusError::UnknownPartition(_))
+    ));
+    for offset in 1..=3 {
+        assert_eq!(next_offset(&mut consumer).await, Some(offset));
+    }
+    assert_eq!(next_offset(&mut consumer).await, None);
+
+    // The partition is free again once its consumer is gone.
+    drop(consumer);
+    assert!(bus.subscribe(partition).is_ok());
+}
+
+#[tokio::test]
+async fn file_log_redelivers_unacked_commands() {
+    let dir = log_dir();
+    let bus = FileCommandBus::open(&dir, PARTITIONS).unwrap();
+    let table_id = TableId::new();
+    let partition = partition_of(table_id, PARTITIONS);
+    send_three(&bus, table_id).await;
+
+    let mut consumer = bus.subscribe(partition).unwrap();
+    assert!(matches!(
+        bus.subscribe(partition),
+        Err(BusError::PartitionTaken(_))
+    ));
+    assert_eq!(next_offset(&mut consumer).await, Some(1));
+    consumer.ack(1).await.unwrap();
+    assert_eq!(next_offset(&mut consumer).await, Some(2));
+    // Taken but never acknowledged, as by a worker that crashed.
+    drop(consumer);
+
+    let mut consumer = bus.subscribe(partition).unwrap();
+    assert_eq!(next_offset(&mut consumer).await, Some(2));
+    assert_eq!(next_offset(&mut consumer).await, Some(3));
+    assert_eq!(next_offset(&mut consumer).await, None);
+    drop(consumer);
+    drop(bus);
+
+    // A restart delivers the same commands again.
+    let bus = FileCommandBus::open(&dir, PARTITIONS).unwrap();
+    let mut consumer = bus.subscribe(partition).unwrap();
+    assert_eq!(next_offset(&mut consumer).await, Some(2));
+    assert_eq!(next_offset(&mut consumer).await, Some(3));
+    consumer.ack(3).await.unwrap();
+    drop(consumer);
+    drop(bus);
+
+    // Once acknowledged, they are gone, and offsets keep counting.
+    let bus = FileCommandBus::open(&dir, PARTITIONS).unwrap();
+    let mut consumer = bus.subscribe(partition).unwrap();
+    assert_eq!(next_offset(&mut consumer).await, None);
+    drop(bus.send(table_id, snapshot()).await.unwrap());
+    assert_eq!(next_offset(&mut consumer).await, Some(4));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn file_log_skips_commands_it_already_applied() {
+    let dir = log_dir();
+    let bus = FileCommandBus::open(&dir, PARTITIONS).unwrap();
+    let table_id = TableId::new();
+    let partition = partition_of(table_id, PARTITIONS);
+    drop(bus.send(table_id, snapshot()).await.unwrap());
+
+    let mut consumer = bus.subscribe(partition).unwrap();
+    let delivery = consumer.next().await.unwrap();
+    consumer.ack(delivery.offset).await.unwrap();
+    drop(consumer);
+    drop(bus);
+
+    // The same envelope lands in the log again, further on.
+    let again = Delivery {
+        offset: delivery.offset + 1,
+        envelope: delivery.envelope,
+    };
+    let log = dir.join(format!("partition-{partition}.log"));
+    let mut file = OpenOptions::new().append(true).open(log).unwrap();
+    writeln!(file, "{}", serde_json::to_string(&again).unwrap()).unwrap();
+
+    let bus = FileCommandBus::open(&dir, PARTITIONS).unwrap();
+    let mut consumer = bus.subscribe(partition).unwrap();
+    assert_eq!(next_offset(&mut consumer).await, None);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn session_runs_on_the_file_log() {
+    let dir = log_dir();
+    let session = InMemoryGameSession::with_buses(
+        Arc::new(InMemoryWallet::new()),
+        TableActorConfig::default(),
+        Buses::local(Arc::new(FileCommandBus::open(&dir, PARTITIONS).unwrap())),
+    )
+    .unwrap();
+
+    let mut ids = vec![];
+    for n in 0..8 {
+        let table = TableRecord {
+            id: TableId::new(),
+            name: format!("Table {n}"),
+            status: TableStatus::Open,
+            settings: TableSettings {
+                min_bet: 10,
+                max_bet: 500,
+                max_players: 5,
+                max_observers: 10,
+                dealer: DealerMode::Automatic,
+                dealers: vec![],
+            },
+        };
+        session.open_table(&table).await.unwrap();
+        ids.push(table.id);
+    }
+    ids.sort();
+    let listed: Vec<TableId> = session.list_tables().await.iter().map(|t| t.id).collect();
+    assert_eq!(listed, ids);
+
+    let player = PlayerId::new();
+    let table_id = ids[0];
+    let mut events = session.subscribe(table_id).await.unwrap();
+    session
+        .send_command(
+            table_id,
+            player,
+            RequestId(1),
+            PlayerAction::JoinTable(JoinTable { player_id: player }),
+        )
+        .await
+        .unwrap();
+    assert!(events.recv().await.is_ok());
+    let snapshot = session.snapshot(table_id, player).await.unwrap();
+    assert!(snapshot.observers.contains(&player));
+
+    // Every command was handled and acknowledged: nothing is left for the
+    // next run.
+    drop(session);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let bus = FileCommandBus::open(&dir, PARTITIONS).unwrap();
+    for partition in 0..PARTITIONS {
+        let mut consumer = bus.subscribe(partition).unwrap();
+        assert_eq!(next_offset(&mut consumer).await, None);
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn a_stopped_partition_is_free_once_the_stop_returns() {
+    let bus = Arc::new(InMemoryCommandBus::new(PARTITIONS));
+    let session = InMemoryGameSession::with_buses(
+        Arc::new(InMemoryWallet::new()),
+        TableActorConfig::default(),
+        Buses::local(bus.clone()),
+    )
+    .unwrap();
+
+    for partition in 0..PARTITIONS {
+        session.stop_partition(partition).await;
+        // The worker has exited and given its consumer back.
+        assert!(bus.subscribe(partition).is_ok());
+    }
+}
@@ -0,0 +1,32 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+/// A migrated pool for `DATABASE_URL`, or `None` when the variable is unset
+/// and Postgres-backed tests should be skipped.
+pub async fn postgres_pool() -> Option<sqlx::PgPool> {
+    let url = std::env::var("DATABASE_URL").ok()?;
+    let pool = sqlx::PgPool::connect(&url)
+        .await
+        .expect("failed to connect to DATABASE_URL");
+    sqlx::migrate!("../migrations")
+        .run(&pool)
+        .await
+        .expect("failed to migrate");
+    Some(pool)
+}
+
+/// A fresh database file in the system temp directory.
+pub fn sqlite_path() -> String {
+    std::env::temp_dir()
+        .join(format!("bj-test-{}.db", ulid::Ulid::new()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// A migrated pool on a fresh SQLite file.
+pub async fn sqlite_pool() -> sqlx::SqlitePool {
+    server::store::connect_sqlite(&sqlite_path(), 5)
+        .await
+        .expect("failed to open sqlite database")
+}
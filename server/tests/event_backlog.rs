@@ -0,0 +1,200 @@
+//! The per-table event backlog behind `Resume`.
+
+use std::{sync::Arc, time::Duration};
+
+use bj_core::domain::{
+    engine::{
+        command::player::{JoinTable, PlaceBet, PlayerAction, TakeSeat},
+        event::{payload::EventPayload, GameEvent},
+        TableTimers,
+    },
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    session::{
+        in_memory::InMemoryGameSession, table_actor::TableActorConfig, Catchup, GameSession,
+        RequestId,
+    },
+    store::TableRecord,
+    wallet::{in_memory::InMemoryWallet, Posting, Wallet},
+};
+
+struct Table {
+    session: Arc<dyn GameSession>,
+    wallet: Arc<dyn Wallet>,
+    id: TableId,
+}
+
+async fn table(config: TableActorConfig) -> Table {
+    let wallet: Arc<dyn Wallet> = Arc::new(InMemoryWallet::new());
+    let session: Arc<dyn GameSession> = InMemoryGameSession::with_config(wallet.clone(), config);
+    let id = TableId::new();
+    session
+        .open_table(&TableRecord {
+            id,
+            name: "Backlog".into(),
+            status: TableStatus::Open,
+            settings: TableSettings {
+                min_bet: 10,
+                max_bet: 500,
+                max_players: 5,
+                max_observers: 10,
+                dealer: DealerMode::Automatic,
+                dealers: vec![],
+            },
+        })
+        .await
+        .unwrap();
+    Table {
+        session,
+        wallet,
+        id,
+    }
+}
+
+impl Table {
+    async fn act(&self, player: PlayerId, action: PlayerAction) {
+        self.session
+            .send_command(self.id, player, RequestId(1), action)
+            .await
+            .unwrap();
+    }
+
+    async fn seat(&self) -> PlayerId {
+        let player = PlayerId::new();
+        self.wallet
+            .credit(player, 1000, Posting::grant())
+            .await
+            .unwrap();
+        self.act(
+            player,
+            PlayerAction::JoinTable(JoinTable { player_id: player }),
+        )
+        .await;
+        self.act(
+            player,
+            PlayerAction::TakeSeat(TakeSeat {
+                player_id: player,
+                seat: None,
+            }),
+        )
+        .await;
+        player
+    }
+
+    async fn last_seq(&self, player: PlayerId) -> u64 {
+        self.session
+            .snapshot(self.id, player)
+            .await
+            .unwrap()
+            .last_seq
+    }
+}
+
+fn seqs(events: &[GameEvent]) -> Vec<u64> {
+    events.iter().map(|e| e.event_seq_id.0).collect()
+}
+
+#[tokio::test]
+async fn resume_replays_events_after_last_seq() {
+    let table = table(TableActorConfig::default()).await;
+    let player = table.seat().await;
+    let seen = table.last_seq(player).await;
+
+    let other = table.seat().await;
+    let now = table.last_seq(player).await;
+
+    let Catchup::Events(missed) = table.session.resume(table.id, player, seen).await.unwrap()
+    else {
+        panic!("gap is within the backlog");
+    };
+    assert_eq!(seqs(&missed), ((seen + 1)..=now).collect::<Vec<_>>());
+    assert!(missed.iter().any(
+        |e| matches!(e.payload, EventPayload::PlayerJoined { player, .. } if player == other)
+    ));
+
+    let Catchup::Events(none) = table.session.resume(table.id, player, now).await.unwrap() else {
+        panic!("an up-to-date client needs nothing");
+    };
+    assert!(none.is_empty());
+}
+
+#[tokio::test]
+async fn resume_past_the_backlog_sends_a_snapshot() {
+    let table = table(TableActorConfig {
+        event_backlog: 2,
+        ..TableActorConfig::default()
+    })
+    .await;
+    let player = table.seat().await;
+    table.seat().await;
+    let now = table.last_seq(player).await;
+    assert!(now > 3);
+
+    let Catchup::Snapshot(snap) = table.session.resume(table.id, player, 0).await.unwrap() else {
+        panic!("gap is older than the backlog");
+    };
+    assert_eq!(snap.last_seq, now);
+    assert_eq!(snap.players.len(), 2);
+
+    // A client ahead of the table cannot be patched up either.
+    assert!(matches!(
+        table.session.resume(table.id, player, now + 10).await,
+        Ok(Catchup::Snapshot(_))
+    ));
+}
+
+#[tokio::test]
+async fn resume_is_only_for_players_at_the_table() {
+    let table = table(TableActorConfig::default()).await;
+    table.seat().await;
+
+    assert!(table
+        .session
+        .resume(table.id, PlayerId::new(), 0)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn sequence_keeps_counting_into_the_next_round() {
+    let tick = Duration::from_millis(20);
+    let table = table(TableActorConfig {
+        timers: TableTimers {
+            betting_window: tick,
+            player_turn: tick,
+            round_delay: tick,
+        },
+        ..TableActorConfig::default()
+    })
+    .await;
+    let player = table.seat().await;
+    let mut rx = table.session.subscribe(table.id).await.unwrap();
+    table
+        .act(
+            player,
+            PlayerAction::PlaceBet(PlaceBet {
+                player_id: player,
+                amount: 10,
+            }),
+        )
+        .await;
+
+    let mut events = vec![];
+    let reset = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let event = rx.recv().await.unwrap();
+            let done = matches!(event.payload, EventPayload::RoundReset { .. });
+            events.push(event);
+            if done {
+                break;
+            }
+        }
+    })
+    .await;
+    assert!(reset.is_ok(), "round never reset");
+
+    let seqs = seqs(&events);
+    assert!(seqs.windows(2).all(|w| w[1] == w[0] + 1), "{seqs:?}");
+    assert!(table.last_seq(player).await >= *seqs.last().unwrap());
+}
@@ -0,0 +1,59 @@
+//! Pings, their pongs, and connections that stop answering.
+
+use std::time::Duration;
+
+use server::{
+    config::WebSocketSettings,
+    heartbeat::{Heartbeat, Unresponsive},
+};
+
+fn heartbeat(interval_ms: u64, missed_pongs: u32) -> Heartbeat {
+    Heartbeat {
+        interval: Some(Duration::from_millis(interval_ms)),
+        missed_pongs,
+    }
+}
+
+#[tokio::test]
+async fn pings_come_at_the_interval_and_pongs_are_timed() {
+    let mut pinger = heartbeat(30, 3).pinger();
+    let started = tokio::time::Instant::now();
+    pinger.due().await;
+    assert!(started.elapsed() >= Duration::from_millis(30));
+
+    let first = pinger.ping().unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let rtt = pinger.pong(&first).unwrap();
+    assert!(rtt >= Duration::from_millis(20), "{rtt:?}");
+    // Answered once only; pongs we never pinged for are ignored.
+    assert_eq!(pinger.pong(&first), None);
+    assert_eq!(pinger.pong(b"unsolicited"), None);
+}
+
+#[tokio::test]
+async fn a_connection_that_stops_answering_is_unresponsive() {
+    let mut pinger = heartbeat(10, 2).pinger();
+    let first = pinger.ping().unwrap();
+    let second = pinger.ping().unwrap();
+    assert_eq!(pinger.ping(), Err(Unresponsive { missed: 2 }));
+
+    // A late pong for the newest ping answers the older one too.
+    assert!(pinger.pong(&second).is_some());
+    assert_eq!(pinger.pong(&first), None);
+    assert!(pinger.ping().is_ok());
+}
+
+#[tokio::test]
+async fn pings_can_be_turned_off() {
+    let heartbeat = Heartbeat::new(&WebSocketSettings {
+        ping_interval_secs: 0,
+        ..WebSocketSettings::default()
+    });
+    assert_eq!(heartbeat.idle_timeout(), None);
+    let mut pinger = heartbeat.pinger();
+    let due = tokio::time::timeout(Duration::from_millis(50), pinger.due()).await;
+    assert!(due.is_err(), "no ping is ever due");
+
+    let defaults = Heartbeat::default();
+    assert_eq!(defaults.idle_timeout(), Some(Duration::from_secs(45)));
+}
@@ -0,0 +1,264 @@
+//! Tables with a manual dealer: the house waits for a dealer to drive each
+//! round instead of running it on timers.
+
+use std::{sync::Arc, time::Duration};
+
+use bj_core::domain::{
+    engine::{
+        command::{
+            dealer::{DealInitialCards, DealerAction, OpenBetting, PlayHand, SettleRound},
+            player::{JoinTable, PlaceBet, PlayerAction, TakeSeat},
+        },
+        phase::Phase,
+        TableTimers,
+    },
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    session::{
+        in_memory::InMemoryGameSession, table_actor::TableActorConfig, GameSession, RequestId,
+        SessionError,
+    },
+    store::TableRecord,
+    wallet::{in_memory::InMemoryWallet, Posting, Wallet},
+};
+
+const TICK: Duration = Duration::from_millis(20);
+
+struct Table {
+    session: Arc<dyn GameSession>,
+    wallet: Arc<dyn Wallet>,
+    record: TableRecord,
+    /// The table's assigned dealer.
+    dealer: PlayerId,
+}
+
+/// A table whose timers would all have run out within a few ticks.
+async fn table(dealer: DealerMode) -> Table {
+    let wallet: Arc<dyn Wallet> = Arc::new(InMemoryWallet::new());
+    let config = TableActorConfig {
+        timers: TableTimers {
+            betting_window: TICK,
+            player_turn: TICK,
+            round_delay: TICK,
+        },
+        ..TableActorConfig::default()
+    };
+    let session: Arc<dyn GameSession> = InMemoryGameSession::with_config(wallet.clone(), config);
+    let assigned = PlayerId::new();
+    let record = TableRecord {
+        id: TableId::new(),
+        name: "Manual".into(),
+        status: TableStatus::Open,
+        settings: TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer,
+            dealers: vec![assigned],
+        },
+    };
+    session.open_table(&record).await.unwrap();
+    Table {
+        session,
+        wallet,
+        record,
+        dealer: assigned,
+    }
+}
+
+impl Table {
+    fn id(&self) -> TableId {
+        self.record.id
+    }
+
+    async fn act(&self, player: PlayerId, action: PlayerAction) {
+        self.session
+            .send_command(self.id(), player, RequestId(1), action)
+            .await
+            .unwrap();
+    }
+
+    async fn deal(&self, action: DealerAction) -> Result<(), SessionError> {
+        self.deal_as(self.dealer, action).await
+    }
+
+    async fn deal_as(&self, dealer: PlayerId, action: DealerAction) -> Result<(), SessionError> {
+        self.session
+            .dealer_command(self.id(), dealer, RequestId(7), action)
+            .await
+            .map(|ack| assert_eq!(ack.request_id, RequestId(7)))
+    }
+
+    async fn seat(&self) -> PlayerId {
+        let player = PlayerId::new();
+        self.wallet
+            .credit(player, 1000, Posting::grant())
+            .await
+            .unwrap();
+        self.act(
+            player,
+            PlayerAction::JoinTable(JoinTable { player_id: player }),
+        )
+        .await;
+        self.act(
+            player,
+            PlayerAction::TakeSeat(TakeSeat {
+                player_id: player,
+                seat: None,
+            }),
+        )
+        .await;
+        player
+    }
+
+    async fn bet(&self, player: PlayerId) {
+        self.act(
+            player,
+            PlayerAction::PlaceBet(PlaceBet {
+                player_id: player,
+                amount: 10,
+            }),
+        )
+        .await;
+    }
+
+    async fn phase(&self, player: PlayerId) -> Phase {
+        self.session
+            .snapshot(self.id(), player)
+            .await
+            .unwrap()
+            .phase
+    }
+}
+
+#[tokio::test]
+async fn manual_dealer_drives_the_round() {
+    let table = table(DealerMode::Manual).await;
+    let player = table.seat().await;
+    table.bet(player).await;
+
+    // The betting window has long closed; nobody deals but the dealer.
+    tokio::time::sleep(TICK * 5).await;
+    assert_eq!(table.phase(player).await, Phase::WaitingForBets);
+
+    table
+        .deal(DealerAction::DealInitialCards(DealInitialCards))
+        .await
+        .unwrap();
+    if let Phase::PlayerTurn(_) = table.phase(player).await {
+        // The player's turn still times out; the dealer plays on from there.
+        tokio::time::sleep(TICK * 5).await;
+    }
+    assert_eq!(table.phase(player).await, Phase::DealerTurn);
+    table.deal(DealerAction::PlayHand(PlayHand)).await.unwrap();
+    table
+        .deal(DealerAction::SettleRound(SettleRound))
+        .await
+        .unwrap();
+
+    tokio::time::sleep(TICK * 5).await;
+    assert_eq!(table.phase(player).await, Phase::Finished);
+    // The settled round still shows the bet, next to the settled balance.
+    let snapshot = table.session.snapshot(table.id(), player).await.unwrap();
+    let seated = &snapshot.players[0];
+    assert_eq!(seated.bet, Some(10));
+    assert_eq!(seated.balance, table.wallet.balance(player).await.unwrap());
+    table
+        .deal(DealerAction::OpenBetting(OpenBetting))
+        .await
+        .unwrap();
+    assert_eq!(table.phase(player).await, Phase::WaitingForBets);
+    let snapshot = table.session.snapshot(table.id(), player).await.unwrap();
+    assert_eq!(snapshot.players[0].bet, None);
+}
+
+#[tokio::test]
+async fn out_of_order_dealer_steps_are_rejected() {
+    let table = table(DealerMode::Manual).await;
+    let player = table.seat().await;
+    table.bet(player).await;
+
+    let err = table
+        .deal(DealerAction::SettleRound(SettleRound))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, SessionError::CommandRejected(_)), "{err}");
+    assert_eq!(table.phase(player).await, Phase::WaitingForBets);
+}
+
+#[tokio::test]
+async fn only_assigned_dealers_deal() {
+    let table = table(DealerMode::Manual).await;
+    let player = table.seat().await;
+    table.bet(player).await;
+
+    let other = PlayerId::new();
+    let err = table
+        .deal_as(other, DealerAction::DealInitialCards(DealInitialCards))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, SessionError::CommandRejected(reason) if reason.contains("not a dealer")),
+        "{err}"
+    );
+    assert_eq!(table.phase(player).await, Phase::WaitingForBets);
+
+    // Assigned mid-round, they take over.
+    let mut reassigned = table.record.clone();
+    reassigned.settings.dealers = vec![other];
+    table.session.update_table(&reassigned).await.unwrap();
+    table
+        .deal_as(other, DealerAction::DealInitialCards(DealInitialCards))
+        .await
+        .unwrap();
+    assert!(table.deal(DealerAction::PlayHand(PlayHand)).await.is_err());
+}
+
+#[tokio::test]
+async fn automatic_tables_refuse_dealer_commands() {
+    let table = table(DealerMode::Automatic).await;
+    let player = table.seat().await;
+    table.bet(player).await;
+
+    let err = table
+        .deal(DealerAction::DealInitialCards(DealInitialCards))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, SessionError::CommandRejected(reason) if reason.contains("automatic")),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn dealer_mode_changes_only_between_rounds() {
+    let table = table(DealerMode::Manual).await;
+    let player = table.seat().await;
+    table.bet(player).await;
+
+    let mut automatic = table.record.clone();
+    automatic.settings.dealer = DealerMode::Automatic;
+    let err = table.session.update_table(&automatic).await.unwrap_err();
+    assert!(matches!(err, SessionError::CommandRejected(_)), "{err}");
+
+    // Played out by hand, then handed to the house between rounds.
+    table
+        .deal(DealerAction::DealInitialCards(DealInitialCards))
+        .await
+        .unwrap();
+    if let Phase::PlayerTurn(_) = table.phase(player).await {
+        tokio::time::sleep(TICK * 5).await;
+    }
+    table.deal(DealerAction::PlayHand(PlayHand)).await.unwrap();
+    table
+        .deal(DealerAction::SettleRound(SettleRound))
+        .await
+        .unwrap();
+    table.session.update_table(&automatic).await.unwrap();
+
+    // The house opens the next round straight away.
+    tokio::time::sleep(TICK * 5).await;
+    assert_eq!(table.phase(player).await, Phase::WaitingForBets);
+}
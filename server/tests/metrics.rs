@@ -0,0 +1,170 @@
+//! What a round played on a manual-dealer table shows on `/metrics`.
+
+use std::sync::Arc;
+
+use bj_core::domain::{
+    engine::{
+        command::{
+            dealer::{DealInitialCards, DealerAction, PlayHand, SettleRound},
+            player::{JoinTable, PlaceBet, PlayerAction, Stand, TakeSeat},
+        },
+        phase::Phase,
+    },
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    metrics::Metrics,
+    session::{
+        in_memory::InMemoryGameSession, table_actor::TableActorConfig, GameSession, RequestId,
+    },
+    store::TableRecord,
+    wallet::{in_memory::InMemoryWallet, metered::MeteredWallet, Posting, Wallet},
+};
+
+/// The value of the sample named `series`, labels included.
+fn sample(text: &str, series: &str) -> Option<f64> {
+    text.lines()
+        .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+        .map(|value| value.parse().unwrap())
+}
+
+async fn act(session: &dyn GameSession, table_id: TableId, player: PlayerId, action: PlayerAction) {
+    // Rejections are what some of these are for.
+    let _ = session
+        .send_command(table_id, player, RequestId(1), action)
+        .await;
+}
+
+async fn deal(session: &dyn GameSession, table: &TableRecord, action: DealerAction) {
+    session
+        .dealer_command(table.id, table.settings.dealers[0], RequestId(2), action)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn a_round_is_counted() {
+    let metrics = Arc::new(Metrics::new());
+    let wallet: Arc<dyn Wallet> = Arc::new(MeteredWallet::new(
+        Arc::new(InMemoryWallet::new()),
+        metrics.clone(),
+    ));
+    let session: Arc<dyn GameSession> = InMemoryGameSession::with_config(
+        wallet.clone(),
+        TableActorConfig {
+            metrics: metrics.clone(),
+            ..TableActorConfig::default()
+        },
+    );
+    let table = TableRecord {
+        id: TableId::new(),
+        name: "Metered".into(),
+        status: TableStatus::Open,
+        settings: TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Manual,
+            dealers: vec![PlayerId::new()],
+        },
+    };
+    session.open_table(&table).await.unwrap();
+    let id = table.id;
+
+    let player = PlayerId::new();
+    wallet.credit(player, 1000, Posting::grant()).await.unwrap();
+    act(
+        session.as_ref(),
+        id,
+        player,
+        PlayerAction::JoinTable(JoinTable { player_id: player }),
+    )
+    .await;
+    act(
+        session.as_ref(),
+        id,
+        player,
+        PlayerAction::TakeSeat(TakeSeat {
+            player_id: player,
+            seat: None,
+        }),
+    )
+    .await;
+    for amount in [5, 10] {
+        act(
+            session.as_ref(),
+            id,
+            player,
+            PlayerAction::PlaceBet(PlaceBet {
+                player_id: player,
+                amount,
+            }),
+        )
+        .await;
+    }
+    deal(
+        session.as_ref(),
+        &table,
+        DealerAction::DealInitialCards(DealInitialCards),
+    )
+    .await;
+    let snapshot = session.snapshot(id, player).await.unwrap();
+    if let Phase::PlayerTurn(_) = snapshot.phase {
+        act(
+            session.as_ref(),
+            id,
+            player,
+            PlayerAction::Stand(Stand { player_id: player }),
+        )
+        .await;
+    }
+    deal(session.as_ref(), &table, DealerAction::PlayHand(PlayHand)).await;
+    deal(
+        session.as_ref(),
+        &table,
+        DealerAction::SettleRound(SettleRound),
+    )
+    .await;
+
+    let text = metrics.render(&session.list_tables().await);
+    assert_eq!(sample(&text, "blackjack_rounds_total"), Some(1.0));
+    assert_eq!(
+        sample(&text, r#"blackjack_tables{phase="Finished"}"#),
+        Some(1.0)
+    );
+    assert_eq!(
+        sample(&text, r#"blackjack_tables{phase="PlayerTurn"}"#),
+        Some(0.0)
+    );
+    assert_eq!(
+        sample(
+            &text,
+            r#"blackjack_command_duration_seconds_count{action="PlaceBet"}"#
+        ),
+        Some(2.0)
+    );
+    assert_eq!(
+        sample(
+            &text,
+            r#"blackjack_command_rejections_total{error="BetBelowMinimum"}"#
+        ),
+        Some(1.0)
+    );
+    assert_eq!(
+        sample(
+            &text,
+            r#"blackjack_wallet_duration_seconds_count{operation="commit"}"#
+        ),
+        Some(1.0)
+    );
+    // Whatever the player is up, the house is down.
+    let balance = wallet.balance(player).await.unwrap();
+    assert_eq!(
+        sample(
+            &text,
+            &format!(r#"blackjack_house_net_chips{{table="{id}"}}"#)
+        ),
+        Some(1000.0 - f64::from(balance))
+    );
+}
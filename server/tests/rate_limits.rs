@@ -0,0 +1,222 @@
+//! Message buckets, per-address connection, login and sign-up caps, login
+//! backoff, and what they show on `/metrics`.
+
+use std::{net::IpAddr, sync::Arc, thread, time::Duration};
+
+use bj_core::domain::PlayerId;
+use server::{
+    config::RateLimitSettings,
+    metrics::Metrics,
+    rate_limit::{Limit, RateLimits, TokenBucket},
+};
+
+fn settings() -> RateLimitSettings {
+    RateLimitSettings {
+        messages_per_sec: 20.0,
+        message_burst: 3,
+        player_messages_per_sec: 20.0,
+        player_message_burst: 4,
+        connections_per_ip: 2,
+        login_backoff_ms: 50,
+        login_lockout_after: 4,
+        login_lockout_secs: 1,
+        signups_per_min: 600.0,
+        signup_burst: 2,
+        logins_per_min: 600.0,
+        login_burst: 3,
+    }
+}
+
+/// The value of the sample named `series`, labels included.
+fn sample(metrics: &Metrics, series: &str) -> f64 {
+    metrics
+        .render(&[])
+        .lines()
+        .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+        .map_or(0.0, |value| value.parse().unwrap())
+}
+
+#[test]
+fn a_bucket_refills_at_its_rate() {
+    let mut bucket = TokenBucket::new(2, 20.0);
+    assert!(bucket.try_take().is_ok());
+    assert!(bucket.try_take().is_ok());
+    let wait = bucket.try_take().unwrap_err();
+    assert!(wait <= Duration::from_millis(50), "{wait:?}");
+
+    thread::sleep(wait + Duration::from_millis(5));
+    assert!(bucket.try_take().is_ok());
+    assert!(bucket.try_take().is_err());
+}
+
+#[test]
+fn a_player_is_limited_across_connections() {
+    let metrics = Arc::new(Metrics::new());
+    let limits = RateLimits::new(&settings(), metrics.clone());
+    let player = PlayerId::new();
+
+    let mut first = limits.connection_bucket();
+    for _ in 0..3 {
+        limits.message(&mut first, Some(player)).unwrap();
+    }
+    let refused = limits.message(&mut first, Some(player)).unwrap_err();
+    assert_eq!(refused.limit, Limit::Connection);
+
+    // A fresh connection has its own bucket, but the player's is nearly out.
+    let mut second = limits.connection_bucket();
+    limits.message(&mut second, Some(player)).unwrap();
+    let refused = limits.message(&mut second, Some(player)).unwrap_err();
+    assert_eq!(refused.limit, Limit::Player);
+    // Before authenticating, only the connection counts.
+    limits.message(&mut second, None).unwrap();
+
+    assert_eq!(
+        sample(
+            &metrics,
+            r#"blackjack_rate_limited_total{scope="connection"}"#
+        ),
+        1.0
+    );
+    assert_eq!(
+        sample(&metrics, r#"blackjack_rate_limited_total{scope="player"}"#),
+        1.0
+    );
+}
+
+#[test]
+fn an_address_holds_a_limited_number_of_connections() {
+    let metrics = Arc::new(Metrics::new());
+    let limits = RateLimits::new(&settings(), metrics.clone());
+    let ip: IpAddr = "10.0.0.7".parse().unwrap();
+
+    let first = limits.connect(ip).unwrap();
+    let _second = limits.connect(ip).unwrap();
+    assert_eq!(limits.connect(ip).err().unwrap().limit, Limit::Ip);
+    // Others are not affected.
+    let _other = limits.connect("10.0.0.8".parse().unwrap()).unwrap();
+
+    drop(first);
+    let _third = limits.connect(ip).unwrap();
+    assert_eq!(
+        sample(&metrics, r#"blackjack_rate_limited_total{scope="ip"}"#),
+        1.0
+    );
+}
+
+#[test]
+fn an_address_signs_up_a_limited_number_of_accounts() {
+    let metrics = Arc::new(Metrics::new());
+    let limits = RateLimits::new(&settings(), metrics.clone());
+    let ip: IpAddr = "10.0.0.7".parse().unwrap();
+
+    limits.signup(ip).unwrap();
+    limits.signup(ip).unwrap();
+    let refused = limits.signup(ip).unwrap_err();
+    assert_eq!(refused.limit, Limit::Signup);
+    assert!(
+        refused.retry_after <= Duration::from_millis(100),
+        "{refused:?}"
+    );
+    // Others are not affected.
+    limits.signup("10.0.0.8".parse().unwrap()).unwrap();
+
+    thread::sleep(refused.retry_after + Duration::from_millis(5));
+    limits.signup(ip).unwrap();
+    assert_eq!(
+        sample(&metrics, r#"blackjack_rate_limited_total{scope="signup"}"#),
+        1.0
+    );
+}
+
+#[test]
+fn an_address_tries_a_limited_number_of_logins() {
+    let metrics = Arc::new(Metrics::new());
+    let limits = RateLimits::new(&settings(), metrics.clone());
+    let ip: IpAddr = "10.0.0.9".parse().unwrap();
+
+    for _ in 0..3 {
+        limits.login_from(ip).unwrap();
+    }
+    let refused = limits.login_from(ip).unwrap_err();
+    assert_eq!(refused.limit, Limit::LoginIp);
+    assert!(
+        refused.retry_after <= Duration::from_millis(100),
+        "{refused:?}"
+    );
+    limits.login_from("10.0.0.10".parse().unwrap()).unwrap();
+
+    thread::sleep(refused.retry_after + Duration::from_millis(5));
+    limits.login_from(ip).unwrap();
+    assert_eq!(
+        sample(
+            &metrics,
+            r#"blackjack_rate_limited_total{scope="login_ip"}"#
+        ),
+        1.0
+    );
+}
+
+#[test]
+fn a_sweep_keeps_what_still_limits() {
+    let limits = RateLimits::new(&settings(), Arc::new(Metrics::new()));
+    let ip: IpAddr = "10.0.0.11".parse().unwrap();
+    for _ in 0..3 {
+        limits.login_from(ip).unwrap();
+    }
+    limits.login_failed("qa");
+
+    limits.sweep();
+    assert_eq!(limits.login_from(ip).unwrap_err().limit, Limit::LoginIp);
+    assert_eq!(
+        limits.login_allowed("qa").unwrap_err().limit,
+        Limit::LoginBackoff
+    );
+}
+
+#[test]
+fn failed_logins_back_off_until_the_account_locks() {
+    let metrics = Arc::new(Metrics::new());
+    let limits = RateLimits::new(&settings(), metrics.clone());
+
+    // 50ms, then 100ms, then 200ms.
+    for failures in 1..=3 {
+        limits.login_allowed("qa").unwrap();
+        assert_eq!(limits.login_failed("qa"), failures);
+        let refused = limits.login_allowed("qa").unwrap_err();
+        assert_eq!(refused.limit, Limit::LoginBackoff);
+        assert!(refused.retry_after <= Duration::from_millis(50 << (failures - 1)));
+        thread::sleep(refused.retry_after + Duration::from_millis(5));
+    }
+    // Other usernames are not affected.
+    limits.login_allowed("dev").unwrap();
+
+    limits.login_allowed("qa").unwrap();
+    assert_eq!(limits.login_failed("qa"), 4);
+    let refused = limits.login_allowed("qa").unwrap_err();
+    assert_eq!(refused.limit, Limit::LoginLocked);
+    assert!(refused.retry_after > Duration::from_millis(500));
+
+    thread::sleep(refused.retry_after + Duration::from_millis(5));
+    limits.login_allowed("qa").unwrap();
+    // The lockout served, the count starts again.
+    assert_eq!(limits.login_failed("qa"), 1);
+    thread::sleep(Duration::from_millis(55));
+    limits.login_succeeded("qa");
+    limits.login_allowed("qa").unwrap();
+
+    assert_eq!(sample(&metrics, "blackjack_login_failures_total"), 5.0);
+    assert_eq!(
+        sample(
+            &metrics,
+            r#"blackjack_rate_limited_total{scope="login_backoff"}"#
+        ),
+        3.0
+    );
+    assert_eq!(
+        sample(
+            &metrics,
+            r#"blackjack_rate_limited_total{scope="login_locked"}"#
+        ),
+        1.0
+    );
+}
@@ -0,0 +1,211 @@
+//! Seats held for players whose connection dropped.
+
+use std::{sync::Arc, time::Duration};
+
+use bj_core::domain::{
+    engine::{
+        command::player::{JoinTable, LeaveTable, PlayerAction, TakeSeat},
+        event::payload::EventPayload,
+    },
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    session::{
+        grace::{SeatHolds, MAX_MISSED_EVENTS},
+        in_memory::InMemoryGameSession,
+        GameSession, RequestId,
+    },
+    store::TableRecord,
+    wallet::{in_memory::InMemoryWallet, Posting, Wallet},
+};
+use ulid::Ulid;
+
+const GRACE: Duration = Duration::from_millis(200);
+
+struct Table {
+    session: Arc<dyn GameSession>,
+    wallet: Arc<dyn Wallet>,
+    id: TableId,
+}
+
+async fn table() -> Table {
+    let wallet: Arc<dyn Wallet> = Arc::new(InMemoryWallet::new());
+    let session: Arc<dyn GameSession> = InMemoryGameSession::new(wallet.clone());
+    let id = TableId::new();
+    session
+        .open_table(&TableRecord {
+            id,
+            name: "Holds".into(),
+            status: TableStatus::Open,
+            settings: TableSettings {
+                min_bet: 10,
+                max_bet: 500,
+                max_players: 5,
+                max_observers: 10,
+                dealer: DealerMode::Automatic,
+                dealers: vec![],
+            },
+        })
+        .await
+        .unwrap();
+    Table {
+        session,
+        wallet,
+        id,
+    }
+}
+
+impl Table {
+    async fn seat(&self) -> PlayerId {
+        let player = PlayerId::new();
+        self.wallet
+            .credit(player, 1000, Posting::grant())
+            .await
+            .unwrap();
+        for action in [
+            PlayerAction::JoinTable(JoinTable { player_id: player }),
+            PlayerAction::TakeSeat(TakeSeat {
+                player_id: player,
+                seat: None,
+            }),
+        ] {
+            self.session
+                .send_command(self.id, player, RequestId(1), action)
+                .await
+                .unwrap();
+        }
+        player
+    }
+
+    async fn is_seated(&self, player: PlayerId) -> bool {
+        let snap = self.session.snapshot(self.id, player).await.unwrap();
+        snap.players.iter().any(|p| p.player_id == player)
+    }
+
+    /// Connects `player`, then drops the connection without a close frame.
+    async fn drop_connection(&self, holds: &SeatHolds, player: PlayerId) {
+        let conn = Ulid::new();
+        let _ = holds.attach(player, conn).await;
+        holds
+            .detach(self.session.clone(), player, conn, Some(self.id), false)
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn reconnecting_within_grace_keeps_seat_and_replays_missed_events() {
+    let table = table().await;
+    let holds = SeatHolds::new(GRACE);
+    let player = table.seat().await;
+
+    table.drop_connection(&holds, player).await;
+    let other = table.seat().await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let (reclaimed, _) = holds.attach(player, Ulid::new()).await;
+    let reclaimed = reclaimed.expect("seat should still be held");
+    assert_eq!(reclaimed.table_id, table.id);
+    let missed = reclaimed.missed.expect("missed events are replayed");
+    assert!(missed.iter().any(
+        |e| matches!(e.payload, EventPayload::PlayerJoined { player, .. } if player == other)
+    ));
+
+    // The hold is over: the seat is not released when the grace runs out.
+    tokio::time::sleep(GRACE * 2).await;
+    assert!(table.is_seated(player).await);
+}
+
+#[tokio::test]
+async fn too_many_missed_events_are_not_replayed() {
+    let table = table().await;
+    let holds = SeatHolds::new(GRACE * 10);
+    let player = table.seat().await;
+
+    table.drop_connection(&holds, player).await;
+    // Each visitor comes and goes: at least two events apiece.
+    for _ in 0..MAX_MISSED_EVENTS / 2 + 1 {
+        let visitor = PlayerId::new();
+        for action in [
+            PlayerAction::JoinTable(JoinTable { player_id: visitor }),
+            PlayerAction::LeaveTable(LeaveTable { player_id: visitor }),
+        ] {
+            table
+                .session
+                .send_command(table.id, visitor, RequestId(1), action)
+                .await
+                .unwrap();
+        }
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let (reclaimed, _) = holds.attach(player, Ulid::new()).await;
+    let reclaimed = reclaimed.expect("seat should still be held");
+    assert!(reclaimed.missed.is_none(), "a snapshot stands in for them");
+    assert!(table.is_seated(player).await);
+}
+
+#[tokio::test]
+async fn seat_is_released_when_grace_runs_out() {
+    let table = table().await;
+    let holds = SeatHolds::new(GRACE);
+    let player = table.seat().await;
+
+    table.drop_connection(&holds, player).await;
+    assert!(table.is_seated(player).await);
+
+    tokio::time::sleep(GRACE * 2).await;
+    assert!(!table.is_seated(player).await);
+    assert!(holds.attach(player, Ulid::new()).await.0.is_none());
+}
+
+#[tokio::test]
+async fn closing_on_purpose_leaves_at_once() {
+    let table = table().await;
+    let holds = SeatHolds::new(GRACE);
+    let player = table.seat().await;
+
+    let conn = Ulid::new();
+    let _ = holds.attach(player, conn).await;
+    holds
+        .detach(table.session.clone(), player, conn, Some(table.id), true)
+        .await;
+
+    assert!(!table.is_seated(player).await);
+    assert!(holds.attach(player, Ulid::new()).await.0.is_none());
+}
+
+#[tokio::test]
+async fn no_grace_period_leaves_at_once() {
+    let table = table().await;
+    let holds = SeatHolds::new(Duration::ZERO);
+    let player = table.seat().await;
+
+    table.drop_connection(&holds, player).await;
+    assert!(!table.is_seated(player).await);
+}
+
+/// The old socket is half-open and has not noticed the drop yet when the
+/// player connects again.
+#[tokio::test]
+async fn new_connection_takes_over_from_stale_one() {
+    let table = table().await;
+    let holds = Arc::new(SeatHolds::new(GRACE));
+    let player = table.seat().await;
+
+    let old = Ulid::new();
+    let (_, superseded) = holds.attach(player, old).await;
+    let stale = {
+        let (holds, session, id) = (holds.clone(), table.session.clone(), table.id);
+        tokio::spawn(async move {
+            let handover = superseded.await.unwrap();
+            holds.detach(session, player, old, Some(id), false).await;
+            handover.done();
+        })
+    };
+
+    let (reclaimed, _) = holds.attach(player, Ulid::new()).await;
+    stale.await.unwrap();
+    assert_eq!(reclaimed.map(|r| r.table_id), Some(table.id));
+    tokio::time::sleep(GRACE * 2).await;
+    assert!(table.is_seated(player).await);
+}
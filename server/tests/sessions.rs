@@ -0,0 +1,224 @@
+//! Token sessions over every `SessionStore`: in memory, SQLite on a temp
+//! file, and Postgres when `DATABASE_URL` is set.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use bj_core::domain::PlayerId;
+use server::{
+    auth::{
+        postgres::{PostgresAuthenticator, PostgresSessionStore},
+        session_store::{InMemorySessionStore, SessionStore},
+        sqlite::{SqliteAuthenticator, SqliteSessionStore},
+        AuthError, Authenticator, PasswordHasher, Sessions,
+    },
+    config::{Argon2Settings, TokenSettings},
+};
+use ulid::Ulid;
+
+fn settings(access_ttl_secs: u64) -> TokenSettings {
+    TokenSettings {
+        secret: Some("test-secret".to_string().into()),
+        access_ttl_secs,
+        refresh_ttl_secs: 3600,
+        recheck_secs: 30,
+    }
+}
+
+/// A session service and a player it may log in. Database sessions reference
+/// a row in `users`, so the player is created there first.
+async fn in_memory(access_ttl_secs: u64) -> (Sessions, PlayerId) {
+    let store = Arc::new(InMemorySessionStore::new());
+    (
+        Sessions::new(store, &settings(access_ttl_secs)),
+        PlayerId::new(),
+    )
+}
+
+fn cheap() -> PasswordHasher {
+    PasswordHasher::new(&Argon2Settings {
+        memory_kib: 1024,
+        iterations: 1,
+        parallelism: 1,
+    })
+    .unwrap()
+}
+
+async fn postgres(access_ttl_secs: u64) -> Option<(Sessions, PlayerId)> {
+    let pool = common::postgres_pool().await?;
+    let player = PostgresAuthenticator::new(pool.clone(), cheap())
+        .seed_user(&format!("user-{}", Ulid::new()), "famly1234")
+        .await
+        .unwrap();
+    let store: Arc<dyn SessionStore> = Arc::new(PostgresSessionStore::new(pool));
+    Some((Sessions::new(store, &settings(access_ttl_secs)), player))
+}
+
+async fn sqlite(access_ttl_secs: u64) -> (Sessions, PlayerId) {
+    let pool = common::sqlite_pool().await;
+    let player = SqliteAuthenticator::new(pool.clone(), cheap())
+        .seed_user("player", "famly1234")
+        .await
+        .unwrap();
+    let store: Arc<dyn SessionStore> = Arc::new(SqliteSessionStore::new(pool));
+    (Sessions::new(store, &settings(access_ttl_secs)), player)
+}
+
+async fn access_token_names_player(sessions: Sessions, player: PlayerId) {
+    let pair = sessions.issue(player).await.unwrap();
+    let claims = sessions.verify(&pair.access_token).await.unwrap();
+    assert_eq!(claims.player_id, player);
+    assert!(matches!(
+        sessions.verify("not-a-token").await,
+        Err(AuthError::InvalidToken)
+    ));
+}
+
+async fn refresh_token_is_single_use(sessions: Sessions, player: PlayerId) {
+    let first = sessions.issue(player).await.unwrap();
+    let second = sessions.refresh(&first.refresh_token).await.unwrap();
+    assert_eq!(second.player_id, player.to_string());
+    assert!(sessions.verify(&second.access_token).await.is_ok());
+    assert!(sessions.refresh(&second.refresh_token).await.is_ok());
+    assert!(matches!(
+        sessions.refresh("never-issued").await,
+        Err(AuthError::InvalidToken)
+    ));
+}
+
+async fn reused_refresh_token_revokes_the_session(sessions: Sessions, player: PlayerId) {
+    let other = sessions.issue(player).await.unwrap();
+    let stolen = sessions.issue(player).await.unwrap();
+    let rotated = sessions.refresh(&stolen.refresh_token).await.unwrap();
+
+    assert!(matches!(
+        sessions.refresh(&stolen.refresh_token).await,
+        Err(AuthError::SessionRevoked)
+    ));
+    // Every token of the session is dead, the rotated ones too.
+    assert!(matches!(
+        sessions.refresh(&rotated.refresh_token).await,
+        Err(AuthError::SessionRevoked)
+    ));
+    assert!(matches!(
+        sessions.verify(&rotated.access_token).await,
+        Err(AuthError::SessionRevoked)
+    ));
+    assert!(sessions.refresh(&other.refresh_token).await.is_ok());
+}
+
+async fn logout_revokes_session(sessions: Sessions, player: PlayerId) {
+    let pair = sessions.issue(player).await.unwrap();
+    let other = sessions.issue(player).await.unwrap();
+    let claims = sessions.verify(&pair.access_token).await.unwrap();
+    sessions.revoke(claims.session_id).await.unwrap();
+
+    assert!(matches!(
+        sessions.verify(&pair.access_token).await,
+        Err(AuthError::SessionRevoked)
+    ));
+    assert!(matches!(
+        sessions.refresh(&pair.refresh_token).await,
+        Err(AuthError::SessionRevoked)
+    ));
+    // Other devices stay logged in.
+    assert!(sessions.verify(&other.access_token).await.is_ok());
+}
+
+async fn revoke_player_ends_every_session(sessions: Sessions, player: PlayerId) {
+    let a = sessions.issue(player).await.unwrap();
+    let b = sessions.issue(player).await.unwrap();
+    sessions.revoke_player(player).await.unwrap();
+    for pair in [a, b] {
+        assert!(matches!(
+            sessions.verify(&pair.access_token).await,
+            Err(AuthError::SessionRevoked)
+        ));
+    }
+}
+
+async fn a_verified_token_is_rechecked_for_revocation(sessions: Sessions, player: PlayerId) {
+    let pair = sessions.issue(player).await.unwrap();
+    let claims = sessions.verify(&pair.access_token).await.unwrap();
+    sessions.recheck(&claims).await.unwrap();
+    // Due at the recheck interval, well before the token expires.
+    assert_eq!(sessions.recheck_in(&claims), Duration::from_secs(30));
+
+    sessions.revoke_player(player).await.unwrap();
+    assert!(matches!(
+        sessions.recheck(&claims).await,
+        Err(AuthError::SessionRevoked)
+    ));
+}
+
+macro_rules! session_tests {
+    ($($name:ident),* $(,)?) => {
+        mod in_memory {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    let (sessions, player) = super::in_memory(60).await;
+                    super::$name(sessions, player).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    if let Some((sessions, player)) = super::postgres(60).await {
+                        super::$name(sessions, player).await;
+                    }
+                }
+            )*
+        }
+
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    let (sessions, player) = super::sqlite(60).await;
+                    super::$name(sessions, player).await;
+                }
+            )*
+        }
+    };
+}
+
+session_tests!(
+    access_token_names_player,
+    refresh_token_is_single_use,
+    reused_refresh_token_revokes_the_session,
+    logout_revokes_session,
+    revoke_player_ends_every_session,
+    a_verified_token_is_rechecked_for_revocation,
+);
+
+#[tokio::test]
+async fn expired_access_token_is_rejected_but_refreshable() {
+    let (sessions, player) = in_memory(0).await;
+    let pair = sessions.issue(player).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert!(matches!(
+        sessions.verify(&pair.access_token).await,
+        Err(AuthError::TokenExpired)
+    ));
+    assert!(sessions.refresh(&pair.refresh_token).await.is_ok());
+}
+
+#[tokio::test]
+async fn a_verified_token_fails_its_recheck_once_expired() {
+    let (sessions, player) = in_memory(1).await;
+    let pair = sessions.issue(player).await.unwrap();
+    let claims = sessions.verify(&pair.access_token).await.unwrap();
+    let due = sessions.recheck_in(&claims);
+    assert!(due <= Duration::from_secs(1), "{due:?}");
+
+    tokio::time::sleep(due).await;
+    assert!(matches!(
+        sessions.recheck(&claims).await,
+        Err(AuthError::TokenExpired)
+    ));
+}
@@ -0,0 +1,211 @@
+//! Draining tables for a shutdown: rounds finish, or are voided at the
+//! deadline, and every table closes.
+
+use std::{sync::Arc, time::Duration};
+
+use bj_core::domain::{
+    engine::{
+        command::{
+            dealer::{DealInitialCards, DealerAction},
+            player::{JoinTable, PlaceBet, PlayerAction, TakeSeat},
+        },
+        event::{payload::EventPayload, GameEvent},
+        TableTimers, Timestamp,
+    },
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    session::{
+        in_memory::InMemoryGameSession, table_actor::TableActorConfig, GameSession, RequestId,
+    },
+    shutdown::{Shutdown, ShutdownState},
+    store::TableRecord,
+    wallet::{in_memory::InMemoryWallet, Posting, Wallet},
+};
+use tokio::sync::broadcast;
+use ulid::Ulid;
+
+/// The dealer assigned to every table here.
+const DEALER: PlayerId = PlayerId(Ulid::nil());
+
+fn record(dealer: DealerMode) -> TableRecord {
+    TableRecord {
+        id: TableId::new(),
+        name: "Draining".into(),
+        status: TableStatus::Open,
+        settings: TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer,
+            dealers: vec![DEALER],
+        },
+    }
+}
+
+async fn act(session: &dyn GameSession, table_id: TableId, player: PlayerId, action: PlayerAction) {
+    session
+        .send_command(table_id, player, RequestId(1), action)
+        .await
+        .unwrap();
+}
+
+/// Seats a player with 1000 chips and has them bet 100.
+async fn bet(session: &dyn GameSession, wallet: &dyn Wallet, table_id: TableId) -> PlayerId {
+    let player = PlayerId::new();
+    wallet.credit(player, 1000, Posting::grant()).await.unwrap();
+    act(
+        session,
+        table_id,
+        player,
+        PlayerAction::JoinTable(JoinTable { player_id: player }),
+    )
+    .await;
+    act(
+        session,
+        table_id,
+        player,
+        PlayerAction::TakeSeat(TakeSeat {
+            player_id: player,
+            seat: None,
+        }),
+    )
+    .await;
+    act(
+        session,
+        table_id,
+        player,
+        PlayerAction::PlaceBet(PlaceBet {
+            player_id: player,
+            amount: 100,
+        }),
+    )
+    .await;
+    player
+}
+
+/// Every event up to and including `TableClosed`.
+async fn until_closed(events: &mut broadcast::Receiver<GameEvent>) -> Vec<EventPayload> {
+    let mut seen = vec![];
+    loop {
+        let event = events.recv().await.unwrap();
+        let closed = matches!(event.payload, EventPayload::TableClosed);
+        seen.push(event.payload);
+        if closed {
+            return seen;
+        }
+    }
+}
+
+#[tokio::test]
+async fn a_round_in_play_finishes_before_its_table_closes() {
+    let tick = Duration::from_millis(30);
+    let wallet: Arc<dyn Wallet> = Arc::new(InMemoryWallet::new());
+    let session = InMemoryGameSession::with_config(
+        wallet.clone(),
+        TableActorConfig {
+            timers: TableTimers {
+                betting_window: tick,
+                player_turn: tick,
+                round_delay: tick,
+            },
+            ..TableActorConfig::default()
+        },
+    );
+    let table = record(DealerMode::Automatic);
+    session.open_table(&table).await.unwrap();
+    let mut events = session.subscribe(table.id).await.unwrap();
+    let player = bet(session.as_ref(), wallet.as_ref(), table.id).await;
+
+    let drained = session.drain(Duration::from_secs(5)).await;
+    assert_eq!(drained, vec![table.id]);
+
+    let seen = until_closed(&mut events).await;
+    assert!(seen.iter().any(|e| matches!(
+        e,
+        EventPayload::GameFinished { result }
+            if result.player_results.iter().any(|r| r.player == player)
+    )));
+    assert!(!seen
+        .iter()
+        .any(|e| matches!(e, EventPayload::RoundVoided { .. })));
+    assert!(session.list_tables().await.is_empty());
+    assert!(wallet.held().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn a_round_still_going_at_the_deadline_is_voided() {
+    let wallet: Arc<dyn Wallet> = Arc::new(InMemoryWallet::new());
+    let session = InMemoryGameSession::new(wallet.clone());
+    let table = record(DealerMode::Manual);
+    session.open_table(&table).await.unwrap();
+    let player = bet(session.as_ref(), wallet.as_ref(), table.id).await;
+    session
+        .dealer_command(
+            table.id,
+            DEALER,
+            RequestId(2),
+            DealerAction::DealInitialCards(DealInitialCards),
+        )
+        .await
+        .unwrap();
+    let mut events = session.subscribe(table.id).await.unwrap();
+
+    let draining = tokio::spawn({
+        let session = session.clone();
+        async move { session.drain(Duration::from_millis(300)).await }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    // Nobody new sits down at a draining table.
+    let late = PlayerId::new();
+    assert!(session
+        .send_command(
+            table.id,
+            late,
+            RequestId(3),
+            PlayerAction::JoinTable(JoinTable { player_id: late }),
+        )
+        .await
+        .is_err());
+    draining.await.unwrap();
+
+    let seen = until_closed(&mut events).await;
+    assert!(seen
+        .iter()
+        .any(|e| matches!(e, EventPayload::RoundVoided { .. })));
+    assert!(session.list_tables().await.is_empty());
+    assert_eq!(wallet.balance(player).await.unwrap(), 1000);
+    assert!(wallet.held().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn connections_follow_the_shutdown() {
+    let shutdown = Arc::new(Shutdown::new());
+    let mut connection = shutdown.watch();
+    assert!(!shutdown.is_draining());
+
+    shutdown.begin(Timestamp::from_millis(42));
+    assert!(shutdown.is_draining());
+    connection.changed().await.unwrap();
+    assert_eq!(
+        *connection.borrow_and_update(),
+        ShutdownState::Draining {
+            deadline: Timestamp::from_millis(42)
+        }
+    );
+
+    // Closing waits for the connection to go.
+    let closing = tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move { shutdown.close_connections(Duration::from_secs(5)).await }
+    });
+    connection.changed().await.unwrap();
+    assert_eq!(*connection.borrow(), ShutdownState::Closing);
+    assert!(!closing.is_finished());
+    drop(connection);
+    tokio::time::timeout(Duration::from_secs(1), closing)
+        .await
+        .expect("closing did not notice the connection went")
+        .unwrap();
+}
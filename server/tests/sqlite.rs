@@ -0,0 +1,106 @@
+//! The SQLite backend keeps everything in one file: what one server wrote is
+//! there for the next one that opens it.
+
+mod common;
+
+use bj_core::domain::{
+    engine::game_id::GameId, DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    auth::{sqlite::SqliteAuthenticator, AuthPayload, Authenticator, Password, PasswordHasher},
+    config::Argon2Settings,
+    store::{connect_sqlite, SqliteTableStore, TableRecord, TableStore},
+    wallet::{sqlite::SqliteWallet, Posting, ReservationStatus, Transaction, Wallet},
+};
+
+fn hasher() -> PasswordHasher {
+    PasswordHasher::new(&Argon2Settings {
+        memory_kib: 1024,
+        iterations: 1,
+        parallelism: 1,
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn data_survives_reopening_the_file() {
+    let path = common::sqlite_path();
+    let table = TableRecord {
+        id: TableId::new(),
+        name: "Persistent".into(),
+        status: TableStatus::Open,
+        settings: TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        },
+    };
+    let game = GameId::new();
+
+    let pool = connect_sqlite(&path, 5).await.unwrap();
+    let player = SqliteAuthenticator::new(pool.clone(), hasher())
+        .seed_user("keeper", "famly1234")
+        .await
+        .unwrap();
+    let wallet = SqliteWallet::new(pool.clone());
+    wallet.credit(player, 1000, Posting::grant()).await.unwrap();
+    wallet.reserve(player, table.id, game, 100).await.unwrap();
+    SqliteTableStore::new(pool.clone())
+        .insert_table(&table)
+        .await
+        .unwrap();
+    pool.close().await;
+
+    let pool = connect_sqlite(&path, 5).await.unwrap();
+    let login = AuthPayload {
+        username: "keeper".into(),
+        password: Password::new("famly1234".into()),
+    };
+    assert_eq!(
+        SqliteAuthenticator::new(pool.clone(), hasher())
+            .authenticate(&login)
+            .await
+            .unwrap(),
+        player
+    );
+    let wallet = SqliteWallet::new(pool.clone());
+    assert_eq!(wallet.balance(player).await.unwrap(), 900);
+    let held = wallet.held().await.unwrap();
+    assert_eq!(held.len(), 1);
+    assert_eq!(held[0].status, ReservationStatus::Held);
+    assert_eq!(wallet.release(player, game).await.unwrap(), 1000);
+    let stored = SqliteTableStore::new(pool)
+        .get_table(table.id)
+        .await
+        .unwrap();
+    assert_eq!(stored.name, "Persistent");
+}
+
+#[tokio::test]
+async fn fresh_file_has_the_seeded_tables() {
+    let store = SqliteTableStore::new(common::sqlite_pool().await);
+    let names: Vec<_> = store
+        .list_tables()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    assert_eq!(names, ["Table Alpha", "Table Bravo", "Table Charlie"]);
+}
+
+#[tokio::test]
+async fn ledger_entries_are_numbered_per_player() {
+    let wallet = SqliteWallet::new(common::sqlite_pool().await);
+    let (a, b) = (PlayerId::new(), PlayerId::new());
+    wallet.credit(a, 100, Posting::grant()).await.unwrap();
+    wallet.credit(b, 100, Posting::grant()).await.unwrap();
+    wallet.credit(a, 50, Posting::grant()).await.unwrap();
+
+    let ids = |ledger: Vec<Transaction>| -> Vec<u64> { ledger.into_iter().map(|t| t.id).collect() };
+    assert_eq!(ids(wallet.transactions(a, 10).await.unwrap()), [2, 1]);
+    assert_eq!(ids(wallet.transactions(b, 10).await.unwrap()), [1]);
+}
@@ -0,0 +1,401 @@
+//! Hot restarts from a state directory: what a save keeps and how it is
+//! written.
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use bj_core::domain::{
+    engine::{
+        command::player::{JoinTable, PlaceBet, PlayerAction, TakeSeat},
+        phase::Phase,
+        Timestamp,
+    },
+    DealerMode, PlayerId, Seat, TableId, TableSettings, TableStatus,
+};
+use server::{
+    auth::{
+        session_store::InMemorySessionStore, AuthPayload, Authenticator, InMemoryAuthenticator,
+        Password, PasswordHasher, Role, Sessions,
+    },
+    config::{Argon2Settings, TokenSettings},
+    session::{
+        in_memory::InMemoryGameSession, table_actor::TableActorConfig, GameSession, RequestId,
+    },
+    shutdown::Shutdown,
+    state_dir::{open_saved_tables, MemoryBackends, SavedState, StateDir, StateSaver, STATE_FILE},
+    store::{InMemoryTableStore, TableRecord, TableStore},
+    wallet::{
+        in_memory::{InMemoryWallet, WalletState},
+        release_held, Posting, TransactionKind, Wallet,
+    },
+};
+
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("bj-state-{}", ulid::Ulid::new()))
+}
+
+/// Cheap parameters so the suite stays fast in debug builds.
+fn cheap() -> PasswordHasher {
+    PasswordHasher::new(&Argon2Settings {
+        memory_kib: 1024,
+        iterations: 1,
+        parallelism: 1,
+    })
+    .unwrap()
+}
+
+fn empty() -> MemoryBackends {
+    MemoryBackends {
+        wallet: Arc::new(InMemoryWallet::new()),
+        auth: Arc::new(InMemoryAuthenticator::new(cheap())),
+        sessions: Arc::new(InMemorySessionStore::new()),
+        tables: Arc::new(InMemoryTableStore::new()),
+    }
+}
+
+fn table() -> TableRecord {
+    TableRecord {
+        id: TableId::new(),
+        name: "Restarted".into(),
+        status: TableStatus::Open,
+        settings: TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        },
+    }
+}
+
+fn token_settings() -> TokenSettings {
+    TokenSettings {
+        secret: Some("test-secret".to_string().into()),
+        access_ttl_secs: 60,
+        refresh_ttl_secs: 3600,
+        recheck_secs: 30,
+    }
+}
+
+async fn sit(session: &dyn GameSession, table_id: TableId, player: PlayerId, seat: Option<Seat>) {
+    session
+        .send_command(
+            table_id,
+            player,
+            RequestId(1),
+            PlayerAction::JoinTable(JoinTable { player_id: player }),
+        )
+        .await
+        .unwrap();
+    session
+        .send_command(
+            table_id,
+            player,
+            RequestId(2),
+            PlayerAction::TakeSeat(TakeSeat {
+                player_id: player,
+                seat,
+            }),
+        )
+        .await
+        .unwrap();
+}
+
+async fn bet(session: &dyn GameSession, table_id: TableId, player: PlayerId, amount: u32) {
+    session
+        .send_command(
+            table_id,
+            player,
+            RequestId(3),
+            PlayerAction::PlaceBet(PlaceBet {
+                player_id: player,
+                amount,
+            }),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn balances_and_seats_survive_a_restart() {
+    let path = temp_dir();
+    let before = empty();
+    let table = table();
+    before.tables.insert_table(&table).await.unwrap();
+    let session =
+        InMemoryGameSession::with_config(before.wallet.clone(), TableActorConfig::default());
+    session.open_table(&table).await.unwrap();
+
+    let alice = before.auth.seed_user("alice", "famly1234").await.unwrap();
+    before.auth.set_role("alice", Role::Dealer).await.unwrap();
+    let bob = PlayerId::new();
+    for player in [alice, bob] {
+        before
+            .wallet
+            .credit(player, 1000, Posting::grant())
+            .await
+            .unwrap();
+    }
+    let adjustment = Posting {
+        kind: TransactionKind::Adjustment,
+        ..Posting::grant()
+    };
+    before.wallet.debit(bob, 250, adjustment).await.unwrap();
+    sit(session.as_ref(), table.id, alice, Some(Seat::Three)).await;
+    sit(session.as_ref(), table.id, bob, None).await;
+    // Saved mid-round: the stake is held by the wallet.
+    bet(session.as_ref(), table.id, alice, 100).await;
+    let tokens = Sessions::new(before.sessions.clone(), &token_settings())
+        .issue(alice)
+        .await
+        .unwrap();
+
+    let saver = StateSaver::new(
+        StateDir::open(&path).unwrap(),
+        before.clone(),
+        session.clone(),
+    );
+    saver.save().await.unwrap();
+    let file = std::fs::read_to_string(path.join(STATE_FILE)).unwrap();
+    assert!(file.contains("$argon2id$"));
+    assert!(
+        !file.contains("famly1234"),
+        "passwords are only saved hashed"
+    );
+    for partition in 0..server::bus::DEFAULT_PARTITIONS {
+        session.stop_partition(partition).await;
+    }
+
+    // Boot from the directory, as `main` does.
+    let mut saved = StateDir::open(&path).unwrap().load().unwrap().unwrap();
+    let after = MemoryBackends::restore(&mut saved, cheap());
+    assert_eq!(release_held(after.wallet.as_ref()).await.unwrap(), 1);
+    let session =
+        InMemoryGameSession::with_config(after.wallet.clone(), TableActorConfig::default());
+    assert_eq!(open_saved_tables(saved.tables, session.as_ref()).await, 1);
+
+    assert_eq!(after.wallet.balance(alice).await.unwrap(), 1000);
+    assert_eq!(after.wallet.balance(bob).await.unwrap(), 750);
+    let snapshot = session.snapshot(table.id, alice).await.unwrap();
+    assert_eq!(snapshot.phase, Phase::WaitingForBets);
+    let seats: Vec<_> = snapshot
+        .players
+        .iter()
+        .map(|p| (p.player_id, p.seat, p.balance, p.bet))
+        .collect();
+    assert!(
+        seats.contains(&(alice, Seat::Three, 1000, None)),
+        "{seats:?}"
+    );
+    assert!(seats.contains(&(bob, Seat::One, 750, None)), "{seats:?}");
+    assert_eq!(
+        after.tables.get_table(table.id).await.unwrap().name,
+        "Restarted"
+    );
+
+    // The interrupted round is gone; the next one takes bets.
+    bet(session.as_ref(), table.id, alice, 100).await;
+    assert_eq!(after.wallet.balance(alice).await.unwrap(), 900);
+
+    // Accounts, roles and logins came back too.
+    let login = AuthPayload {
+        username: "alice".into(),
+        password: Password::new("famly1234".into()),
+    };
+    assert_eq!(after.auth.authenticate(&login).await.unwrap(), alice);
+    assert_eq!(after.auth.role(alice).await.unwrap(), Role::Dealer);
+    let sessions = Sessions::new(after.sessions.clone(), &token_settings());
+    sessions.refresh(&tokens.refresh_token).await.unwrap();
+}
+
+async fn state(balance: u32) -> SavedState {
+    let wallet = InMemoryWallet::new();
+    wallet
+        .credit(PlayerId::new(), balance, Posting::grant())
+        .await
+        .unwrap();
+    SavedState {
+        saved_at: Timestamp::from_millis(u64::from(balance)),
+        tables: vec![],
+        wallet: wallet.export(),
+        users: vec![],
+        sessions: Default::default(),
+    }
+}
+
+fn balance(saved: &SavedState) -> u32 {
+    let WalletState { accounts, .. } = &saved.wallet;
+    accounts[0].balance
+}
+
+#[tokio::test]
+async fn a_save_replaces_the_last_one_whole() {
+    let path = temp_dir();
+    let dir = StateDir::open(&path).unwrap();
+    assert!(dir.load().unwrap().is_none(), "nothing saved yet");
+
+    dir.write(&state(100).await).unwrap();
+    // A save that died before its rename leaves the last one in place.
+    std::fs::write(
+        path.join(format!("{STATE_FILE}.partial")),
+        b"{\"saved_at\":",
+    )
+    .unwrap();
+    assert_eq!(balance(&dir.load().unwrap().unwrap()), 100);
+
+    dir.write(&state(200).await).unwrap();
+    assert_eq!(balance(&dir.load().unwrap().unwrap()), 200);
+    let files: Vec<_> = std::fs::read_dir(&path)
+        .unwrap()
+        .map(|f| f.unwrap().file_name())
+        .collect();
+    assert_eq!(files, vec![STATE_FILE]);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(dir.file()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600, "the state holds credentials");
+    }
+}
+
+#[tokio::test]
+async fn a_closed_table_stays_closed() {
+    let path = temp_dir();
+    let before = empty();
+    let (open, mut closed) = (table(), table());
+    closed.status = TableStatus::Closed;
+    for table in [&open, &closed] {
+        before.tables.insert_table(table).await.unwrap();
+    }
+    let session = InMemoryGameSession::new(before.wallet.clone());
+    session.open_table(&open).await.unwrap();
+    StateSaver::new(StateDir::open(&path).unwrap(), before, session)
+        .save()
+        .await
+        .unwrap();
+
+    let mut saved = StateDir::open(&path).unwrap().load().unwrap().unwrap();
+    let after = MemoryBackends::restore(&mut saved, cheap());
+    let session = InMemoryGameSession::new(after.wallet.clone());
+    assert_eq!(open_saved_tables(saved.tables, session.as_ref()).await, 1);
+    let listed: Vec<_> = session.list_tables().await.iter().map(|t| t.id).collect();
+    assert_eq!(listed, vec![open.id]);
+    assert_eq!(after.tables.list_tables().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn the_state_is_saved_periodically_until_shutdown() {
+    let path = temp_dir();
+    let backends = empty();
+    let session = InMemoryGameSession::new(backends.wallet.clone());
+    let saver = Arc::new(StateSaver::new(
+        StateDir::open(&path).unwrap(),
+        backends.clone(),
+        session,
+    ));
+    let shutdown = Shutdown::new();
+    let saving = tokio::spawn(
+        saver
+            .clone()
+            .run(Duration::from_millis(20), shutdown.watch()),
+    );
+
+    let player = PlayerId::new();
+    backends
+        .wallet
+        .credit(player, 300, Posting::grant())
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let dir = StateDir::open(&path).unwrap();
+    assert_eq!(balance(&dir.load().unwrap().unwrap()), 300);
+
+    shutdown.begin(Timestamp::from_millis(0));
+    tokio::time::timeout(Duration::from_secs(1), saving)
+        .await
+        .expect("periodic saves stop when the shutdown begins")
+        .unwrap();
+
+    // The last save is the one at shutdown; nothing overwrites it.
+    saver.save_final().await.unwrap();
+    backends
+        .wallet
+        .credit(player, 1, Posting::grant())
+        .await
+        .unwrap();
+    saver.save().await.unwrap();
+    assert_eq!(balance(&dir.load().unwrap().unwrap()), 300);
+}
+
+#[tokio::test]
+async fn the_last_save_follows_the_drain_and_keeps_the_seats() {
+    let path = temp_dir();
+    let backends = empty();
+    let table = table();
+    backends.tables.insert_table(&table).await.unwrap();
+    let session =
+        InMemoryGameSession::with_config(backends.wallet.clone(), TableActorConfig::default());
+    session.open_table(&table).await.unwrap();
+    let alice = PlayerId::new();
+    backends
+        .wallet
+        .credit(alice, 1000, Posting::grant())
+        .await
+        .unwrap();
+    sit(session.as_ref(), table.id, alice, Some(Seat::Three)).await;
+    bet(session.as_ref(), table.id, alice, 100).await;
+    let saver = StateSaver::new(
+        StateDir::open(&path).unwrap(),
+        backends.clone(),
+        session.clone(),
+    );
+
+    // The drain voids the round and closes the table; the save after it
+    // has the stake back and the seat kept.
+    assert_eq!(session.drain(Duration::from_millis(50)).await, [table.id]);
+    saver.save_final().await.unwrap();
+
+    let mut saved = StateDir::open(&path).unwrap().load().unwrap().unwrap();
+    let after = MemoryBackends::restore(&mut saved, cheap());
+    assert_eq!(release_held(after.wallet.as_ref()).await.unwrap(), 0);
+    assert_eq!(after.wallet.balance(alice).await.unwrap(), 1000);
+    let session =
+        InMemoryGameSession::with_config(after.wallet.clone(), TableActorConfig::default());
+    assert_eq!(open_saved_tables(saved.tables, session.as_ref()).await, 1);
+    let snapshot = session.snapshot(table.id, alice).await.unwrap();
+    let seats: Vec<_> = snapshot
+        .players
+        .iter()
+        .map(|p| (p.player_id, p.seat, p.balance))
+        .collect();
+    assert_eq!(seats, [(alice, Seat::Three, 1000)]);
+}
+
+#[tokio::test]
+async fn only_live_sessions_are_saved() {
+    let store = Arc::new(InMemorySessionStore::new());
+    let sessions = Sessions::new(store.clone(), &token_settings());
+    let player = PlayerId::new();
+    let kept = sessions.issue(player).await.unwrap();
+    let revoked = sessions.issue(player).await.unwrap();
+    let claims = sessions.verify(&revoked.access_token).await.unwrap();
+    sessions.revoke(claims.session_id).await.unwrap();
+    // Spent tokens are kept with the session that spent them.
+    let refreshed = sessions.refresh(&kept.refresh_token).await.unwrap();
+
+    let saved = store.export();
+    let live = sessions.verify(&refreshed.access_token).await.unwrap();
+    assert_eq!(
+        saved.sessions.iter().map(|s| s.id).collect::<Vec<_>>(),
+        vec![live.session_id]
+    );
+    assert_eq!(saved.spent.len(), 1);
+
+    let restored = Sessions::new(
+        Arc::new(InMemorySessionStore::restore(saved)),
+        &token_settings(),
+    );
+    assert!(restored.verify(&refreshed.access_token).await.is_ok());
+    assert!(restored.verify(&revoked.access_token).await.is_err());
+}
@@ -0,0 +1,256 @@
+//! A table that panics is rebuilt with its players and its round voided.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use bj_core::domain::{
+    engine::{
+        command::{
+            dealer::{DealInitialCards, DealerAction, PlayHand, SettleRound},
+            player::{JoinTable, PlaceBet, PlayerAction, Stand, TakeSeat},
+        },
+        event::payload::EventPayload,
+        game_id::GameId,
+        phase::Phase,
+    },
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use server::{
+    session::{
+        in_memory::InMemoryGameSession, table_actor::TableActorConfig, GameSession, RequestId,
+        SessionError,
+    },
+    store::TableRecord,
+    wallet::{in_memory::InMemoryWallet, Posting, Reservation, Transaction, Wallet, WalletError},
+};
+use ulid::Ulid;
+
+/// An in-memory wallet whose next `commit` panics once armed.
+#[derive(Default)]
+struct FaultyWallet {
+    inner: InMemoryWallet,
+    armed: AtomicBool,
+}
+
+#[async_trait]
+impl Wallet for FaultyWallet {
+    async fn balance(&self, player: PlayerId) -> Result<u32, WalletError> {
+        self.inner.balance(player).await
+    }
+
+    async fn debit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError> {
+        self.inner.debit(player, amount, posting).await
+    }
+
+    async fn credit(
+        &self,
+        player: PlayerId,
+        amount: u32,
+        posting: Posting,
+    ) -> Result<u32, WalletError> {
+        self.inner.credit(player, amount, posting).await
+    }
+
+    async fn reserve(
+        &self,
+        player: PlayerId,
+        table_id: TableId,
+        game_id: GameId,
+        amount: u32,
+    ) -> Result<u32, WalletError> {
+        self.inner.reserve(player, table_id, game_id, amount).await
+    }
+
+    async fn commit(
+        &self,
+        player: PlayerId,
+        game_id: GameId,
+        payout: u32,
+    ) -> Result<u32, WalletError> {
+        if self.armed.swap(false, Ordering::SeqCst) {
+            panic!("wallet fault injected by the test");
+        }
+        self.inner.commit(player, game_id, payout).await
+    }
+
+    async fn release(&self, player: PlayerId, game_id: GameId) -> Result<u32, WalletError> {
+        self.inner.release(player, game_id).await
+    }
+
+    async fn held(&self) -> Result<Vec<Reservation>, WalletError> {
+        self.inner.held().await
+    }
+
+    async fn transactions(
+        &self,
+        player: PlayerId,
+        limit: usize,
+    ) -> Result<Vec<Transaction>, WalletError> {
+        self.inner.transactions(player, limit).await
+    }
+}
+
+/// The dealer assigned to every table here.
+const DEALER: PlayerId = PlayerId(Ulid::nil());
+
+fn record(name: &str) -> TableRecord {
+    TableRecord {
+        id: TableId::new(),
+        name: name.into(),
+        status: TableStatus::Open,
+        settings: TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Manual,
+            dealers: vec![DEALER],
+        },
+    }
+}
+
+async fn act(session: &dyn GameSession, table_id: TableId, player: PlayerId, action: PlayerAction) {
+    session
+        .send_command(table_id, player, RequestId(1), action)
+        .await
+        .unwrap();
+}
+
+async fn deal(
+    session: &dyn GameSession,
+    table_id: TableId,
+    action: DealerAction,
+) -> Result<(), SessionError> {
+    session
+        .dealer_command(table_id, DEALER, RequestId(2), action)
+        .await
+        .map(|_| ())
+}
+
+#[tokio::test]
+async fn a_panicking_table_voids_its_round_and_keeps_its_players() {
+    let wallet = Arc::new(FaultyWallet::default());
+    let session: Arc<dyn GameSession> = InMemoryGameSession::with_config(
+        wallet.clone(),
+        TableActorConfig {
+            recovery_period: Duration::from_millis(200),
+            ..TableActorConfig::default()
+        },
+    );
+    let table = record("Fragile");
+    let bystander = record("Bystander");
+    session.open_table(&table).await.unwrap();
+    session.open_table(&bystander).await.unwrap();
+    let id = table.id;
+
+    let player = PlayerId::new();
+    wallet.credit(player, 1000, Posting::grant()).await.unwrap();
+    act(
+        session.as_ref(),
+        id,
+        player,
+        PlayerAction::JoinTable(JoinTable { player_id: player }),
+    )
+    .await;
+    act(
+        session.as_ref(),
+        id,
+        player,
+        PlayerAction::TakeSeat(TakeSeat {
+            player_id: player,
+            seat: None,
+        }),
+    )
+    .await;
+    act(
+        session.as_ref(),
+        id,
+        player,
+        PlayerAction::PlaceBet(PlaceBet {
+            player_id: player,
+            amount: 100,
+        }),
+    )
+    .await;
+    deal(
+        session.as_ref(),
+        id,
+        DealerAction::DealInitialCards(DealInitialCards),
+    )
+    .await
+    .unwrap();
+    if let Phase::PlayerTurn(_) = session.snapshot(id, player).await.unwrap().phase {
+        act(
+            session.as_ref(),
+            id,
+            player,
+            PlayerAction::Stand(Stand { player_id: player }),
+        )
+        .await;
+    }
+    deal(session.as_ref(), id, DealerAction::PlayHand(PlayHand))
+        .await
+        .unwrap();
+    let before = session.snapshot(id, player).await.unwrap().last_seq;
+    let mut events = session.subscribe(id).await.unwrap();
+
+    wallet.armed.store(true, Ordering::SeqCst);
+    let settle = deal(session.as_ref(), id, DealerAction::SettleRound(SettleRound)).await;
+    assert!(matches!(settle, Err(SessionError::Internal)), "{settle:?}");
+
+    // Clients hear why their round is gone, in sequence.
+    let voided = events.recv().await.unwrap();
+    assert!(matches!(voided.payload, EventPayload::RoundVoided { .. }));
+    assert_eq!(voided.event_seq_id.0, before + 1);
+
+    let after = session.snapshot(id, player).await.unwrap();
+    assert_eq!(after.phase, Phase::WaitingForBets);
+    let seat = after
+        .players
+        .iter()
+        .find(|p| p.player_id == player)
+        .expect("player kept their seat");
+    assert_eq!(seat.bet, None);
+    assert_eq!(wallet.balance(player).await.unwrap(), 1000);
+    assert!(wallet.held().await.unwrap().is_empty());
+
+    let degraded = |tables: Vec<server::session::TableSummary>| {
+        tables
+            .into_iter()
+            .filter(|t| t.degraded)
+            .map(|t| t.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(degraded(session.list_tables().await), vec![id]);
+
+    // The table takes bets again straight away, and recovers on its own.
+    act(
+        session.as_ref(),
+        id,
+        player,
+        PlayerAction::PlaceBet(PlaceBet {
+            player_id: player,
+            amount: 50,
+        }),
+    )
+    .await;
+    assert_eq!(wallet.balance(player).await.unwrap(), 950);
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert!(degraded(session.list_tables().await).is_empty());
+    assert!(session
+        .list_tables()
+        .await
+        .iter()
+        .any(|t| t.id == bystander.id));
+}
@@ -0,0 +1,200 @@
+//! Table configuration shared by every `TableStore` implementation, and
+//! admin changes reaching running tables.
+
+mod common;
+
+use std::sync::Arc;
+
+use bj_core::domain::{
+    engine::command::player::{JoinTable, PlayerAction, TakeSeat},
+    DealerMode, PlayerId, Seat, TableId, TableSettings, TableStatus,
+};
+use server::{
+    session::{in_memory::InMemoryGameSession, GameSession, RequestId, SessionError},
+    store::{
+        open_stored_tables, InMemoryTableStore, PostgresTableStore, SqliteTableStore, TableRecord,
+        TableStore, TableStoreError,
+    },
+    wallet::in_memory::InMemoryWallet,
+};
+
+async fn postgres() -> Option<PostgresTableStore> {
+    let pool = common::postgres_pool().await?;
+    Some(PostgresTableStore::new(pool))
+}
+
+async fn sqlite() -> SqliteTableStore {
+    SqliteTableStore::new(common::sqlite_pool().await)
+}
+
+fn settings() -> TableSettings {
+    TableSettings {
+        min_bet: 10,
+        max_bet: 500,
+        max_players: 5,
+        max_observers: 10,
+        dealer: DealerMode::Automatic,
+        dealers: vec![],
+    }
+}
+
+/// Ids are unique per run so tests can share a database.
+fn record(name: &str) -> TableRecord {
+    TableRecord {
+        id: TableId::new(),
+        name: name.to_string(),
+        status: TableStatus::Open,
+        settings: settings(),
+    }
+}
+
+async fn inserted_table_reads_back(store: &dyn TableStore) {
+    let table = record("Read Back");
+    store.insert_table(&table).await.unwrap();
+
+    let stored = store.get_table(table.id).await.unwrap();
+    assert_eq!(stored.id, table.id);
+    assert_eq!(stored.name, "Read Back");
+    assert_eq!(stored.status, TableStatus::Open);
+    assert_eq!(stored.settings.max_bet, 500);
+    assert!(store
+        .list_tables()
+        .await
+        .unwrap()
+        .iter()
+        .any(|t| t.id == table.id));
+}
+
+async fn update_replaces_name_settings_and_status(store: &dyn TableStore) {
+    let mut table = record("Before");
+    store.insert_table(&table).await.unwrap();
+
+    table.name = "After".into();
+    table.settings.min_bet = 50;
+    table.status = TableStatus::Closed;
+    store.update_table(&table).await.unwrap();
+
+    let stored = store.get_table(table.id).await.unwrap();
+    assert_eq!(stored.name, "After");
+    assert_eq!(stored.settings.min_bet, 50);
+    assert_eq!(stored.status, TableStatus::Closed);
+}
+
+async fn unknown_table_is_not_found(store: &dyn TableStore) {
+    let table = record("Nowhere");
+    assert!(matches!(
+        store.get_table(table.id).await,
+        Err(TableStoreError::NotFound)
+    ));
+    assert!(matches!(
+        store.update_table(&table).await,
+        Err(TableStoreError::NotFound)
+    ));
+}
+
+macro_rules! table_store_tests {
+    ($($name:ident),* $(,)?) => {
+        mod in_memory {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(&super::InMemoryTableStore::new()).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    if let Some(store) = super::postgres().await {
+                        super::$name(&store).await;
+                    }
+                }
+            )*
+        }
+
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(&super::sqlite().await).await;
+                }
+            )*
+        }
+    };
+}
+
+table_store_tests!(
+    inserted_table_reads_back,
+    update_replaces_name_settings_and_status,
+    unknown_table_is_not_found,
+);
+
+fn session() -> Arc<dyn GameSession> {
+    InMemoryGameSession::new(Arc::new(InMemoryWallet::new()))
+}
+
+#[tokio::test]
+async fn only_open_tables_are_opened_at_boot() {
+    let store = InMemoryTableStore::new();
+    let open = record("Open");
+    let closed = TableRecord {
+        status: TableStatus::Closed,
+        ..record("Closed")
+    };
+    store.insert_table(&open).await.unwrap();
+    store.insert_table(&closed).await.unwrap();
+    let session = session();
+
+    assert_eq!(
+        open_stored_tables(&store, session.as_ref()).await.unwrap(),
+        1
+    );
+    let live: Vec<_> = session.list_tables().await.iter().map(|t| t.id).collect();
+    assert_eq!(live, vec![open.id]);
+    assert!(session.open_table(&open).await.is_err());
+}
+
+#[tokio::test]
+async fn update_reaches_the_running_table() {
+    let session = session();
+    let mut table = record("Old Name");
+    session.open_table(&table).await.unwrap();
+
+    table.name = "New Name".into();
+    table.settings.max_players = 7;
+    session.update_table(&table).await.unwrap();
+
+    let summary = session.list_tables().await.remove(0);
+    assert_eq!(summary.name, "New Name");
+    assert_eq!(summary.settings.max_players, 7);
+}
+
+#[tokio::test]
+async fn seats_in_use_cannot_be_removed() {
+    let session = session();
+    let mut table = record("Full House");
+    session.open_table(&table).await.unwrap();
+    let player = PlayerId::new();
+    for action in [
+        PlayerAction::JoinTable(JoinTable { player_id: player }),
+        PlayerAction::TakeSeat(TakeSeat {
+            player_id: player,
+            seat: Some(Seat::Four),
+        }),
+    ] {
+        session
+            .send_command(table.id, player, RequestId(1), action)
+            .await
+            .unwrap();
+    }
+
+    table.settings.max_players = 3;
+    assert!(matches!(
+        session.update_table(&table).await,
+        Err(SessionError::CommandRejected(_))
+    ));
+    table.settings.max_players = 4;
+    assert!(session.update_table(&table).await.is_ok());
+}
@@ -0,0 +1,125 @@
+//! A command's spans join the caller's trace, across the file-backed
+//! command log.
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use bj_core::domain::{
+    engine::command::player::{JoinTable, PlayerAction},
+    DealerMode, PlayerId, TableId, TableSettings, TableStatus,
+};
+use opentelemetry::{global, trace::TracerProvider as _, Value};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::{InMemorySpanExporter, SdkTracerProvider, SpanData},
+};
+use server::{
+    bus::{Buses, FileCommandBus},
+    session::{
+        in_memory::InMemoryGameSession, table_actor::TableActorConfig, GameSession, RequestId,
+    },
+    store::TableRecord,
+    wallet::in_memory::InMemoryWallet,
+};
+use tracing::Instrument;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use ulid::Ulid;
+
+fn log_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("bj-trace-{}", Ulid::new()))
+}
+
+fn attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| &kv.value)
+}
+
+#[tokio::test]
+async fn command_spans_continue_the_callers_trace() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")))
+        .init();
+
+    let dir = log_dir();
+    let session = InMemoryGameSession::with_buses(
+        Arc::new(InMemoryWallet::new()),
+        TableActorConfig::default(),
+        Buses::local(Arc::new(FileCommandBus::open(&dir, 4).unwrap())),
+    )
+    .unwrap();
+    let table = TableRecord {
+        id: TableId::new(),
+        name: "Traced".into(),
+        status: TableStatus::Open,
+        settings: TableSettings {
+            min_bet: 10,
+            max_bet: 500,
+            max_players: 5,
+            max_observers: 10,
+            dealer: DealerMode::Automatic,
+            dealers: vec![],
+        },
+    };
+    session.open_table(&table).await.unwrap();
+
+    let player = PlayerId::new();
+    session
+        .send_command(
+            table.id,
+            player,
+            RequestId(42),
+            PlayerAction::JoinTable(JoinTable { player_id: player }),
+        )
+        .instrument(tracing::info_span!("client_message"))
+        .await
+        .unwrap();
+    provider.force_flush().unwrap();
+
+    let spans = exporter.get_finished_spans().unwrap();
+    let named = |name: &str| {
+        spans
+            .iter()
+            .find(|s| s.name == name)
+            .unwrap_or_else(|| panic!("no {name} span"))
+    };
+    let root = named("client_message");
+    let command = spans
+        .iter()
+        .find(|s| s.name == "table.command" && attribute(s, "request_id").is_some())
+        .expect("no span for the player's command");
+    let engine = spans
+        .iter()
+        .find(|s| s.name == "engine.handle" && s.parent_span_id == command.span_context.span_id())
+        .expect("no engine span under the command");
+
+    let trace_id = root.span_context.trace_id();
+    assert_eq!(command.span_context.trace_id(), trace_id);
+    assert_eq!(engine.span_context.trace_id(), trace_id);
+    let request = spans
+        .iter()
+        .find(|s| s.name == "session.request" && s.span_context.trace_id() == trace_id)
+        .expect("no session span in the caller's trace");
+    assert_eq!(command.parent_span_id, request.span_context.span_id());
+    assert_eq!(
+        attribute(command, "table"),
+        Some(&Value::from(table.id.to_string()))
+    );
+    assert_eq!(
+        attribute(command, "player"),
+        Some(&Value::from(player.to_string()))
+    );
+    assert_eq!(
+        attribute(command, "request_id").map(|v| v.as_str().into_owned()),
+        Some("42".to_string())
+    );
+
+    drop(session);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,216 @@
+//! Escrow behaviour shared by every `Wallet` implementation.
+//!
+//! Each scenario runs against `InMemoryWallet`, `SqliteWallet` on a temp
+//! file, and `PostgresWallet` when `DATABASE_URL` points at a database the
+//! tests may migrate.
+//!
+//! A "crash" is a reply that never reached the table actor, or an actor that
+//! stopped mid-round. The database wallets keep no state of their own, so the
+//! same instance stands in for the wallet after a restart.
+
+mod common;
+
+use bj_core::domain::{engine::game_id::GameId, PlayerId, TableId};
+use server::wallet::{
+    in_memory::InMemoryWallet, postgres::PostgresWallet, sqlite::SqliteWallet, Posting,
+    ReservationStatus, TransactionKind, Wallet, WalletError,
+};
+
+async fn postgres() -> Option<PostgresWallet> {
+    common::postgres_pool().await.map(PostgresWallet::new)
+}
+
+async fn sqlite() -> SqliteWallet {
+    SqliteWallet::new(common::sqlite_pool().await)
+}
+
+async fn player(wallet: &dyn Wallet) -> PlayerId {
+    let player = PlayerId::new();
+    wallet.credit(player, 1000, Posting::grant()).await.unwrap();
+    player
+}
+
+async fn kinds(wallet: &dyn Wallet, player: PlayerId) -> Vec<TransactionKind> {
+    let mut kinds: Vec<_> = wallet
+        .transactions(player, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.kind)
+        .collect();
+    kinds.reverse();
+    kinds
+}
+
+async fn reserve_then_commit_pays_out(wallet: &dyn Wallet) {
+    let (p, table, game) = (player(wallet).await, TableId::new(), GameId::new());
+
+    assert_eq!(wallet.reserve(p, table, game, 100).await.unwrap(), 900);
+    assert_eq!(wallet.balance(p).await.unwrap(), 900);
+    assert_eq!(wallet.commit(p, game, 200).await.unwrap(), 1100);
+
+    let ledger = wallet.transactions(p, 10).await.unwrap();
+    assert_eq!(ledger[0].kind, TransactionKind::Payout);
+    assert_eq!(ledger[0].amount, 200);
+    assert_eq!(ledger[0].game_id, Some(game));
+    assert_eq!(ledger[1].kind, TransactionKind::Bet);
+    assert_eq!(ledger[1].amount, -100);
+    assert_eq!(ledger[1].table_id, Some(table));
+}
+
+async fn lost_round_posts_no_payout(wallet: &dyn Wallet) {
+    let (p, game) = (player(wallet).await, GameId::new());
+    wallet.reserve(p, TableId::new(), game, 100).await.unwrap();
+    assert_eq!(wallet.commit(p, game, 0).await.unwrap(), 900);
+    assert_eq!(
+        kinds(wallet, p).await,
+        [TransactionKind::Grant, TransactionKind::Bet]
+    );
+}
+
+/// The actor retries a reservation whose reply was lost.
+async fn retried_reserve_takes_stake_once(wallet: &dyn Wallet) {
+    let (p, table, game) = (player(wallet).await, TableId::new(), GameId::new());
+    wallet.reserve(p, table, game, 100).await.unwrap();
+    assert_eq!(wallet.reserve(p, table, game, 100).await.unwrap(), 900);
+    assert!(matches!(
+        wallet.reserve(p, table, game, 50).await,
+        Err(WalletError::ReservationConflict {
+            held: 100,
+            amount: 50
+        })
+    ));
+    assert_eq!(
+        kinds(wallet, p).await,
+        [TransactionKind::Grant, TransactionKind::Bet]
+    );
+}
+
+/// Settlement is replayed after a crash between commit and the broadcast.
+async fn replayed_commit_pays_once(wallet: &dyn Wallet) {
+    let (p, game) = (player(wallet).await, GameId::new());
+    wallet.reserve(p, TableId::new(), game, 100).await.unwrap();
+    wallet.commit(p, game, 250).await.unwrap();
+    assert_eq!(wallet.commit(p, game, 250).await.unwrap(), 1150);
+    assert_eq!(wallet.balance(p).await.unwrap(), 1150);
+}
+
+async fn voided_round_releases_stake(wallet: &dyn Wallet) {
+    let (p, game) = (player(wallet).await, GameId::new());
+    wallet.reserve(p, TableId::new(), game, 100).await.unwrap();
+    assert_eq!(wallet.release(p, game).await.unwrap(), 1000);
+    assert_eq!(wallet.release(p, game).await.unwrap(), 1000);
+    assert!(matches!(
+        wallet.commit(p, game, 200).await,
+        Err(WalletError::ReservationSettled(ReservationStatus::Released))
+    ));
+    assert_eq!(
+        kinds(wallet, p).await,
+        [
+            TransactionKind::Grant,
+            TransactionKind::Bet,
+            TransactionKind::Refund
+        ]
+    );
+}
+
+async fn settling_unknown_round_fails(wallet: &dyn Wallet) {
+    let p = player(wallet).await;
+    assert!(matches!(
+        wallet.commit(p, GameId::new(), 100).await,
+        Err(WalletError::ReservationNotFound)
+    ));
+    assert!(matches!(
+        wallet.release(p, GameId::new()).await,
+        Err(WalletError::ReservationNotFound)
+    ));
+}
+
+/// A player seated at two tables cannot stake the same chips twice.
+async fn stake_cannot_be_spent_twice(wallet: &dyn Wallet) {
+    let p = player(wallet).await;
+    wallet
+        .reserve(p, TableId::new(), GameId::new(), 700)
+        .await
+        .unwrap();
+    assert!(matches!(
+        wallet.reserve(p, TableId::new(), GameId::new(), 700).await,
+        Err(WalletError::InsufficientBalance {
+            balance: 300,
+            amount: 700
+        })
+    ));
+}
+
+/// The table stopped mid-round; on the next boot its stakes are still held
+/// and are given back.
+async fn interrupted_round_is_released_on_restart(wallet: &dyn Wallet) {
+    let (p, table, game) = (player(wallet).await, TableId::new(), GameId::new());
+    wallet.reserve(p, table, game, 100).await.unwrap();
+
+    let held: Vec<_> = wallet
+        .held()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|r| r.player_id == p)
+        .collect();
+    assert_eq!(held.len(), 1);
+    assert_eq!((held[0].table_id, held[0].game_id), (table, game));
+    assert_eq!(held[0].amount, 100);
+
+    for r in held {
+        wallet.release(r.player_id, r.game_id).await.unwrap();
+    }
+    assert_eq!(wallet.balance(p).await.unwrap(), 1000);
+    assert!(!wallet
+        .held()
+        .await
+        .unwrap()
+        .iter()
+        .any(|r| r.player_id == p));
+}
+
+macro_rules! wallet_tests {
+    ($($name:ident),* $(,)?) => {
+        mod in_memory {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(&super::InMemoryWallet::new()).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    if let Some(wallet) = super::postgres().await {
+                        super::$name(&wallet).await;
+                    }
+                }
+            )*
+        }
+
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(&super::sqlite().await).await;
+                }
+            )*
+        }
+    };
+}
+
+wallet_tests!(
+    reserve_then_commit_pays_out,
+    lost_round_posts_no_payout,
+    retried_reserve_takes_stake_once,
+    replayed_commit_pays_once,
+    voided_round_releases_stake,
+    settling_unknown_round_fails,
+    stake_cannot_be_spent_twice,
+    interrupted_round_is_released_on_restart,
+);